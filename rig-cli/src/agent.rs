@@ -0,0 +1,69 @@
+//! Type-erased streaming agents driven by the REPL.
+
+use futures::future::LocalBoxFuture;
+use rig::{
+    agent::Agent,
+    completion::{CompletionError, Message},
+    streaming::{StreamingCompletion, StreamingCompletionModel, StreamingResult},
+    tool::ToolSetError,
+};
+
+/// Agent driven by the [Repl](crate::repl::Repl): streams its answers and calls its tools.
+/// Implemented by the [Agent]s of all the streaming completion models.
+pub trait ChatAgent {
+    /// Stream the answer to `prompt` given the chat history
+    fn stream(
+        &self,
+        prompt: Message,
+        chat_history: Vec<Message>,
+    ) -> LocalBoxFuture<'_, Result<StreamingResult, CompletionError>>;
+
+    /// Call the tool `name` with `args` (JSON arguments generated by the model)
+    fn call_tool<'a>(
+        &'a self,
+        name: &'a str,
+        args: String,
+    ) -> LocalBoxFuture<'a, Result<String, ToolSetError>>;
+}
+
+impl<M: StreamingCompletionModel> ChatAgent for Agent<M> {
+    fn stream(
+        &self,
+        prompt: Message,
+        chat_history: Vec<Message>,
+    ) -> LocalBoxFuture<'_, Result<StreamingResult, CompletionError>> {
+        Box::pin(async move {
+            self.stream_completion(prompt, chat_history)
+                .await?
+                .stream()
+                .await
+        })
+    }
+
+    fn call_tool<'a>(
+        &'a self,
+        name: &'a str,
+        args: String,
+    ) -> LocalBoxFuture<'a, Result<String, ToolSetError>> {
+        Box::pin(self.tools.call(name, args))
+    }
+}
+
+/// Settings of the agent of the REPL, changed with the slash-commands
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentConfig {
+    /// Model identifier of the form `provider:model` (e.g.: `"openai:gpt-4o-mini"`)
+    pub model: String,
+    pub preamble: Option<String>,
+    pub temperature: Option<f64>,
+}
+
+impl AgentConfig {
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            preamble: None,
+            temperature: None,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Files attached to the conversation with `/attach`: they are loaded, split into chunks and
+//! embedded into an in-memory vector store, from which the agent retrieves its dynamic context.
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use rig::{
+    chunking::{Chunk, Chunker, RecursiveCharacterSplitter},
+    embeddings::{BoxEmbeddingModel, EmbeddingsBuilder},
+    loaders::load_document,
+    vector_store::in_memory_store::{InMemoryVectorIndex, InMemoryVectorStore},
+};
+
+/// Size (in characters) of the chunks of the attached files
+const CHUNK_SIZE: usize = 1000;
+const CHUNK_OVERLAP: usize = 200;
+
+pub struct Attachments {
+    model: Option<BoxEmbeddingModel>,
+    store: InMemoryVectorStore<Chunk>,
+    files: Vec<PathBuf>,
+}
+
+impl Attachments {
+    /// Embed the attached files with `model`
+    pub fn new(model: BoxEmbeddingModel) -> Self {
+        Self {
+            model: Some(model),
+            store: InMemoryVectorStore::from_documents(vec![]),
+            files: vec![],
+        }
+    }
+
+    /// No embedding model is available: files can not be attached
+    pub fn disabled() -> Self {
+        Self {
+            model: None,
+            store: InMemoryVectorStore::from_documents(vec![]),
+            files: vec![],
+        }
+    }
+
+    /// Attach the file at `pattern`, or the files matching the glob `pattern`, skipping the files
+    /// already attached. Returns the number of chunks added to the store.
+    pub async fn attach(&mut self, pattern: &str) -> anyhow::Result<usize> {
+        let Some(model) = self.model.clone() else {
+            anyhow::bail!("No embedding model available to attach files");
+        };
+        let paths = glob::glob(pattern)?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|path| path.is_file() && !self.files.contains(path))
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            anyhow::bail!("No new file matches `{pattern}`");
+        }
+
+        let splitter = RecursiveCharacterSplitter::new(CHUNK_SIZE, CHUNK_OVERLAP);
+        let mut chunks = vec![];
+        for path in &paths {
+            let documents = load_document(path)
+                .with_context(|| format!("Failed to load {}", path.display()))?;
+            chunks.extend(splitter.chunk_documents(&documents));
+        }
+        if chunks.is_empty() {
+            anyhow::bail!("The files matching `{pattern}` are empty");
+        }
+        let count = chunks.len();
+
+        let embeddings = EmbeddingsBuilder::new(model)
+            .documents(chunks)?
+            .build()
+            .await?;
+        self.store.add_documents_with_ids(
+            embeddings
+                .into_iter()
+                .map(|(chunk, embeddings)| (chunk.id.clone(), chunk, embeddings)),
+        );
+        self.files.extend(paths);
+
+        Ok(count)
+    }
+
+    /// Paths of the attached files
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Index of the chunks of the attached files, used as dynamic context by the agent (`None`
+    /// if no file is attached)
+    pub fn index(&self) -> Option<InMemoryVectorIndex<BoxEmbeddingModel, Chunk>> {
+        let model = self.model.clone().filter(|_| !self.is_empty())?;
+        Some(self.store.clone().index(model))
+    }
+}
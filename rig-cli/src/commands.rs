@@ -0,0 +1,189 @@
+//! Parsing of the lines typed in the REPL: prompts and slash-commands (e.g.: `/model`).
+
+/// List of the slash-commands, printed by `/help`
+pub const HELP: &str = "\
+Commands:
+  /model [provider:model]   Show or switch the model (e.g.: /model anthropic:claude-3-7-sonnet-latest)
+  /preamble [text|none]     Show, set or remove the preamble (system prompt)
+  /temperature [value|none] Show, set or reset the temperature
+  /attach <path>            Attach a file, or the files matching a glob, for RAG
+  /attachments              List the attached files
+  /tools                    Show the tool calls of the session and their results
+  /history                  Show the chat history
+  /clear                    Clear the chat history
+  /help                     Show this message
+  /exit                     Quit
+
+Lines starting with // are sent as prompts starting with /.";
+
+/// Line typed in the REPL
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    Empty,
+    Prompt(String),
+    Command(Command),
+}
+
+/// Slash-command typed in the REPL
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Show the model, or switch to the given `provider:model`
+    Model(Option<String>),
+    Preamble(Setting<String>),
+    Temperature(Setting<f64>),
+    /// Attach the files matching the path (or glob)
+    Attach(String),
+    Attachments,
+    Tools,
+    History,
+    Clear,
+    Help,
+    Exit,
+}
+
+/// Argument of the commands changing an optional setting of the agent
+#[derive(Clone, Debug, PartialEq)]
+pub enum Setting<T> {
+    /// No argument: show the current value
+    Show,
+    Set(T),
+    /// `none`: remove the value
+    Unset,
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum CommandError {
+    #[error("Unknown command /{0}, type /help for the list of commands")]
+    UnknownCommand(String),
+
+    #[error("Missing argument, usage: {0}")]
+    MissingArgument(&'static str),
+
+    #[error("Invalid argument `{0}`: {1}")]
+    InvalidArgument(String, String),
+}
+
+/// Parse a line typed in the REPL
+pub fn parse(line: &str) -> Result<Input, CommandError> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(Input::Empty);
+    }
+
+    let Some(command) = line.strip_prefix('/') else {
+        return Ok(Input::Prompt(line.to_string()));
+    };
+    if command.starts_with('/') {
+        return Ok(Input::Prompt(command.to_string()));
+    }
+
+    let (name, argument) = match command.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, Some(argument.trim()).filter(|arg| !arg.is_empty())),
+        None => (command, None),
+    };
+
+    let command = match name {
+        "model" => Command::Model(argument.map(str::to_string)),
+        "preamble" => Command::Preamble(setting(argument, |preamble| Ok(preamble.to_string()))?),
+        "temperature" => Command::Temperature(setting(argument, |temperature| {
+            temperature
+                .parse::<f64>()
+                .map_err(|error| error.to_string())
+        })?),
+        "attach" => Command::Attach(
+            argument
+                .ok_or(CommandError::MissingArgument("/attach <path>"))?
+                .to_string(),
+        ),
+        "attachments" => Command::Attachments,
+        "tools" => Command::Tools,
+        "history" => Command::History,
+        "clear" => Command::Clear,
+        "help" => Command::Help,
+        "exit" | "quit" => Command::Exit,
+        _ => return Err(CommandError::UnknownCommand(name.to_string())),
+    };
+
+    Ok(Input::Command(command))
+}
+
+fn setting<T>(
+    argument: Option<&str>,
+    parse: impl FnOnce(&str) -> Result<T, String>,
+) -> Result<Setting<T>, CommandError> {
+    match argument {
+        None => Ok(Setting::Show),
+        Some("none") => Ok(Setting::Unset),
+        Some(argument) => parse(argument)
+            .map(Setting::Set)
+            .map_err(|error| CommandError::InvalidArgument(argument.to_string(), error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prompt() {
+        assert_eq!(parse("  "), Ok(Input::Empty));
+        assert_eq!(
+            parse(" What is Rig? \n"),
+            Ok(Input::Prompt("What is Rig?".to_string()))
+        );
+        assert_eq!(
+            parse("//etc/hosts is a file"),
+            Ok(Input::Prompt("/etc/hosts is a file".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse("/model"), Ok(Input::Command(Command::Model(None))));
+        assert_eq!(
+            parse("/model ollama:llama3.2:3b"),
+            Ok(Input::Command(Command::Model(Some(
+                "ollama:llama3.2:3b".to_string()
+            ))))
+        );
+        assert_eq!(
+            parse("/preamble You are a pirate."),
+            Ok(Input::Command(Command::Preamble(Setting::Set(
+                "You are a pirate.".to_string()
+            ))))
+        );
+        assert_eq!(
+            parse("/preamble none"),
+            Ok(Input::Command(Command::Preamble(Setting::Unset)))
+        );
+        assert_eq!(
+            parse("/temperature 0.2"),
+            Ok(Input::Command(Command::Temperature(Setting::Set(0.2))))
+        );
+        assert_eq!(
+            parse("/temperature"),
+            Ok(Input::Command(Command::Temperature(Setting::Show)))
+        );
+        assert_eq!(
+            parse("/attach docs/**/*.md"),
+            Ok(Input::Command(Command::Attach("docs/**/*.md".to_string())))
+        );
+        assert_eq!(parse("/quit"), Ok(Input::Command(Command::Exit)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse("/unknown"),
+            Err(CommandError::UnknownCommand("unknown".to_string()))
+        );
+        assert_eq!(
+            parse("/attach "),
+            Err(CommandError::MissingArgument("/attach <path>"))
+        );
+        assert!(matches!(
+            parse("/temperature hot"),
+            Err(CommandError::InvalidArgument(argument, _)) if argument == "hot"
+        ));
+    }
+}
@@ -0,0 +1,38 @@
+//! Interactive chat REPL over the providers of Rig, installed as the `rig` binary:
+//! - the answers of the model are streamed to the terminal;
+//! - slash-commands switch the model (`/model anthropic:claude-3-7-sonnet-latest`), change the
+//!   preamble or temperature, and show the chat history;
+//! - files attached with `/attach` are chunked, embedded and retrieved as dynamic context (RAG);
+//! - the tool calls of the model and their results are printed as they happen, and can be listed
+//!   with `/tools`.
+//!
+//! The [Repl] can also be driven by custom agents (e.g.: agents with tools) built by a factory:
+//! ```rust,ignore
+//! use rig::{embeddings::BoxEmbeddingModel, providers::openai};
+//! use rig_cli::{AgentConfig, Attachments, Repl};
+//!
+//! let openai = openai::Client::from_env();
+//! let attachments = Attachments::new(BoxEmbeddingModel::new(
+//!     openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL),
+//! ));
+//!
+//! let mut repl = Repl::with_factory(
+//!     AgentConfig::new("openai:gpt-4o"),
+//!     attachments,
+//!     move |config, _attachments| {
+//!         let (_, model) = config.model.split_once(':').unwrap();
+//!         Ok(Box::new(openai.agent(model).tool(Adder).build()))
+//!     },
+//! )?;
+//! repl.run().await?;
+//! ```
+
+pub mod agent;
+pub mod attachments;
+pub mod commands;
+pub mod providers;
+pub mod repl;
+
+pub use agent::{AgentConfig, ChatAgent};
+pub use attachments::Attachments;
+pub use repl::Repl;
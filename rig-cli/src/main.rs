@@ -0,0 +1,65 @@
+use clap::Parser;
+use rig::registry::ModelRegistry;
+use rig_cli::{AgentConfig, Attachments, Repl};
+
+/// Interactive chat with the models of the providers of Rig. Type /help in the REPL for the list
+/// of commands.
+#[derive(Parser)]
+#[command(name = "rig", version)]
+struct Args {
+    /// Model of the agent, of the form `provider:model`
+    #[arg(short, long, env = "RIG_MODEL", default_value = "openai:gpt-4o-mini")]
+    model: String,
+
+    /// Preamble (system prompt) of the agent
+    #[arg(short, long)]
+    preamble: Option<String>,
+
+    /// Temperature of the model
+    #[arg(short, long)]
+    temperature: Option<f64>,
+
+    /// Model embedding the attached files, of the form `provider:model`
+    #[arg(
+        long,
+        env = "RIG_EMBEDDING_MODEL",
+        default_value = "openai:text-embedding-3-small"
+    )]
+    embedding_model: String,
+
+    /// Files (or globs) to attach before the first prompt
+    #[arg(short, long)]
+    attach: Vec<String>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+
+    let args = Args::parse();
+
+    // Resolving the embedding model only checks its credentials (nothing is embedded until files
+    // are attached), the REPL can run without it
+    let mut attachments = match ModelRegistry::from_env().embedding_model(&args.embedding_model) {
+        Ok(model) => Attachments::new(model),
+        Err(error) if args.attach.is_empty() => {
+            eprintln!("Warning: {error}, files can not be attached");
+            Attachments::disabled()
+        }
+        Err(error) => return Err(error.into()),
+    };
+    for pattern in &args.attach {
+        attachments.attach(pattern).await?;
+    }
+
+    let config = AgentConfig {
+        model: args.model,
+        preamble: args.preamble,
+        temperature: args.temperature,
+    };
+
+    Repl::new(config, attachments)?.run().await
+}
@@ -0,0 +1,38 @@
+//! Agents of the streaming providers of Rig, built from `provider:model` identifiers resolved by
+//! the [ModelRegistry] of Rig. The credentials of the providers are read from the same
+//! environment variables as their `from_env` constructors (e.g.: `OPENAI_API_KEY`).
+
+use anyhow::bail;
+use rig::{agent::AgentBuilder, registry::ModelRegistry};
+
+use crate::{
+    agent::{AgentConfig, ChatAgent},
+    attachments::Attachments,
+};
+
+/// Number of chunks of the attached files retrieved for each prompt
+const DYNAMIC_CONTEXT_SAMPLES: usize = 4;
+
+/// Build the agent described by `config`, retrieving its dynamic context from `attachments`
+pub fn agent(
+    config: &AgentConfig,
+    attachments: &Attachments,
+) -> anyhow::Result<Box<dyn ChatAgent>> {
+    let model = ModelRegistry::from_env().completion_model(&config.model)?;
+    if !model.is_streaming() {
+        bail!("The model `{}` does not support streaming", config.model);
+    }
+
+    let mut builder = AgentBuilder::new(model);
+    if let Some(preamble) = &config.preamble {
+        builder = builder.preamble(preamble);
+    }
+    if let Some(temperature) = config.temperature {
+        builder = builder.temperature(temperature);
+    }
+    if let Some(index) = attachments.index() {
+        builder = builder.dynamic_context(DYNAMIC_CONTEXT_SAMPLES, index);
+    }
+
+    Ok(Box::new(builder.build()))
+}
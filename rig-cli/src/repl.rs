@@ -0,0 +1,388 @@
+//! The REPL: reads prompts and slash-commands from stdin, streams the answers of the agent to
+//! stdout and calls the tools requested by the model, feeding their results back to it.
+
+use std::io::{self, Write};
+
+use futures::StreamExt;
+use rig::{
+    completion::Message,
+    message::{AssistantContent, ToolResultContent, UserContent},
+    streaming::StreamingChoice,
+    OneOrMany,
+};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::{
+    agent::{AgentConfig, ChatAgent},
+    attachments::Attachments,
+    commands::{self, Command, Input, Setting, HELP},
+    providers,
+};
+
+/// Maximum number of consecutive turns of tool calls answering a single prompt
+const MAX_TOOL_TURNS: usize = 10;
+
+/// Factory building the agent of the REPL from its configuration and attachments
+pub type AgentFactory =
+    Box<dyn Fn(&AgentConfig, &Attachments) -> anyhow::Result<Box<dyn ChatAgent>>>;
+
+/// Tool call made during the session, listed by `/tools`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToolCallRecord {
+    pub name: String,
+    pub args: serde_json::Value,
+    /// Output of the tool, or the error it returned
+    pub result: String,
+}
+
+pub struct Repl {
+    config: AgentConfig,
+    factory: AgentFactory,
+    agent: Box<dyn ChatAgent>,
+    attachments: Attachments,
+    history: Vec<Message>,
+    tool_calls: Vec<ToolCallRecord>,
+}
+
+impl Repl {
+    /// REPL over the agents of the built-in providers (see [providers::agent])
+    pub fn new(config: AgentConfig, attachments: Attachments) -> anyhow::Result<Self> {
+        Self::with_factory(config, attachments, providers::agent)
+    }
+
+    /// REPL over the agents built by `factory` (e.g.: agents with tools). The factory is called
+    /// again whenever the configuration changes or files are attached.
+    pub fn with_factory(
+        config: AgentConfig,
+        attachments: Attachments,
+        factory: impl Fn(&AgentConfig, &Attachments) -> anyhow::Result<Box<dyn ChatAgent>> + 'static,
+    ) -> anyhow::Result<Self> {
+        let agent = factory(&config, &attachments)?;
+
+        Ok(Self {
+            config,
+            factory: Box::new(factory),
+            agent,
+            attachments,
+            history: vec![],
+            tool_calls: vec![],
+        })
+    }
+
+    /// Read and handle lines from stdin until `/exit` or the end of the input
+    pub async fn run(&mut self) -> anyhow::Result<()> {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+        println!(
+            "Chatting with {}. Type /help for the list of commands.",
+            self.config.model
+        );
+        loop {
+            print!("> ");
+            io::stdout().flush()?;
+
+            let Some(line) = lines.next_line().await? else {
+                break;
+            };
+
+            let result = match commands::parse(&line) {
+                Ok(Input::Empty) => Ok(()),
+                Ok(Input::Prompt(prompt)) => self.prompt(&prompt).await,
+                Ok(Input::Command(Command::Exit)) => break,
+                Ok(Input::Command(command)) => self.command(command).await,
+                Err(error) => Err(error.into()),
+            };
+            if let Err(error) = result {
+                eprintln!("Error: {error:#}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stream the answer to `prompt`, calling the tools requested by the model until it answers
+    /// without tool calls
+    pub async fn prompt(&mut self, prompt: &str) -> anyhow::Result<()> {
+        let mut prompt = Message::user(prompt);
+
+        for _ in 0..MAX_TOOL_TURNS {
+            let mut stream = self
+                .agent
+                .stream(prompt.clone(), self.history.clone())
+                .await?;
+
+            let mut text = String::new();
+            let mut content = vec![];
+            while let Some(choice) = stream.next().await {
+                match choice? {
+                    StreamingChoice::Message(delta) => {
+                        print!("{delta}");
+                        io::stdout().flush()?;
+                        text.push_str(&delta);
+                    }
+                    StreamingChoice::ToolCall(name, id, args) => {
+                        content.push(AssistantContent::tool_call(id, name, args));
+                    }
+                    _ => {}
+                }
+            }
+            if !text.is_empty() {
+                println!();
+                content.insert(0, AssistantContent::text(text));
+            }
+
+            self.history.push(prompt);
+            let Ok(content) = OneOrMany::many(content) else {
+                return Ok(());
+            };
+            self.history.push(Message::Assistant {
+                content: content.clone(),
+            });
+
+            let mut results = vec![];
+            for content in content {
+                let AssistantContent::ToolCall(tool_call) = content else {
+                    continue;
+                };
+                let name = tool_call.function.name;
+                let args = tool_call.function.arguments;
+
+                println!("[tool call] {name}({args})");
+                let result = self
+                    .agent
+                    .call_tool(&name, args.to_string())
+                    .await
+                    .unwrap_or_else(|error| format!("Error: {error}"));
+                println!("[tool result] {result}");
+
+                results.push(UserContent::tool_result(
+                    tool_call.id,
+                    OneOrMany::one(ToolResultContent::text(result.clone())),
+                ));
+                self.tool_calls.push(ToolCallRecord { name, args, result });
+            }
+
+            let Ok(results) = OneOrMany::many(results) else {
+                return Ok(());
+            };
+            prompt = Message::User { content: results };
+        }
+
+        anyhow::bail!("No answer after {MAX_TOOL_TURNS} turns of tool calls")
+    }
+
+    /// Handle a slash-command
+    pub async fn command(&mut self, command: Command) -> anyhow::Result<()> {
+        match command {
+            Command::Model(None) => println!("{}", self.config.model),
+            Command::Model(Some(model)) => {
+                self.reconfigure(AgentConfig {
+                    model,
+                    ..self.config.clone()
+                })?;
+                println!("Switched to {}", self.config.model);
+            }
+            Command::Preamble(Setting::Show) => println!(
+                "{}",
+                self.config.preamble.as_deref().unwrap_or("(no preamble)")
+            ),
+            Command::Preamble(setting) => self.reconfigure(AgentConfig {
+                preamble: match setting {
+                    Setting::Set(preamble) => Some(preamble),
+                    _ => None,
+                },
+                ..self.config.clone()
+            })?,
+            Command::Temperature(Setting::Show) => match self.config.temperature {
+                Some(temperature) => println!("{temperature}"),
+                None => println!("(default temperature of the model)"),
+            },
+            Command::Temperature(setting) => self.reconfigure(AgentConfig {
+                temperature: match setting {
+                    Setting::Set(temperature) => Some(temperature),
+                    _ => None,
+                },
+                ..self.config.clone()
+            })?,
+            Command::Attach(pattern) => {
+                let chunks = self.attachments.attach(&pattern).await?;
+                self.agent = (self.factory)(&self.config, &self.attachments)?;
+                println!(
+                    "Attached {chunks} chunks ({} files attached)",
+                    self.attachments.files().len()
+                );
+            }
+            Command::Attachments => {
+                if self.attachments.is_empty() {
+                    println!("No attached files");
+                }
+                for file in self.attachments.files() {
+                    println!("{}", file.display());
+                }
+            }
+            Command::Tools => {
+                if self.tool_calls.is_empty() {
+                    println!("No tool calls");
+                }
+                for ToolCallRecord { name, args, result } in &self.tool_calls {
+                    println!("{name}({args}) -> {result}");
+                }
+            }
+            Command::History => self.history.iter().for_each(print_message),
+            Command::Clear => {
+                self.history.clear();
+                println!("Chat history cleared");
+            }
+            Command::Help => println!("{HELP}"),
+            Command::Exit => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn config(&self) -> &AgentConfig {
+        &self.config
+    }
+
+    pub fn history(&self) -> &[Message] {
+        &self.history
+    }
+
+    pub fn tool_calls(&self) -> &[ToolCallRecord] {
+        &self.tool_calls
+    }
+
+    /// Switch to the agent built from `config`, keeping the current agent if it can not be built
+    fn reconfigure(&mut self, config: AgentConfig) -> anyhow::Result<()> {
+        self.agent = (self.factory)(&config, &self.attachments)?;
+        self.config = config;
+        Ok(())
+    }
+}
+
+fn print_message(message: &Message) {
+    match message {
+        Message::System { content } => println!("[system] {content}"),
+        Message::User { content } => content.iter().for_each(|content| match content {
+            UserContent::Text(text) => println!("[user] {}", text.text),
+            UserContent::ToolResult(tool_result) => println!("[tool result] {}", tool_result.id),
+            UserContent::Image(_) => println!("[user] (image)"),
+            UserContent::Audio(_) => println!("[user] (audio)"),
+            UserContent::Document(_) => println!("[user] (document)"),
+        }),
+        Message::Assistant { content } => content.iter().for_each(|content| match content {
+            AssistantContent::Text(text) => println!("[assistant] {}", text.text),
+            AssistantContent::ToolCall(tool_call) => println!(
+                "[tool call] {}({})",
+                tool_call.function.name, tool_call.function.arguments
+            ),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rig::{
+        agent::AgentBuilder,
+        completion::ToolDefinition,
+        embeddings::BoxEmbeddingModel,
+        providers::mock::{MockCompletionModel, MockEmbeddingModel},
+        tool::Tool,
+    };
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct AddArgs {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("Math error")]
+    struct MathError;
+
+    struct Adder;
+
+    impl Tool for Adder {
+        const NAME: &'static str = "add";
+
+        type Error = MathError;
+        type Args = AddArgs;
+        type Output = i32;
+
+        async fn definition(&self, _prompt: String) -> ToolDefinition {
+            ToolDefinition {
+                name: "add".to_string(),
+                description: "Add x and y together".to_string(),
+                parameters: json!({}),
+            }
+        }
+
+        async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+            Ok(args.x + args.y)
+        }
+    }
+
+    fn repl(model: MockCompletionModel) -> Repl {
+        Repl::with_factory(
+            AgentConfig::new("mock:model"),
+            Attachments::new(BoxEmbeddingModel::new(MockEmbeddingModel::new(8))),
+            move |config, _| {
+                if config.model != "mock:model" {
+                    anyhow::bail!("Unknown model {}", config.model);
+                }
+                Ok(Box::new(
+                    AgentBuilder::new(model.clone()).tool(Adder).build(),
+                ))
+            },
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_prompt_with_tool_calls() {
+        let model = MockCompletionModel::new()
+            .tool_call("add", json!({"x": 1, "y": 2}))
+            .text("1 + 2 = 3");
+        let mut repl = repl(model.clone());
+
+        repl.prompt("What is 1 + 2?").await.unwrap();
+
+        assert_eq!(
+            repl.tool_calls(),
+            vec![ToolCallRecord {
+                name: "add".to_string(),
+                args: json!({"x": 1, "y": 2}),
+                result: "3".to_string(),
+            }]
+        );
+        assert_eq!(repl.history().len(), 4);
+        assert_eq!(
+            repl.history()[3],
+            Message::assistant("1 + 2 = 3".to_string())
+        );
+        assert!(matches!(
+            &model.requests()[1].prompt,
+            Message::User { content } if matches!(content.first(), UserContent::ToolResult(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_failed_model_switch() {
+        let mut repl = repl(MockCompletionModel::new());
+
+        assert!(repl
+            .command(Command::Model(Some("other:model".to_string())))
+            .await
+            .is_err());
+        assert_eq!(repl.config().model, "mock:model");
+
+        repl.command(Command::Temperature(Setting::Set(0.5)))
+            .await
+            .unwrap();
+        assert_eq!(repl.config().temperature, Some(0.5));
+    }
+}
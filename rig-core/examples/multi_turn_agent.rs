@@ -128,6 +128,9 @@ async fn main() -> anyhow::Result<()> {
 
     println!("\n\nOpenAI Calculator Agent: {}", result);
 
+    // Token usage accumulated over all the turns of both prompts
+    println!("\n\nTotal usage: {}", agent.agent.total_usage());
+
     Ok(())
 }
 
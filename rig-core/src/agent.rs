@@ -108,14 +108,17 @@
 //! ```
 use std::collections::HashMap;
 
-use futures::{stream, StreamExt, TryStreamExt};
+use futures::{future::try_join_all, stream, StreamExt, TryStreamExt};
+use tracing::Instrument;
 
 use crate::{
+    citations::{Source, SourcedResponse, CITATION_INSTRUCTIONS},
     completion::{
-        Chat, Completion, CompletionError, CompletionModel, CompletionRequestBuilder, Document,
-        Message, Prompt, PromptError,
+        Chat, Completion, CompletionError, CompletionModel, CompletionRequestBuilder,
+        CompletionResponse, Document, Message, Prompt, PromptError, Usage, UsageTracker,
     },
     message::AssistantContent,
+    retrieval::{ContextPacker, QueryRewriter},
     streaming::{
         StreamingChat, StreamingCompletion, StreamingCompletionModel, StreamingPrompt,
         StreamingResult,
@@ -165,6 +168,26 @@ pub struct Agent<M: CompletionModel> {
     dynamic_tools: Vec<(usize, Box<dyn VectorStoreIndexDyn>)>,
     /// Actual tool implementations
     pub tools: ToolSet,
+    /// Accumulated token usage of the agent's completion requests
+    usage_tracker: UsageTracker,
+    /// Rewriter of the prompts before retrieving the dynamic context
+    query_rewriter: Option<Box<dyn QueryRewriter>>,
+    /// Packer fitting the documents and chat history into the context window of the model
+    context_packer: Option<ContextPacker>,
+}
+
+impl<M: CompletionModel> Agent<M> {
+    /// Total token usage of all the completion requests made by the agent (including requests
+    /// built with [Completion::completion] and sent by the caller, e.g.: in multi-turn tool loops).
+    /// If the agent shares its [UsageTracker] with other agents, the total includes their usage.
+    pub fn total_usage(&self) -> Usage {
+        self.usage_tracker.total_usage()
+    }
+
+    /// The usage tracker the agent's token usage is accumulated in
+    pub fn usage_tracker(&self) -> &UsageTracker {
+        &self.usage_tracker
+    }
 }
 
 impl<M: CompletionModel> Completion<M> for Agent<M> {
@@ -173,8 +196,63 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
         prompt: impl Into<Message> + Send,
         chat_history: Vec<Message>,
     ) -> Result<CompletionRequestBuilder<M>, CompletionError> {
+        let (completion_request, _) = self.completion_with_sources(prompt, chat_history).await?;
+        Ok(completion_request)
+    }
+}
+
+impl<M: CompletionModel> Agent<M> {
+    /// Send a prompt to the agent, returning its answer along with the documents retrieved from
+    /// its dynamic context to answer it (see [crate::citations])
+    pub async fn prompt_with_sources(
+        &self,
+        prompt: impl Into<Message> + Send,
+    ) -> Result<SourcedResponse, PromptError> {
+        self.chat_with_sources(prompt, vec![]).await
+    }
+
+    /// Send a prompt with a chat history to the agent, returning its answer along with the
+    /// documents retrieved from its dynamic context to answer it (see [crate::citations])
+    pub async fn chat_with_sources(
+        &self,
+        prompt: impl Into<Message> + Send,
+        chat_history: Vec<Message>,
+    ) -> Result<SourcedResponse, PromptError> {
+        let span = self.chat_span(&chat_history);
+
+        async move {
+            let (completion_request, sources) =
+                self.completion_with_sources(prompt, chat_history).await?;
+            let text = self.answer(completion_request.send().await?).await?;
+
+            Ok(SourcedResponse::new(text, sources))
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Span of a chat with the agent (see [crate::telemetry])
+    fn chat_span(&self, chat_history: &[Message]) -> tracing::Span {
+        tracing::info_span!(target: "rig", "agent_chat",
+            provider = crate::telemetry::provider_name::<M>(),
+            history_len = chat_history.len(),
+        )
+    }
+
+    /// Build a completion request like [Completion::completion], also returning the documents
+    /// retrieved from the dynamic context and added to the request
+    async fn completion_with_sources(
+        &self,
+        prompt: impl Into<Message> + Send,
+        chat_history: Vec<Message>,
+    ) -> Result<(CompletionRequestBuilder<M>, Vec<Source>), CompletionError> {
         let prompt = prompt.into();
         let rag_text = prompt.rag_text().clone();
+        let queries = match &rag_text {
+            Some(text) => self.retrieval_queries(text, &chat_history).await,
+            None => vec![],
+        };
+        let queries = &queries;
 
         let completion_request = self
             .model
@@ -184,30 +262,18 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
             .temperature_opt(self.temperature)
             .max_tokens_opt(self.max_tokens)
             .additional_params_opt(self.additional_params.clone())
-            .documents(self.static_context.clone());
+            .documents(self.static_context.clone())
+            .usage_tracker(self.usage_tracker.clone());
 
-        let agent = match &rag_text {
+        let (completion_request, mut sources) = match &rag_text {
             Some(text) => {
-                let dynamic_context = stream::iter(self.dynamic_context.iter())
-                    .then(|(num_sample, index)| async {
-                        Ok::<_, VectorStoreError>(
-                            index
-                                .top_n(text, *num_sample)
-                                .await?
-                                .into_iter()
-                                .map(|(_, id, doc)| {
-                                    // Pretty print the document if possible for better readability
-                                    let text = serde_json::to_string_pretty(&doc)
-                                        .unwrap_or_else(|_| doc.to_string());
-
-                                    Document {
-                                        id,
-                                        text,
-                                        additional_props: HashMap::new(),
-                                    }
-                                })
-                                .collect::<Vec<_>>(),
+                let mut sources = stream::iter(self.dynamic_context.iter())
+                    .then(|(num_sample, index)| async move {
+                        let results = try_join_all(
+                            queries.iter().map(|query| index.top_n(query, *num_sample)),
                         )
+                        .await?;
+                        Ok::<_, VectorStoreError>(merge_results(results, *num_sample))
                     })
                     .try_fold(vec![], |mut acc, docs| async {
                         acc.extend(docs);
@@ -216,6 +282,10 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
                     .await
                     .map_err(|e| CompletionError::RequestError(Box::new(e)))?;
 
+                if self.context_packer.is_some() {
+                    sources.sort_by(|a, b| b.score.total_cmp(&a.score));
+                }
+
                 let dynamic_tools = stream::iter(self.dynamic_tools.iter())
                     .then(|(num_sample, index)| async {
                         Ok::<_, VectorStoreError>(
@@ -255,9 +325,27 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
                     .collect::<Vec<_>>()
                     .await;
 
-                completion_request
-                    .documents(dynamic_context)
-                    .tools([static_tools.clone(), dynamic_tools].concat())
+                let dynamic_context = sources
+                    .iter()
+                    .map(|source| {
+                        // Pretty print the document if possible for better readability
+                        let text = serde_json::to_string_pretty(&source.document)
+                            .unwrap_or_else(|_| source.document.to_string());
+
+                        Document {
+                            id: source.id.clone(),
+                            text,
+                            additional_props: HashMap::new(),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                (
+                    completion_request
+                        .documents(dynamic_context)
+                        .tools([static_tools.clone(), dynamic_tools].concat()),
+                    sources,
+                )
             }
             None => {
                 let static_tools = stream::iter(self.static_tools.iter())
@@ -276,12 +364,106 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
                     .collect::<Vec<_>>()
                     .await;
 
-                completion_request.tools(static_tools)
+                (completion_request.tools(static_tools), vec![])
             }
         };
 
-        Ok(agent)
+        let Some(context_packer) = &self.context_packer else {
+            return Ok((completion_request, sources));
+        };
+
+        let completion_request = completion_request
+            .pack_context(context_packer)
+            .map_err(|e| CompletionError::RequestError(Box::new(e)))?;
+        sources.retain(|source| {
+            completion_request
+                .packed_documents()
+                .iter()
+                .any(|document| document.id == source.id)
+        });
+
+        Ok((completion_request, sources))
     }
+
+    /// Queries to retrieve the dynamic context of `prompt` with: the prompt itself, or the
+    /// queries of the query rewriter (if any)
+    async fn retrieval_queries(&self, prompt: &str, chat_history: &[Message]) -> Vec<String> {
+        let Some(query_rewriter) = self
+            .query_rewriter
+            .as_ref()
+            .filter(|_| !self.dynamic_context.is_empty())
+        else {
+            return vec![prompt.to_string()];
+        };
+
+        match query_rewriter
+            .rewrite(prompt, chat_history, Some(&self.usage_tracker))
+            .await
+        {
+            Ok(queries) if !queries.is_empty() => queries,
+            Ok(_) => vec![prompt.to_string()],
+            Err(error) => {
+                tracing::warn!(target: "rig", "Failed to rewrite the query, using the prompt: {error}");
+                vec![prompt.to_string()]
+            }
+        }
+    }
+
+    /// Text of the answer of the model, or result of the tool it called
+    async fn answer(
+        &self,
+        response: CompletionResponse<M::Response>,
+    ) -> Result<String, PromptError> {
+        // TODO: consider returning a `Message` instead of `String` for parallel responses / tool calls
+        match response.choice.first() {
+            AssistantContent::Text(text) => Ok(text.text.clone()),
+            AssistantContent::ToolCall(tool_call) => Ok(self
+                .tools
+                .call(
+                    &tool_call.function.name,
+                    tool_call.function.arguments.to_string(),
+                )
+                .await?),
+        }
+    }
+}
+
+/// Merge the documents retrieved with each query into at most `num_sample` sources, taking the
+/// best ranked documents of each query in turn (scores of different indexes or queries are not
+/// always comparable) and skipping the documents already taken
+fn merge_results(
+    results: Vec<Vec<(f64, String, serde_json::Value)>>,
+    num_sample: usize,
+) -> Vec<Source> {
+    let mut results = results
+        .into_iter()
+        .map(|documents| documents.into_iter())
+        .collect::<Vec<_>>();
+    let mut sources: Vec<Source> = vec![];
+
+    while sources.len() < num_sample {
+        let mut exhausted = true;
+        for documents in &mut results {
+            let Some((score, id, document)) = documents.next() else {
+                continue;
+            };
+            exhausted = false;
+
+            if sources.len() < num_sample && sources.iter().all(|source| source.id != id) {
+                sources.push(Source {
+                    id,
+                    score,
+                    document,
+                    cited: false,
+                });
+            }
+        }
+        if exhausted {
+            break;
+        }
+    }
+
+    sources
 }
 
 impl<M: CompletionModel> Prompt for Agent<M> {
@@ -302,19 +484,14 @@ impl<M: CompletionModel> Chat for Agent<M> {
         prompt: impl Into<Message> + Send,
         chat_history: Vec<Message>,
     ) -> Result<String, PromptError> {
-        let resp = self.completion(prompt, chat_history).await?.send().await?;
+        let span = self.chat_span(&chat_history);
 
-        // TODO: consider returning a `Message` instead of `String` for parallel responses / tool calls
-        match resp.choice.first() {
-            AssistantContent::Text(text) => Ok(text.text.clone()),
-            AssistantContent::ToolCall(tool_call) => Ok(self
-                .tools
-                .call(
-                    &tool_call.function.name,
-                    tool_call.function.arguments.to_string(),
-                )
-                .await?),
+        async move {
+            let resp = self.completion(prompt, chat_history).await?.send().await?;
+            self.answer(resp).await
         }
+        .instrument(span)
+        .await
     }
 }
 
@@ -360,6 +537,14 @@ pub struct AgentBuilder<M: CompletionModel> {
     temperature: Option<f64>,
     /// Actual tool implementations
     tools: ToolSet,
+    /// Tracker the token usage of the agent is accumulated in
+    usage_tracker: Option<UsageTracker>,
+    /// Whether to ask the model to cite the documents of the dynamic context
+    citations: bool,
+    /// Rewriter of the prompts before retrieving the dynamic context
+    query_rewriter: Option<Box<dyn QueryRewriter>>,
+    /// Packer fitting the documents and chat history into the context window of the model
+    context_packer: Option<ContextPacker>,
 }
 
 impl<M: CompletionModel> AgentBuilder<M> {
@@ -375,6 +560,10 @@ impl<M: CompletionModel> AgentBuilder<M> {
             dynamic_context: vec![],
             dynamic_tools: vec![],
             tools: ToolSet::default(),
+            usage_tracker: None,
+            citations: false,
+            query_rewriter: None,
+            context_packer: None,
         }
     }
 
@@ -455,11 +644,49 @@ impl<M: CompletionModel> AgentBuilder<M> {
         self
     }
 
+    /// Set the tracker the token usage of the agent is accumulated in. Share the same tracker
+    /// between several agents to meter a whole run (e.g.: a pipeline).
+    /// If not set, the agent accumulates its usage in its own tracker.
+    pub fn usage_tracker(mut self, usage_tracker: UsageTracker) -> Self {
+        self.usage_tracker = Some(usage_tracker);
+        self
+    }
+
+    /// Rewrite the prompts with `query_rewriter` before retrieving the dynamic context (e.g.: to
+    /// resolve the references of follow-up questions to the conversation)
+    pub fn query_rewriter(mut self, query_rewriter: impl QueryRewriter + 'static) -> Self {
+        self.query_rewriter = Some(Box::new(query_rewriter));
+        self
+    }
+
+    /// Fit the documents and chat history of the requests into the context window of the model
+    /// with `context_packer`: the retrieved documents are packed by decreasing score (across all
+    /// the indexes of the dynamic context), and the documents which do not fit are dropped (and
+    /// not reported as sources).
+    pub fn context_packer(mut self, context_packer: ContextPacker) -> Self {
+        self.context_packer = Some(context_packer);
+        self
+    }
+
+    /// Ask the model to cite the documents of the dynamic context it uses with inline markers
+    /// (e.g.: `[doc0]`), which are reported by [Agent::prompt_with_sources] and
+    /// [Agent::chat_with_sources]
+    pub fn citations(mut self) -> Self {
+        self.citations = true;
+        self
+    }
+
     /// Build the agent
     pub fn build(self) -> Agent<M> {
+        let preamble = match (self.preamble, self.citations) {
+            (Some(preamble), true) => format!("{preamble}\n\n{CITATION_INSTRUCTIONS}"),
+            (None, true) => CITATION_INSTRUCTIONS.to_string(),
+            (preamble, false) => preamble.unwrap_or_default(),
+        };
+
         Agent {
             model: self.model,
-            preamble: self.preamble.unwrap_or_default(),
+            preamble,
             static_context: self.static_context,
             static_tools: self.static_tools,
             temperature: self.temperature,
@@ -468,6 +695,9 @@ impl<M: CompletionModel> AgentBuilder<M> {
             dynamic_context: self.dynamic_context,
             dynamic_tools: self.dynamic_tools,
             tools: self.tools,
+            usage_tracker: self.usage_tracker.unwrap_or_default(),
+            query_rewriter: self.query_rewriter,
+            context_packer: self.context_packer,
         }
     }
 }
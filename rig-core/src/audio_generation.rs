@@ -1,11 +1,45 @@
+//! This module provides functionality for working with audio generation (text-to-speech)
+//! models: the provider-agnostic [AudioGenerationModel] trait, its requests and responses, and
+//! [BoxAudioGenerationModel], which erases the type of a model so that the provider can be
+//! selected at runtime (see [TranscriptionModel](crate::transcription::TranscriptionModel) for
+//! speech-to-text). These items are also exported under their speech names (e.g.:
+//! [SpeechModel](crate::speech::SpeechModel)) by the [speech](crate::speech) module.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     audio_generation::{AudioGenerationModel, BoxAudioGenerationModel},
+//!     providers::openai,
+//! };
+//!
+//! let model: BoxAudioGenerationModel = openai::Client::from_env()
+//!     .audio_generation_model(openai::TTS_1)
+//!     .boxed();
+//!
+//! let response = model
+//!     .audio_generation_request()
+//!     .text("Hello, world!")
+//!     .voice("alloy")
+//!     .send()
+//!     .await?;
+//! std::fs::write("hello.mp3", response.audio)?;
+//! ```
+
+use std::sync::Arc;
+
 use serde_json::Value;
 use thiserror::Error;
 
+use crate::{
+    completion::BoxRawResponse,
+    wasm_compat::{WasmBoxedFuture, WasmCompatSend},
+};
+
 #[derive(Debug, Error)]
 pub enum AudioGenerationError {
     /// Http error (e.g.: connection error, timeout, etc.)
     #[error("HttpError: {0}")]
-    HttpError(#[from] reqwest::Error),
+    HttpError(#[from] crate::http_client::HttpError),
 
     /// Json error (e.g.: serialization, deserialization)
     #[error("JsonError: {0}")]
@@ -23,6 +57,12 @@ pub enum AudioGenerationError {
     #[error("ProviderError: {0}")]
     ProviderError(String),
 }
+
+impl From<reqwest::Error> for AudioGenerationError {
+    fn from(error: reqwest::Error) -> Self {
+        AudioGenerationError::HttpError(error.into())
+    }
+}
 pub trait AudioGeneration<M: AudioGenerationModel> {
     /// Generates an audio generation request builder for the given `text` and `voice`.
     /// This function is meant to be called by the user to further customize the
@@ -38,7 +78,7 @@ pub trait AudioGeneration<M: AudioGenerationModel> {
         voice: &str,
     ) -> impl std::future::Future<
         Output = Result<AudioGenerationRequestBuilder<M>, AudioGenerationError>,
-    > + Send;
+    > + WasmCompatSend;
 }
 
 pub struct AudioGenerationResponse<T> {
@@ -54,11 +94,21 @@ pub trait AudioGenerationModel: Clone + Send + Sync {
         request: AudioGenerationRequest,
     ) -> impl std::future::Future<
         Output = Result<AudioGenerationResponse<Self::Response>, AudioGenerationError>,
-    > + Send;
+    > + WasmCompatSend;
 
     fn audio_generation_request(&self) -> AudioGenerationRequestBuilder<Self> {
         AudioGenerationRequestBuilder::new(self.clone())
     }
+
+    /// Erases the type of the model, e.g.: to select the provider at runtime (see
+    /// [BoxAudioGenerationModel]).
+    fn boxed(self) -> BoxAudioGenerationModel
+    where
+        Self: 'static,
+        Self::Response: 'static,
+    {
+        BoxAudioGenerationModel::new(self)
+    }
 }
 
 pub struct AudioGenerationRequest {
@@ -126,3 +176,112 @@ impl<M: AudioGenerationModel> AudioGenerationRequestBuilder<M> {
         model.audio_generation(self.build()).await
     }
 }
+
+/// Object safe version of [AudioGenerationModel], implemented by all audio generation models.
+///
+/// The methods are prefixed with `dyn_` so that they do not conflict with the methods of
+/// [AudioGenerationModel] when both traits are in scope.
+pub trait DynAudioGenerationModel: Send + Sync {
+    /// Generates audio for the given audio generation request
+    /// (see [AudioGenerationModel::audio_generation]).
+    fn dyn_audio_generation(
+        &self,
+        request: AudioGenerationRequest,
+    ) -> WasmBoxedFuture<'_, Result<AudioGenerationResponse<BoxRawResponse>, AudioGenerationError>>;
+}
+
+impl<M> DynAudioGenerationModel for M
+where
+    M: AudioGenerationModel + 'static,
+    M::Response: 'static,
+{
+    fn dyn_audio_generation(
+        &self,
+        request: AudioGenerationRequest,
+    ) -> WasmBoxedFuture<'_, Result<AudioGenerationResponse<BoxRawResponse>, AudioGenerationError>>
+    {
+        Box::pin(async move {
+            let response = self.audio_generation(request).await?;
+
+            Ok(AudioGenerationResponse {
+                audio: response.audio,
+                response: Box::new(response.response) as BoxRawResponse,
+            })
+        })
+    }
+}
+
+/// Type-erased audio generation model. Cheap to clone.
+#[derive(Clone)]
+pub struct BoxAudioGenerationModel(Arc<dyn DynAudioGenerationModel>);
+
+impl BoxAudioGenerationModel {
+    pub fn new<M>(model: M) -> Self
+    where
+        M: AudioGenerationModel + 'static,
+        M::Response: 'static,
+    {
+        Self(Arc::new(model))
+    }
+}
+
+impl AudioGenerationModel for BoxAudioGenerationModel {
+    /// The raw response of the wrapped model, which can be downcast to its `Response` type
+    type Response = BoxRawResponse;
+
+    async fn audio_generation(
+        &self,
+        request: AudioGenerationRequest,
+    ) -> Result<AudioGenerationResponse<BoxRawResponse>, AudioGenerationError> {
+        self.0.dyn_audio_generation(request).await
+    }
+
+    fn boxed(self) -> BoxAudioGenerationModel {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Model "speaking" the text as UTF-8 bytes, returning the voice as raw response
+    #[derive(Clone)]
+    struct Utf8Model;
+
+    impl AudioGenerationModel for Utf8Model {
+        type Response = String;
+
+        async fn audio_generation(
+            &self,
+            request: AudioGenerationRequest,
+        ) -> Result<AudioGenerationResponse<String>, AudioGenerationError> {
+            Ok(AudioGenerationResponse {
+                audio: request.text.into_bytes(),
+                response: request.voice,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_box_audio_generation_model() {
+        let model = Utf8Model.boxed();
+
+        let response = model
+            .audio_generation_request()
+            .text("Hello")
+            .voice("alloy")
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.audio, b"Hello");
+        assert_eq!(
+            response
+                .response
+                .downcast_ref::<String>()
+                .map(String::as_str),
+            Some("alloy")
+        );
+    }
+}
@@ -0,0 +1,475 @@
+//! This module provides the [BatchCompletionModel] trait, a provider-agnostic interface to run
+//! large offline workloads of completion requests (e.g.: labeling, evaluation, summarization).
+//!
+//! A batch of [BatchRequest]s is submitted at once ([BatchCompletionModel::submit_batch]), polled
+//! until it ends ([BatchCompletionModel::batch_status]), and its results are then fetched
+//! ([BatchCompletionModel::batch_results]). [BatchCompletionModel::run_batch] does all three.
+//!
+//! The completion models of providers with a native batch API (OpenAI and Anthropic) implement
+//! [BatchCompletionModel]: their batches are processed asynchronously by the provider (within
+//! 24 hours, usually at a discount). Any other completion model can run batches wrapped in a
+//! [ConcurrentBatchModel], which sends the requests of a batch with bounded concurrency.
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//! use rig::{
+//!     batch::{BatchCompletionModel, BatchRequest, ConcurrentBatchModel},
+//!     completion::CompletionModel,
+//!     providers::{ollama, openai},
+//! };
+//!
+//! let model = openai::Client::from_env().completion_model(openai::GPT_4O_MINI);
+//!
+//! let requests = reviews
+//!     .iter()
+//!     .enumerate()
+//!     .map(|(i, review)| {
+//!         BatchRequest::new(
+//!             format!("review-{i}"),
+//!             model
+//!                 .completion_request(review.as_str())
+//!                 .preamble("Label the sentiment of the review.".to_string())
+//!                 .build(),
+//!         )
+//!     })
+//!     .collect::<Vec<_>>();
+//!
+//! // Native batch API
+//! let results = model
+//!     .run_batch(requests.clone(), Duration::from_secs(60))
+//!     .await?;
+//!
+//! // Emulated with at most 4 requests in flight
+//! let results = ConcurrentBatchModel::new(ollama::Client::new().completion_model("llama3.2"), 4)
+//!     .run_batch(requests, Duration::from_secs(1))
+//!     .await?;
+//!
+//! for result in results {
+//!     match result.response {
+//!         Ok(response) => println!("{}: {:?}", result.custom_id, response.choice),
+//!         Err(error) => println!("{}: {error}", result.custom_id),
+//!     }
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::pin::Pin;
+
+use futures::{Future, StreamExt};
+#[cfg(not(target_arch = "wasm32"))]
+use futures::{FutureExt, Stream};
+
+use crate::{
+    completion::{
+        CompletionError, CompletionModel, CompletionRequest, CompletionResponse, PreparedRequest,
+    },
+    wasm_compat::WasmCompatSend,
+};
+
+/// Request of a batch
+#[derive(Clone, Debug)]
+pub struct BatchRequest {
+    /// Identifier of the request, unique within the batch, used to match the results with the
+    /// requests
+    pub custom_id: String,
+    pub request: CompletionRequest,
+}
+
+impl BatchRequest {
+    pub fn new(custom_id: impl Into<String>, request: CompletionRequest) -> Self {
+        Self {
+            custom_id: custom_id.into(),
+            request,
+        }
+    }
+}
+
+/// Processing state of a batch
+#[derive(Clone, Debug, PartialEq)]
+pub enum BatchState {
+    /// The requests of the batch are being processed
+    InProgress,
+    /// The batch was processed (or expired, or was cancelled): its results can be fetched, the
+    /// requests which were not processed having an error as result
+    Ended,
+    /// The batch failed as a whole (e.g.: it was rejected as invalid) and has no results
+    Failed(String),
+}
+
+/// Status of a batch
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchStatus {
+    pub state: BatchState,
+    /// Number of requests in the batch
+    pub total: usize,
+    /// Number of requests processed successfully so far
+    pub succeeded: usize,
+    /// Number of requests which failed so far (including expired and cancelled requests)
+    pub failed: usize,
+}
+
+impl BatchStatus {
+    /// Whether the batch is no longer in progress
+    pub fn is_done(&self) -> bool {
+        self.state != BatchState::InProgress
+    }
+}
+
+/// Result of a request of a batch
+#[derive(Debug)]
+pub struct BatchResult<T> {
+    /// Identifier of the request (see [BatchRequest::custom_id])
+    pub custom_id: String,
+    pub response: Result<CompletionResponse<T>, CompletionError>,
+}
+
+/// Trait defining a completion model able to process batches of requests (see the
+/// [module documentation](self))
+pub trait BatchCompletionModel: CompletionModel {
+    /// Submit a batch of requests, returning the identifier of the batch
+    fn submit_batch(
+        &self,
+        requests: Vec<BatchRequest>,
+    ) -> impl Future<Output = Result<String, CompletionError>> + WasmCompatSend;
+
+    /// Get the status of the batch `batch_id`
+    fn batch_status(
+        &self,
+        batch_id: &str,
+    ) -> impl Future<Output = Result<BatchStatus, CompletionError>> + WasmCompatSend;
+
+    /// Fetch the results of the ended batch `batch_id`, in no particular order
+    fn batch_results(
+        &self,
+        batch_id: &str,
+    ) -> impl Future<Output = Result<Vec<BatchResult<Self::Response>>, CompletionError>> + WasmCompatSend;
+
+    /// Submit a batch of requests, poll its status every `poll_interval` until it ends and fetch
+    /// its results, returned in the order of the requests
+    fn run_batch(
+        &self,
+        requests: Vec<BatchRequest>,
+        poll_interval: Duration,
+    ) -> impl Future<Output = Result<Vec<BatchResult<Self::Response>>, CompletionError>> + WasmCompatSend
+    {
+        async move {
+            let order = requests
+                .iter()
+                .enumerate()
+                .map(|(i, request)| (request.custom_id.clone(), i))
+                .collect::<HashMap<_, _>>();
+
+            let batch_id = self.submit_batch(requests).await?;
+            tracing::info!(target: "rig", "Submitted batch {batch_id} of {} requests", order.len());
+
+            loop {
+                let status = self.batch_status(&batch_id).await?;
+                match status.state {
+                    BatchState::InProgress => {
+                        tracing::debug!(target: "rig",
+                            "Batch {batch_id} in progress: {} succeeded, {} failed out of {}",
+                            status.succeeded,
+                            status.failed,
+                            status.total
+                        );
+                        futures_timer::Delay::new(poll_interval).await;
+                    }
+                    BatchState::Ended => break,
+                    BatchState::Failed(error) => {
+                        return Err(CompletionError::ProviderError(format!(
+                            "Batch {batch_id} failed: {error}"
+                        )))
+                    }
+                }
+            }
+
+            let mut results = self.batch_results(&batch_id).await?;
+            // The results of unknown requests (if any) come last
+            results
+                .sort_by_key(|result| order.get(&result.custom_id).copied().unwrap_or(usize::MAX));
+            Ok(results)
+        }
+    }
+}
+
+/// Results of the requests of a batch of a [ConcurrentBatchModel] which were not processed yet
+#[cfg(not(target_arch = "wasm32"))]
+type PendingResults<T> = Pin<Box<dyn Stream<Item = BatchResult<T>> + Send>>;
+
+/// Batch of a [ConcurrentBatchModel]: the results of its requests, as they are processed
+struct ConcurrentBatch<T> {
+    total: usize,
+    results: Vec<BatchResult<T>>,
+    /// Requests in flight or not sent yet, which make progress whenever the batch is polled
+    #[cfg(not(target_arch = "wasm32"))]
+    pending: Option<PendingResults<T>>,
+}
+
+impl<T> ConcurrentBatch<T> {
+    /// Make progress on the pending requests and collect the results of the processed ones,
+    /// without waiting
+    fn poll(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        while let Some(pending) = &mut self.pending {
+            match pending.next().now_or_never() {
+                Some(Some(result)) => self.results.push(result),
+                Some(None) => self.pending = None,
+                None => break,
+            }
+        }
+    }
+
+    fn status(&self) -> BatchStatus {
+        let succeeded = self
+            .results
+            .iter()
+            .filter(|result| result.response.is_ok())
+            .count();
+
+        BatchStatus {
+            state: if self.results.len() < self.total {
+                BatchState::InProgress
+            } else {
+                BatchState::Ended
+            },
+            total: self.total,
+            succeeded,
+            failed: self.results.len() - succeeded,
+        }
+    }
+}
+
+/// Batches of a [ConcurrentBatchModel], until their results are fetched
+type ConcurrentBatches<T> = Arc<Mutex<HashMap<String, ConcurrentBatch<T>>>>;
+
+/// Completion model emulating a batch API for a model without one: the requests of a batch are
+/// sent at most `concurrency` at a time, and the status of the batch is polled as with a native
+/// batch API.
+///
+/// No task is spawned: the requests of a batch make progress whenever it is polled (with
+/// [BatchCompletionModel::batch_status] or [BatchCompletionModel::batch_results]), which
+/// [BatchCompletionModel::run_batch] does every `poll_interval`. On `wasm32`, the requests are
+/// sent in the background on the event loop of the browser instead. The results of a batch are
+/// kept in memory until they are fetched (they can be fetched once).
+pub struct ConcurrentBatchModel<M: CompletionModel> {
+    model: M,
+    concurrency: usize,
+    batches: ConcurrentBatches<M::Response>,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl<M: CompletionModel> Clone for ConcurrentBatchModel<M> {
+    fn clone(&self) -> Self {
+        Self {
+            model: self.model.clone(),
+            concurrency: self.concurrency,
+            batches: self.batches.clone(),
+            next_id: self.next_id.clone(),
+        }
+    }
+}
+
+impl<M: CompletionModel> ConcurrentBatchModel<M> {
+    /// Run the batches of `model` with at most `concurrency` requests in flight
+    pub fn new(model: M, concurrency: usize) -> Self {
+        Self {
+            model,
+            concurrency: concurrency.max(1),
+            batches: Default::default(),
+            next_id: Default::default(),
+        }
+    }
+
+    /// The wrapped model
+    pub fn inner(&self) -> &M {
+        &self.model
+    }
+}
+
+impl<M: CompletionModel> CompletionModel for ConcurrentBatchModel<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<M::Response>, CompletionError> {
+        self.model.completion(request).await
+    }
+
+    fn prepare_request(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<PreparedRequest, CompletionError> {
+        self.model.prepare_request(request)
+    }
+}
+
+impl<M> BatchCompletionModel for ConcurrentBatchModel<M>
+where
+    M: CompletionModel + 'static,
+    M::Response: 'static,
+{
+    async fn submit_batch(&self, requests: Vec<BatchRequest>) -> Result<String, CompletionError> {
+        let batch_id = format!("batch_{}", self.next_id.fetch_add(1, Ordering::Relaxed));
+        let total = requests.len();
+
+        let model = self.model.clone();
+        let pending = futures::stream::iter(requests)
+            .map(move |BatchRequest { custom_id, request }| {
+                let model = model.clone();
+                async move {
+                    BatchResult {
+                        custom_id,
+                        response: model.completion(request).await,
+                    }
+                }
+            })
+            .buffer_unordered(self.concurrency);
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let batches = self.batches.clone();
+            let id = batch_id.clone();
+            wasm_bindgen_futures::spawn_local(pending.for_each(move |result| {
+                if let Some(batch) = batches
+                    .lock()
+                    .expect("Mutex should not be poisoned")
+                    .get_mut(&id)
+                {
+                    batch.results.push(result);
+                }
+                futures::future::ready(())
+            }));
+        }
+
+        self.batches
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .insert(
+                batch_id.clone(),
+                ConcurrentBatch {
+                    total,
+                    results: vec![],
+                    #[cfg(not(target_arch = "wasm32"))]
+                    pending: Some(Box::pin(pending)),
+                },
+            );
+
+        Ok(batch_id)
+    }
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus, CompletionError> {
+        let mut batches = self.batches.lock().expect("Mutex should not be poisoned");
+        let batch = batches
+            .get_mut(batch_id)
+            .ok_or_else(|| unknown_batch(batch_id))?;
+        batch.poll();
+
+        Ok(batch.status())
+    }
+
+    async fn batch_results(
+        &self,
+        batch_id: &str,
+    ) -> Result<Vec<BatchResult<M::Response>>, CompletionError> {
+        let mut batches = self.batches.lock().expect("Mutex should not be poisoned");
+        let batch = batches
+            .get_mut(batch_id)
+            .ok_or_else(|| unknown_batch(batch_id))?;
+        batch.poll();
+        if !batch.status().is_done() {
+            return Err(CompletionError::ProviderError(format!(
+                "Batch {batch_id} is still in progress"
+            )));
+        }
+
+        Ok(batches
+            .remove(batch_id)
+            .map(|batch| batch.results)
+            .unwrap_or_default())
+    }
+}
+
+fn unknown_batch(batch_id: &str) -> CompletionError {
+    CompletionError::ProviderError(format!(
+        "Unknown batch {batch_id} (the results of a batch can only be fetched once)"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{completion::CompletionRequestBuilder, providers::mock::MockCompletionModel};
+
+    fn request(prompt: &str) -> CompletionRequest {
+        CompletionRequestBuilder::new(MockCompletionModel::new(), prompt).build()
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_batch() {
+        let model = ConcurrentBatchModel::new(
+            MockCompletionModel::new()
+                .text("positive")
+                .error(CompletionError::ProviderError("Overloaded".to_string()))
+                .text("negative"),
+            1,
+        );
+
+        let results = model
+            .run_batch(
+                vec![
+                    BatchRequest::new("a", request("Great!")),
+                    BatchRequest::new("b", request("Meh.")),
+                    BatchRequest::new("c", request("Awful.")),
+                ],
+                Duration::from_millis(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.custom_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b", "c"]
+        );
+        assert!(results[0].response.is_ok());
+        assert!(results[1].response.is_err());
+        assert!(results[2].response.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_batch_status() {
+        let model = ConcurrentBatchModel::new(MockCompletionModel::new().text("positive"), 4);
+
+        let batch_id = model
+            .submit_batch(vec![BatchRequest::new("a", request("Great!"))])
+            .await
+            .unwrap();
+
+        // The requests are only sent when the batch is polled
+        assert!(model.inner().requests().is_empty());
+        assert_eq!(
+            model.batch_status(&batch_id).await.unwrap(),
+            BatchStatus {
+                state: BatchState::Ended,
+                total: 1,
+                succeeded: 1,
+                failed: 0,
+            }
+        );
+        assert_eq!(model.batch_results(&batch_id).await.unwrap().len(), 1);
+        assert!(model.batch_results(&batch_id).await.is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! This module provides a synchronous API over the async API of rig, for CLI tools and
+//! codebases that do not use an async runtime.
+//!
+//! A [Blocking] wraps an agent, a completion model, an extractor or an embedding model and
+//! exposes their methods as blocking functions, run on a runtime managed by rig (shared by all
+//! the wrappers and created on first use). The runtime is multi-threaded, so the blocking
+//! functions can be called from several threads at once, each call making progress
+//! independently. Any other future (e.g.: building embeddings with an
+//! [EmbeddingsBuilder](crate::embeddings::EmbeddingsBuilder)) can be run with [block_on].
+//!
+//! Note: the blocking functions must not be called from an async context (i.e.: from a
+//! future run by an async runtime), use the async API there instead.
+//!
+//! # Example
+//! ```rust
+//! use rig::{blocking::Blocking, providers::openai};
+//!
+//! let openai = openai::Client::from_env();
+//!
+//! let agent = Blocking::new(
+//!     openai
+//!         .agent(openai::GPT_4O)
+//!         .preamble("You are a helpful assistant.")
+//!         .build(),
+//! );
+//! let answer = agent.prompt("What is the capital of France?")?;
+//!
+//! let model = Blocking::new(openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL));
+//! let embedding = model.embed_text("Paris is the capital of France.")?;
+//! ```
+
+use std::{future::Future, sync::OnceLock};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    completion::{
+        Chat, CompletionError, CompletionModel, CompletionRequest, CompletionResponse, Message,
+        Prompt, PromptError,
+    },
+    embeddings::{Embedding, EmbeddingError, EmbeddingModel},
+    extractor::{ExtractionError, Extractor, Grounded, GroundedExtractor, MultiExtractor},
+};
+
+/// Runtime shared by the blocking functions, created on first use. A current thread runtime would
+/// only drive its IO and timers from one of the threads blocked on it at a time.
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("Tokio runtime should build")
+    })
+}
+
+/// Run `future` to completion on the runtime managed by rig, blocking the current thread.
+///
+/// # Panics
+/// Panics if called from an async context.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    runtime().block_on(future)
+}
+
+/// Blocking wrapper of an agent, a completion model, an extractor or an embedding model
+/// (see the [module documentation](self))
+#[derive(Clone, Debug)]
+pub struct Blocking<T> {
+    inner: T,
+}
+
+impl<T> Blocking<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped async value
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> From<T> for Blocking<T> {
+    fn from(inner: T) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<T: Prompt> Blocking<T> {
+    /// Blocking version of [Prompt::prompt]
+    pub fn prompt(&self, prompt: impl Into<Message> + Send) -> Result<String, PromptError> {
+        block_on(self.inner.prompt(prompt))
+    }
+}
+
+impl<T: Chat> Blocking<T> {
+    /// Blocking version of [Chat::chat]
+    pub fn chat(
+        &self,
+        prompt: impl Into<Message> + Send,
+        chat_history: Vec<Message>,
+    ) -> Result<String, PromptError> {
+        block_on(self.inner.chat(prompt, chat_history))
+    }
+}
+
+impl<M: CompletionModel> Blocking<M> {
+    /// Blocking version of [CompletionModel::completion]. The request can be built with
+    /// `model.inner().completion_request(prompt)`.
+    pub fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<M::Response>, CompletionError> {
+        block_on(self.inner.completion(request))
+    }
+}
+
+impl<M: EmbeddingModel> Blocking<M> {
+    /// Blocking version of [EmbeddingModel::embed_text]
+    pub fn embed_text(&self, text: &str) -> Result<Embedding, EmbeddingError> {
+        block_on(self.inner.embed_text(text))
+    }
+
+    /// Blocking version of [EmbeddingModel::embed_texts]
+    pub fn embed_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        block_on(self.inner.embed_texts(texts))
+    }
+}
+
+impl<M: CompletionModel, T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync>
+    Blocking<Extractor<M, T>>
+{
+    /// Blocking version of [Extractor::extract]
+    pub fn extract(&self, text: &str) -> Result<T, ExtractionError> {
+        block_on(self.inner.extract(text))
+    }
+}
+
+impl<M: CompletionModel, T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync>
+    Blocking<MultiExtractor<M, T>>
+{
+    /// Blocking version of [MultiExtractor::extract]
+    pub fn extract(&self, text: &str) -> Result<Vec<T>, ExtractionError> {
+        block_on(self.inner.extract(text))
+    }
+}
+
+impl<M: CompletionModel, T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync>
+    Blocking<GroundedExtractor<M, T>>
+{
+    /// Blocking version of [GroundedExtractor::extract]
+    pub fn extract(&self, query: &str) -> Result<Grounded<T>, ExtractionError> {
+        block_on(self.inner.extract(query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        agent::AgentBuilder,
+        extractor::ExtractorBuilder,
+        providers::mock::{MockCompletionModel, MockEmbeddingModel},
+    };
+
+    #[derive(Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+    struct Person {
+        name: String,
+    }
+
+    #[test]
+    fn test_blocking_prompt() {
+        let agent = Blocking::new(
+            AgentBuilder::new(MockCompletionModel::new().text("Paris").text("Lyon")).build(),
+        );
+
+        assert_eq!(agent.prompt("Capital of France?").unwrap(), "Paris");
+        assert_eq!(
+            agent
+                .chat("And the third largest city?", vec![Message::user("Hello")])
+                .unwrap(),
+            "Lyon"
+        );
+    }
+
+    #[test]
+    fn test_blocking_extract() {
+        let extractor = Blocking::new(
+            ExtractorBuilder::<Person, _>::new(
+                MockCompletionModel::new().tool_call("submit", json!({"name": "John Doe"})),
+            )
+            .build(),
+        );
+
+        assert_eq!(
+            extractor.extract("John Doe is a doctor.").unwrap(),
+            Person {
+                name: "John Doe".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_blocking_embeddings() {
+        let model = Blocking::new(MockEmbeddingModel::new(2).embedding("flurbo", vec![1.0, 0.0]));
+
+        assert_eq!(model.embed_text("flurbo").unwrap().vec, vec![1.0, 0.0]);
+        assert_eq!(
+            model
+                .embed_texts(vec!["a".to_string(), "b".to_string()])
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,719 @@
+//! This module provides a response caching layer for completion models.
+//!
+//! A [CachedModel] wraps a completion model and serves cached responses for requests it has
+//! already seen, without calling the wrapped model. Requests are matched:
+//! - exactly, by hashing the whole [CompletionRequest] (prompt, preamble, chat history, documents,
+//!   tools and parameters);
+//! - optionally semantically (see [CachedModel::semantic]): a request whose prompt is similar
+//!   enough to the prompt of a cached request (cosine similarity of their embeddings above a
+//!   threshold) is served the cached response, provided the rest of the request is identical.
+//!
+//! The keys of the cache are hashes of the requests: the request that produced a cached response
+//! is stored along with it and compared with the request on a hit, so that hash collisions are
+//! never served the wrong response.
+//!
+//! Responses are stored in a [CacheBackend] ([InMemoryCache] by default), which can be implemented
+//! to share the cache between processes (e.g.: Redis). Entries can expire after a TTL. The
+//! semantic index holds the embeddings of the most recently cached prompts, up to a capacity (see
+//! [CachedModel::semantic_capacity]).
+//!
+//! Note: cached responses report zero token usage and their `raw_response` is `None`. Errors of
+//! the backend are logged and do not fail the completion. Streaming completions are not cached.
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//! use rig::{cache::CachedModel, completion::Prompt, providers::openai};
+//!
+//! let openai = openai::Client::from_env();
+//!
+//! let model = CachedModel::new(openai.completion_model(openai::GPT_4O))
+//!     .ttl(Duration::from_secs(3600))
+//!     .semantic(openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL), 0.95);
+//!
+//! let agent = rig::agent::AgentBuilder::new(model).build();
+//!
+//! let answer = agent.prompt("What is the capital of France?").await?;
+//! // Served from the cache
+//! let answer = agent.prompt("What's the capital of France?").await?;
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    completion::{self, CompletionError, CompletionModel, CompletionRequest, Usage},
+    embeddings::{distance::VectorDistance, Embedding, EmbeddingError, EmbeddingModel},
+    layer::CompletionLayer,
+    message::{AssistantContent, Message, UserContent},
+    wasm_compat::{Instant, WasmBoxedFuture, WasmCompatSend},
+    OneOrMany,
+};
+
+/// Error returned by a [CacheBackend]
+#[derive(Debug, thiserror::Error)]
+#[error("CacheError: {0}")]
+pub struct CacheError(#[from] pub Box<dyn std::error::Error + Send + Sync + 'static>);
+
+/// Completion response stored in a [CacheBackend]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CachedResponse {
+    pub choice: OneOrMany<AssistantContent>,
+    /// Token usage of the request that produced the response
+    pub usage: Usage,
+    /// The request that produced the response (serialized [CompletionRequest]), compared with the
+    /// request on a cache hit
+    #[serde(default)]
+    pub request: Value,
+}
+
+/// Storage of the responses cached by a [CachedModel]
+pub trait CacheBackend: Send + Sync {
+    /// Get the response stored under `key`, if it exists and has not expired
+    fn get(
+        &self,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<CachedResponse>, CacheError>> + WasmCompatSend;
+
+    /// Store `response` under `key`, expiring after `ttl` if set
+    fn set(
+        &self,
+        key: &str,
+        response: CachedResponse,
+        ttl: Option<Duration>,
+    ) -> impl Future<Output = Result<(), CacheError>> + WasmCompatSend;
+}
+
+/// In-memory [CacheBackend]. The cache is cheap to clone and all clones share the same entries.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryCache {
+    entries: Arc<Mutex<HashMap<String, CacheEntry>>>,
+}
+
+/// Entry of an [InMemoryCache]: the cached response and its expiration time (if any)
+type CacheEntry = (CachedResponse, Option<Instant>);
+
+impl InMemoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of entries in the cache (including expired entries not yet evicted)
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all the entries of the cache
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .clear();
+    }
+}
+
+impl InMemoryCache {
+    fn get_at(&self, key: &str, now: Instant) -> Option<CachedResponse> {
+        let mut entries = self.entries.lock().expect("Mutex should not be poisoned");
+
+        match entries.get(key) {
+            Some((_, Some(expires_at))) if *expires_at <= now => {
+                entries.remove(key);
+                None
+            }
+            Some((response, _)) => Some(response.clone()),
+            None => None,
+        }
+    }
+
+    fn set_at(&self, key: &str, response: CachedResponse, ttl: Option<Duration>, now: Instant) {
+        let expires_at = ttl.map(|ttl| now + ttl);
+        self.entries
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .insert(key.to_string(), (response, expires_at));
+    }
+}
+
+impl CacheBackend for InMemoryCache {
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, CacheError> {
+        Ok(self.get_at(key, Instant::now()))
+    }
+
+    async fn set(
+        &self,
+        key: &str,
+        response: CachedResponse,
+        ttl: Option<Duration>,
+    ) -> Result<(), CacheError> {
+        self.set_at(key, response, ttl, Instant::now());
+        Ok(())
+    }
+}
+
+type EmbedFn = Arc<
+    dyn Fn(String) -> WasmBoxedFuture<'static, Result<Embedding, EmbeddingError>> + Send + Sync,
+>;
+
+/// Default maximum number of prompt embeddings held by the semantic index
+const DEFAULT_SEMANTIC_CAPACITY: usize = 10_000;
+
+/// Prompt embedding of a cached request
+struct SemanticEntry {
+    /// Hash of the request without its prompt
+    scope: String,
+    embedding: Embedding,
+    key: String,
+}
+
+struct SemanticIndex {
+    embed: EmbedFn,
+    threshold: f64,
+    entries: Mutex<VecDeque<SemanticEntry>>,
+}
+
+impl SemanticIndex {
+    /// Key of the most similar cached prompt within `scope`, if its similarity is above the
+    /// threshold
+    fn find(&self, scope: &str, embedding: &Embedding) -> Option<String> {
+        let entries = self.entries.lock().expect("Mutex should not be poisoned");
+
+        entries
+            .iter()
+            .filter(|entry| entry.scope == scope)
+            .map(|entry| (entry.embedding.cosine_similarity(embedding, false), entry))
+            .filter(|(similarity, _)| *similarity >= self.threshold)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, entry)| entry.key.clone())
+    }
+
+    /// Add an entry, evicting the oldest entries beyond `capacity`
+    fn insert(&self, scope: String, embedding: Embedding, key: String, capacity: usize) {
+        let mut entries = self.entries.lock().expect("Mutex should not be poisoned");
+        while entries.len() >= capacity.max(1) {
+            entries.pop_front();
+        }
+        entries.push_back(SemanticEntry {
+            scope,
+            embedding,
+            key,
+        });
+    }
+
+    /// Remove the entries pointing to `key` (e.g.: because it expired)
+    fn remove(&self, key: &str) {
+        self.entries
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .retain(|entry| entry.key != key);
+    }
+}
+
+/// Completion model wrapped by a response cache
+pub struct CachedModel<M, B = InMemoryCache> {
+    model: M,
+    backend: Arc<B>,
+    namespace: String,
+    ttl: Option<Duration>,
+    semantic: Option<Arc<SemanticIndex>>,
+    semantic_capacity: usize,
+}
+
+// Implemented manually since the backend does not need to be `Clone`
+impl<M: Clone, B> Clone for CachedModel<M, B> {
+    fn clone(&self) -> Self {
+        Self {
+            model: self.model.clone(),
+            backend: self.backend.clone(),
+            namespace: self.namespace.clone(),
+            ttl: self.ttl,
+            semantic: self.semantic.clone(),
+            semantic_capacity: self.semantic_capacity,
+        }
+    }
+}
+
+impl<M: CompletionModel> CachedModel<M> {
+    /// Wrap `model` with an [InMemoryCache]
+    pub fn new(model: M) -> Self {
+        Self::with_backend(model, InMemoryCache::new())
+    }
+}
+
+impl<M: CompletionModel, B: CacheBackend> CachedModel<M, B> {
+    /// Wrap `model` with a custom cache backend
+    pub fn with_backend(model: M, backend: B) -> Self {
+        Self {
+            model,
+            backend: Arc::new(backend),
+            namespace: String::new(),
+            ttl: None,
+            semantic: None,
+            semantic_capacity: DEFAULT_SEMANTIC_CAPACITY,
+        }
+    }
+
+    /// Prefix of the cache keys, to share a backend between several models
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Expire the cached responses after `ttl`
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Also serve cached responses to requests whose prompt has a cosine similarity of at least
+    /// `threshold` with the prompt of a cached request (the rest of the requests must be
+    /// identical). The prompts are embedded with `embedding_model`.
+    ///
+    /// Note: the semantic index is kept in memory by the wrapper, even with a custom backend.
+    pub fn semantic<E>(mut self, embedding_model: E, threshold: f64) -> Self
+    where
+        E: EmbeddingModel + 'static,
+    {
+        let embed: EmbedFn = Arc::new(move |text| {
+            let model = embedding_model.clone();
+            Box::pin(async move { model.embed_text(&text).await })
+        });
+
+        self.semantic = Some(Arc::new(SemanticIndex {
+            embed,
+            threshold,
+            entries: Mutex::new(VecDeque::new()),
+        }));
+        self
+    }
+
+    /// Set the maximum number of prompt embeddings held by the semantic index (10,000 by
+    /// default), the oldest ones being evicted first (see [CachedModel::semantic])
+    pub fn semantic_capacity(mut self, capacity: usize) -> Self {
+        self.semantic_capacity = capacity;
+        self
+    }
+
+    fn key(&self, value: &Value) -> String {
+        format!(
+            "{}{:016x}",
+            self.namespace,
+            fnv1a(value.to_string().as_bytes())
+        )
+    }
+
+    async fn get(&self, key: &str) -> Option<CachedResponse> {
+        match self.backend.get(key).await {
+            Ok(response) => response,
+            Err(error) => {
+                tracing::warn!(target: "rig", "Failed to read response cache: {error}");
+                None
+            }
+        }
+    }
+
+    /// Embed the prompt of the request for semantic matching, if enabled and the prompt has text
+    async fn embed_prompt(&self, request: &CompletionRequest) -> Option<Embedding> {
+        let semantic = self.semantic.as_ref()?;
+        let text = prompt_text(&request.prompt)?;
+
+        match (semantic.embed)(text).await {
+            Ok(embedding) => Some(embedding),
+            Err(error) => {
+                tracing::warn!(target: "rig", "Failed to embed prompt for response cache: {error}");
+                None
+            }
+        }
+    }
+}
+
+impl<M: CompletionModel, B: CacheBackend> CompletionModel for CachedModel<M, B> {
+    /// The raw response of the wrapped model, or `None` when the response is cached
+    type Response = Option<M::Response>;
+
+    fn prepare_request(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        self.model.prepare_request(request)
+    }
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<Self::Response>, CompletionError> {
+        let request_json = serde_json::to_value(&request)?;
+        let key = self.key(&request_json);
+
+        // The cached request is compared with the request, in case of a hash collision
+        let mut cached = self
+            .get(&key)
+            .await
+            .filter(|cached| cached.request == request_json);
+
+        let mut semantic_match = None;
+        if let Some(semantic) = self.semantic.as_ref().filter(|_| cached.is_none()) {
+            let scope_json = without_prompt(request_json.clone());
+            let scope = self.key(&scope_json);
+
+            if let Some(embedding) = self.embed_prompt(&request).await {
+                if let Some(similar_key) = semantic.find(&scope, &embedding) {
+                    cached = self
+                        .get(&similar_key)
+                        .await
+                        .filter(|cached| without_prompt(cached.request.clone()) == scope_json);
+                    if cached.is_none() {
+                        semantic.remove(&similar_key);
+                    }
+                }
+                semantic_match = Some((scope, embedding));
+            }
+        }
+
+        if let Some(cached) = cached {
+            return Ok(completion::CompletionResponse {
+                choice: cached.choice,
+                usage: Usage::default(),
+                timing: completion::ResponseTiming::default(),
+                raw_response: None,
+            });
+        }
+
+        let response = self.model.completion(request).await?;
+
+        let cached = CachedResponse {
+            choice: response.choice.clone(),
+            usage: response.usage,
+            request: request_json,
+        };
+        match self.backend.set(&key, cached, self.ttl).await {
+            Ok(()) => {
+                if let (Some(semantic), Some((scope, embedding))) = (&self.semantic, semantic_match)
+                {
+                    semantic.insert(scope, embedding, key, self.semantic_capacity);
+                }
+            }
+            Err(error) => tracing::warn!(target: "rig", "Failed to write response cache: {error}"),
+        }
+
+        Ok(completion::CompletionResponse {
+            choice: response.choice,
+            usage: response.usage,
+            timing: response.timing,
+            raw_response: Some(response.raw_response),
+        })
+    }
+}
+
+/// Layer wrapping completion models with a response cache (see [CachedModel]). All the models
+/// wrapped by the layer share the same backend.
+///
+/// Note: semantic matching is not supported by the layer, use [CachedModel::semantic] instead.
+pub struct CacheLayer<B = InMemoryCache> {
+    backend: Arc<B>,
+    namespace: String,
+    ttl: Option<Duration>,
+}
+
+impl<B> Clone for CacheLayer<B> {
+    fn clone(&self) -> Self {
+        Self {
+            backend: self.backend.clone(),
+            namespace: self.namespace.clone(),
+            ttl: self.ttl,
+        }
+    }
+}
+
+impl CacheLayer {
+    /// Create a layer caching the responses in an [InMemoryCache]
+    pub fn new() -> Self {
+        Self::with_backend(InMemoryCache::new())
+    }
+}
+
+impl Default for CacheLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: CacheBackend> CacheLayer<B> {
+    /// Create a layer caching the responses in a custom cache backend
+    pub fn with_backend(backend: B) -> Self {
+        Self {
+            backend: Arc::new(backend),
+            namespace: String::new(),
+            ttl: None,
+        }
+    }
+
+    /// Prefix of the cache keys (see [CachedModel::namespace])
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Expire the cached responses after `ttl`
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+}
+
+impl<M: CompletionModel, B: CacheBackend> CompletionLayer<M> for CacheLayer<B> {
+    type Model = CachedModel<M, B>;
+
+    fn layer(&self, model: M) -> CachedModel<M, B> {
+        CachedModel {
+            model,
+            backend: self.backend.clone(),
+            namespace: self.namespace.clone(),
+            ttl: self.ttl,
+            semantic: None,
+            semantic_capacity: DEFAULT_SEMANTIC_CAPACITY,
+        }
+    }
+}
+
+/// Text content of a user prompt, used for semantic matching
+fn prompt_text(prompt: &Message) -> Option<String> {
+    let Message::User { content } = prompt else {
+        return None;
+    };
+
+    let text = content
+        .iter()
+        .filter_map(|content| match content {
+            UserContent::Text(text) => Some(text.text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    (!text.is_empty()).then_some(text)
+}
+
+/// Serialized request without its prompt, identifying the requests that only differ by their
+/// prompt for semantic matching
+fn without_prompt(mut request: Value) -> Value {
+    if let Some(request) = request.as_object_mut() {
+        request.remove("prompt");
+    }
+    request
+}
+
+/// 64-bit FNV-1a hash, stable across processes so that keys can be shared through a backend
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::completion::{Completion, Prompt};
+
+    #[derive(Clone)]
+    struct EchoModel {
+        calls: Arc<Mutex<usize>>,
+    }
+
+    impl CompletionModel for EchoModel {
+        type Response = ();
+
+        async fn completion(
+            &self,
+            request: CompletionRequest,
+        ) -> Result<completion::CompletionResponse<()>, CompletionError> {
+            *self.calls.lock().unwrap() += 1;
+            let prompt = prompt_text(&request.prompt).unwrap_or_default();
+
+            Ok(completion::CompletionResponse {
+                choice: OneOrMany::one(AssistantContent::text(format!("echo: {prompt}"))),
+                usage: Usage::new(1, 2),
+                timing: completion::ResponseTiming::default(),
+                raw_response: (),
+            })
+        }
+    }
+
+    /// Embeds texts by their lowercase letters, ignoring punctuation
+    #[derive(Clone)]
+    struct LetterEmbeddingModel;
+
+    impl EmbeddingModel for LetterEmbeddingModel {
+        const MAX_DOCUMENTS: usize = 16;
+
+        fn ndims(&self) -> usize {
+            26
+        }
+
+        async fn embed_texts(
+            &self,
+            texts: impl IntoIterator<Item = String> + Send,
+        ) -> Result<Vec<Embedding>, EmbeddingError> {
+            Ok(texts
+                .into_iter()
+                .map(|text| {
+                    let mut vec = vec![0.0; 26];
+                    for c in text.to_lowercase().chars().filter(char::is_ascii_lowercase) {
+                        vec[(c as u8 - b'a') as usize] += 1.0;
+                    }
+                    Embedding {
+                        document: text,
+                        vec,
+                    }
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exact_cache() {
+        let calls = Arc::new(Mutex::new(0));
+        let model = CachedModel::new(EchoModel {
+            calls: calls.clone(),
+        });
+        let agent = crate::agent::AgentBuilder::new(model).build();
+
+        assert_eq!(agent.prompt("hello").await.unwrap(), "echo: hello");
+        assert_eq!(agent.prompt("hello").await.unwrap(), "echo: hello");
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        assert_eq!(agent.prompt("hello!").await.unwrap(), "echo: hello!");
+        assert_eq!(*calls.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_cache_ttl() {
+        let cache = InMemoryCache::new();
+        let response = CachedResponse {
+            choice: OneOrMany::one(AssistantContent::text("hello")),
+            usage: Usage::new(1, 2),
+            request: Value::Null,
+        };
+        let now = Instant::now();
+
+        cache.set_at("key", response, Some(Duration::from_secs(60)), now);
+        assert!(cache.get_at("key", now + Duration::from_secs(59)).is_some());
+        assert!(cache.get_at("key", now + Duration::from_secs(60)).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_hash_collision() {
+        let calls = Arc::new(Mutex::new(0));
+        let backend = InMemoryCache::new();
+        let model = CachedModel::with_backend(
+            EchoModel {
+                calls: calls.clone(),
+            },
+            backend.clone(),
+        );
+        let agent = crate::agent::AgentBuilder::new(model).build();
+        assert_eq!(agent.prompt("hello").await.unwrap(), "echo: hello");
+
+        // Store the response under the key of another request, as if their hashes collided
+        let request = agent.completion("bye", vec![]).await.unwrap().build();
+        let key = format!(
+            "{:016x}",
+            fnv1a(
+                serde_json::to_value(&request)
+                    .unwrap()
+                    .to_string()
+                    .as_bytes()
+            )
+        );
+        let entries = backend.entries.lock().unwrap().clone();
+        let (response, _) = entries.values().next().unwrap();
+        backend.set_at(&key, response.clone(), None, Instant::now());
+
+        assert_eq!(agent.prompt("bye").await.unwrap(), "echo: bye");
+        assert_eq!(*calls.lock().unwrap(), 2);
+
+        // The response of the request replaced the colliding one
+        let cached = backend.get_at(&key, Instant::now()).unwrap();
+        assert_eq!(cached.request, serde_json::to_value(&request).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_semantic_cache() {
+        let calls = Arc::new(Mutex::new(0));
+        let model = CachedModel::new(EchoModel {
+            calls: calls.clone(),
+        })
+        .semantic(LetterEmbeddingModel, 0.99);
+
+        let agent = crate::agent::AgentBuilder::new(model.clone()).build();
+        assert_eq!(
+            agent.prompt("Hello world").await.unwrap(),
+            "echo: Hello world"
+        );
+        assert_eq!(
+            agent.prompt("hello, world!").await.unwrap(),
+            "echo: Hello world"
+        );
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        assert_eq!(agent.prompt("goodbye").await.unwrap(), "echo: goodbye");
+        assert_eq!(*calls.lock().unwrap(), 2);
+
+        // Similar prompts with a different preamble are not matched
+        let agent = crate::agent::AgentBuilder::new(model)
+            .preamble("Be concise.")
+            .build();
+        assert_eq!(
+            agent.prompt("hello, world!").await.unwrap(),
+            "echo: hello, world!"
+        );
+        assert_eq!(*calls.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_capacity() {
+        let calls = Arc::new(Mutex::new(0));
+        let model = CachedModel::new(EchoModel {
+            calls: calls.clone(),
+        })
+        .semantic(LetterEmbeddingModel, 0.99)
+        .semantic_capacity(1);
+
+        let agent = crate::agent::AgentBuilder::new(model.clone()).build();
+        agent.prompt("Hello world").await.unwrap();
+        agent.prompt("goodbye").await.unwrap();
+        assert_eq!(
+            model
+                .semantic
+                .as_ref()
+                .unwrap()
+                .entries
+                .lock()
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // The embedding of the first prompt was evicted
+        assert_eq!(
+            agent.prompt("hello, world!").await.unwrap(),
+            "echo: hello, world!"
+        );
+        assert_eq!(*calls.lock().unwrap(), 3);
+    }
+}
@@ -0,0 +1,482 @@
+//! This module provides a record/replay ("cassette") transport for the provider clients, so that
+//! agent and RAG integration tests can run offline and deterministically in CI.
+//!
+//! A [Cassette] is a fixture file holding the HTTP interactions (request and response pairs) of
+//! the provider clients it is attached to with their `with_transport` method. In
+//! [CassetteMode::Record] mode, requests are forwarded to the provider and the interactions are
+//! saved to the file. In [CassetteMode::Replay] mode, the provider is never called: responses are
+//! served from the file by matching the requests, and an error is returned for requests that were
+//! not recorded. [CassetteMode::Auto] replays the cassette if the file exists and records it
+//! otherwise.
+//!
+//! Since the interactions are recorded at the HTTP level, replayed responses are parsed by the
+//! provider like live ones (e.g.: the `raw_response` of completion responses is available) and
+//! streaming responses are replayed as well. Requests are matched on their method, URL and body;
+//! their headers (e.g.: API keys) and the `key` query parameter are never recorded.
+//!
+//! Requires the `cassette` feature.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     cassette::{Cassette, CassetteMode},
+//!     completion::Prompt,
+//!     http_client::HttpClientSettings,
+//!     providers::openai,
+//! };
+//!
+//! #[tokio::test]
+//! async fn test_agent() {
+//!     // Record with `RIG_CASSETTE_MODE=record`, replay in CI with `RIG_CASSETTE_MODE=replay`
+//!     let cassette = Cassette::from_env("tests/fixtures/agent.json").unwrap();
+//!
+//!     let openai = openai::Client::from_env().with_transport(cassette);
+//!     let agent = openai
+//!         .agent(openai::GPT_4O)
+//!         .preamble("You are a comedian.")
+//!         .build();
+//!
+//!     let response = agent.prompt("Tell me a joke").await.unwrap();
+//!     assert!(!response.is_empty());
+//! }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use bytes::Bytes;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_LENGTH, TRANSFER_ENCODING};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    http_client::{HttpTransport, TransportError},
+    wasm_compat::WasmBoxedFuture,
+};
+
+/// Mode of a [Cassette]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CassetteMode {
+    /// Forward requests to the provider and save the interactions (overwriting the file)
+    Record,
+    /// Serve the responses from the recorded interactions without calling the provider
+    Replay,
+    /// Replay if the cassette file exists, record otherwise
+    Auto,
+}
+
+impl CassetteMode {
+    /// Read the mode from the `RIG_CASSETTE_MODE` environment variable (`record`, `replay` or
+    /// `auto`), defaulting to [CassetteMode::Auto] if it is not set or invalid.
+    pub fn from_env() -> Self {
+        match std::env::var("RIG_CASSETTE_MODE").as_deref() {
+            Ok("record") => Self::Record,
+            Ok("replay") => Self::Replay,
+            _ => Self::Auto,
+        }
+    }
+}
+
+/// Body of a recorded request or response
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", content = "content", rename_all = "snake_case")]
+enum Body {
+    Empty,
+    Json(Value),
+    Text(String),
+    Base64(String),
+}
+
+impl Body {
+    fn new(bytes: &[u8]) -> Self {
+        if bytes.is_empty() {
+            return Self::Empty;
+        }
+
+        match std::str::from_utf8(bytes) {
+            Ok(text) => match serde_json::from_str(text) {
+                Ok(json) => Self::Json(json),
+                Err(_) => Self::Text(text.to_string()),
+            },
+            Err(_) => Self::Base64(BASE64_STANDARD.encode(bytes)),
+        }
+    }
+
+    fn into_bytes(self) -> Bytes {
+        match self {
+            Self::Empty => Bytes::new(),
+            Self::Json(json) => json.to_string().into(),
+            Self::Text(text) => text.into(),
+            Self::Base64(base64) => BASE64_STANDARD.decode(base64).unwrap_or_default().into(),
+        }
+    }
+}
+
+/// Recorded request, without its headers
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+struct Request {
+    method: String,
+    url: String,
+    body: Body,
+}
+
+impl Request {
+    fn new(request: &http::Request<Bytes>) -> Self {
+        Self {
+            method: request.method().to_string(),
+            url: redact_url(&request.uri().to_string()),
+            body: Body::new(request.body()),
+        }
+    }
+
+    /// Whether `self` matches the `recorded` request. Bodies that are not JSON (e.g.: multipart
+    /// forms, whose boundary is random) are not compared.
+    fn matches(&self, recorded: &Request) -> bool {
+        self.method == recorded.method
+            && self.url == recorded.url
+            && match (&self.body, &recorded.body) {
+                (Body::Json(body), Body::Json(recorded)) => body == recorded,
+                (Body::Json(_), _) | (_, Body::Json(_)) => false,
+                _ => true,
+            }
+    }
+}
+
+/// Remove the `key` query parameter (API key of some providers, e.g.: Gemini) from `url`
+fn redact_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut url) => {
+            let query = url
+                .query_pairs()
+                .filter(|(name, _)| name != "key")
+                .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                .collect::<Vec<_>>();
+            if query.is_empty() {
+                url.set_query(None);
+            } else {
+                url.query_pairs_mut().clear().extend_pairs(query);
+            }
+            url.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Recorded response
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Response {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    body: Body,
+}
+
+impl Response {
+    fn new(response: &http::Response<Bytes>) -> Self {
+        Self {
+            status: response.status().as_u16(),
+            headers: response
+                .headers()
+                .iter()
+                // The replayed body may differ in length and encoding from the recorded one
+                .filter(|(name, _)| {
+                    ![CONTENT_LENGTH, CONTENT_ENCODING, TRANSFER_ENCODING].contains(*name)
+                })
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.into())))
+                .collect(),
+            body: Body::new(response.body()),
+        }
+    }
+
+    fn into_http(self) -> http::Response<Bytes> {
+        let mut builder = http::Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        builder
+            .body(self.body.into_bytes())
+            .expect("Recorded response should be valid")
+    }
+}
+
+/// Single recorded interaction with a provider
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Interaction {
+    request: Request,
+    response: Response,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Default)]
+struct State {
+    interactions: Vec<Interaction>,
+    /// Whether each interaction has already been replayed
+    replayed: Vec<bool>,
+}
+
+/// Fixture file of recorded HTTP interactions, used as the [HttpTransport] of provider clients.
+/// The cassette is cheap to clone and all clones share the same interactions, so a single
+/// cassette can be attached to several clients.
+#[derive(Clone)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    state: Arc<Mutex<State>>,
+    /// Serializes the writes of the cassette file
+    write_lock: Arc<futures::lock::Mutex<()>>,
+    /// Transport forwarding the requests to the provider when recording
+    transport: Arc<dyn HttpTransport>,
+}
+
+impl Cassette {
+    /// Open the cassette at `path`. In [CassetteMode::Replay] mode, the file must exist.
+    /// [CassetteMode::Auto] is resolved to [CassetteMode::Replay] or [CassetteMode::Record]
+    /// depending on whether the file exists.
+    pub fn open(path: impl AsRef<Path>, mode: CassetteMode) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let mode = match mode {
+            CassetteMode::Auto if path.exists() => CassetteMode::Replay,
+            CassetteMode::Auto => CassetteMode::Record,
+            mode => mode,
+        };
+
+        let state = match mode {
+            CassetteMode::Replay => {
+                let file: CassetteFile = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+                State {
+                    replayed: vec![false; file.interactions.len()],
+                    interactions: file.interactions,
+                }
+            }
+            _ => State::default(),
+        };
+
+        Ok(Self {
+            path,
+            mode,
+            state: Arc::new(Mutex::new(state)),
+            write_lock: Arc::new(futures::lock::Mutex::new(())),
+            transport: Arc::new(reqwest::Client::new()),
+        })
+    }
+
+    /// Open the cassette at `path` in the mode set by the `RIG_CASSETTE_MODE` environment
+    /// variable (see [CassetteMode::from_env]).
+    pub fn from_env(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::open(path, CassetteMode::from_env())
+    }
+
+    /// Forward the requests to the provider with `transport` when recording, instead of a
+    /// default [reqwest::Client]
+    pub fn with_transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    /// The resolved mode of the cassette (either [CassetteMode::Record] or [CassetteMode::Replay])
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    /// Find the first interaction matching the request that has not been replayed yet
+    fn replay(&self, request: &Request) -> Option<Response> {
+        let mut state = self.state.lock().expect("Mutex should not be poisoned");
+        let State {
+            interactions,
+            replayed,
+        } = &mut *state;
+
+        interactions
+            .iter()
+            .zip(replayed.iter_mut())
+            .find(|(interaction, replayed)| !**replayed && request.matches(&interaction.request))
+            .map(|(interaction, replayed)| {
+                *replayed = true;
+                interaction.response.clone()
+            })
+    }
+
+    /// Add an interaction and save the cassette
+    async fn record(&self, interaction: Interaction) -> std::io::Result<()> {
+        {
+            let mut state = self.state.lock().expect("Mutex should not be poisoned");
+            state.interactions.push(interaction);
+            state.replayed.push(false);
+        }
+
+        // The file is serialized while holding the write lock, so that the last write saves all
+        // the interactions
+        let _write = self.write_lock.lock().await;
+        let file = {
+            let state = self.state.lock().expect("Mutex should not be poisoned");
+            serde_json::to_string_pretty(&CassetteFile {
+                interactions: state.interactions.clone(),
+            })?
+        };
+
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&self.path, file).await
+    }
+
+    fn missing_interaction(&self, request: &Request) -> String {
+        format!(
+            "No recorded interaction matching request in cassette {}: {} {}",
+            self.path.display(),
+            request.method,
+            request.url
+        )
+    }
+}
+
+impl HttpTransport for Cassette {
+    fn execute(
+        &self,
+        request: http::Request<Bytes>,
+    ) -> WasmBoxedFuture<'_, Result<http::Response<Bytes>, TransportError>> {
+        Box::pin(async move {
+            let recorded_request = Request::new(&request);
+
+            if self.mode == CassetteMode::Replay {
+                return self
+                    .replay(&recorded_request)
+                    .map(Response::into_http)
+                    .ok_or_else(|| self.missing_interaction(&recorded_request).into())
+                    .map_err(TransportError::Other);
+            }
+
+            let response = self.transport.execute(request).await?;
+            self.record(Interaction {
+                request: recorded_request,
+                response: Response::new(&response),
+            })
+            .await
+            .map_err(|e| TransportError::Other(e.into()))?;
+
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::future::BoxFuture;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        completion::{Completion, Prompt},
+        http_client::HttpClientSettings,
+        providers::openai,
+    };
+
+    /// Transport answering the completion requests with the prompt
+    #[derive(Clone, Default)]
+    struct EchoTransport {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl HttpTransport for EchoTransport {
+        fn execute(
+            &self,
+            request: http::Request<Bytes>,
+        ) -> BoxFuture<'_, Result<http::Response<Bytes>, TransportError>> {
+            Box::pin(async move {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                let body: Value = serde_json::from_slice(request.body()).unwrap();
+                let content = &body["messages"].as_array().unwrap().last().unwrap()["content"];
+                let prompt = content[0]["text"].as_str().or(content.as_str()).unwrap();
+
+                let response = json!({
+                    "id": "chatcmpl-1",
+                    "object": "chat.completion",
+                    "created": 0,
+                    "model": openai::GPT_4O_MINI,
+                    "choices": [{
+                        "index": 0,
+                        "message": {"role": "assistant", "content": format!("echo: {prompt}")},
+                        "finish_reason": "stop"
+                    }],
+                    "usage": {"prompt_tokens": 1, "completion_tokens": 2, "total_tokens": 3}
+                });
+                Ok(http::Response::new(Bytes::from(response.to_string())))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("cassette.json");
+        let transport = EchoTransport::default();
+
+        let cassette = Cassette::open(&path, CassetteMode::Auto)
+            .unwrap()
+            .with_transport(transport.clone());
+        assert_eq!(cassette.mode(), CassetteMode::Record);
+        let agent = openai::Client::new("fake-key")
+            .with_transport(cassette)
+            .agent(openai::GPT_4O_MINI)
+            .build();
+        assert_eq!(agent.prompt("hello").await.unwrap(), "echo: hello");
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 1);
+
+        // The API key is not recorded
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("fake-key"));
+
+        let cassette = Cassette::open(&path, CassetteMode::Auto)
+            .unwrap()
+            .with_transport(transport.clone());
+        assert_eq!(cassette.mode(), CassetteMode::Replay);
+        let model = openai::Client::new("other-key")
+            .with_transport(cassette)
+            .completion_model(openai::GPT_4O_MINI);
+        let agent = crate::agent::AgentBuilder::new(model.clone()).build();
+        assert_eq!(agent.prompt("hello").await.unwrap(), "echo: hello");
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 1);
+
+        // Interactions are only replayed once, and unknown requests are not served
+        assert!(agent.prompt("hello").await.is_err());
+        assert!(agent.prompt("bye").await.is_err());
+
+        // The raw response of replayed completions is available (the request of the agent is
+        // replayed, since requests are matched on their body)
+        let cassette = Cassette::open(&path, CassetteMode::Replay).unwrap();
+        let agent = openai::Client::new("fake-key")
+            .with_transport(cassette)
+            .agent(openai::GPT_4O_MINI)
+            .build();
+        let response = agent
+            .completion("hello", vec![])
+            .await
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.raw_response.id, "chatcmpl-1");
+    }
+
+    #[test]
+    fn test_redact_url() {
+        assert_eq!(
+            redact_url("https://example.com/v1/models?alt=sse&key=secret"),
+            "https://example.com/v1/models?alt=sse"
+        );
+        assert_eq!(
+            redact_url("https://example.com/v1/models?key=secret"),
+            "https://example.com/v1/models"
+        );
+    }
+}
@@ -0,0 +1,616 @@
+//! This module provides splitters to cut texts (e.g.: documents loaded by the
+//! [loaders](crate::loaders)) into chunks small enough to be embedded and retrieved.
+//!
+//! All the splitters implement the [Chunker] trait and produce [Chunk]s of at most a configurable
+//! size, with a configurable overlap between consecutive chunks:
+//! - [RecursiveCharacterSplitter] splits texts at paragraphs, then lines, then words, then
+//!   characters, until the pieces fit in a chunk (size in characters);
+//! - [SentenceSplitter] splits texts at sentences (size in characters);
+//! - [MarkdownSplitter] splits Markdown texts at their headings, then recursively (size in
+//!   characters), keeping track of the headings each chunk is nested under;
+//! - [TokenSplitter] splits texts at words, measuring the size of the chunks in tokens.
+//!
+//! Each [Chunk] records its position in the text it comes from (byte offsets) and the id of that
+//! text, and implements [Embed] so chunks can be added directly to an
+//! [EmbeddingsBuilder](crate::embeddings::EmbeddingsBuilder) and stored in vector stores.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     chunking::{Chunker, RecursiveCharacterSplitter},
+//!     embeddings::EmbeddingsBuilder,
+//!     loaders::{DocumentLoader, MarkdownLoader},
+//! };
+//!
+//! let documents = MarkdownLoader::new().load_glob("docs/**/*.md")?;
+//! let chunks = RecursiveCharacterSplitter::new(1000, 200).chunk_documents(&documents);
+//!
+//! let embeddings = EmbeddingsBuilder::new(model)
+//!     .documents(chunks)?
+//!     .build()
+//!     .await?;
+//! ```
+use std::{collections::VecDeque, ops::Range, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    embeddings::{Embed, EmbedError, TextEmbedder},
+    loaders::{markdown::atx_heading, LoadedDocument},
+    tokenizer::Tokenizer,
+};
+
+/// Chunk of a text, produced by a [Chunker]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Chunk {
+    /// Identifier of the chunk (e.g.: `"docs/manual.pdf#page=3#chunk=0"`)
+    pub id: String,
+    /// Identifier of the text (e.g.: of the [LoadedDocument]) the chunk comes from
+    pub parent_id: String,
+    /// Position (starting from 0) of the chunk among the chunks of its parent
+    pub index: usize,
+    pub text: String,
+    /// Byte offset of the start of the chunk in the text of its parent
+    pub start: usize,
+    /// Byte offset of the end (excluded) of the chunk in the text of its parent
+    pub end: usize,
+    /// Headings the chunk is nested under, from the outermost to the innermost
+    pub headings: Vec<String>,
+}
+
+impl Embed for Chunk {
+    fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+        embedder.embed(self.text.clone());
+        Ok(())
+    }
+}
+
+/// Part of a text delimited by a [Chunker]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    /// Byte range of the span in the text
+    pub range: Range<usize>,
+    /// Headings the span is nested under, from the outermost to the innermost
+    pub headings: Vec<String>,
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Self {
+            range,
+            headings: vec![],
+        }
+    }
+}
+
+/// Splitter cutting texts into chunks
+pub trait Chunker {
+    /// Delimit the chunks of `text`
+    fn split(&self, text: &str) -> Vec<Span>;
+
+    /// Split `text`, identified by `parent_id`, into chunks
+    fn chunk(&self, parent_id: &str, text: &str) -> Vec<Chunk> {
+        self.split(text)
+            .into_iter()
+            .enumerate()
+            .map(|(index, span)| Chunk {
+                id: format!("{parent_id}#chunk={index}"),
+                parent_id: parent_id.to_string(),
+                index,
+                text: text[span.range.clone()].to_string(),
+                start: span.range.start,
+                end: span.range.end,
+                headings: span.headings,
+            })
+            .collect()
+    }
+
+    /// Split a loaded document into chunks, nested under the headings of the document
+    fn chunk_document(&self, document: &LoadedDocument) -> Vec<Chunk> {
+        self.chunk(&document.id, &document.text)
+            .into_iter()
+            .map(|mut chunk| {
+                let mut headings = document.metadata.headings.clone();
+                headings.append(&mut chunk.headings);
+                chunk.headings = headings;
+                chunk
+            })
+            .collect()
+    }
+
+    /// Split loaded documents into chunks
+    fn chunk_documents(&self, documents: &[LoadedDocument]) -> Vec<Chunk> {
+        documents
+            .iter()
+            .flat_map(|document| self.chunk_document(document))
+            .collect()
+    }
+}
+
+/// Size and overlap of the chunks, and how to measure them
+#[derive(Clone)]
+struct ChunkSize {
+    size: usize,
+    overlap: usize,
+    length: Arc<dyn Fn(&str) -> usize + Send + Sync>,
+}
+
+impl ChunkSize {
+    fn new(size: usize, overlap: usize, length: Arc<dyn Fn(&str) -> usize + Send + Sync>) -> Self {
+        assert!(size > 0, "Chunk size should be positive");
+        assert!(
+            overlap < size,
+            "Chunk overlap should be smaller than the chunk size"
+        );
+
+        Self {
+            size,
+            overlap,
+            length,
+        }
+    }
+
+    fn characters(size: usize, overlap: usize) -> Self {
+        Self::new(size, overlap, Arc::new(|text: &str| text.chars().count()))
+    }
+
+    /// Merge consecutive `pieces` (contiguous byte ranges of `text`) into chunks of at most
+    /// `size`, repeating at most `overlap` worth of trailing pieces of a chunk at the start of the
+    /// next one. Pieces larger than `size` make chunks of their own.
+    fn merge(&self, text: &str, pieces: &[Range<usize>]) -> Vec<Range<usize>> {
+        let mut chunks = vec![];
+        let mut window: VecDeque<(Range<usize>, usize)> = VecDeque::new();
+        let mut total = 0;
+
+        for piece in pieces {
+            let length = (self.length)(&text[piece.clone()]);
+
+            if total + length > self.size && !window.is_empty() {
+                chunks.extend(window_range(&window).and_then(|range| trim(text, range)));
+
+                while total > self.overlap || (total > 0 && total + length > self.size) {
+                    let (_, removed) = window.pop_front().expect("Window should not be empty");
+                    total -= removed;
+                }
+            }
+
+            window.push_back((piece.clone(), length));
+            total += length;
+        }
+        chunks.extend(window_range(&window).and_then(|range| trim(text, range)));
+
+        chunks
+    }
+}
+
+impl std::fmt::Debug for ChunkSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChunkSize")
+            .field("size", &self.size)
+            .field("overlap", &self.overlap)
+            .finish_non_exhaustive()
+    }
+}
+
+fn window_range(window: &VecDeque<(Range<usize>, usize)>) -> Option<Range<usize>> {
+    Some(window.front()?.0.start..window.back()?.0.end)
+}
+
+/// Shrink `range` to exclude the whitespace at its edges, if anything is left
+fn trim(text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let slice = &text[range.clone()];
+    let start = range.start + (slice.len() - slice.trim_start().len());
+    let end = range.end - (slice.len() - slice.trim_end().len());
+    (start < end).then_some(start..end)
+}
+
+/// Split `range` of `text` at the first of `separators` it contains (keeping the separators at
+/// the end of the pieces) into pieces of at most `size`, splitting the pieces which are still too
+/// large at the next separators. The empty separator splits at characters.
+fn split_recursive(
+    text: &str,
+    range: Range<usize>,
+    separators: &[&str],
+    chunk_size: &ChunkSize,
+    pieces: &mut Vec<Range<usize>>,
+) {
+    let slice = &text[range.clone()];
+    if (chunk_size.length)(slice) <= chunk_size.size {
+        pieces.push(range);
+        return;
+    }
+
+    match separators.split_first() {
+        Some((separator, rest)) if !separator.is_empty() => {
+            let mut start = range.start;
+            for (index, _) in slice.match_indices(separator) {
+                let end = range.start + index + separator.len();
+                split_recursive(text, start..end, rest, chunk_size, pieces);
+                start = end;
+            }
+            if start < range.end {
+                split_recursive(text, start..range.end, rest, chunk_size, pieces);
+            }
+        }
+        Some(_) => pieces.extend(
+            slice
+                .char_indices()
+                .map(|(index, c)| range.start + index..range.start + index + c.len_utf8()),
+        ),
+        None => pieces.push(range),
+    }
+}
+
+const DEFAULT_SEPARATORS: &[&str] = &["\n\n", "\n", " ", ""];
+
+/// [RecursiveCharacterSplitter] splits texts at paragraphs (`"\n\n"`), then lines, then words,
+/// then characters (or at custom separators), until the pieces are at most `chunk_size`
+/// characters long, then merges consecutive pieces into chunks of at most `chunk_size`
+/// characters, overlapping by at most `chunk_overlap` characters.
+///
+/// # Panics
+/// [RecursiveCharacterSplitter::new] panics if `chunk_overlap` is not smaller than `chunk_size`.
+#[derive(Clone, Debug)]
+pub struct RecursiveCharacterSplitter {
+    chunk_size: ChunkSize,
+    separators: Vec<&'static str>,
+}
+
+impl RecursiveCharacterSplitter {
+    pub fn new(chunk_size: usize, chunk_overlap: usize) -> Self {
+        Self {
+            chunk_size: ChunkSize::characters(chunk_size, chunk_overlap),
+            separators: DEFAULT_SEPARATORS.to_vec(),
+        }
+    }
+
+    /// Split the texts at `separators`, in order of preference, instead of paragraphs, lines,
+    /// words and characters. Add the empty separator last to split at characters as a last resort.
+    pub fn separators(mut self, separators: Vec<&'static str>) -> Self {
+        self.separators = separators;
+        self
+    }
+}
+
+impl Chunker for RecursiveCharacterSplitter {
+    fn split(&self, text: &str) -> Vec<Span> {
+        let mut pieces = vec![];
+        split_recursive(
+            text,
+            0..text.len(),
+            &self.separators,
+            &self.chunk_size,
+            &mut pieces,
+        );
+
+        self.chunk_size
+            .merge(text, &pieces)
+            .into_iter()
+            .map(Span::from)
+            .collect()
+    }
+}
+
+/// [SentenceSplitter] splits texts at sentences (ending with `.`, `!` or `?` followed by a
+/// whitespace, or with a blank line), then merges consecutive sentences into chunks of at most
+/// `chunk_size` characters, overlapping by at most `chunk_overlap` characters. Sentences longer
+/// than `chunk_size` are split at words.
+///
+/// # Panics
+/// [SentenceSplitter::new] panics if `chunk_overlap` is not smaller than `chunk_size`.
+#[derive(Clone, Debug)]
+pub struct SentenceSplitter {
+    chunk_size: ChunkSize,
+}
+
+impl SentenceSplitter {
+    pub fn new(chunk_size: usize, chunk_overlap: usize) -> Self {
+        Self {
+            chunk_size: ChunkSize::characters(chunk_size, chunk_overlap),
+        }
+    }
+}
+
+impl Chunker for SentenceSplitter {
+    fn split(&self, text: &str) -> Vec<Span> {
+        let mut pieces = vec![];
+        for sentence in sentences(text) {
+            split_recursive(text, sentence, &[" ", ""], &self.chunk_size, &mut pieces);
+        }
+
+        self.chunk_size
+            .merge(text, &pieces)
+            .into_iter()
+            .map(Span::from)
+            .collect()
+    }
+}
+
+/// Byte ranges of the sentences of `text`, including their trailing whitespace
+fn sentences(text: &str) -> Vec<Range<usize>> {
+    let mut sentences = vec![];
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((_, c)) = chars.next() {
+        let ends_sentence = match chars.peek() {
+            Some((_, next)) => {
+                (matches!(c, '.' | '!' | '?') && next.is_whitespace())
+                    || (c == '\n' && *next == '\n')
+            }
+            None => false,
+        };
+        if !ends_sentence {
+            continue;
+        }
+
+        while let Some((index, next)) = chars.peek().copied() {
+            if !next.is_whitespace() {
+                sentences.push(start..index);
+                start = index;
+                break;
+            }
+            chars.next();
+        }
+    }
+    if start < text.len() {
+        sentences.push(start..text.len());
+    }
+
+    sentences
+}
+
+/// [MarkdownSplitter] splits Markdown texts at their headings (ignoring the headings inside
+/// fenced code blocks), then splits the sections longer than `chunk_size` characters like the
+/// [RecursiveCharacterSplitter]. Chunks never span several sections, and keep track of the
+/// headings they are nested under.
+///
+/// # Panics
+/// [MarkdownSplitter::new] panics if `chunk_overlap` is not smaller than `chunk_size`.
+#[derive(Clone, Debug)]
+pub struct MarkdownSplitter {
+    chunk_size: ChunkSize,
+}
+
+impl MarkdownSplitter {
+    pub fn new(chunk_size: usize, chunk_overlap: usize) -> Self {
+        Self {
+            chunk_size: ChunkSize::characters(chunk_size, chunk_overlap),
+        }
+    }
+}
+
+/// Markdown heading: its level and its text
+type Heading = (usize, String);
+
+impl Chunker for MarkdownSplitter {
+    fn split(&self, text: &str) -> Vec<Span> {
+        // Sections of the text, with the headings they are nested under
+        let mut sections: Vec<(Range<usize>, Vec<Heading>)> = vec![(0..0, vec![])];
+        let mut fence: Option<&str> = None;
+        let mut offset = 0;
+
+        for line in text.split_inclusive('\n') {
+            let trimmed = line.trim_start();
+
+            match fence {
+                Some(marker) if trimmed.starts_with(marker) => fence = None,
+                Some(_) => {}
+                None if trimmed.starts_with("```") => fence = Some("```"),
+                None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+                None => {
+                    if let Some((level, heading)) = atx_heading(line) {
+                        let (_, enclosing) = sections.last().expect("Sections should not be empty");
+                        let mut headings = enclosing.clone();
+                        headings.retain(|(enclosing, _)| *enclosing < level);
+                        headings.push((level, heading.to_string()));
+                        sections.push((offset..offset, headings));
+                    }
+                }
+            }
+
+            offset += line.len();
+            sections
+                .last_mut()
+                .expect("Sections should not be empty")
+                .0
+                .end = offset;
+        }
+
+        sections
+            .into_iter()
+            .flat_map(|(range, headings)| {
+                let mut pieces = vec![];
+                split_recursive(
+                    text,
+                    range,
+                    DEFAULT_SEPARATORS,
+                    &self.chunk_size,
+                    &mut pieces,
+                );
+
+                let headings = headings
+                    .into_iter()
+                    .map(|(_, heading)| heading)
+                    .collect::<Vec<_>>();
+                self.chunk_size
+                    .merge(text, &pieces)
+                    .into_iter()
+                    .map(move |range| Span {
+                        range,
+                        headings: headings.clone(),
+                    })
+            })
+            .collect()
+    }
+}
+
+/// [TokenSplitter] splits texts at words, then merges consecutive words into chunks of at most
+/// `chunk_size` tokens, overlapping by at most `chunk_overlap` tokens. Words longer than
+/// `chunk_size` tokens make chunks of their own.
+///
+/// By default, tokens are estimated as 4 bytes of text each: use [TokenSplitter::tokenizer] (or
+/// [TokenSplitter::counter]) to count them with the tokenizer of the embedding model instead.
+///
+/// # Panics
+/// [TokenSplitter::new] panics if `chunk_overlap` is not smaller than `chunk_size`.
+///
+/// # Example
+/// ```rust
+/// use rig::chunking::TokenSplitter;
+///
+/// // Count whitespace-separated words as tokens
+/// let splitter = TokenSplitter::new(512, 64).counter(|text| text.split_whitespace().count());
+/// ```
+#[derive(Clone, Debug)]
+pub struct TokenSplitter {
+    chunk_size: ChunkSize,
+}
+
+impl TokenSplitter {
+    pub fn new(chunk_size: usize, chunk_overlap: usize) -> Self {
+        Self {
+            chunk_size: ChunkSize::new(
+                chunk_size,
+                chunk_overlap,
+                Arc::new(|text: &str| text.trim_end().len().div_ceil(4)),
+            ),
+        }
+    }
+
+    /// Count the tokens of the texts with `counter`
+    pub fn counter(mut self, counter: impl Fn(&str) -> usize + Send + Sync + 'static) -> Self {
+        self.chunk_size.length = Arc::new(counter);
+        self
+    }
+
+    /// Count the tokens of the texts with `tokenizer` (e.g.: the [Tokenizer] of the embedding
+    /// model)
+    pub fn tokenizer(self, tokenizer: impl Tokenizer + 'static) -> Self {
+        self.counter(move |text| tokenizer.count_text(text))
+    }
+}
+
+impl Chunker for TokenSplitter {
+    fn split(&self, text: &str) -> Vec<Span> {
+        // Words, with their trailing whitespace
+        let mut words = vec![];
+        let mut start = 0;
+        let mut previous_whitespace = false;
+        for (index, c) in text.char_indices() {
+            if previous_whitespace && !c.is_whitespace() {
+                words.push(start..index);
+                start = index;
+            }
+            previous_whitespace = c.is_whitespace();
+        }
+        if start < text.len() {
+            words.push(start..text.len());
+        }
+
+        self.chunk_size
+            .merge(text, &words)
+            .into_iter()
+            .map(Span::from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(chunker: &impl Chunker, text: &str) -> Vec<String> {
+        chunker
+            .chunk("doc", text)
+            .into_iter()
+            .map(|chunk| chunk.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_recursive_character_splitter() {
+        let text =
+            "The first paragraph.\n\nThe second paragraph is a bit longer.\nIt has two lines.";
+        let chunks = RecursiveCharacterSplitter::new(40, 0).chunk("doc", text);
+
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| chunk.text.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "The first paragraph.",
+                "The second paragraph is a bit longer.",
+                "It has two lines."
+            ]
+        );
+        for chunk in &chunks {
+            assert_eq!(&text[chunk.start..chunk.end], chunk.text);
+        }
+        assert_eq!(chunks[1].id, "doc#chunk=1");
+        assert_eq!(chunks[1].parent_id, "doc");
+    }
+
+    #[test]
+    fn test_overlap() {
+        let chunks = texts(
+            &RecursiveCharacterSplitter::new(11, 6),
+            "one two three four five",
+        );
+
+        assert_eq!(
+            chunks,
+            vec!["one two", "two three", "three four", "four five"]
+        );
+    }
+
+    #[test]
+    fn test_sentence_splitter() {
+        let chunks = texts(
+            &SentenceSplitter::new(30, 0),
+            "Rig is a library. It builds agents! Does it embed? Yes.",
+        );
+
+        assert_eq!(
+            chunks,
+            vec![
+                "Rig is a library.",
+                "It builds agents!",
+                "Does it embed? Yes."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markdown_splitter() {
+        let text = "# Guide\nIntro.\n## Install\n```\n# cargo\n```\n# Usage\nRun it.\n";
+        let chunks = MarkdownSplitter::new(100, 0).chunk("guide.md", text);
+
+        let sections = chunks
+            .iter()
+            .map(|chunk| (chunk.headings.clone(), chunk.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sections,
+            vec![
+                (vec!["Guide".to_string()], "# Guide\nIntro."),
+                (
+                    vec!["Guide".to_string(), "Install".to_string()],
+                    "## Install\n```\n# cargo\n```"
+                ),
+                (vec!["Usage".to_string()], "# Usage\nRun it."),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_token_splitter() {
+        let chunks = texts(
+            &TokenSplitter::new(3, 1).counter(|text| text.split_whitespace().count()),
+            "a b c d e f g",
+        );
+
+        assert_eq!(chunks, vec!["a b c", "c d e", "e f g"]);
+    }
+}
@@ -0,0 +1,185 @@
+//! This module provides the types used to track which retrieved documents (e.g.: chunks of a
+//! knowledge base) an [Agent](crate::agent::Agent) answered with, so that applications can show
+//! the sources of an answer.
+//!
+//! The documents retrieved from the dynamic context of an agent are returned as [Source]s (with
+//! their id, retrieval score and content) along with the answer, by
+//! [Agent::prompt_with_sources](crate::agent::Agent::prompt_with_sources) and
+//! [Agent::chat_with_sources](crate::agent::Agent::chat_with_sources).
+//!
+//! If the agent is built with [AgentBuilder::citations](crate::agent::AgentBuilder::citations),
+//! the model is also asked to cite the documents it uses with inline markers (e.g.: `[doc0]`),
+//! and the sources cited by the answer are flagged as such.
+//!
+//! # Example
+//! ```rust
+//! let agent = openai.agent(openai::GPT_4O)
+//!     .preamble("You answer questions about the Rig documentation.")
+//!     .dynamic_context(4, index)
+//!     .citations()
+//!     .build();
+//!
+//! let response = agent.prompt_with_sources("How do I define a tool?").await?;
+//!
+//! println!("{}", response.text);
+//! for source in response.cited_sources() {
+//!     println!("[{}] (score: {:.2}) {}", source.id, source.score, source.document["parent_id"]);
+//! }
+//! ```
+use serde::{Deserialize, Serialize};
+
+/// Instruction appended to the preamble of agents asked to cite their sources
+pub(crate) const CITATION_INSTRUCTIONS: &str = "When you use information from the provided \
+    documents, cite them inline with their id in square brackets, right after the information \
+    they support (e.g.: [doc1] or [doc1, doc2]). Only cite the ids of the provided documents.";
+
+/// Document retrieved from the dynamic context of an agent and provided to the model
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Source {
+    /// Id of the document in its vector store
+    pub id: String,
+    /// Retrieval score of the document (e.g.: similarity with the prompt)
+    pub score: f64,
+    /// Content of the document, with its metadata (e.g.: the `parent_id` and `headings` of a
+    /// [Chunk](crate::chunking::Chunk))
+    pub document: serde_json::Value,
+    /// Whether the answer cites the document with an inline marker
+    pub cited: bool,
+}
+
+/// Answer of an agent, with the documents it was given to answer
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SourcedResponse {
+    pub text: String,
+    /// Documents retrieved from the dynamic context of the agent, in the order they were given to
+    /// the model
+    pub sources: Vec<Source>,
+}
+
+impl SourcedResponse {
+    /// Build a response from the text of the answer and the documents given to the model, flagging
+    /// the documents cited by the answer
+    pub fn new(text: String, mut sources: Vec<Source>) -> Self {
+        let citations = citation_markers(&text);
+        for source in &mut sources {
+            source.cited = citations.contains(&source.id.as_str());
+        }
+
+        Self { text, sources }
+    }
+
+    /// The documents cited by the answer
+    pub fn cited_sources(&self) -> impl Iterator<Item = &Source> {
+        self.sources.iter().filter(|source| source.cited)
+    }
+}
+
+/// Ids cited with inline markers (e.g.: `[doc1]` or `[doc1, doc2]`) in `text`
+pub fn citation_markers(text: &str) -> Vec<&str> {
+    let mut ids = vec![];
+    let mut rest = text;
+
+    while let Some(start) = rest.find('[') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find([']', '[']) else {
+            break;
+        };
+        if rest[end..].starts_with(']') {
+            ids.extend(
+                rest[..end]
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|id| !id.is_empty()),
+            );
+        }
+        rest = &rest[end..];
+    }
+
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        agent::AgentBuilder, providers::mock::MockCompletionModel, retrieval::tests::FixedIndex,
+    };
+
+    #[test]
+    fn test_citation_markers() {
+        assert_eq!(
+            citation_markers("Flurbos are green [doc0]. They live on [doc1, doc2 ] cold [planets."),
+            vec!["doc0", "doc1", "doc2"]
+        );
+        assert!(citation_markers("No citations").is_empty());
+    }
+
+    #[test]
+    fn test_sourced_response() {
+        let source = |id: &str| Source {
+            id: id.to_string(),
+            score: 0.5,
+            document: json!({ "text": id }),
+            cited: false,
+        };
+
+        let response = SourcedResponse::new(
+            "A flurbo is green [doc1].".to_string(),
+            vec![source("doc0"), source("doc1")],
+        );
+        assert_eq!(
+            response
+                .cited_sources()
+                .map(|source| source.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["doc1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_agent_citations() {
+        let model = MockCompletionModel::new().text("Flurbos are green [doc1].");
+        let agent = AgentBuilder::new(model.clone())
+            .preamble("You know flurbos.")
+            .dynamic_context(
+                2,
+                FixedIndex(vec![
+                    (0.9, "doc0".to_string(), json!("Flurbos live on Mars.")),
+                    (0.8, "doc1".to_string(), json!("Flurbos are green.")),
+                ]),
+            )
+            .citations()
+            .build();
+
+        let response = agent
+            .prompt_with_sources("What color are flurbos?")
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "Flurbos are green [doc1].");
+        assert_eq!(
+            response
+                .sources
+                .iter()
+                .map(|source| (source.id.as_str(), source.cited))
+                .collect::<Vec<_>>(),
+            vec![("doc0", false), ("doc1", true)]
+        );
+
+        let request = &model.requests()[0];
+        assert!(request
+            .preamble
+            .as_deref()
+            .is_some_and(|preamble| preamble.ends_with(CITATION_INSTRUCTIONS)));
+        assert_eq!(
+            request
+                .documents
+                .iter()
+                .map(|document| document.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["doc0", "doc1"]
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! This module provides [BoxCompletionModel], a type-erased completion model.
+//!
+//! The [CompletionModel] trait is not object safe (its methods are generic and its raw response
+//! type is an associated type), so models of different types cannot be stored in the same
+//! collection or selected at runtime. [BoxCompletionModel] wraps any completion model behind the
+//! object safe [DynCompletionModel] trait, erasing its raw response type to
+//! `Box<dyn Any + Send + Sync>` (which can be downcast back to the `Response` type of the model).
+//! It implements [CompletionModel] itself, so it can be used to build agents, extractors, etc.
+//!
+//! Models boxed with [BoxCompletionModel::new_streaming] (or
+//! [StreamingCompletionModel::boxed_streaming]) can also stream their responses, as
+//! [BoxCompletionModel] implements [StreamingCompletionModel] too.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     agent::Agent,
+//!     completion::{BoxCompletionModel, CompletionModel},
+//!     providers::{anthropic, openai},
+//! };
+//!
+//! let model: BoxCompletionModel = match std::env::var("PROVIDER").as_deref() {
+//!     Ok("anthropic") => anthropic::Client::from_env()
+//!         .completion_model(anthropic::CLAUDE_3_5_SONNET)
+//!         .boxed(),
+//!     _ => openai::Client::from_env().completion_model(openai::GPT_4O).boxed(),
+//! };
+//!
+//! let agent: Agent<BoxCompletionModel> = rig::agent::AgentBuilder::new(model).build();
+//! ```
+
+use std::{any::Any, sync::Arc};
+
+use futures::future::LocalBoxFuture;
+
+use super::{
+    CompletionError, CompletionModel, CompletionRequest, CompletionResponse, PreparedRequest,
+};
+use crate::{
+    streaming::{StreamingCompletionModel, StreamingResult},
+    wasm_compat::WasmBoxedFuture,
+};
+
+/// Raw response of a [BoxCompletionModel]
+pub type BoxRawResponse = Box<dyn Any + Send + Sync>;
+
+/// Object safe version of [CompletionModel], implemented by all completion models.
+///
+/// The methods are prefixed with `dyn_` so that they do not conflict with the methods of
+/// [CompletionModel] when both traits are in scope.
+pub trait DynCompletionModel: Send + Sync {
+    /// Generates a completion response for the given completion request
+    /// (see [CompletionModel::completion]).
+    fn dyn_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> WasmBoxedFuture<'_, Result<CompletionResponse<BoxRawResponse>, CompletionError>>;
+
+    /// Prepares the HTTP request that would be sent to the provider for the given completion
+    /// request (see [CompletionModel::prepare_request]).
+    fn dyn_prepare_request(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<PreparedRequest, CompletionError>;
+}
+
+impl<M> DynCompletionModel for M
+where
+    M: CompletionModel + 'static,
+    M::Response: 'static,
+{
+    fn dyn_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> WasmBoxedFuture<'_, Result<CompletionResponse<BoxRawResponse>, CompletionError>> {
+        Box::pin(async move {
+            let response = self.completion(request).await?;
+
+            Ok(CompletionResponse {
+                choice: response.choice,
+                usage: response.usage,
+                timing: response.timing,
+                raw_response: Box::new(response.raw_response) as BoxRawResponse,
+            })
+        })
+    }
+
+    fn dyn_prepare_request(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<PreparedRequest, CompletionError> {
+        self.prepare_request(request)
+    }
+}
+
+/// Object safe version of [StreamingCompletionModel], implemented by all streaming completion
+/// models.
+///
+/// The future returned by [StreamingCompletionModel::stream] is not required to be `Send`, hence
+/// the local future.
+pub trait DynStreamingCompletionModel: Send + Sync {
+    /// Streams a completion response for the given completion request
+    /// (see [StreamingCompletionModel::stream]).
+    fn dyn_stream(
+        &self,
+        request: CompletionRequest,
+    ) -> LocalBoxFuture<'_, Result<StreamingResult, CompletionError>>;
+}
+
+impl<M> DynStreamingCompletionModel for M
+where
+    M: StreamingCompletionModel + 'static,
+{
+    fn dyn_stream(
+        &self,
+        request: CompletionRequest,
+    ) -> LocalBoxFuture<'_, Result<StreamingResult, CompletionError>> {
+        Box::pin(self.stream(request))
+    }
+}
+
+/// Type-erased completion model. Cheap to clone.
+#[derive(Clone)]
+pub struct BoxCompletionModel {
+    model: Arc<dyn DynCompletionModel>,
+    streaming: Option<Arc<dyn DynStreamingCompletionModel>>,
+}
+
+impl BoxCompletionModel {
+    /// Box a completion model. The boxed model cannot stream its responses, use
+    /// [BoxCompletionModel::new_streaming] for streaming completion models.
+    pub fn new<M>(model: M) -> Self
+    where
+        M: CompletionModel + 'static,
+        M::Response: 'static,
+    {
+        Self {
+            model: Arc::new(model),
+            streaming: None,
+        }
+    }
+
+    /// Box a streaming completion model, keeping its ability to stream its responses
+    pub fn new_streaming<M>(model: M) -> Self
+    where
+        M: StreamingCompletionModel + 'static,
+        M::Response: 'static,
+    {
+        let model = Arc::new(model);
+
+        Self {
+            model: model.clone(),
+            streaming: Some(model),
+        }
+    }
+
+    /// Whether the boxed model can stream its responses
+    pub fn is_streaming(&self) -> bool {
+        self.streaming.is_some()
+    }
+}
+
+impl CompletionModel for BoxCompletionModel {
+    /// The raw response of the wrapped model, which can be downcast to its `Response` type
+    type Response = BoxRawResponse;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<BoxRawResponse>, CompletionError> {
+        self.model.dyn_completion(request).await
+    }
+
+    fn prepare_request(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<PreparedRequest, CompletionError> {
+        self.model.dyn_prepare_request(request)
+    }
+
+    fn boxed(self) -> BoxCompletionModel {
+        self
+    }
+}
+
+impl StreamingCompletionModel for BoxCompletionModel {
+    /// Streams the response of the wrapped model, or fails with a
+    /// [CompletionError::RequestError] if it was not boxed with
+    /// [BoxCompletionModel::new_streaming]
+    async fn stream(&self, request: CompletionRequest) -> Result<StreamingResult, CompletionError> {
+        match &self.streaming {
+            Some(model) => model.dyn_stream(request).await,
+            None => Err(CompletionError::RequestError(
+                "Streaming is not supported by this completion model".into(),
+            )),
+        }
+    }
+
+    fn boxed_streaming(self) -> BoxCompletionModel {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        completion::{Prompt, ResponseTiming, Usage},
+        message::AssistantContent,
+        providers::mock::MockCompletionModel,
+        streaming::{StreamingChoice, StreamingPrompt},
+        OneOrMany,
+    };
+    use futures::StreamExt;
+
+    #[derive(Clone)]
+    struct ConstantModel(&'static str);
+
+    impl CompletionModel for ConstantModel {
+        type Response = usize;
+
+        async fn completion(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<CompletionResponse<usize>, CompletionError> {
+            Ok(CompletionResponse {
+                choice: OneOrMany::one(AssistantContent::text(self.0)),
+                usage: Usage::default(),
+                timing: ResponseTiming::default(),
+                raw_response: self.0.len(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_box_completion_model() {
+        let models = [ConstantModel("foo").boxed(), ConstantModel("quux").boxed()];
+
+        let response = models[1]
+            .completion_request("Hi")
+            .send()
+            .await
+            .expect("Model should respond");
+        assert_eq!(response.raw_response.downcast_ref::<usize>(), Some(&4));
+
+        let agent = crate::agent::AgentBuilder::new(models[0].clone()).build();
+        assert_eq!(agent.prompt("Hi").await.unwrap(), "foo");
+    }
+
+    #[tokio::test]
+    async fn test_box_streaming_completion_model() {
+        let model = MockCompletionModel::new().text("Hello!").boxed_streaming();
+        assert!(model.is_streaming());
+
+        let agent = crate::agent::AgentBuilder::new(model).build();
+        let mut stream = agent.stream_prompt("Hi").await.unwrap();
+        let chunk = stream.next().await.unwrap().unwrap();
+        assert!(matches!(chunk, StreamingChoice::Message(text) if text == "Hello!"));
+
+        let model = ConstantModel("foo").boxed();
+        assert!(!model.is_streaming());
+        assert!(matches!(
+            model.completion_request("Hi").stream().await,
+            Err(CompletionError::RequestError(_))
+        ));
+    }
+}
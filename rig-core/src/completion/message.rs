@@ -1,6 +1,7 @@
 use std::{convert::Infallible, str::FromStr};
 
-use crate::OneOrMany;
+use crate::{EmptyListError, OneOrMany};
+use base64::{prelude::BASE64_STANDARD, Engine};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -10,7 +11,7 @@ use super::CompletionError;
 // Message models
 // ================================================================
 
-/// A message represents a run of input (user) and output (assistant).
+/// A message represents a run of input (user), output (assistant) or instructions (system).
 /// Each message type (based on it's `role`) can contain a atleast one bit of content such as text,
 ///  images, audio, documents, or tool related information. While each message type can contain
 ///  multiple content, most often, you'll only see one content type per message
@@ -30,6 +31,11 @@ pub enum Message {
     Assistant {
         content: OneOrMany<AssistantContent>,
     },
+
+    /// System message with instructions given at any point of the conversation (e.g.: "now
+    /// answer in French"), in addition to the preamble. Providers without system messages merge
+    /// them into their system prompt.
+    System { content: String },
 }
 
 /// Describes the content of a message, which can be text, a tool result, an image, audio, or
@@ -92,6 +98,11 @@ pub struct Text {
 }
 
 /// Image content containing image data and metadata about it.
+///
+/// The image is either base64 encoded data (`ContentFormat::Base64`, see [Image::base64]) or a URL
+/// (`ContentFormat::String`, see [Image::url]): a http(s) URL, a data URL or a provider specific
+/// URI (e.g.: a `gs://` URI for Gemini). Each provider converts the image into its own format
+/// (e.g.: Anthropic image blocks, OpenAI `image_url` parts or Gemini `inline_data` parts).
 #[derive(Default, Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Image {
     pub data: String,
@@ -104,6 +115,10 @@ pub struct Image {
 }
 
 /// Audio content containing audio data and metadata about it.
+///
+/// The audio is either base64 encoded data (see [Audio::base64] and [Audio::from_bytes]) or a URL
+/// (`ContentFormat::String`, e.g.: the URI of a file uploaded to Gemini). Only some providers
+/// accept audio in chat (e.g.: OpenAI audio models such as `gpt-4o-audio-preview`, or Gemini).
 #[derive(Default, Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Audio {
     pub data: String,
@@ -114,6 +129,12 @@ pub struct Audio {
 }
 
 /// Document content containing document data and metadata about it.
+///
+/// The document is either base64 encoded data (e.g.: the bytes of a PDF, see
+/// [Document::from_bytes]), plain text (`ContentFormat::String`) or the id of a file uploaded to
+/// the provider (`ContentFormat::FileId`, see [Document::file_id]), so that providers which read
+/// documents natively (e.g.: Anthropic document blocks or Gemini `file_data` parts) are given the
+/// document itself rather than text extracted from it.
 #[derive(Default, Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Document {
     pub data: String,
@@ -123,13 +144,18 @@ pub struct Document {
     pub media_type: Option<DocumentMediaType>,
 }
 
-/// Describes the format of the content, which can be base64 or string.
+/// Describes the format of the content, which can be base64, string (e.g.: the URL of an image)
+/// or the id of a file uploaded to the provider.
 #[derive(Default, Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ContentFormat {
     #[default]
     Base64,
     String,
+    /// Id of a file uploaded to the provider (e.g.: with the Anthropic Files API), or URI of a
+    /// file uploaded to Gemini
+    #[serde(rename = "file_id")]
+    FileId,
 }
 
 /// Helper enum that tracks the media type of the content.
@@ -229,6 +255,188 @@ impl Message {
             content: OneOrMany::one(AssistantContent::text(text)),
         }
     }
+
+    /// Helper constructor to make creating system messages easier.
+    pub fn system(text: impl Into<String>) -> Self {
+        Message::System {
+            content: text.into(),
+        }
+    }
+
+    /// Builder of a user message with mixed content (see [UserMessageBuilder])
+    pub fn user_builder() -> UserMessageBuilder {
+        UserMessageBuilder::default()
+    }
+
+    /// Builder of an assistant message with mixed content (see [AssistantMessageBuilder])
+    pub fn assistant_builder() -> AssistantMessageBuilder {
+        AssistantMessageBuilder::default()
+    }
+}
+
+impl Image {
+    /// Image at `url` (a http(s) URL, a data URL or a provider specific URI)
+    pub fn url(url: impl Into<String>) -> Self {
+        Image {
+            data: url.into(),
+            format: Some(ContentFormat::String),
+            ..Default::default()
+        }
+    }
+
+    /// Base64 encoded image of the given media type
+    pub fn base64(data: impl Into<String>, media_type: ImageMediaType) -> Self {
+        Image {
+            data: data.into(),
+            format: Some(ContentFormat::Base64),
+            media_type: Some(media_type),
+            detail: None,
+        }
+    }
+
+    /// Image of the given media type from its raw bytes (e.g.: the bytes of a PNG file)
+    pub fn from_bytes(bytes: impl AsRef<[u8]>, media_type: ImageMediaType) -> Self {
+        Image::base64(BASE64_STANDARD.encode(bytes), media_type)
+    }
+
+    /// Image parsed from `url`: the base64 encoded image of a data URL (e.g.:
+    /// `data:image/png;base64,...`), or the image at the URL otherwise
+    pub fn from_url(url: impl Into<String>) -> Self {
+        let url = url.into();
+        match parse_data_url(&url) {
+            Some((mime_type, data)) => match ImageMediaType::from_mime_type(mime_type) {
+                Some(media_type) => Image::base64(data, media_type),
+                None => Image::url(url),
+            },
+            None => Image::url(url),
+        }
+    }
+
+    /// Set the detail of the image (OpenAI specific)
+    pub fn with_detail(mut self, detail: ImageDetail) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+
+    /// Whether the image is a URL (images without format are URLs if their data looks like one)
+    pub fn is_url(&self) -> bool {
+        match self.format {
+            Some(ContentFormat::String | ContentFormat::FileId) => true,
+            Some(ContentFormat::Base64) => false,
+            None => looks_like_url(&self.data),
+        }
+    }
+
+    /// URL of the image: its URL, or a data URL of its base64 encoded data (as JPEG if its media
+    /// type is unknown)
+    pub fn to_url(&self) -> String {
+        if self.is_url() {
+            return self.data.clone();
+        }
+
+        let mime_type = self
+            .media_type
+            .as_ref()
+            .map(MimeType::to_mime_type)
+            .unwrap_or("image/jpeg");
+        format!("data:{mime_type};base64,{}", self.data)
+    }
+
+    /// The image with its data URL (if any) decoded into base64 encoded data, for providers
+    /// which do not accept data URLs
+    pub(crate) fn resolve_data_url(self) -> Self {
+        if !self.is_url() {
+            return self;
+        }
+
+        let image = Image::from_url(self.data);
+        Image {
+            media_type: image.media_type.or(self.media_type),
+            detail: self.detail,
+            ..image
+        }
+    }
+}
+
+impl Audio {
+    /// Base64 encoded audio of the given media type
+    pub fn base64(data: impl Into<String>, media_type: AudioMediaType) -> Self {
+        Audio {
+            data: data.into(),
+            format: Some(ContentFormat::Base64),
+            media_type: Some(media_type),
+        }
+    }
+
+    /// Audio of the given media type from its raw bytes (e.g.: read from a file)
+    pub fn from_bytes(bytes: impl AsRef<[u8]>, media_type: AudioMediaType) -> Self {
+        Audio::base64(BASE64_STANDARD.encode(bytes), media_type)
+    }
+
+    /// Audio at `url` (a provider specific URI)
+    pub fn url(url: impl Into<String>, media_type: Option<AudioMediaType>) -> Self {
+        Audio {
+            data: url.into(),
+            format: Some(ContentFormat::String),
+            media_type,
+        }
+    }
+
+    /// Whether the audio is a URL (audio without format is a URL if its data looks like one)
+    pub fn is_url(&self) -> bool {
+        match self.format {
+            Some(ContentFormat::String | ContentFormat::FileId) => true,
+            Some(ContentFormat::Base64) => false,
+            None => looks_like_url(&self.data),
+        }
+    }
+}
+
+impl Document {
+    /// Base64 encoded document of the given media type
+    pub fn base64(data: impl Into<String>, media_type: DocumentMediaType) -> Self {
+        Document {
+            data: data.into(),
+            format: Some(ContentFormat::Base64),
+            media_type: Some(media_type),
+        }
+    }
+
+    /// Document of the given media type from its raw bytes (e.g.: the bytes of a PDF file)
+    pub fn from_bytes(bytes: impl AsRef<[u8]>, media_type: DocumentMediaType) -> Self {
+        Document::base64(BASE64_STANDARD.encode(bytes), media_type)
+    }
+
+    /// Plain text document
+    pub fn text(text: impl Into<String>) -> Self {
+        Document {
+            data: text.into(),
+            format: Some(ContentFormat::String),
+            media_type: Some(DocumentMediaType::TXT),
+        }
+    }
+
+    /// Document uploaded to the provider, with the given file id (e.g.: `file_011CNha8iCJc` for
+    /// Anthropic, or the URI of the file for Gemini)
+    pub fn file_id(file_id: impl Into<String>, media_type: Option<DocumentMediaType>) -> Self {
+        Document {
+            data: file_id.into(),
+            format: Some(ContentFormat::FileId),
+            media_type,
+        }
+    }
+}
+
+/// Whether `data` looks like a URL rather than base64 encoded data (which never contains `:`)
+fn looks_like_url(data: &str) -> bool {
+    data.starts_with("data:") || data.contains("://")
+}
+
+/// MIME type and base64 encoded data of a data URL (`data:{mime type};base64,{data}`)
+pub(crate) fn parse_data_url(url: &str) -> Option<(&str, &str)> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let mime_type = header.strip_suffix(";base64")?;
+    Some((mime_type, data))
 }
 
 impl UserContent {
@@ -252,6 +460,16 @@ impl UserContent {
         })
     }
 
+    /// Helper constructor to make creating user image content from a URL easier.
+    pub fn image_url(url: impl Into<String>) -> Self {
+        UserContent::Image(Image::url(url))
+    }
+
+    /// Helper constructor to make creating user image content from base64 encoded data easier.
+    pub fn image_base64(data: impl Into<String>, media_type: ImageMediaType) -> Self {
+        UserContent::Image(Image::base64(data, media_type))
+    }
+
     /// Helper constructor to make creating user audio content easier.
     pub fn audio(
         data: impl Into<String>,
@@ -265,6 +483,11 @@ impl UserContent {
         })
     }
 
+    /// Helper constructor to make creating user audio content from base64 encoded data easier.
+    pub fn audio_base64(data: impl Into<String>, media_type: AudioMediaType) -> Self {
+        UserContent::Audio(Audio::base64(data, media_type))
+    }
+
     /// Helper constructor to make creating user document content easier.
     pub fn document(
         data: impl Into<String>,
@@ -422,9 +645,9 @@ impl MimeType for DocumentMediaType {
 impl MimeType for AudioMediaType {
     fn from_mime_type(mime_type: &str) -> Option<Self> {
         match mime_type {
-            "audio/wav" => Some(AudioMediaType::WAV),
-            "audio/mp3" => Some(AudioMediaType::MP3),
-            "audio/aiff" => Some(AudioMediaType::AIFF),
+            "audio/wav" | "audio/x-wav" | "audio/wave" => Some(AudioMediaType::WAV),
+            "audio/mp3" | "audio/mpeg" => Some(AudioMediaType::MP3),
+            "audio/aiff" | "audio/x-aiff" => Some(AudioMediaType::AIFF),
             "audio/aac" => Some(AudioMediaType::AAC),
             "audio/ogg" => Some(AudioMediaType::OGG),
             "audio/flac" => Some(AudioMediaType::FLAC),
@@ -457,6 +680,139 @@ impl std::str::FromStr for ImageDetail {
     }
 }
 
+// ================================================================
+// Message builders
+// ================================================================
+
+/// Builder of a user message with mixed content (text, images, audio, documents and tool
+/// results), in the order the content is added.
+///
+/// # Example
+/// ```rust
+/// use rig::message::{ImageMediaType, Message};
+///
+/// let message = Message::user_builder()
+///     .text("Compare the chart with the figures of the report")
+///     .image_bytes(std::fs::read("chart.png")?, ImageMediaType::PNG)
+///     .pdf(std::fs::read("report.pdf")?)
+///     .build()?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UserMessageBuilder {
+    content: Vec<UserContent>,
+}
+
+impl UserMessageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add any content (e.g.: an [Image], [Audio] or [Document])
+    pub fn content(mut self, content: impl Into<UserContent>) -> Self {
+        self.content.push(content.into());
+        self
+    }
+
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.content(UserContent::text(text))
+    }
+
+    pub fn image(self, image: Image) -> Self {
+        self.content(image)
+    }
+
+    /// Add the image at `url` (see [Image::url])
+    pub fn image_url(self, url: impl Into<String>) -> Self {
+        self.content(Image::url(url))
+    }
+
+    /// Add an image from its raw bytes
+    pub fn image_bytes(self, bytes: impl AsRef<[u8]>, media_type: ImageMediaType) -> Self {
+        self.content(Image::from_bytes(bytes, media_type))
+    }
+
+    pub fn audio(self, audio: Audio) -> Self {
+        self.content(audio)
+    }
+
+    /// Add an audio clip from its raw bytes
+    pub fn audio_bytes(self, bytes: impl AsRef<[u8]>, media_type: AudioMediaType) -> Self {
+        self.content(Audio::from_bytes(bytes, media_type))
+    }
+
+    pub fn document(self, document: Document) -> Self {
+        self.content(document)
+    }
+
+    /// Add a PDF document from its raw bytes
+    pub fn pdf(self, bytes: impl AsRef<[u8]>) -> Self {
+        self.content(Document::from_bytes(bytes, DocumentMediaType::PDF))
+    }
+
+    /// Add the text result of the tool call `id`
+    pub fn tool_result(self, id: impl Into<String>, text: impl Into<String>) -> Self {
+        self.content(UserContent::tool_result(
+            id,
+            OneOrMany::one(ToolResultContent::text(text)),
+        ))
+    }
+
+    /// Build the message (which must have some content)
+    pub fn build(self) -> Result<Message, EmptyListError> {
+        Ok(Message::User {
+            content: OneOrMany::many(self.content)?,
+        })
+    }
+}
+
+/// Builder of an assistant message with mixed content (text and tool calls), e.g.: to write the
+/// chat history of a few-shot prompt.
+///
+/// # Example
+/// ```rust
+/// use rig::message::Message;
+///
+/// let message = Message::assistant_builder()
+///     .text("Let me check the weather")
+///     .tool_call("call_1", "get_weather", serde_json::json!({"city": "Paris"}))
+///     .build()?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AssistantMessageBuilder {
+    content: Vec<AssistantContent>,
+}
+
+impl AssistantMessageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn content(mut self, content: impl Into<AssistantContent>) -> Self {
+        self.content.push(content.into());
+        self
+    }
+
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.content(AssistantContent::text(text))
+    }
+
+    pub fn tool_call(
+        self,
+        id: impl Into<String>,
+        name: impl Into<String>,
+        arguments: serde_json::Value,
+    ) -> Self {
+        self.content(AssistantContent::tool_call(id, name, arguments))
+    }
+
+    /// Build the message (which must have some content)
+    pub fn build(self) -> Result<Message, EmptyListError> {
+        Ok(Message::Assistant {
+            content: OneOrMany::many(self.content)?,
+        })
+    }
+}
+
 // ================================================================
 // FromStr, From<String>, and From<&str> impls
 // ================================================================
@@ -547,6 +903,42 @@ impl From<String> for UserContent {
     }
 }
 
+impl From<Text> for UserContent {
+    fn from(text: Text) -> Self {
+        UserContent::Text(text)
+    }
+}
+
+impl From<Image> for UserContent {
+    fn from(image: Image) -> Self {
+        UserContent::Image(image)
+    }
+}
+
+impl From<Audio> for UserContent {
+    fn from(audio: Audio) -> Self {
+        UserContent::Audio(audio)
+    }
+}
+
+impl From<Document> for UserContent {
+    fn from(document: Document) -> Self {
+        UserContent::Document(document)
+    }
+}
+
+impl From<ToolResult> for UserContent {
+    fn from(tool_result: ToolResult) -> Self {
+        UserContent::ToolResult(tool_result)
+    }
+}
+
+impl From<ToolCall> for AssistantContent {
+    fn from(tool_call: ToolCall) -> Self {
+        AssistantContent::ToolCall(tool_call)
+    }
+}
+
 // ================================================================
 // Error types
 // ================================================================
@@ -556,6 +948,9 @@ impl From<String> for UserContent {
 pub enum MessageError {
     #[error("Message conversion error: {0}")]
     ConversionError(String),
+
+    #[error("Message JSON error: {0}")]
+    JsonError(serde_json::Error),
 }
 
 impl From<MessageError> for CompletionError {
@@ -563,3 +958,61 @@ impl From<MessageError> for CompletionError {
         CompletionError::RequestError(error.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_builders() {
+        let message = Message::user_builder()
+            .text("Compare the chart with the report")
+            .image_bytes(b"hello", ImageMediaType::PNG)
+            .pdf(b"%PDF")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            message,
+            Message::User {
+                content: OneOrMany::many(vec![
+                    UserContent::text("Compare the chart with the report"),
+                    UserContent::image_base64("aGVsbG8=", ImageMediaType::PNG),
+                    UserContent::document(
+                        "JVBERg==",
+                        Some(ContentFormat::Base64),
+                        Some(DocumentMediaType::PDF),
+                    ),
+                ])
+                .unwrap(),
+            }
+        );
+
+        let message = Message::assistant_builder()
+            .text("Let me check")
+            .tool_call(
+                "call_1",
+                "get_weather",
+                serde_json::json!({"city": "Paris"}),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            message,
+            Message::Assistant {
+                content: OneOrMany::many(vec![
+                    AssistantContent::text("Let me check"),
+                    AssistantContent::tool_call(
+                        "call_1",
+                        "get_weather",
+                        serde_json::json!({"city": "Paris"}),
+                    ),
+                ])
+                .unwrap(),
+            }
+        );
+
+        assert!(Message::user_builder().build().is_err());
+    }
+}
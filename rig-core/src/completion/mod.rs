@@ -1,5 +1,9 @@
+pub mod boxed;
 pub mod message;
 pub mod request;
 
+pub use boxed::{
+    BoxCompletionModel, BoxRawResponse, DynCompletionModel, DynStreamingCompletionModel,
+};
 pub use message::{AssistantContent, Message, MessageError};
 pub use request::*;
@@ -63,26 +63,36 @@
 //! For more information on how to use the completion functionality, refer to the documentation of
 //! the individual traits, structs, and enums defined in this module.
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tracing::{field, Instrument};
 
-use crate::streaming::{StreamingCompletionModel, StreamingResult};
+use crate::streaming::{StreamingChoice, StreamingCompletionModel, StreamingResult};
 use crate::OneOrMany;
 use crate::{
+    http_client::{self, ApiError},
     json_utils,
     message::{Message, UserContent},
+    retrieval::{ContextOverflowError, ContextPacker},
+    telemetry,
     tool::ToolSetError,
+    wasm_compat::{Instant, WasmCompatSend},
 };
 
+use super::boxed::BoxCompletionModel;
 use super::message::AssistantContent;
 
 // Errors
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum CompletionError {
     /// Http error (e.g.: connection error, timeout, etc.)
     #[error("HttpError: {0}")]
-    HttpError(#[from] reqwest::Error),
+    HttpError(#[from] crate::http_client::HttpError),
 
     /// Json error (e.g.: serialization, deserialization)
     #[error("JsonError: {0}")]
@@ -99,6 +109,55 @@ pub enum CompletionError {
     /// Error returned by the completion model provider
     #[error("ProviderError: {0}")]
     ProviderError(String),
+
+    /// Error response returned by the completion model provider (e.g.: rate limiting,
+    /// authentication error, invalid request)
+    #[error("ApiError: {0}")]
+    ApiError(#[from] ApiError),
+}
+
+impl From<reqwest::Error> for CompletionError {
+    fn from(error: reqwest::Error) -> Self {
+        CompletionError::HttpError(error.into())
+    }
+}
+
+impl CompletionError {
+    /// The error response returned by the provider, if any
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            CompletionError::ApiError(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status of the error response, if any
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self {
+            CompletionError::HttpError(error) => error.status(),
+            CompletionError::ApiError(error) => Some(error.status),
+            _ => None,
+        }
+    }
+
+    /// Delay requested by the provider before retrying, if any
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            CompletionError::HttpError(error) => error.retry_after(),
+            CompletionError::ApiError(error) => error.retry_after,
+            _ => None,
+        }
+    }
+
+    /// Whether the request may succeed if retried (e.g.: connection error, rate limiting or
+    /// server error)
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            CompletionError::HttpError(error) => error.is_retryable(),
+            CompletionError::ApiError(error) => error.is_retryable(),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -163,7 +222,7 @@ pub trait Prompt: Send + Sync {
     fn prompt(
         &self,
         prompt: impl Into<Message> + Send,
-    ) -> impl std::future::Future<Output = Result<String, PromptError>> + Send;
+    ) -> impl std::future::Future<Output = Result<String, PromptError>> + WasmCompatSend;
 }
 
 /// Trait defining a high-level LLM chat interface (i.e.: prompt and chat history in, response out).
@@ -180,7 +239,7 @@ pub trait Chat: Send + Sync {
         &self,
         prompt: impl Into<Message> + Send,
         chat_history: Vec<Message>,
-    ) -> impl std::future::Future<Output = Result<String, PromptError>> + Send;
+    ) -> impl std::future::Future<Output = Result<String, PromptError>> + WasmCompatSend;
 }
 
 /// Trait defining a low-level LLM completion interface
@@ -200,20 +259,254 @@ pub trait Completion<M: CompletionModel> {
         &self,
         prompt: impl Into<Message> + Send,
         chat_history: Vec<Message>,
-    ) -> impl std::future::Future<Output = Result<CompletionRequestBuilder<M>, CompletionError>> + Send;
+    ) -> impl std::future::Future<Output = Result<CompletionRequestBuilder<M>, CompletionError>>
+           + WasmCompatSend;
 }
 
 /// General completion response struct that contains the high-level completion choice
 /// and the raw response. The completion choice contains one or more assistant content.
+///
+/// Custom [CompletionModel]s outside of rig build their responses with
+/// [CompletionResponse::new], as the struct is `#[non_exhaustive]`.
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct CompletionResponse<T> {
     /// The completion choice (represented by one or more assistant message content)
     /// returned by the completion model provider
     pub choice: OneOrMany<AssistantContent>,
+    /// The token usage reported by the completion model provider (zero if not reported)
+    pub usage: Usage,
+    /// Timing of the request (filled in by [CompletionRequestBuilder::send])
+    pub timing: ResponseTiming,
     /// The raw response returned by the completion model provider
     pub raw_response: T,
 }
 
+impl<T> CompletionResponse<T> {
+    /// Create a response from the completion choice and the raw response of the provider, with
+    /// no token usage (see [CompletionResponse::with_usage]) and the default timing
+    pub fn new(choice: OneOrMany<AssistantContent>, raw_response: T) -> Self {
+        Self {
+            choice,
+            usage: Usage::default(),
+            timing: ResponseTiming::default(),
+            raw_response,
+        }
+    }
+
+    /// Set the token usage reported by the provider
+    pub fn with_usage(mut self, usage: Usage) -> Self {
+        self.usage = usage;
+        self
+    }
+}
+
+/// Token usage of one or more completion requests
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Usage {
+    /// Number of tokens in the prompt (including chat history, documents and tools)
+    pub input_tokens: u64,
+    /// Number of tokens generated by the model
+    pub output_tokens: u64,
+    /// Total number of tokens (usually `input_tokens + output_tokens`)
+    pub total_tokens: u64,
+}
+
+impl Usage {
+    /// Create a new usage from the number of input and output tokens
+    pub fn new(input_tokens: u64, output_tokens: u64) -> Self {
+        Self {
+            input_tokens,
+            output_tokens,
+            total_tokens: input_tokens + output_tokens,
+        }
+    }
+}
+
+impl std::ops::Add for Usage {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            input_tokens: self.input_tokens + other.input_tokens,
+            output_tokens: self.output_tokens + other.output_tokens,
+            total_tokens: self.total_tokens + other.total_tokens,
+        }
+    }
+}
+
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl std::iter::Sum for Usage {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Usage::default(), |acc, usage| acc + usage)
+    }
+}
+
+impl std::fmt::Display for Usage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Input tokens: {} Output tokens: {} Total tokens: {}",
+            self.input_tokens, self.output_tokens, self.total_tokens
+        )
+    }
+}
+
+/// Latency breakdown of a completion or embedding request
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResponseTiming {
+    /// Time spent waiting before the request was sent (e.g.: for a rate limiter)
+    pub queue_time: Duration,
+    /// Time until the first byte of the response was received, if measured by the model
+    pub time_to_first_byte: Option<Duration>,
+    /// Total duration of the request, including queueing and retries
+    pub total: Duration,
+    /// Number of times the request was retried
+    pub retries: u32,
+}
+
+/// Headers whose values are redacted from [PreparedRequest]s
+const SENSITIVE_HEADERS: &[&str] = &["authorization", "x-api-key", "api-key", "x-goog-api-key"];
+/// Query parameters whose values are redacted from [PreparedRequest]s
+const SENSITIVE_QUERY_PARAMS: &[&str] = &["key", "api_key", "api-key"];
+const REDACTED: &str = "<REDACTED>";
+
+/// HTTP request prepared by a completion model provider for a [CompletionRequest], as returned by
+/// [CompletionRequestBuilder::dry_run]. Useful to reproduce issues against the provider directly.
+///
+/// Credentials (e.g.: `Authorization` header, `key` query parameter) are redacted.
+#[derive(Clone, Debug, Serialize)]
+pub struct PreparedRequest {
+    /// HTTP method (e.g.: `POST`)
+    pub method: String,
+    /// Full URL of the request
+    pub url: String,
+    /// Request specific headers
+    pub headers: Vec<(String, String)>,
+    /// JSON body of the request (`null` if the request has no body)
+    pub body: serde_json::Value,
+}
+
+impl PreparedRequest {
+    /// Builds the request of a [reqwest::RequestBuilder] (or [crate::http_client::RequestBuilder])
+    /// without sending it.
+    pub fn from_request_builder(
+        builder: impl Into<reqwest::RequestBuilder>,
+    ) -> Result<Self, CompletionError> {
+        let request = builder.into().build()?;
+
+        let mut url = request.url().clone();
+        if url.query().is_some() {
+            let query = url
+                .query_pairs()
+                .map(|(name, value)| {
+                    let value = if SENSITIVE_QUERY_PARAMS.contains(&name.to_lowercase().as_str()) {
+                        REDACTED.to_string()
+                    } else {
+                        value.into_owned()
+                    };
+                    (name.into_owned(), value)
+                })
+                .collect::<Vec<_>>();
+            url.query_pairs_mut().clear().extend_pairs(query);
+        }
+
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                    REDACTED
+                } else {
+                    value.to_str().unwrap_or_default()
+                };
+                (name.to_string(), value.to_string())
+            })
+            .collect();
+
+        let body = match request.body().and_then(|body| body.as_bytes()) {
+            Some(bytes) => serde_json::from_slice(bytes)?,
+            None => serde_json::Value::Null,
+        };
+
+        Ok(Self {
+            method: request.method().to_string(),
+            url: url.to_string(),
+            headers,
+            body,
+        })
+    }
+
+    /// Renders the request as a `curl` command
+    pub fn to_curl(&self) -> String {
+        let mut curl = format!("curl -X {} '{}'", self.method, self.url);
+
+        for (name, value) in &self.headers {
+            curl.push_str(&format!(" \\\n  -H '{name}: {value}'"));
+        }
+
+        if !self.body.is_null() {
+            // Escape single quotes for the shell
+            let body = self.body.to_string().replace('\'', r"'\''");
+            curl.push_str(&format!(" \\\n  -d '{body}'"));
+        }
+
+        curl
+    }
+}
+
+/// Accumulates the token usage of all the completion requests it is attached to.
+///
+/// The tracker is cheap to clone and all clones share the same total, so a single tracker can
+/// be attached to several agents, extractors or completion requests (e.g.: all the agents of a
+/// pipeline, or all the turns of a multi-turn tool loop) to meter a whole run.
+///
+/// # Example
+/// ```rust
+/// use rig::{completion::{Prompt, UsageTracker}, providers::openai};
+///
+/// let openai = openai::Client::from_env();
+/// let tracker = UsageTracker::new();
+///
+/// let researcher = openai.agent(openai::GPT_4O).usage_tracker(tracker.clone()).build();
+/// let writer = openai.agent(openai::GPT_4O_MINI).usage_tracker(tracker.clone()).build();
+///
+/// let notes = researcher.prompt("Research the history of Rust").await?;
+/// let article = writer.prompt(notes).await?;
+///
+/// println!("Run usage: {}", tracker.total_usage());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UsageTracker {
+    usage: Arc<Mutex<Usage>>,
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the usage of a request to the total
+    pub fn add(&self, usage: Usage) {
+        *self.usage.lock().expect("Mutex should not be poisoned") += usage;
+    }
+
+    /// Total usage of all the requests recorded so far
+    pub fn total_usage(&self) -> Usage {
+        *self.usage.lock().expect("Mutex should not be poisoned")
+    }
+
+    /// Reset the total usage to zero
+    pub fn reset(&self) {
+        *self.usage.lock().expect("Mutex should not be poisoned") = Usage::default();
+    }
+}
+
 /// Trait defining a completion model that can be used to generate completion responses.
 /// This trait is meant to be implemented by the user to define a custom completion model,
 /// either from a third party provider (e.g.: OpenAI) or a local model.
@@ -226,15 +519,39 @@ pub trait CompletionModel: Clone + Send + Sync {
         &self,
         request: CompletionRequest,
     ) -> impl std::future::Future<Output = Result<CompletionResponse<Self::Response>, CompletionError>>
-           + Send;
+           + WasmCompatSend;
+
+    /// Prepares the HTTP request that would be sent to the provider for the given completion
+    /// request, without sending it (see [CompletionRequestBuilder::dry_run]).
+    /// The default implementation returns an error, for models that do not support it.
+    fn prepare_request(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<PreparedRequest, CompletionError> {
+        let _ = request;
+        Err(CompletionError::RequestError(
+            "Dry run is not supported by this completion model".into(),
+        ))
+    }
 
     /// Generates a completion request builder for the given `prompt`.
     fn completion_request(&self, prompt: impl Into<Message>) -> CompletionRequestBuilder<Self> {
         CompletionRequestBuilder::new(self.clone(), prompt)
     }
+
+    /// Erases the type of the model, e.g.: to store models of different types in the same
+    /// collection (see [BoxCompletionModel]).
+    fn boxed(self) -> BoxCompletionModel
+    where
+        Self: 'static,
+        Self::Response: 'static,
+    {
+        BoxCompletionModel::new(self)
+    }
 }
 
 /// Struct representing a general completion request that can be sent to a completion model provider.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CompletionRequest {
     /// The prompt to be sent to the completion model provider
     pub prompt: Message,
@@ -252,9 +569,45 @@ pub struct CompletionRequest {
     pub max_tokens: Option<u64>,
     /// Additional provider-specific parameters to be sent to the completion model provider
     pub additional_params: Option<serde_json::Value>,
+    /// Beginning of the assistant answer that the model must continue (see
+    /// [CompletionRequestBuilder::prefill])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefill: Option<String>,
 }
 
 impl CompletionRequest {
+    /// Fails if the request has a [prefill](CompletionRequest::prefill), for the providers which
+    /// do not continue a trailing assistant message (so that the prefill is not silently dropped)
+    pub fn reject_prefill(&self, provider: &str) -> Result<(), CompletionError> {
+        match self.prefill {
+            Some(_) => Err(CompletionError::RequestError(
+                format!("{provider} does not support prefilling the answer").into(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Moves the system messages of the chat history to the end of the preamble, for providers
+    /// which only support a system prompt (e.g.: Anthropic and Gemini).
+    pub fn merge_system_messages(mut self) -> Self {
+        let (system, chat_history): (Vec<_>, Vec<_>) = self
+            .chat_history
+            .into_iter()
+            .partition(|message| matches!(message, Message::System { .. }));
+        self.chat_history = chat_history;
+
+        let instructions = self
+            .preamble
+            .into_iter()
+            .chain(system.into_iter().filter_map(|message| match message {
+                Message::System { content } => Some(content),
+                _ => None,
+            }))
+            .collect::<Vec<_>>();
+        self.preamble = (!instructions.is_empty()).then(|| instructions.join("\n\n"));
+        self
+    }
+
     pub fn prompt_with_context(&self) -> Message {
         let mut new_prompt = self.prompt.clone();
         if let Message::User { ref mut content } = new_prompt {
@@ -329,6 +682,8 @@ pub struct CompletionRequestBuilder<M: CompletionModel> {
     temperature: Option<f64>,
     max_tokens: Option<u64>,
     additional_params: Option<serde_json::Value>,
+    prefill: Option<String>,
+    usage_tracker: Option<UsageTracker>,
 }
 
 impl<M: CompletionModel> CompletionRequestBuilder<M> {
@@ -343,9 +698,18 @@ impl<M: CompletionModel> CompletionRequestBuilder<M> {
             temperature: None,
             max_tokens: None,
             additional_params: None,
+            prefill: None,
+            usage_tracker: None,
         }
     }
 
+    /// Sets the usage tracker the token usage of the response will be added to when the
+    /// request is sent with [CompletionRequestBuilder::send].
+    pub fn usage_tracker(mut self, usage_tracker: UsageTracker) -> Self {
+        self.usage_tracker = Some(usage_tracker);
+        self
+    }
+
     /// Sets the preamble for the completion request.
     pub fn preamble(mut self, preamble: String) -> Self {
         self.preamble = Some(preamble);
@@ -444,6 +808,19 @@ impl<M: CompletionModel> CompletionRequestBuilder<M> {
         self
     }
 
+    /// Sets the beginning of the assistant answer, which the model must continue, e.g.: `{` to
+    /// force a JSON answer, or the text of an answer cut off by `max_tokens` to continue it.
+    /// The response only contains the continuation, without the prefill.
+    ///
+    /// The prefill is sent as a trailing assistant message, which is only supported by Anthropic
+    /// (trailing whitespace is trimmed, as required by the API). Other providers (including
+    /// OpenAI, which answers a trailing assistant message instead of continuing it) fail with a
+    /// [CompletionError::RequestError].
+    pub fn prefill(mut self, prefill: impl Into<String>) -> Self {
+        self.prefill = Some(prefill.into());
+        self
+    }
+
     /// Builds the completion request.
     pub fn build(self) -> CompletionRequest {
         CompletionRequest {
@@ -455,30 +832,219 @@ impl<M: CompletionModel> CompletionRequestBuilder<M> {
             temperature: self.temperature,
             max_tokens: self.max_tokens,
             additional_params: self.additional_params,
+            prefill: self.prefill,
         }
     }
 
+    /// Fits the documents and chat history of the request into the context window of the model
+    /// with `packer` (see [ContextPacker]).
+    pub fn pack_context(self, packer: &ContextPacker) -> Result<Self, ContextOverflowError> {
+        let request = packer.pack(CompletionRequest {
+            prompt: self.prompt,
+            preamble: self.preamble,
+            chat_history: self.chat_history,
+            documents: self.documents,
+            tools: self.tools,
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            additional_params: self.additional_params,
+            prefill: self.prefill,
+        })?;
+
+        Ok(Self {
+            model: self.model,
+            prompt: request.prompt,
+            preamble: request.preamble,
+            chat_history: request.chat_history,
+            documents: request.documents,
+            tools: request.tools,
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            additional_params: request.additional_params,
+            prefill: request.prefill,
+            usage_tracker: self.usage_tracker,
+        })
+    }
+
+    /// Documents of the request, as left by [CompletionRequestBuilder::pack_context]
+    pub(crate) fn packed_documents(&self) -> &[Document] {
+        &self.documents
+    }
+
+    /// Prepares the HTTP request that [send](Self::send) would send to the completion model
+    /// provider and returns it without sending it (see [PreparedRequest]).
+    pub fn dry_run(self) -> Result<PreparedRequest, CompletionError> {
+        let model = self.model.clone();
+        model.prepare_request(self.build())
+    }
+
+    /// Renders the HTTP request that [send](Self::send) would send to the completion model
+    /// provider as a `curl` command (see [PreparedRequest::to_curl]).
+    pub fn to_curl(self) -> Result<String, CompletionError> {
+        Ok(self.dry_run()?.to_curl())
+    }
+
     /// Sends the completion request to the completion model provider and returns the completion response.
     pub async fn send(self) -> Result<CompletionResponse<M::Response>, CompletionError> {
+        let span = tracing::info_span!(target: "rig", "completion",
+            provider = telemetry::provider_name::<M>(),
+            model = field::Empty,
+            input_tokens = field::Empty,
+            output_tokens = field::Empty,
+            latency_ms = field::Empty,
+            retries = field::Empty,
+            tool_calls = field::Empty,
+        );
+
         let model = self.model.clone();
-        model.completion(self.build()).await
+        let usage_tracker = self.usage_tracker.clone();
+        let request = self.build();
+
+        async move {
+            let (mut response, timing) = http_client::with_timing(model.completion(request)).await;
+            telemetry::record_timing(&timing);
+
+            match &mut response {
+                Ok(response) => {
+                    response.timing = timing;
+                    telemetry::record_token_usage(
+                        response.usage.input_tokens,
+                        response.usage.output_tokens,
+                    );
+                    if let Some(usage_tracker) = usage_tracker {
+                        usage_tracker.add(response.usage);
+                    }
+
+                    let tool_calls = response
+                        .choice
+                        .iter()
+                        .filter_map(|content| match content {
+                            AssistantContent::ToolCall(tool_call) => {
+                                Some(tool_call.function.name.as_str())
+                            }
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+
+                    if !tool_calls.is_empty() {
+                        tracing::Span::current().record("tool_calls", tool_calls.join(","));
+                    }
+                }
+                Err(e) => tracing::debug!(target: "rig", "Completion request failed: {e}"),
+            }
+
+            response
+        }
+        .instrument(span)
+        .await
     }
 }
 
 impl<M: StreamingCompletionModel> CompletionRequestBuilder<M> {
-    /// Stream the completion request
+    /// Stream the completion request. The token usage reported at the end of the stream (see
+    /// [StreamingChoice::Usage]) is added to the usage tracker of the request, if any.
     pub async fn stream(self) -> Result<StreamingResult, CompletionError> {
+        let span = tracing::info_span!(target: "rig", "streaming_completion",
+            provider = telemetry::provider_name::<M>(),
+            model = field::Empty,
+            input_tokens = field::Empty,
+            output_tokens = field::Empty,
+            latency_ms = field::Empty,
+            retries = field::Empty,
+        );
+
         let model = self.model.clone();
-        model.stream(self.build()).await
+        let usage_tracker = self.usage_tracker.clone();
+        let request = self.build();
+
+        let stream = async {
+            let start = Instant::now();
+            let stream = model.stream(request).await;
+            // Time until the provider accepted the request and started streaming
+            telemetry::record_latency(start);
+            stream
+        }
+        .instrument(span.clone())
+        .await?;
+
+        Ok(Box::pin(stream.inspect(move |chunk| {
+            if let Ok(StreamingChoice::Usage(usage)) = chunk {
+                let _enter = span.enter();
+                telemetry::record_token_usage(usage.input_tokens, usage.output_tokens);
+                if let Some(usage_tracker) = &usage_tracker {
+                    usage_tracker.add(*usage);
+                }
+            }
+        })))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::OneOrMany;
+    use crate::{providers::mock::MockCompletionModel, OneOrMany};
 
     use super::*;
 
+    #[test]
+    fn test_prepared_request() {
+        let client = reqwest::Client::new();
+        let prepared = PreparedRequest::from_request_builder(
+            client
+                .post("https://example.com/v1/chat?key=secret&alt=sse")
+                .header("x-api-key", "secret")
+                .header("anthropic-version", "2023-06-01")
+                .json(&serde_json::json!({"prompt": "it's"})),
+        )
+        .unwrap();
+
+        assert_eq!(prepared.method, "POST");
+        assert_eq!(
+            prepared.url,
+            "https://example.com/v1/chat?key=%3CREDACTED%3E&alt=sse"
+        );
+        assert!(prepared
+            .headers
+            .contains(&("x-api-key".to_string(), REDACTED.to_string())));
+        assert_eq!(prepared.body, serde_json::json!({"prompt": "it's"}));
+
+        let curl = prepared.to_curl();
+        assert!(curl.starts_with("curl -X POST 'https://example.com/v1/chat?"));
+        assert!(curl.contains("-H 'anthropic-version: 2023-06-01'"));
+        assert!(curl.ends_with(r#"-d '{"prompt":"it'\''s"}'"#));
+    }
+
+    #[test]
+    fn test_usage_tracker() {
+        let tracker = UsageTracker::new();
+        let shared = tracker.clone();
+
+        tracker.add(Usage::new(10, 5));
+        shared.add(Usage::new(1, 2));
+
+        assert_eq!(tracker.total_usage(), Usage::new(11, 7));
+        assert_eq!(shared.total_usage().total_tokens, 18);
+
+        shared.reset();
+        assert_eq!(tracker.total_usage(), Usage::default());
+    }
+
+    #[tokio::test]
+    async fn test_usage_tracker_streaming() {
+        let tracker = UsageTracker::new();
+        let model = MockCompletionModel::new()
+            .stream_tokens(["Hello", " world"])
+            .usage(Usage::new(10, 2));
+
+        let mut stream = CompletionRequestBuilder::new(model, "Hi")
+            .usage_tracker(tracker.clone())
+            .stream()
+            .await
+            .unwrap();
+        while stream.next().await.is_some() {}
+
+        assert_eq!(tracker.total_usage(), Usage::new(10, 2));
+    }
+
     #[test]
     fn test_document_display_without_metadata() {
         let doc = Document {
@@ -535,6 +1101,7 @@ mod tests {
             temperature: None,
             max_tokens: None,
             additional_params: None,
+            prefill: None,
         };
 
         let expected = Message::User {
@@ -554,4 +1121,26 @@ mod tests {
 
         assert_eq!(request.prompt_with_context(), expected);
     }
+
+    #[test]
+    fn test_merge_system_messages() {
+        let request = CompletionRequestBuilder::new(MockCompletionModel::new(), "Bonjour")
+            .preamble("You are a helpful assistant.".to_string())
+            .messages(vec![
+                Message::user("Hello"),
+                Message::assistant("Hi!"),
+                Message::system("Now answer in French."),
+            ])
+            .build()
+            .merge_system_messages();
+
+        assert_eq!(
+            request.preamble.as_deref(),
+            Some("You are a helpful assistant.\n\nNow answer in French.")
+        );
+        assert_eq!(
+            request.chat_history,
+            vec![Message::user("Hello"), Message::assistant("Hi!")]
+        );
+    }
 }
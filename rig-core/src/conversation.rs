@@ -0,0 +1,420 @@
+//! This module provides [Conversation], a versioned transcript of a chat session (messages,
+//! including tool calls and tool results, token usage and metadata) which can be exported to and
+//! imported from JSON or JSONL, so that sessions can be archived, resumed, or fed into
+//! fine-tuning and evaluation pipelines.
+//!
+//! In the JSON format, the whole conversation is a single object. In the JSONL format, the first
+//! line is the header of the conversation (version, id, preamble and metadata) and each following
+//! line is a turn, so that transcripts can be appended to and streamed line by line.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     completion::Chat,
+//!     conversation::Conversation,
+//!     message::Message,
+//!     providers::openai,
+//! };
+//!
+//! let openai = openai::Client::from_env();
+//! let agent = openai.agent(openai::GPT_4O).preamble("You are a comedian.").build();
+//!
+//! // Resume an archived session
+//! let mut conversation = Conversation::load("sessions/jokes.jsonl")?;
+//!
+//! let prompt = "Tell me another joke";
+//! let response = agent.chat(prompt, conversation.messages()).await?;
+//! conversation.push(Message::user(prompt));
+//! conversation.push(Message::assistant(response));
+//!
+//! conversation.save("sessions/jokes.jsonl")?;
+//! ```
+
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    completion::{CompletionResponse, Message, Usage},
+    message::{AssistantContent, UserContent},
+};
+
+/// Version of the transcript format written by [Conversation]. Transcripts of a newer version are
+/// rejected on import.
+pub const CONVERSATION_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConversationError {
+    #[error("JsonError: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// The transcript was written by a newer version of the format
+    #[error("Unsupported conversation version {0} (latest supported: {CONVERSATION_VERSION})")]
+    UnsupportedVersion(u32),
+
+    /// The JSONL transcript has no header line
+    #[error("Empty JSONL transcript")]
+    EmptyTranscript,
+}
+
+/// Message of a [Conversation], with the token usage of the request which generated it (for
+/// assistant messages) and free-form metadata (e.g.: a timestamp or the name of the model).
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Turn {
+    pub message: Message,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub metadata: Map<String, Value>,
+}
+
+impl Turn {
+    pub fn new(message: impl Into<Message>) -> Self {
+        Self {
+            message: message.into(),
+            usage: None,
+            metadata: Map::new(),
+        }
+    }
+
+    pub fn usage(mut self, usage: Usage) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// Header of a [Conversation]: everything but the turns (first line of the JSONL format)
+#[derive(Deserialize, Serialize)]
+struct Header {
+    version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    preamble: Option<String>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    metadata: Map<String, Value>,
+}
+
+/// Versioned transcript of a chat session. See the [module documentation](self) for the formats.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Conversation {
+    /// Version of the transcript format (see [CONVERSATION_VERSION])
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// System prompt of the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preamble: Option<String>,
+    #[serde(default)]
+    pub turns: Vec<Turn>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub metadata: Map<String, Value>,
+}
+
+impl Default for Conversation {
+    fn default() -> Self {
+        Self {
+            version: CONVERSATION_VERSION,
+            id: None,
+            preamble: None,
+            turns: vec![],
+            metadata: Map::new(),
+        }
+    }
+}
+
+impl From<Vec<Message>> for Conversation {
+    fn from(messages: Vec<Message>) -> Self {
+        Self {
+            turns: messages.into_iter().map(Turn::new).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Conversation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn preamble(mut self, preamble: impl Into<String>) -> Self {
+        self.preamble = Some(preamble.into());
+        self
+    }
+
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add a message to the conversation
+    pub fn push(&mut self, message: impl Into<Message>) {
+        self.turns.push(Turn::new(message));
+    }
+
+    /// Add a turn (a message with its usage and metadata) to the conversation
+    pub fn push_turn(&mut self, turn: Turn) {
+        self.turns.push(turn);
+    }
+
+    /// Add the answer of a completion model to the conversation, with its token usage
+    pub fn push_response<T>(&mut self, response: &CompletionResponse<T>) {
+        self.turns.push(
+            Turn::new(Message::Assistant {
+                content: response.choice.clone(),
+            })
+            .usage(response.usage),
+        );
+    }
+
+    /// Messages of the conversation, e.g.: to resume the session with
+    /// [Chat::chat](crate::completion::Chat::chat)
+    pub fn messages(&self) -> Vec<Message> {
+        self.turns.iter().map(|turn| turn.message.clone()).collect()
+    }
+
+    /// Tool calls made by the assistant during the conversation
+    pub fn tool_calls(&self) -> impl Iterator<Item = &crate::message::ToolCall> {
+        self.turns.iter().flat_map(|turn| match &turn.message {
+            Message::Assistant { content } => content
+                .iter()
+                .filter_map(|content| match content {
+                    AssistantContent::ToolCall(tool_call) => Some(tool_call),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        })
+    }
+
+    /// Tool results given to the assistant during the conversation
+    pub fn tool_results(&self) -> impl Iterator<Item = &crate::message::ToolResult> {
+        self.turns.iter().flat_map(|turn| match &turn.message {
+            Message::User { content } => content
+                .iter()
+                .filter_map(|content| match content {
+                    UserContent::ToolResult(tool_result) => Some(tool_result),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        })
+    }
+
+    /// Total token usage of the conversation
+    pub fn usage(&self) -> Usage {
+        self.turns
+            .iter()
+            .filter_map(|turn| turn.usage)
+            .fold(Usage::default(), |total, usage| total + usage)
+    }
+
+    /// Export the conversation to a (pretty printed) JSON object
+    pub fn to_json(&self) -> Result<String, ConversationError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Import a conversation from a JSON object
+    pub fn from_json(json: &str) -> Result<Self, ConversationError> {
+        let conversation: Self = serde_json::from_str(json)?;
+        check_version(conversation.version)?;
+        Ok(conversation)
+    }
+
+    /// Export the conversation to JSONL: a header line followed by one line per turn
+    pub fn to_jsonl(&self) -> Result<String, ConversationError> {
+        let header = Header {
+            version: self.version,
+            id: self.id.clone(),
+            preamble: self.preamble.clone(),
+            metadata: self.metadata.clone(),
+        };
+
+        let mut jsonl = serde_json::to_string(&header)?;
+        jsonl.push('\n');
+        for turn in &self.turns {
+            jsonl.push_str(&serde_json::to_string(turn)?);
+            jsonl.push('\n');
+        }
+        Ok(jsonl)
+    }
+
+    /// Import a conversation from JSONL (see [Conversation::to_jsonl]). Blank lines are ignored.
+    pub fn from_jsonl(jsonl: &str) -> Result<Self, ConversationError> {
+        let mut lines = jsonl.lines().filter(|line| !line.trim().is_empty());
+
+        let header: Header =
+            serde_json::from_str(lines.next().ok_or(ConversationError::EmptyTranscript)?)?;
+        check_version(header.version)?;
+
+        Ok(Self {
+            version: header.version,
+            id: header.id,
+            preamble: header.preamble,
+            turns: lines
+                .map(serde_json::from_str)
+                .collect::<Result<Vec<_>, _>>()?,
+            metadata: header.metadata,
+        })
+    }
+
+    /// Save the conversation to `path`, in the JSONL format if its extension is `jsonl` and in
+    /// the JSON format otherwise
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ConversationError> {
+        let path = path.as_ref();
+        let content = if is_jsonl(path) {
+            self.to_jsonl()?
+        } else {
+            self.to_json()?
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(fs::write(path, content)?)
+    }
+
+    /// Load the conversation saved at `path` (see [Conversation::save])
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConversationError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        if is_jsonl(path) {
+            Self::from_jsonl(&content)
+        } else {
+            Self::from_json(&content)
+        }
+    }
+}
+
+fn check_version(version: u32) -> Result<(), ConversationError> {
+    if version > CONVERSATION_VERSION {
+        return Err(ConversationError::UnsupportedVersion(version));
+    }
+    Ok(())
+}
+
+fn is_jsonl(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "jsonl")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{completion::ResponseTiming, message::ToolResultContent, OneOrMany};
+
+    fn conversation() -> Conversation {
+        let mut conversation = Conversation::new()
+            .id("session-1")
+            .preamble("You are a weather bot.")
+            .metadata("user", "alice");
+
+        conversation.push(Message::user("What is the weather in Paris?"));
+        conversation.push_response(&CompletionResponse {
+            choice: OneOrMany::one(AssistantContent::tool_call(
+                "call_1",
+                "get_weather",
+                json!({"city": "Paris"}),
+            )),
+            usage: Usage::new(10, 5),
+            timing: ResponseTiming::default(),
+            raw_response: (),
+        });
+        conversation.push(Message::User {
+            content: OneOrMany::one(UserContent::tool_result(
+                "call_1",
+                OneOrMany::one(ToolResultContent::text("15 degrees")),
+            )),
+        });
+        conversation.push_turn(
+            Turn::new(Message::assistant("It is 15 degrees in Paris."))
+                .usage(Usage::new(20, 8))
+                .metadata("model", "gpt-4o"),
+        );
+        conversation
+    }
+
+    #[test]
+    fn test_conversation() {
+        let conversation = conversation();
+
+        assert_eq!(conversation.messages().len(), 4);
+        assert_eq!(conversation.usage(), Usage::new(30, 13));
+        assert_eq!(
+            conversation
+                .tool_calls()
+                .map(|tool_call| tool_call.function.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["get_weather"]
+        );
+        assert_eq!(
+            conversation
+                .tool_results()
+                .map(|tool_result| tool_result.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["call_1"]
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let conversation = conversation();
+
+        let json = conversation.to_json().unwrap();
+        assert_eq!(Conversation::from_json(&json).unwrap(), conversation);
+
+        let jsonl = conversation.to_jsonl().unwrap();
+        assert_eq!(jsonl.lines().count(), 5);
+        assert_eq!(
+            serde_json::from_str::<Value>(jsonl.lines().next().unwrap()).unwrap(),
+            json!({
+                "version": 1,
+                "id": "session-1",
+                "preamble": "You are a weather bot.",
+                "metadata": {"user": "alice"}
+            })
+        );
+        assert_eq!(Conversation::from_jsonl(&jsonl).unwrap(), conversation);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        assert!(matches!(
+            Conversation::from_json(r#"{"version": 2, "turns": []}"#),
+            Err(ConversationError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            Conversation::from_jsonl(""),
+            Err(ConversationError::EmptyTranscript)
+        ));
+    }
+
+    #[test]
+    fn test_save_load() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let conversation = conversation();
+
+        for file in ["conversation.json", "conversation.jsonl"] {
+            let path = dir.path().join("sessions").join(file);
+            conversation.save(&path).unwrap();
+            assert_eq!(Conversation::load(&path).unwrap(), conversation);
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! This module provides cost accounting for completion and embedding requests.
+//!
+//! Prices are expressed in US dollars per million tokens and are looked up by model name.
+//! Rig ships with a [PricingTable] of known list prices for the most common models, which can
+//! be extended or overridden at runtime (e.g.: for negotiated rates or new models) either
+//! globally with [set_pricing] or by passing a custom [PricingTable] to a [CostTracker].
+//!
+//! Model names are matched exactly first, then by their longest known prefix, so dated
+//! model versions (e.g.: `gpt-4o-2024-08-06`) are priced like their base model (e.g.: `gpt-4o`).
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     completion::{Completion, Usage},
+//!     cost::{self, CostTracker, ModelPricing},
+//!     providers::openai,
+//! };
+//!
+//! // Override the price of a model globally
+//! cost::set_pricing("my-fine-tuned-model", ModelPricing::new(3.0, 12.0));
+//!
+//! // Track the cost of an agent session
+//! let tracker = CostTracker::new();
+//!
+//! let response = agent.completion("Hello!", vec![]).await?.send().await?;
+//! tracker.record(openai::GPT_4O, response.usage);
+//!
+//! println!("Session cost: ${:.4}", tracker.total_cost());
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::completion::Usage;
+
+/// Price of a model in US dollars per million tokens
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ModelPricing {
+    /// Price of one million input (prompt) tokens
+    pub input_per_million: f64,
+    /// Price of one million output (generated) tokens
+    pub output_per_million: f64,
+}
+
+impl ModelPricing {
+    pub const fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+        }
+    }
+
+    /// Cost in US dollars of the given token usage
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.input_tokens as f64 * self.input_per_million
+            + usage.output_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// List prices (USD per million input/output tokens) of known models
+const DEFAULT_PRICES: &[(&str, f64, f64)] = &[
+    // OpenAI
+    ("gpt-4.5-preview", 75.0, 150.0),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4-turbo", 10.0, 30.0),
+    ("gpt-4", 30.0, 60.0),
+    ("gpt-4-32k", 60.0, 120.0),
+    ("gpt-3.5-turbo", 0.5, 1.5),
+    ("o1", 15.0, 60.0),
+    ("o1-preview", 15.0, 60.0),
+    ("o1-mini", 1.1, 4.4),
+    ("o3-mini", 1.1, 4.4),
+    ("text-embedding-3-small", 0.02, 0.0),
+    ("text-embedding-3-large", 0.13, 0.0),
+    ("text-embedding-ada-002", 0.1, 0.0),
+    // Anthropic
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-opus", 15.0, 75.0),
+    ("claude-3-sonnet", 3.0, 15.0),
+    ("claude-3-haiku", 0.25, 1.25),
+    // Google Gemini
+    ("gemini-2.0-flash", 0.1, 0.4),
+    ("gemini-2.0-flash-lite", 0.075, 0.3),
+    ("gemini-1.5-pro", 1.25, 5.0),
+    ("gemini-1.5-flash", 0.075, 0.3),
+    ("gemini-1.5-flash-8b", 0.0375, 0.15),
+    // Cohere
+    ("command-r-plus", 2.5, 10.0),
+    ("command-r", 0.15, 0.6),
+    // DeepSeek
+    ("deepseek-chat", 0.27, 1.1),
+    ("deepseek-reasoner", 0.55, 2.19),
+    // xAI
+    ("grok-2", 2.0, 10.0),
+    ("grok-beta", 5.0, 15.0),
+];
+
+/// Table of model prices, keyed by model name
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PricingTable {
+    prices: HashMap<String, ModelPricing>,
+}
+
+impl Default for PricingTable {
+    /// Pricing table pre-populated with the list prices of known models
+    fn default() -> Self {
+        Self {
+            prices: DEFAULT_PRICES
+                .iter()
+                .map(|(model, input, output)| {
+                    (model.to_string(), ModelPricing::new(*input, *output))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PricingTable {
+    /// Create a pricing table without any price
+    pub fn empty() -> Self {
+        Self {
+            prices: HashMap::new(),
+        }
+    }
+
+    /// Set (or override) the price of a model
+    pub fn set(&mut self, model: &str, pricing: ModelPricing) {
+        self.prices.insert(model.to_string(), pricing);
+    }
+
+    /// Builder-style version of [PricingTable::set]
+    pub fn with_pricing(mut self, model: &str, pricing: ModelPricing) -> Self {
+        self.set(model, pricing);
+        self
+    }
+
+    /// Get the price of a model, matching its name exactly or by its longest known prefix
+    pub fn get(&self, model: &str) -> Option<ModelPricing> {
+        self.prices.get(model).copied().or_else(|| {
+            self.prices
+                .iter()
+                .filter(|(name, _)| model.starts_with(name.as_str()))
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, pricing)| *pricing)
+        })
+    }
+
+    /// Cost in US dollars of the given token usage, or `None` if the model price is unknown
+    pub fn cost(&self, model: &str, usage: &Usage) -> Option<f64> {
+        self.get(model).map(|pricing| pricing.cost(usage))
+    }
+}
+
+fn global_pricing() -> &'static RwLock<PricingTable> {
+    static PRICING: OnceLock<RwLock<PricingTable>> = OnceLock::new();
+    PRICING.get_or_init(|| RwLock::new(PricingTable::default()))
+}
+
+/// Set (or override) the price of a model in the global pricing table
+pub fn set_pricing(model: &str, pricing: ModelPricing) {
+    global_pricing()
+        .write()
+        .expect("Pricing table lock should not be poisoned")
+        .set(model, pricing);
+}
+
+/// Get the price of a model from the global pricing table
+pub fn pricing(model: &str) -> Option<ModelPricing> {
+    global_pricing()
+        .read()
+        .expect("Pricing table lock should not be poisoned")
+        .get(model)
+}
+
+/// Cost in US dollars of the given token usage according to the global pricing table,
+/// or `None` if the model price is unknown
+pub fn cost(model: &str, usage: &Usage) -> Option<f64> {
+    pricing(model).map(|pricing| pricing.cost(usage))
+}
+
+/// Usage and cost accumulated for a single model by a [CostTracker]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct ModelCost {
+    /// Number of requests recorded
+    pub requests: u64,
+    /// Total token usage
+    pub usage: Usage,
+    /// Total cost in US dollars (excluding requests to models with unknown prices)
+    pub cost: f64,
+}
+
+/// Aggregates the token usage and cost of the requests made during a session (e.g.: an agent
+/// conversation). The tracker can be shared between tasks since recording only takes `&self`.
+#[derive(Debug, Default)]
+pub struct CostTracker {
+    /// Custom pricing table. If `None`, the global pricing table is used.
+    pricing: Option<PricingTable>,
+    models: Mutex<HashMap<String, ModelCost>>,
+}
+
+impl CostTracker {
+    /// Create a new tracker using the global pricing table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new tracker using a custom pricing table
+    pub fn with_pricing(pricing: PricingTable) -> Self {
+        Self {
+            pricing: Some(pricing),
+            models: Mutex::default(),
+        }
+    }
+
+    /// Record the usage of a request made to `model`. Returns the cost of the request in
+    /// US dollars, or `None` if the model price is unknown (the usage is recorded regardless).
+    pub fn record(&self, model: &str, usage: Usage) -> Option<f64> {
+        let cost = match &self.pricing {
+            Some(pricing) => pricing.cost(model, &usage),
+            None => cost(model, &usage),
+        };
+
+        if cost.is_none() {
+            tracing::warn!(target: "rig", "No pricing found for model {model}, cost not tracked");
+        }
+
+        let mut models = self.models.lock().expect("Mutex should not be poisoned");
+        let entry = models.entry(model.to_string()).or_default();
+        entry.requests += 1;
+        entry.usage += usage;
+        entry.cost += cost.unwrap_or_default();
+
+        cost
+    }
+
+    /// Total cost in US dollars of all recorded requests
+    pub fn total_cost(&self) -> f64 {
+        self.breakdown().values().map(|model| model.cost).sum()
+    }
+
+    /// Total token usage of all recorded requests
+    pub fn total_usage(&self) -> Usage {
+        self.breakdown().values().map(|model| model.usage).sum()
+    }
+
+    /// Usage and cost of the recorded requests, per model
+    pub fn breakdown(&self) -> HashMap<String, ModelCost> {
+        self.models
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .clone()
+    }
+
+    /// Clear all recorded requests
+    pub fn reset(&self) {
+        self.models
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_pricing_cost() {
+        let pricing = ModelPricing::new(2.5, 10.0);
+        let usage = Usage::new(1_000_000, 500_000);
+
+        assert_eq!(pricing.cost(&usage), 7.5);
+    }
+
+    #[test]
+    fn test_pricing_table_prefix_match() {
+        let table = PricingTable::default();
+
+        assert_eq!(table.get("gpt-4o"), Some(ModelPricing::new(2.5, 10.0)));
+        assert_eq!(
+            table.get("gpt-4o-2024-08-06"),
+            Some(ModelPricing::new(2.5, 10.0))
+        );
+        assert_eq!(
+            table.get("gpt-4o-mini-2024-07-18"),
+            Some(ModelPricing::new(0.15, 0.6))
+        );
+        assert_eq!(table.get("unknown-model"), None);
+    }
+
+    #[test]
+    fn test_pricing_table_override() {
+        let table = PricingTable::default().with_pricing("gpt-4o", ModelPricing::new(1.0, 1.0));
+
+        assert_eq!(table.get("gpt-4o"), Some(ModelPricing::new(1.0, 1.0)));
+        assert_eq!(
+            PricingTable::empty().cost("gpt-4o", &Usage::new(10, 10)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cost_tracker() {
+        let tracker = CostTracker::with_pricing(
+            PricingTable::empty()
+                .with_pricing("model-a", ModelPricing::new(1.0, 2.0))
+                .with_pricing("model-b", ModelPricing::new(10.0, 20.0)),
+        );
+
+        assert_eq!(
+            tracker.record("model-a", Usage::new(1_000_000, 1_000_000)),
+            Some(3.0)
+        );
+        assert_eq!(tracker.record("model-a", Usage::new(500_000, 0)), Some(0.5));
+        assert_eq!(tracker.record("model-b", Usage::new(100_000, 0)), Some(1.0));
+        assert_eq!(tracker.record("model-c", Usage::new(100, 100)), None);
+
+        assert_eq!(tracker.total_cost(), 4.5);
+        assert_eq!(tracker.total_usage(), Usage::new(1_600_100, 1_000_100));
+        assert_eq!(tracker.breakdown()["model-a"].requests, 2);
+
+        tracker.reset();
+        assert_eq!(tracker.total_cost(), 0.0);
+    }
+}
@@ -0,0 +1,192 @@
+//! This module provides support for short-lived credentials (e.g.: Azure AD, GCP or AWS STS
+//! issued tokens), fetched on demand by a [CredentialProvider] instead of a static API key.
+//!
+//! A [CredentialProvider] can be attached to any provider client with its `with_credentials`
+//! method, in which case it replaces the API key the client was created with. The credential is
+//! cached and shared by all the requests of the client, and is fetched again shortly before it
+//! expires (one minute by default, see [Credentials::refresh_margin]) or after a request was
+//! rejected with `401 Unauthorized`. Concurrent requests wait for a single fetch.
+//!
+//! Any async closure returning a [Credential] is a [CredentialProvider].
+//!
+//! # Example
+//! ```rust
+//! use std::time::{Duration, SystemTime};
+//! use rig::{credentials::Credential, http_client::HttpClientSettings, providers::azure};
+//!
+//! let azure = azure::Client::from_token("", "2024-10-21", "https://my-resource.openai.azure.com")
+//!     .with_credentials(|| async {
+//!         // e.g.: request a token from Azure AD
+//!         let token = fetch_azure_ad_token().await?;
+//!         Ok(Credential::new(token.access_token)
+//!             .expires_at(SystemTime::now() + Duration::from_secs(token.expires_in)))
+//!     });
+//! ```
+
+use std::{future::Future, sync::Arc, time::Duration};
+
+use futures::lock::Mutex;
+
+use crate::wasm_compat::{SystemTime, WasmBoxedFuture, WasmCompatSend};
+
+/// Error returned by a [CredentialProvider]
+#[derive(Debug, thiserror::Error)]
+#[error("CredentialError: {0}")]
+pub struct CredentialError(#[from] pub Box<dyn std::error::Error + Send + Sync + 'static>);
+
+impl From<String> for CredentialError {
+    fn from(error: String) -> Self {
+        Self(error.into())
+    }
+}
+
+/// Secret (API key or token) returned by a [CredentialProvider]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Credential {
+    /// The secret sent with the requests, in place of the API key
+    pub secret: String,
+    /// When the secret expires (`None` if it does not expire)
+    pub expires_at: Option<SystemTime>,
+}
+
+impl Credential {
+    /// Create a credential that does not expire
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self {
+            secret: secret.into(),
+            expires_at: None,
+        }
+    }
+
+    /// Set when the credential expires
+    pub fn expires_at(mut self, expires_at: SystemTime) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Whether the credential expires within `margin` of `now`
+    fn expires_within(&self, margin: Duration, now: SystemTime) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= now + margin)
+    }
+}
+
+/// Source of short-lived credentials
+pub trait CredentialProvider: Send + Sync {
+    /// Fetch a new credential
+    fn fetch(&self) -> WasmBoxedFuture<'_, Result<Credential, CredentialError>>;
+}
+
+impl<F, Fut> CredentialProvider for F
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Credential, CredentialError>> + WasmCompatSend + 'static,
+{
+    fn fetch(&self) -> WasmBoxedFuture<'_, Result<Credential, CredentialError>> {
+        Box::pin(self())
+    }
+}
+
+/// Cached credential of a [CredentialProvider], refreshed before it expires. Cheap to clone, all
+/// clones share the same cached credential.
+#[derive(Clone)]
+pub struct Credentials {
+    provider: Arc<dyn CredentialProvider>,
+    refresh_margin: Duration,
+    cached: Arc<Mutex<Option<Credential>>>,
+}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("refresh_margin", &self.refresh_margin)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Credentials {
+    pub fn new(provider: impl CredentialProvider + 'static) -> Self {
+        Self {
+            provider: Arc::new(provider),
+            refresh_margin: Duration::from_secs(60),
+            cached: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Set how long before its expiration the credential is refreshed
+    pub fn refresh_margin(mut self, refresh_margin: Duration) -> Self {
+        self.refresh_margin = refresh_margin;
+        self
+    }
+
+    /// The current secret, fetching a new credential if there is none or if it is about to
+    /// expire
+    pub async fn secret(&self) -> Result<String, CredentialError> {
+        let mut cached = self.cached.lock().await;
+
+        match &*cached {
+            Some(credential)
+                if !credential.expires_within(self.refresh_margin, SystemTime::now()) =>
+            {
+                Ok(credential.secret.clone())
+            }
+            _ => {
+                tracing::debug!(target: "rig", "Fetching new credential");
+                let credential = self.provider.fetch().await?;
+                let secret = credential.secret.clone();
+                *cached = Some(credential);
+                Ok(secret)
+            }
+        }
+    }
+
+    /// Discard the cached credential (e.g.: because it was rejected), so that a new one is
+    /// fetched for the next request
+    pub async fn invalidate(&self) {
+        *self.cached.lock().await = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_refresh_before_expiry() {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let credentials = Credentials::new({
+            let fetches = fetches.clone();
+            move || {
+                let fetch = fetches.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    Ok::<_, CredentialError>(
+                        Credential::new(format!("token-{fetch}"))
+                            .expires_at(SystemTime::now() + Duration::from_secs(90)),
+                    )
+                }
+            }
+        });
+
+        assert_eq!(credentials.secret().await.unwrap(), "token-0");
+        assert_eq!(credentials.secret().await.unwrap(), "token-0");
+
+        // The token expires within the refresh margin
+        let credentials = credentials.refresh_margin(Duration::from_secs(120));
+        assert_eq!(credentials.secret().await.unwrap(), "token-1");
+
+        credentials.invalidate().await;
+        assert_eq!(credentials.secret().await.unwrap(), "token-2");
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_error() {
+        let credentials = Credentials::new(|| async {
+            Err::<Credential, _>(CredentialError::from("No token".to_string()))
+        });
+
+        assert!(credentials.secret().await.is_err());
+    }
+}
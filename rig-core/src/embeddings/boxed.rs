@@ -0,0 +1,87 @@
+//! This module provides [BoxEmbeddingModel], a type-erased embedding model (see
+//! [BoxCompletionModel](crate::completion::BoxCompletionModel) for completion models).
+
+use std::sync::Arc;
+
+use super::{Embedding, EmbeddingError, EmbeddingModel};
+use crate::wasm_compat::WasmBoxedFuture;
+
+/// Object safe version of [EmbeddingModel], implemented by all embedding models.
+///
+/// The methods are prefixed with `dyn_` so that they do not conflict with the methods of
+/// [EmbeddingModel] when both traits are in scope.
+pub trait DynEmbeddingModel: Send + Sync {
+    /// The maximum number of documents that can be embedded in a single request
+    /// (see [EmbeddingModel::MAX_DOCUMENTS]).
+    fn dyn_max_documents(&self) -> usize;
+
+    /// The number of dimensions in the embedding vector (see [EmbeddingModel::ndims]).
+    fn dyn_ndims(&self) -> usize;
+
+    /// Embed multiple text documents in a single request (see [EmbeddingModel::embed_texts]).
+    fn dyn_embed_texts(
+        &self,
+        texts: Vec<String>,
+    ) -> WasmBoxedFuture<'_, Result<Vec<Embedding>, EmbeddingError>>;
+}
+
+impl<M: EmbeddingModel + 'static> DynEmbeddingModel for M {
+    fn dyn_max_documents(&self) -> usize {
+        self.max_documents()
+    }
+
+    fn dyn_ndims(&self) -> usize {
+        self.ndims()
+    }
+
+    fn dyn_embed_texts(
+        &self,
+        texts: Vec<String>,
+    ) -> WasmBoxedFuture<'_, Result<Vec<Embedding>, EmbeddingError>> {
+        Box::pin(self.embed_texts(texts))
+    }
+}
+
+/// Type-erased embedding model. Cheap to clone.
+///
+/// Since the maximum number of documents of the wrapped model is only known at runtime,
+/// [BoxEmbeddingModel::MAX_DOCUMENTS] is unbounded, and the limit of the wrapped model is returned
+/// by [EmbeddingModel::max_documents] instead (which the
+/// [EmbeddingsBuilder](crate::embeddings::EmbeddingsBuilder) uses to batch the documents). Larger
+/// batches passed to [EmbeddingModel::embed_texts] are split into batches of the maximum size of
+/// the wrapped model, which are sent one after the other.
+#[derive(Clone)]
+pub struct BoxEmbeddingModel(Arc<dyn DynEmbeddingModel>);
+
+impl BoxEmbeddingModel {
+    pub fn new<M: EmbeddingModel + 'static>(model: M) -> Self {
+        Self(Arc::new(model))
+    }
+}
+
+impl EmbeddingModel for BoxEmbeddingModel {
+    const MAX_DOCUMENTS: usize = usize::MAX;
+
+    fn max_documents(&self) -> usize {
+        self.0.dyn_max_documents()
+    }
+
+    fn ndims(&self) -> usize {
+        self.0.dyn_ndims()
+    }
+
+    async fn embed_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        let texts = texts.into_iter().collect::<Vec<_>>();
+        let max_documents = self.0.dyn_max_documents().max(1);
+
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(max_documents) {
+            embeddings.extend(self.0.dyn_embed_texts(batch.to_vec()).await?);
+        }
+
+        Ok(embeddings)
+    }
+}
@@ -5,12 +5,14 @@
 use std::{cmp::max, collections::HashMap};
 
 use futures::{stream, StreamExt};
+use tracing::{field, Instrument};
 
 use crate::{
     embeddings::{
-        embed::TextEmbedder, Embed, EmbedError, Embedding, EmbeddingError, EmbeddingModel,
+        embed::TextEmbedder, Deduplicator, Embed, EmbedError, Embedding, EmbeddingError,
+        EmbeddingModel,
     },
-    OneOrMany,
+    telemetry, OneOrMany,
 };
 
 /// Builder for creating embeddings from one or more documents of type `T`.
@@ -50,6 +52,7 @@ use crate::{
 pub struct EmbeddingsBuilder<M: EmbeddingModel, T: Embed> {
     model: M,
     documents: Vec<(T, Vec<String>)>,
+    deduplicator: Option<Deduplicator<T>>,
 }
 
 impl<M: EmbeddingModel, T: Embed> EmbeddingsBuilder<M, T> {
@@ -58,6 +61,7 @@ impl<M: EmbeddingModel, T: Embed> EmbeddingsBuilder<M, T> {
         Self {
             model,
             documents: vec![],
+            deduplicator: None,
         }
     }
 
@@ -80,6 +84,14 @@ impl<M: EmbeddingModel, T: Embed> EmbeddingsBuilder<M, T> {
 
         Ok(builder)
     }
+
+    /// Drop (or merge) the near-duplicate documents with `deduplicator` when building the
+    /// embeddings. Duplicates detected from the texts of the documents (i.e.:
+    /// [Deduplicator::minhash]) are not embedded.
+    pub fn deduplicate(mut self, deduplicator: Deduplicator<T>) -> Self {
+        self.deduplicator = Some(deduplicator);
+        self
+    }
 }
 
 impl<M: EmbeddingModel, T: Embed + Send> EmbeddingsBuilder<M, T> {
@@ -88,31 +100,53 @@ impl<M: EmbeddingModel, T: Embed + Send> EmbeddingsBuilder<M, T> {
     pub async fn build(self) -> Result<Vec<(T, OneOrMany<Embedding>)>, EmbeddingError> {
         use stream::TryStreamExt;
 
-        // Store the documents and their texts in a HashMap for easy access.
-        let mut docs = HashMap::new();
+        let documents = match &self.deduplicator {
+            Some(deduplicator) if deduplicator.before_embedding() => {
+                deduplicator.dedup_unembedded(self.documents)
+            }
+            _ => self.documents,
+        };
+
+        // Store the documents and their texts for easy access.
+        let mut docs = Vec::new();
         let mut texts = Vec::new();
 
         // Iterate over all documents in the builder and insert their docs and texts into the lookup stores.
-        for (i, (doc, doc_texts)) in self.documents.into_iter().enumerate() {
-            docs.insert(i, doc);
+        for (i, (doc, doc_texts)) in documents.into_iter().enumerate() {
+            docs.push(doc);
             texts.push((i, doc_texts));
         }
 
+        let max_documents = self.model.max_documents().max(1);
+
         // Compute the embeddings.
         let mut embeddings = stream::iter(texts.into_iter())
             // Merge the texts of each document into a single list of texts.
             .flat_map(|(i, texts)| stream::iter(texts.into_iter().map(move |text| (i, text))))
             // Chunk them into batches. Each batch size is at most the embedding API limit per request.
-            .chunks(M::MAX_DOCUMENTS)
+            .chunks(max_documents)
             // Generate the embeddings for each batch.
-            .map(|text| async {
-                let (ids, docs): (Vec<_>, Vec<_>) = text.into_iter().unzip();
-
-                let embeddings = self.model.embed_texts(docs).await?;
-                Ok::<_, EmbeddingError>(ids.into_iter().zip(embeddings).collect::<Vec<_>>())
+            .map(|text| {
+                let span = tracing::info_span!(target: "rig", "embedding",
+                    provider = telemetry::provider_name::<M>(),
+                    model = field::Empty,
+                    documents = text.len(),
+                    latency_ms = field::Empty,
+                    retries = field::Empty,
+                );
+
+                async {
+                    let (ids, docs): (Vec<_>, Vec<_>) = text.into_iter().unzip();
+
+                    let (embeddings, timing) = self.model.embed_texts_with_timing(docs).await?;
+                    telemetry::record_timing(&timing);
+
+                    Ok::<_, EmbeddingError>(ids.into_iter().zip(embeddings).collect::<Vec<_>>())
+                }
+                .instrument(span)
             })
             // Parallelize the embeddings generation over 10 concurrent requests
-            .buffer_unordered(max(1, 1024 / M::MAX_DOCUMENTS))
+            .buffer_unordered(max(1, 1024 / max_documents))
             // Collect the embeddings into a HashMap.
             .try_fold(
                 HashMap::new(),
@@ -129,22 +163,31 @@ impl<M: EmbeddingModel, T: Embed + Send> EmbeddingsBuilder<M, T> {
             .await?;
 
         // Merge the embeddings with their respective documents
-        Ok(docs
+        let documents = docs
             .into_iter()
+            .enumerate()
             .map(|(i, doc)| {
                 (
                     doc,
                     embeddings.remove(&i).expect("Document should be present"),
                 )
             })
-            .collect())
+            .collect();
+
+        Ok(match &self.deduplicator {
+            Some(deduplicator) if !deduplicator.before_embedding() => deduplicator.dedup(documents),
+            _ => documents,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        embeddings::{embed::EmbedError, embed::TextEmbedder, Embedding, EmbeddingModel},
+        embeddings::{
+            embed::EmbedError, embed::TextEmbedder, BoxEmbeddingModel, Deduplicator, Embedding,
+            EmbeddingModel,
+        },
         Embed,
     };
 
@@ -384,4 +427,44 @@ mod tests {
             second_definition.1.rest()[0].document, "A fictional creature found in the distant, swampy marshlands of the planet Glibbo in the Andromeda galaxy.".to_string()
         )
     }
+
+    #[tokio::test]
+    async fn test_build_deduplicated() {
+        let mut fake_definitions = definitions_single_text();
+        fake_definitions.push(WordDefinitionSingle {
+            id: "doc2".to_string(),
+            definition: "A green alien that lives on cold planets!".to_string(),
+        });
+
+        let result = EmbeddingsBuilder::new(Model)
+            .documents(fake_definitions)
+            .unwrap()
+            .deduplicate(Deduplicator::minhash(0.8))
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result
+                .iter()
+                .map(|(definition, _)| definition.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["doc0", "doc1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_boxed_model() {
+        let model = BoxEmbeddingModel::new(Model);
+        assert_eq!(model.max_documents(), 5);
+
+        let result = EmbeddingsBuilder::new(model)
+            .documents(definitions_multiple_text())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 2);
+    }
 }
@@ -0,0 +1,347 @@
+//! The module defines the [Deduplicator] struct which detects near-duplicate documents (e.g.: the
+//! same boilerplate paragraph repeated on every page of a website) so that they are only indexed
+//! once, instead of filling the context window of agents with repeats when retrieved.
+//!
+//! Two strategies are available:
+//! - [Deduplicator::minhash]: compares the texts of the documents (estimating the Jaccard
+//!   similarity of their word shingles with MinHash). Used with an [EmbeddingsBuilder], the
+//!   duplicates are dropped before being embedded.
+//! - [Deduplicator::similarity]: compares the embeddings of the documents (cosine similarity),
+//!   which also catches paraphrases, at the cost of embedding the duplicates.
+//!
+//! # Example
+//! ```rust
+//! use rig::embeddings::{Deduplicator, EmbeddingsBuilder};
+//!
+//! let embeddings = EmbeddingsBuilder::new(model.clone())
+//!     .documents(chunks)?
+//!     .deduplicate(Deduplicator::minhash(0.8))
+//!     .build()
+//!     .await?;
+//! ```
+//!
+//! [EmbeddingsBuilder]: crate::embeddings::EmbeddingsBuilder
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use super::{distance::VectorDistance, Embedding};
+use crate::OneOrMany;
+
+/// Number of hashes of the MinHash signatures
+const NUM_HASHES: usize = 128;
+/// Number of rows per band of the MinHash signatures, used to find candidate duplicates
+const BAND_ROWS: usize = 4;
+/// Number of words per shingle
+const SHINGLE_LEN: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Strategy {
+    MinHash(f64),
+    Similarity(f64),
+}
+
+/// Detects near-duplicate documents, dropping them or merging them into the first document they
+/// duplicate (with [Deduplicator::merge]). The first occurrence of each document is kept, and the
+/// order of the documents is preserved.
+pub struct Deduplicator<T> {
+    strategy: Strategy,
+    #[allow(clippy::type_complexity)]
+    merge: Option<Arc<dyn Fn(&mut T, T) + Send + Sync>>,
+}
+
+impl<T> Clone for Deduplicator<T> {
+    fn clone(&self) -> Self {
+        Self {
+            strategy: self.strategy,
+            merge: self.merge.clone(),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Deduplicator<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Deduplicator")
+            .field("strategy", &self.strategy)
+            .field("merge", &self.merge.is_some())
+            .finish()
+    }
+}
+
+impl<T> Deduplicator<T> {
+    /// Documents whose texts have an estimated Jaccard similarity (of their 3-word shingles) of at
+    /// least `threshold` are duplicates. A `threshold` of 0.8 catches documents differing by a few
+    /// words (e.g.: a date or page number).
+    pub fn minhash(threshold: f64) -> Self {
+        Self {
+            strategy: Strategy::MinHash(threshold),
+            merge: None,
+        }
+    }
+
+    /// Documents whose embeddings have a cosine similarity of at least `threshold` are
+    /// duplicates (for documents with several embeddings, each embedding of the duplicate must be
+    /// similar to an embedding of the kept document). Note: every pair of documents is compared.
+    pub fn similarity(threshold: f64) -> Self {
+        Self {
+            strategy: Strategy::Similarity(threshold),
+            merge: None,
+        }
+    }
+
+    /// Merge the duplicates into the document they duplicate with `merge` (e.g.: to keep track
+    /// of all the sources of a document) instead of dropping them. The embeddings of the kept
+    /// document are left as is.
+    pub fn merge(mut self, merge: impl Fn(&mut T, T) + Send + Sync + 'static) -> Self {
+        self.merge = Some(Arc::new(merge));
+        self
+    }
+
+    /// Whether the duplicates can be detected before the documents are embedded
+    pub(crate) fn before_embedding(&self) -> bool {
+        matches!(self.strategy, Strategy::MinHash(_))
+    }
+
+    /// Deduplicate embedded documents (e.g.: built by an
+    /// [EmbeddingsBuilder](crate::embeddings::EmbeddingsBuilder))
+    pub fn dedup(
+        &self,
+        documents: Vec<(T, OneOrMany<Embedding>)>,
+    ) -> Vec<(T, OneOrMany<Embedding>)> {
+        match self.strategy {
+            Strategy::MinHash(_) => self.dedup_texts(
+                documents
+                    .into_iter()
+                    .map(|(document, embeddings)| {
+                        let texts = embeddings
+                            .iter()
+                            .map(|embedding| embedding.document.clone())
+                            .collect::<Vec<_>>();
+                        ((document, embeddings), texts)
+                    })
+                    .collect(),
+                |(kept, _), (duplicate, _)| self.merge_into(kept, duplicate),
+            ),
+            Strategy::Similarity(threshold) => {
+                let mut kept: Vec<(T, OneOrMany<Embedding>)> = vec![];
+                for (document, embeddings) in documents {
+                    match kept
+                        .iter()
+                        .position(|(_, other)| similar(&embeddings, other, threshold))
+                    {
+                        Some(i) => self.merge_into(&mut kept[i].0, document),
+                        None => kept.push((document, embeddings)),
+                    }
+                }
+                kept
+            }
+        }
+    }
+
+    /// Deduplicate documents by their texts (only with the [Deduplicator::minhash] strategy,
+    /// documents are returned as is otherwise)
+    pub(crate) fn dedup_unembedded(
+        &self,
+        documents: Vec<(T, Vec<String>)>,
+    ) -> Vec<(T, Vec<String>)> {
+        match self.strategy {
+            Strategy::MinHash(_) => self.dedup_texts(
+                documents
+                    .into_iter()
+                    .map(|(document, texts)| {
+                        let item = (document, texts.clone());
+                        (item, texts)
+                    })
+                    .collect(),
+                |(kept, _), (duplicate, _)| self.merge_into(kept, duplicate),
+            ),
+            Strategy::Similarity(_) => documents,
+        }
+    }
+
+    /// Deduplicate `items` by their texts with MinHash, merging the duplicates with `merge`
+    fn dedup_texts<I>(&self, items: Vec<(I, Vec<String>)>, merge: impl Fn(&mut I, I)) -> Vec<I> {
+        let Strategy::MinHash(threshold) = self.strategy else {
+            return items.into_iter().map(|(item, _)| item).collect();
+        };
+
+        let mut kept: Vec<(I, Vec<u64>)> = vec![];
+        // Index of the kept items by band of their signature: items sharing a band are candidate
+        // duplicates, which avoids comparing every pair of items
+        let mut bands: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+
+        for (item, texts) in items {
+            let signature = minhash_signature(&texts);
+            let keys = band_keys(&signature);
+
+            let duplicate_of = keys
+                .iter()
+                .filter_map(|key| bands.get(key))
+                .flatten()
+                .copied()
+                .find(|&i| jaccard_estimate(&kept[i].1, &signature) >= threshold);
+
+            match duplicate_of {
+                Some(i) => merge(&mut kept[i].0, item),
+                None => {
+                    for key in keys {
+                        bands.entry(key).or_default().push(kept.len());
+                    }
+                    kept.push((item, signature));
+                }
+            }
+        }
+
+        kept.into_iter().map(|(item, _)| item).collect()
+    }
+
+    fn merge_into(&self, kept: &mut T, duplicate: T) {
+        if let Some(merge) = &self.merge {
+            merge(kept, duplicate);
+        }
+    }
+}
+
+/// Whether each embedding of `embeddings` has a similar embedding in `other`
+fn similar(
+    embeddings: &OneOrMany<Embedding>,
+    other: &OneOrMany<Embedding>,
+    threshold: f64,
+) -> bool {
+    embeddings.iter().all(|embedding| {
+        other
+            .iter()
+            .any(|other| embedding.cosine_similarity(other, false) >= threshold)
+    })
+}
+
+/// Lowercased word shingles of `texts`
+fn shingles(texts: &[String]) -> Vec<u64> {
+    let words = texts
+        .iter()
+        .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+
+    words
+        .windows(SHINGLE_LEN.min(words.len()).max(1))
+        .map(|shingle| {
+            let mut hasher = DefaultHasher::new();
+            shingle.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+/// MinHash signature of `texts`: the minimum of each of the [NUM_HASHES] hash functions over
+/// their shingles
+fn minhash_signature(texts: &[String]) -> Vec<u64> {
+    let shingles = shingles(texts);
+    let mut seed = 0x9E37_79B9_7F4A_7C15u64;
+
+    (0..NUM_HASHES)
+        .map(|_| {
+            let (a, b) = (splitmix64(&mut seed) | 1, splitmix64(&mut seed));
+            shingles
+                .iter()
+                .map(|shingle| shingle.wrapping_mul(a).wrapping_add(b))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Keys of the bands of `signature` (band index, hash of the band)
+fn band_keys(signature: &[u64]) -> Vec<(usize, u64)> {
+    signature
+        .chunks(BAND_ROWS)
+        .enumerate()
+        .map(|(i, band)| {
+            let mut hasher = DefaultHasher::new();
+            band.hash(&mut hasher);
+            (i, hasher.finish())
+        })
+        .collect()
+}
+
+/// Estimated Jaccard similarity of the sets of shingles with the signatures `a` and `b`
+fn jaccard_estimate(a: &[u64], b: &[u64]) -> f64 {
+    let equal = a.iter().zip(b).filter(|(a, b)| a == b).count();
+    equal as f64 / NUM_HASHES as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn embedding(document: &str, vec: Vec<f64>) -> OneOrMany<Embedding> {
+        OneOrMany::one(Embedding {
+            document: document.to_string(),
+            vec,
+        })
+    }
+
+    #[test]
+    fn test_minhash() {
+        let page = |n: usize| {
+            format!(
+                "Flurbos are green aliens living on cold planets. They are known for their love \
+                of jazz and their fear of warm weather. Page {n}."
+            )
+        };
+        let documents = vec![
+            ("doc0", vec![page(1)]),
+            (
+                "doc1",
+                vec!["Glarbs are ancient tools used to farm the land.".to_string()],
+            ),
+            ("doc2", vec![page(2)]),
+            ("doc3", vec![page(1).to_uppercase()]),
+        ];
+
+        let deduplicated = Deduplicator::minhash(0.8)
+            .merge(|kept: &mut &str, duplicate| {
+                assert_eq!(*kept, "doc0");
+                assert!(["doc2", "doc3"].contains(&duplicate));
+            })
+            .dedup_unembedded(documents);
+
+        assert_eq!(
+            deduplicated.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec!["doc0", "doc1"]
+        );
+    }
+
+    #[test]
+    fn test_similarity() {
+        let documents = vec![
+            (vec!["doc0"], embedding("doc0", vec![1.0, 0.0, 0.0])),
+            (vec!["doc1"], embedding("doc1", vec![0.0, 1.0, 0.0])),
+            (vec!["doc2"], embedding("doc2", vec![0.99, 0.05, 0.0])),
+            (vec!["doc3"], embedding("doc3", vec![0.7, 0.7, 0.0])),
+        ];
+
+        let deduplicated = Deduplicator::similarity(0.95)
+            .merge(|kept: &mut Vec<&str>, duplicate| kept.extend(duplicate))
+            .dedup(documents);
+
+        assert_eq!(
+            deduplicated
+                .into_iter()
+                .map(|(ids, _)| ids)
+                .collect::<Vec<_>>(),
+            vec![vec!["doc0", "doc2"], vec!["doc1"], vec!["doc3"]]
+        );
+    }
+}
@@ -6,13 +6,22 @@
 //! Finally, the module defines the [EmbeddingError] enum, which represents various errors that
 //! can occur during embedding generation or processing.
 
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    completion::ResponseTiming,
+    http_client::{self, ApiError},
+    wasm_compat::WasmCompatSend,
+};
+
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum EmbeddingError {
     /// Http error (e.g.: connection error, timeout, etc.)
     #[error("HttpError: {0}")]
-    HttpError(#[from] reqwest::Error),
+    HttpError(#[from] crate::http_client::HttpError),
 
     /// Json error (e.g.: serialization, deserialization)
     #[error("JsonError: {0}")]
@@ -29,6 +38,55 @@ pub enum EmbeddingError {
     /// Error returned by the embedding model provider
     #[error("ProviderError: {0}")]
     ProviderError(String),
+
+    /// Error response returned by the embedding model provider (e.g.: rate limiting,
+    /// authentication error, invalid request)
+    #[error("ApiError: {0}")]
+    ApiError(#[from] ApiError),
+}
+
+impl From<reqwest::Error> for EmbeddingError {
+    fn from(error: reqwest::Error) -> Self {
+        EmbeddingError::HttpError(error.into())
+    }
+}
+
+impl EmbeddingError {
+    /// The error response returned by the provider, if any
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            EmbeddingError::ApiError(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status of the error response, if any
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self {
+            EmbeddingError::HttpError(error) => error.status(),
+            EmbeddingError::ApiError(error) => Some(error.status),
+            _ => None,
+        }
+    }
+
+    /// Delay requested by the provider before retrying, if any
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            EmbeddingError::HttpError(error) => error.retry_after(),
+            EmbeddingError::ApiError(error) => error.retry_after,
+            _ => None,
+        }
+    }
+
+    /// Whether the request may succeed if retried (e.g.: connection error, rate limiting or
+    /// server error)
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            EmbeddingError::HttpError(error) => error.is_retryable(),
+            EmbeddingError::ApiError(error) => error.is_retryable(),
+            _ => false,
+        }
+    }
 }
 
 /// Trait for embedding models that can generate embeddings for documents.
@@ -36,6 +94,13 @@ pub trait EmbeddingModel: Clone + Sync + Send {
     /// The maximum number of documents that can be embedded in a single request.
     const MAX_DOCUMENTS: usize;
 
+    /// The maximum number of documents that can be embedded in a single request by this model,
+    /// [EmbeddingModel::MAX_DOCUMENTS] by default. Overridden by models whose limit is only known
+    /// at runtime (e.g.: [BoxEmbeddingModel](crate::embeddings::BoxEmbeddingModel)).
+    fn max_documents(&self) -> usize {
+        Self::MAX_DOCUMENTS
+    }
+
     /// The number of dimensions in the embedding vector.
     fn ndims(&self) -> usize;
 
@@ -43,13 +108,27 @@ pub trait EmbeddingModel: Clone + Sync + Send {
     fn embed_texts(
         &self,
         texts: impl IntoIterator<Item = String> + Send,
-    ) -> impl std::future::Future<Output = Result<Vec<Embedding>, EmbeddingError>> + Send;
+    ) -> impl std::future::Future<Output = Result<Vec<Embedding>, EmbeddingError>> + WasmCompatSend;
+
+    /// Embed multiple text documents in a single request, returning the timing of the request
+    /// (queueing, time to first byte and retries of the [http_client::HttpClient]) along with
+    /// the embeddings.
+    fn embed_texts_with_timing(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> impl std::future::Future<Output = Result<(Vec<Embedding>, ResponseTiming), EmbeddingError>>
+           + WasmCompatSend {
+        async {
+            let (embeddings, timing) = http_client::with_timing(self.embed_texts(texts)).await;
+            Ok((embeddings?, timing))
+        }
+    }
 
     /// Embed a single text document.
     fn embed_text(
         &self,
         text: &str,
-    ) -> impl std::future::Future<Output = Result<Embedding, EmbeddingError>> + Send {
+    ) -> impl std::future::Future<Output = Result<Embedding, EmbeddingError>> + WasmCompatSend {
         async {
             Ok(self
                 .embed_texts(vec![text.to_string()])
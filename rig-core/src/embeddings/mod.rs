@@ -3,13 +3,17 @@
 //! natural language processing (NLP) tasks such as text classification, information retrieval,
 //! and document similarity.
 
+pub mod boxed;
 pub mod builder;
+pub mod dedup;
 pub mod embed;
 pub mod embedding;
 pub mod tool;
 
 pub mod distance;
+pub use boxed::{BoxEmbeddingModel, DynEmbeddingModel};
 pub use builder::EmbeddingsBuilder;
+pub use dedup::Deduplicator;
 pub use embed::{to_texts, Embed, EmbedError, TextEmbedder};
 pub use embedding::{Embedding, EmbeddingError, EmbeddingModel};
 pub use tool::ToolSchema;
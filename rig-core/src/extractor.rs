@@ -27,8 +27,50 @@
 //!     .await
 //!     .expect("Failed to extract data from text");
 //! ```
+//!
+//! # Extracting lists of entities
+//! When the text contains an arbitrary number of entities, use a [MultiExtractor]. Long inputs
+//! are split into chunks, each chunk is extracted separately and identical entities found in
+//! several chunks are only returned once.
+//! ```
+//! use rig::{extractor::MultiExtractorBuilder, providers::openai};
+//!
+//! let openai = openai::Client::new("your-open-ai-api-key");
+//!
+//! let extractor = MultiExtractorBuilder::<Person, _>::new(openai.completion_model(openai::GPT_4O))
+//!     .chunk_size(4_000)
+//!     .build();
+//!
+//! let people = extractor.extract(&long_document)
+//!     .await
+//!     .expect("Failed to extract data from text");
+//! ```
+//!
+//! # Extracting from a knowledge base
+//! A [GroundedExtractor] retrieves the documents relevant to a query from a vector store index
+//! and extracts the target structure from them, recording which documents each field came from.
+//! ```
+//! use rig::{extractor::GroundedExtractorBuilder, providers::openai};
+//!
+//! let openai = openai::Client::new("your-open-ai-api-key");
+//!
+//! let extractor = GroundedExtractorBuilder::<Person, _>::new(
+//!     openai.completion_model(openai::GPT_4O),
+//!     5,
+//!     index,
+//! )
+//! .build();
+//!
+//! let grounded = extractor.extract("Who is the head of the research team?")
+//!     .await
+//!     .expect("Failed to extract data from documents");
+//!
+//! println!("{:?} (sources: {:?})", grounded.data.name, grounded.sources_for("name"));
+//! ```
 
-use std::marker::PhantomData;
+use std::{collections::HashSet, marker::PhantomData};
+
+use futures::{stream, StreamExt, TryStreamExt};
 
 use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
@@ -36,8 +78,10 @@ use serde_json::json;
 
 use crate::{
     agent::{Agent, AgentBuilder},
-    completion::{CompletionModel, Prompt, PromptError, ToolDefinition},
+    chunking::{Chunker, RecursiveCharacterSplitter},
+    completion::{CompletionModel, Prompt, PromptError, ToolDefinition, Usage, UsageTracker},
     tool::Tool,
+    vector_store::VectorStoreIndexDyn,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -71,6 +115,11 @@ where
 
         Ok(serde_json::from_str(&summary)?)
     }
+
+    /// Total token usage of all the extractions made by the extractor
+    pub fn total_usage(&self) -> Usage {
+        self.agent.total_usage()
+    }
 }
 
 /// Builder for the Extractor
@@ -113,6 +162,23 @@ impl<T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync, M: Compl
         self
     }
 
+    /// Add some dynamic context to the extractor. On each extraction, `sample` documents
+    /// relevant to the input text will be retrieved from the index and inserted in the request.
+    pub fn dynamic_context(
+        mut self,
+        sample: usize,
+        dynamic_context: impl VectorStoreIndexDyn + 'static,
+    ) -> Self {
+        self.agent_builder = self.agent_builder.dynamic_context(sample, dynamic_context);
+        self
+    }
+
+    /// Set the tracker the token usage of the extractor is accumulated in
+    pub fn usage_tracker(mut self, usage_tracker: UsageTracker) -> Self {
+        self.agent_builder = self.agent_builder.usage_tracker(usage_tracker);
+        self
+    }
+
     /// Build the Extractor
     pub fn build(self) -> Extractor<M, T> {
         Extractor {
@@ -122,6 +188,270 @@ impl<T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync, M: Compl
     }
 }
 
+/// Wrapper schema used by the [MultiExtractor]. Tool parameters must be a JSON object, so the
+/// extracted list is nested under the `items` field.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct Items<T> {
+    /// Every entity found in the provided text
+    pub items: Vec<T>,
+}
+
+/// Extractor for lists of structured data from (possibly long) text
+pub struct MultiExtractor<
+    M: CompletionModel,
+    T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync,
+> {
+    extractor: Extractor<M, Items<T>>,
+    splitter: RecursiveCharacterSplitter,
+    concurrency: usize,
+}
+
+impl<T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync, M: CompletionModel>
+    MultiExtractor<M, T>
+where
+    M: Sync,
+{
+    /// Extract every entity of type `T` from `text`.
+    ///
+    /// The text is split into chunks of at most `chunk_size` characters (with a
+    /// [RecursiveCharacterSplitter]) which are extracted independently. Entities that serialize to the same JSON value are deduplicated, keeping
+    /// the order in which they first appear in the text.
+    pub async fn extract(&self, text: &str) -> Result<Vec<T>, ExtractionError> {
+        let chunks = self
+            .splitter
+            .split(text)
+            .into_iter()
+            .map(|span| text[span.range].trim())
+            .filter(|chunk| !chunk.is_empty());
+
+        let results = stream::iter(chunks)
+            .map(|chunk| async move {
+                match self.extractor.extract(chunk).await {
+                    Ok(Items { items }) => Ok(items),
+                    // A chunk without any entity is not an error for list extraction
+                    Err(ExtractionError::NoData) => Ok(vec![]),
+                    Err(e) => Err(e),
+                }
+            })
+            .buffered(self.concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        dedup(results.into_iter().flatten())
+    }
+
+    /// Total token usage of all the extractions made by the extractor
+    pub fn total_usage(&self) -> Usage {
+        self.extractor.total_usage()
+    }
+}
+
+/// Builder for the MultiExtractor
+pub struct MultiExtractorBuilder<
+    T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync + 'static,
+    M: CompletionModel,
+> {
+    extractor_builder: ExtractorBuilder<Items<T>, M>,
+    chunk_size: usize,
+    concurrency: usize,
+}
+
+impl<T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync, M: CompletionModel>
+    MultiExtractorBuilder<T, M>
+{
+    /// Default maximum number of characters per chunk
+    pub const DEFAULT_CHUNK_SIZE: usize = 8_000;
+
+    pub fn new(model: M) -> Self {
+        Self {
+            extractor_builder: ExtractorBuilder::new(model).preamble(
+                "The text may mention any number of entities. Submit ALL of them in the `items` \
+                list, each one only once. If the text does not mention any, submit an empty list.",
+            ),
+            chunk_size: Self::DEFAULT_CHUNK_SIZE,
+            concurrency: 1,
+        }
+    }
+
+    /// Add additional preamble to the extractor
+    pub fn preamble(mut self, preamble: &str) -> Self {
+        self.extractor_builder = self.extractor_builder.preamble(preamble);
+        self
+    }
+
+    /// Add a context document to the extractor
+    pub fn context(mut self, doc: &str) -> Self {
+        self.extractor_builder = self.extractor_builder.context(doc);
+        self
+    }
+
+    /// Set the maximum number of characters of text sent in a single extraction request
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set the number of chunks extracted concurrently
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Set the tracker the token usage of the extractor is accumulated in
+    pub fn usage_tracker(mut self, usage_tracker: UsageTracker) -> Self {
+        self.extractor_builder = self.extractor_builder.usage_tracker(usage_tracker);
+        self
+    }
+
+    /// Build the MultiExtractor
+    pub fn build(self) -> MultiExtractor<M, T> {
+        MultiExtractor {
+            extractor: self.extractor_builder.build(),
+            splitter: RecursiveCharacterSplitter::new(self.chunk_size, 0),
+            concurrency: self.concurrency,
+        }
+    }
+}
+
+/// Ids of the documents a field of the extracted data was taken from
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct FieldSource {
+    /// Name of the field (use dots for nested fields, e.g. `address.city`)
+    pub field: String,
+    /// Ids of the documents the value of the field was found in
+    pub document_ids: Vec<String>,
+}
+
+/// Data extracted by a [GroundedExtractor] along with the sources of each field
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct Grounded<T> {
+    /// The extracted data
+    pub data: T,
+    /// The documents each field of `data` was taken from
+    pub sources: Vec<FieldSource>,
+}
+
+impl<T> Grounded<T> {
+    /// Ids of the documents the given field was taken from (empty if the field is unsourced)
+    pub fn sources_for(&self, field: &str) -> Vec<&str> {
+        self.sources
+            .iter()
+            .filter(|source| source.field == field)
+            .flat_map(|source| source.document_ids.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Ids of all the documents used to extract the data
+    pub fn document_ids(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.sources
+            .iter()
+            .flat_map(|source| source.document_ids.iter().map(String::as_str))
+            .filter(|id| seen.insert(*id))
+            .collect()
+    }
+}
+
+/// Extractor for structured data grounded in documents retrieved from a vector store index
+pub struct GroundedExtractor<
+    M: CompletionModel,
+    T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync,
+> {
+    extractor: Extractor<M, Grounded<T>>,
+}
+
+impl<T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync, M: CompletionModel>
+    GroundedExtractor<M, T>
+where
+    M: Sync,
+{
+    /// Retrieve the documents relevant to `query` and extract the target structure from them.
+    pub async fn extract(&self, query: &str) -> Result<Grounded<T>, ExtractionError> {
+        self.extractor.extract(query).await
+    }
+
+    /// Total token usage of all the extractions made by the extractor
+    pub fn total_usage(&self) -> Usage {
+        self.extractor.total_usage()
+    }
+}
+
+/// Builder for the GroundedExtractor
+pub struct GroundedExtractorBuilder<
+    T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync + 'static,
+    M: CompletionModel,
+> {
+    extractor_builder: ExtractorBuilder<Grounded<T>, M>,
+}
+
+impl<T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync, M: CompletionModel>
+    GroundedExtractorBuilder<T, M>
+{
+    /// Create a new builder. On each extraction, `sample` documents are retrieved from `index`.
+    pub fn new(model: M, sample: usize, index: impl VectorStoreIndexDyn + 'static) -> Self {
+        Self {
+            extractor_builder: ExtractorBuilder::new(model)
+                .preamble(
+                    "Only use information found in the attached files to answer the user's query. \
+                    Put the extracted structure in the `data` field and, for every field you fill, \
+                    list the ids of the files the value was taken from in `sources`.",
+                )
+                .dynamic_context(sample, index),
+        }
+    }
+
+    /// Add additional preamble to the extractor
+    pub fn preamble(mut self, preamble: &str) -> Self {
+        self.extractor_builder = self.extractor_builder.preamble(preamble);
+        self
+    }
+
+    /// Add a context document to the extractor
+    pub fn context(mut self, doc: &str) -> Self {
+        self.extractor_builder = self.extractor_builder.context(doc);
+        self
+    }
+
+    /// Retrieve documents from an additional index on each extraction
+    pub fn dynamic_context(
+        mut self,
+        sample: usize,
+        dynamic_context: impl VectorStoreIndexDyn + 'static,
+    ) -> Self {
+        self.extractor_builder = self
+            .extractor_builder
+            .dynamic_context(sample, dynamic_context);
+        self
+    }
+
+    /// Set the tracker the token usage of the extractor is accumulated in
+    pub fn usage_tracker(mut self, usage_tracker: UsageTracker) -> Self {
+        self.extractor_builder = self.extractor_builder.usage_tracker(usage_tracker);
+        self
+    }
+
+    /// Build the GroundedExtractor
+    pub fn build(self) -> GroundedExtractor<M, T> {
+        GroundedExtractor {
+            extractor: self.extractor_builder.build(),
+        }
+    }
+}
+
+/// Remove items that serialize to the same JSON value, keeping the first occurrence.
+fn dedup<T: Serialize>(items: impl IntoIterator<Item = T>) -> Result<Vec<T>, ExtractionError> {
+    let mut seen = HashSet::new();
+    let mut unique = vec![];
+
+    for item in items {
+        if seen.insert(serde_json::to_string(&item)?) {
+            unique.push(item);
+        }
+    }
+
+    Ok(unique)
+}
+
 #[derive(Deserialize, Serialize)]
 struct SubmitTool<T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync> {
     _t: PhantomData<T>,
@@ -150,3 +480,84 @@ impl<T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync> Tool for
         Ok(data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_multi_extractor_chunks() {
+        use crate::{message::Message, providers::mock::MockCompletionModel};
+
+        let model = MockCompletionModel::new()
+            .tool_call("submit", json!({ "items": ["a", "b"] }))
+            .tool_call("submit", json!({ "items": ["b", "c"] }));
+        let extractor = MultiExtractorBuilder::<String, _>::new(model.clone())
+            .chunk_size(30)
+            .build();
+
+        let items = extractor
+            .extract("First paragraph here.\n\nSecond paragraph here.")
+            .await
+            .unwrap();
+        assert_eq!(items, vec!["a", "b", "c"]);
+
+        let prompts = model
+            .requests()
+            .into_iter()
+            .map(|request| request.prompt)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            prompts,
+            vec![
+                Message::user("First paragraph here."),
+                Message::user("Second paragraph here.")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grounded_sources() {
+        let grounded = Grounded {
+            data: (),
+            sources: vec![
+                FieldSource {
+                    field: "name".to_string(),
+                    document_ids: vec!["doc1".to_string(), "doc2".to_string()],
+                },
+                FieldSource {
+                    field: "age".to_string(),
+                    document_ids: vec!["doc2".to_string()],
+                },
+            ],
+        };
+
+        assert_eq!(grounded.sources_for("name"), vec!["doc1", "doc2"]);
+        assert!(grounded.sources_for("profession").is_empty());
+        assert_eq!(grounded.document_ids(), vec!["doc1", "doc2"]);
+    }
+
+    #[test]
+    fn test_dedup() {
+        #[derive(Debug, PartialEq, Serialize)]
+        struct Person {
+            name: String,
+        }
+
+        let people = ["Alice", "Bob", "Alice"].into_iter().map(|name| Person {
+            name: name.to_string(),
+        });
+
+        assert_eq!(
+            dedup(people).unwrap(),
+            vec![
+                Person {
+                    name: "Alice".to_string()
+                },
+                Person {
+                    name: "Bob".to_string()
+                }
+            ]
+        );
+    }
+}
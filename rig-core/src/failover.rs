@@ -0,0 +1,249 @@
+//! This module provides a [FailoverModel], a completion model wrapping an ordered list of
+//! completion models (possibly from different providers) and falling back to the next model when
+//! a model fails.
+//!
+//! The [CompletionRequest]s being provider agnostic, the same request is sent to each model in
+//! turn, each model translating it to the format of its provider. Which errors trigger a failover
+//! can be configured with [FailoverModel::should_failover] (all errors by default), and each model
+//! can be given a timeout with [FailoverModel::timeout], after which the next model is tried.
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//! use rig::{completion::{CompletionError, Prompt}, failover::FailoverModel, providers::{anthropic, openai}};
+//!
+//! let openai = openai::Client::from_env();
+//! let anthropic = anthropic::Client::from_env();
+//!
+//! let model = FailoverModel::new(openai.completion_model(openai::GPT_4O))
+//!     .fallback(anthropic.completion_model(anthropic::CLAUDE_3_5_SONNET))
+//!     .timeout(Duration::from_secs(30))
+//!     // Do not fail over on invalid requests
+//!     .should_failover(|error| !matches!(error, CompletionError::RequestError(_)));
+//!
+//! let agent = rig::agent::AgentBuilder::new(model).build();
+//! let answer = agent.prompt("Hello!").await?;
+//! ```
+
+use std::{sync::Arc, time::Duration};
+
+use futures::future::{self, Either};
+
+use crate::completion::{
+    self, BoxCompletionModel, BoxRawResponse, CompletionError, CompletionModel, CompletionRequest,
+};
+
+/// Raw response of a [FailoverModel]
+#[derive(Debug)]
+pub struct FailoverResponse {
+    /// Index of the model which returned the response (0 for the primary model)
+    pub model_index: usize,
+    /// Raw response of the model, which can be downcast to the `Response` type of the model
+    pub raw_response: BoxRawResponse,
+}
+
+type FailoverPredicate = Arc<dyn Fn(&CompletionError) -> bool + Send + Sync>;
+
+/// Completion model falling back to the next of its models when a model fails
+#[derive(Clone)]
+pub struct FailoverModel {
+    models: Vec<BoxCompletionModel>,
+    should_failover: FailoverPredicate,
+    timeout: Option<Duration>,
+}
+
+impl FailoverModel {
+    /// Create a failover model with `model` as its primary model
+    pub fn new<M>(model: M) -> Self
+    where
+        M: CompletionModel + 'static,
+        M::Response: 'static,
+    {
+        Self {
+            models: vec![model.boxed()],
+            should_failover: Arc::new(|_| true),
+            timeout: None,
+        }
+    }
+
+    /// Add a model to try if the previous models failed
+    pub fn fallback<M>(mut self, model: M) -> Self
+    where
+        M: CompletionModel + 'static,
+        M::Response: 'static,
+    {
+        self.models.push(model.boxed());
+        self
+    }
+
+    /// Set which errors trigger a failover to the next model. Other errors are returned
+    /// immediately. By default, all errors trigger a failover.
+    pub fn should_failover(
+        mut self,
+        should_failover: impl Fn(&CompletionError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.should_failover = Arc::new(should_failover);
+        self
+    }
+
+    /// Fail over to the next model if a model does not respond within `timeout`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send the request to a single model, within the timeout (if any)
+    async fn try_model(
+        &self,
+        model: &BoxCompletionModel,
+        request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<BoxRawResponse>, CompletionError> {
+        let Some(timeout) = self.timeout else {
+            return model.completion(request).await;
+        };
+
+        let completion = std::pin::pin!(model.completion(request));
+        match future::select(completion, futures_timer::Delay::new(timeout)).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(CompletionError::ProviderError(format!(
+                "Model did not respond within {timeout:?}"
+            ))),
+        }
+    }
+}
+
+impl CompletionModel for FailoverModel {
+    type Response = FailoverResponse;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<FailoverResponse>, CompletionError> {
+        let mut models = self.models.iter().enumerate().peekable();
+
+        loop {
+            let (model_index, model) = models.next().expect("There should be at least one model");
+
+            match self.try_model(model, request.clone()).await {
+                Ok(response) => {
+                    return Ok(completion::CompletionResponse {
+                        choice: response.choice,
+                        usage: response.usage,
+                        timing: response.timing,
+                        raw_response: FailoverResponse {
+                            model_index,
+                            raw_response: response.raw_response,
+                        },
+                    })
+                }
+                Err(error) if models.peek().is_some() && (self.should_failover)(&error) => {
+                    tracing::warn!(target: "rig",
+                        "Model {model_index} failed: {error}, failing over to model {}",
+                        model_index + 1
+                    );
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        completion::{Prompt, Usage},
+        message::AssistantContent,
+        OneOrMany,
+    };
+
+    #[derive(Clone)]
+    struct FailingModel {
+        calls: Arc<Mutex<usize>>,
+    }
+
+    impl CompletionModel for FailingModel {
+        type Response = ();
+
+        async fn completion(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<completion::CompletionResponse<()>, CompletionError> {
+            *self.calls.lock().unwrap() += 1;
+            Err(CompletionError::ProviderError("Overloaded".to_string()))
+        }
+    }
+
+    #[derive(Clone)]
+    struct SlowModel;
+
+    impl CompletionModel for SlowModel {
+        type Response = ();
+
+        async fn completion(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<completion::CompletionResponse<()>, CompletionError> {
+            futures_timer::Delay::new(Duration::from_secs(10)).await;
+            Err(CompletionError::ProviderError("Too slow".to_string()))
+        }
+    }
+
+    #[derive(Clone)]
+    struct HelloModel;
+
+    impl CompletionModel for HelloModel {
+        type Response = String;
+
+        async fn completion(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<completion::CompletionResponse<String>, CompletionError> {
+            Ok(completion::CompletionResponse {
+                choice: OneOrMany::one(AssistantContent::text("Hello!")),
+                usage: Usage::new(1, 1),
+                timing: completion::ResponseTiming::default(),
+                raw_response: "raw".to_string(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failover() {
+        let calls = Arc::new(Mutex::new(0));
+        let model = FailoverModel::new(FailingModel {
+            calls: calls.clone(),
+        })
+        .fallback(SlowModel)
+        .fallback(HelloModel)
+        .timeout(Duration::from_millis(50));
+
+        let response = model
+            .completion_request("Hi")
+            .send()
+            .await
+            .expect("Failover model should respond");
+        assert_eq!(*calls.lock().unwrap(), 1);
+        assert_eq!(response.raw_response.model_index, 2);
+        assert_eq!(
+            response.raw_response.raw_response.downcast_ref::<String>(),
+            Some(&"raw".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_should_failover() {
+        let calls = Arc::new(Mutex::new(0));
+        let model = FailoverModel::new(FailingModel {
+            calls: calls.clone(),
+        })
+        .fallback(HelloModel)
+        .should_failover(|error| !matches!(error, CompletionError::ProviderError(_)));
+
+        let agent = crate::agent::AgentBuilder::new(model).build();
+        assert!(agent.prompt("Hi").await.is_err());
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+}
@@ -0,0 +1,1342 @@
+//! This module provides the HTTP client shared by all the providers.
+//!
+//! The [HttpClient] wraps a [reqwest::Client] and can retry the requests that fail with a
+//! transient error (rate limiting (`429`), server errors (`5xx`) or connection errors) with an
+//! exponential backoff, honoring the `retry-after` headers returned by the provider.
+//!
+//! The HTTP settings of the provider clients are set with the methods of [HttpClientSettings]
+//! (and the settings of their [reqwest::Client] with the methods of [ReqwestClientSettings] on
+//! their `ClientBuilder`).
+//!
+//! Requests are not retried by default: retries are enabled with the `with_retry_policy` method
+//! of the provider client (e.g.: with [RetryPolicy::default]). Requests that cannot be safely
+//! replayed (e.g.: creating a batch) are marked with [RequestBuilder::idempotent] and are never
+//! retried.
+//!
+//! The rate limiting of a provider client can be configured
+//! and a client-side [RateLimiter] can be attached with its `with_rate_limiter` method. Requests
+//! can be distributed across several API keys with an [ApiKeyPool], attached with the
+//! `with_api_keys` method of the provider client, and short-lived credentials can be fetched by
+//! a [CredentialProvider](crate::credentials::CredentialProvider) attached with the
+//! `with_credentials` method of the provider client.
+//!
+//! The default headers of the provider (e.g.: authentication headers) are set on each request
+//! rather than on the [reqwest::Client], so that the [reqwest::Client] can be replaced with the
+//! `with_http_client` method of the `ClientBuilder` of the provider (e.g.: to configure a proxy,
+//! custom root certificates or connection pooling). Extra headers (e.g.: the headers required by an API
+//! gateway) can be added with the `with_headers` method of the provider client.
+//!
+//! The requests are sent by the [reqwest::Client] unless an [HttpTransport] is attached with the
+//! `with_transport` method of the provider client, in which case the requests are still built
+//! (and retried, rate limited, etc.) by the [HttpClient] but sent by the transport (e.g.: a
+//! transport based on another HTTP library, or an in-process transport for tests). The errors of
+//! the transport are returned as [HttpError::Transport] errors.
+//!
+//! On `wasm32`, the requests are sent with the `fetch` API of the browser by [reqwest::Client],
+//! and only [reqwest::Client] can create responses: requests exceeding the budget of a
+//! [RateLimiter] wait for the budget regardless of its behavior, and custom transports are not
+//! supported.
+//!
+//! The timing of the requests (time spent waiting for the [RateLimiter], time to the first byte
+//! of the response and number of retries) is reported to the [ResponseTiming] of the completion
+//! and embedding responses (see [with_timing]).
+//!
+//! The error responses of the providers are parsed into an [ApiError], carrying the HTTP status,
+//! the error code and type returned by the provider (if any) and the delay requested by its
+//! `retry-after` headers, so that errors can be classified without matching on their message
+//! (see [CompletionError::is_retryable](crate::completion::CompletionError::is_retryable)).
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use rig::{
+//!     http_client::{HttpClientSettings, ReqwestClientSettings, RetryPolicy},
+//!     providers::openai::{self, ClientBuilder},
+//! };
+//!
+//! // Retry up to 3 times
+//! let openai = openai::Client::from_env().with_retry_policy(RetryPolicy::default());
+//!
+//! let openai = openai::Client::from_env().with_retry_policy(RetryPolicy {
+//!     max_retries: 5,
+//!     initial_backoff: Duration::from_secs(1),
+//!     ..Default::default()
+//! });
+//!
+//! // Send the requests through a corporate proxy
+//! let openai = ClientBuilder::new("your-open-ai-api-key")
+//!     .with_http_client(
+//!         reqwest::Client::builder()
+//!             .proxy(reqwest::Proxy::all("http://proxy.corp.example:8080")?)
+//!             .build()?,
+//!     )
+//!     .build();
+//! # Ok::<(), reqwest::Error>(())
+//! ```
+
+use std::{
+    cell::RefCell,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use reqwest::{
+    header::{HeaderMap, InvalidHeaderValue},
+    IntoUrl, StatusCode,
+};
+use serde::Serialize;
+
+use crate::{
+    completion::ResponseTiming,
+    credentials::{CredentialError, CredentialProvider, Credentials},
+    key_pool::{ApiKeyPlacement, ApiKeyPool},
+    rate_limit::{estimate_tokens, RateLimitExceeded, RateLimiter},
+    wasm_compat::{Instant, SystemTime, WasmBoxedFuture, WasmCompatSend, WasmCompatSync},
+};
+
+thread_local! {
+    /// Timing of the requests sent while a future wrapped by [with_timing] is polled
+    static TIMING: RefCell<Option<Arc<Mutex<ResponseTiming>>>> = const { RefCell::new(None) };
+}
+
+/// Run `future`, collecting the timing of the requests sent by the [HttpClient]s while it is
+/// polled: the time spent waiting for the [RateLimiter]s and the retries are summed over the
+/// requests, and the time to first byte is the one of the last request. The `total` of the
+/// returned timing is the duration of `future`.
+pub async fn with_timing<F: Future>(future: F) -> (F::Output, ResponseTiming) {
+    let timing = Arc::new(Mutex::new(ResponseTiming::default()));
+    let start = Instant::now();
+
+    let mut future = std::pin::pin!(future);
+    let output = futures::future::poll_fn(|cx| {
+        let previous = TIMING.with(|current| current.replace(Some(timing.clone())));
+        let poll = future.as_mut().poll(cx);
+        TIMING.with(|current| *current.borrow_mut() = previous);
+        poll
+    })
+    .await;
+
+    let timing = ResponseTiming {
+        total: start.elapsed(),
+        ..*timing.lock().expect("Mutex should not be poisoned")
+    };
+    (output, timing)
+}
+
+/// Update the timing collected by the enclosing [with_timing], if any
+fn record_timing(update: impl FnOnce(&mut ResponseTiming)) {
+    TIMING.with(|timing| {
+        if let Some(timing) = &*timing.borrow() {
+            update(&mut timing.lock().expect("Mutex should not be poisoned"));
+        }
+    });
+}
+
+/// Policy used by the [HttpClient] to retry failed requests
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries of a request (0 disables retries)
+    pub max_retries: u32,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Maximum delay between two retries (unless the provider asks for a longer delay
+    /// with a `retry-after` header)
+    pub max_backoff: Duration,
+    /// Factor by which the delay is multiplied after each retry
+    pub multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries requests
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before the retry following `retries` previous retries
+    pub fn backoff(&self, retries: u32) -> Duration {
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(retries as i32);
+        Duration::from_secs_f64(backoff.min(self.max_backoff.as_secs_f64()))
+    }
+
+    /// Whether a response with the given status should be retried
+    pub fn should_retry(&self, status: StatusCode) -> bool {
+        is_retryable_status(status)
+    }
+}
+
+/// Whether a response with the given status is a transient failure (request timeout, rate
+/// limiting or server error)
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+}
+
+/// Whether a request error is transient (e.g.: connection refused or reset, timeout)
+pub(crate) fn is_transient(error: &reqwest::Error) -> bool {
+    // Connection errors are only reported as request errors by `fetch` on wasm32
+    #[cfg(target_arch = "wasm32")]
+    let connection_error = error.is_request();
+    #[cfg(not(target_arch = "wasm32"))]
+    let connection_error = error.is_connect();
+
+    connection_error || error.is_timeout() || error.status().is_some_and(is_retryable_status)
+}
+
+/// Delay requested by the provider with the `retry-after-ms` or `retry-after` headers (either a
+/// number of seconds or an HTTP date)
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+    };
+    let seconds = |value: &str| {
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite() && *value >= 0.0)
+    };
+
+    header("retry-after-ms")
+        .and_then(seconds)
+        .map(|ms| Duration::from_secs_f64(ms / 1000.0))
+        .or_else(|| {
+            let value = header("retry-after")?;
+            seconds(value)
+                .map(Duration::from_secs_f64)
+                .or_else(|| retry_after_date(value))
+        })
+}
+
+/// Delay until the HTTP date of a `retry-after` header (zero if the date is in the past)
+fn retry_after_date(value: &str) -> Option<Duration> {
+    let date = httpdate::parse_http_date(value)
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    // `std::time::SystemTime::now` panics on wasm32
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?;
+
+    Some(date.saturating_sub(now))
+}
+
+/// Error response returned by a provider
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+#[error("{status}: {message}")]
+pub struct ApiError {
+    /// HTTP status of the response
+    pub status: StatusCode,
+    /// Error code returned by the provider (e.g.: `"rate_limit_exceeded"`), if any
+    pub code: Option<String>,
+    /// Error type returned by the provider (e.g.: `"overloaded_error"`), if any
+    pub error_type: Option<String>,
+    /// Error message returned by the provider (or the body of the response if it could not be
+    /// parsed)
+    pub message: String,
+    /// Delay requested by the provider before retrying, if any
+    pub retry_after: Option<Duration>,
+}
+
+impl ApiError {
+    /// Parse an error response of a provider. The error code, type and message are read from the
+    /// common error formats (e.g.: `{"error": {"message": ..., "type": ..., "code": ...}}`),
+    /// falling back to the raw body.
+    pub async fn from_response(response: reqwest::Response) -> reqwest::Result<Self> {
+        let status = response.status();
+        let retry_after = retry_after(response.headers());
+        let body = response.text().await?;
+
+        Ok(Self::from_body(status, retry_after, body))
+    }
+
+    /// Parse the body of an error response of a provider (see [ApiError::from_response]), e.g.:
+    /// an error returned with a `2xx` status
+    pub fn from_body(status: StatusCode, retry_after: Option<Duration>, body: String) -> Self {
+        let json = serde_json::from_str::<serde_json::Value>(&body).unwrap_or_default();
+        // e.g.: `{"error": {"message": ...}}` or `{"error": "..."}` or `{"message": ...}`
+        let error = match json.get("error") {
+            Some(error) if error.is_object() => error,
+            _ => &json,
+        };
+        let string = |value: Option<&serde_json::Value>| match value? {
+            serde_json::Value::String(string) => Some(string.clone()),
+            serde_json::Value::Number(number) => Some(number.to_string()),
+            _ => None,
+        };
+
+        Self {
+            status,
+            code: string(error.get("code")),
+            // Gemini returns the type of the error in `status` (e.g.: `"RESOURCE_EXHAUSTED"`)
+            error_type: string(error.get("type")).or_else(|| string(error.get("status"))),
+            message: string(error.get("message"))
+                .or_else(|| string(json.get("error")))
+                .unwrap_or(body),
+            retry_after,
+        }
+    }
+
+    /// Whether the request may succeed if retried (request timeout, rate limiting or server
+    /// error)
+    pub fn is_retryable(&self) -> bool {
+        is_retryable_status(self.status)
+    }
+
+    /// Whether the request was rejected because of rate limiting
+    pub fn is_rate_limited(&self) -> bool {
+        self.status == StatusCode::TOO_MANY_REQUESTS
+    }
+}
+
+/// Error sending a request with the [HttpClient]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum HttpError {
+    /// Error of the [reqwest::Client] (e.g.: connection error, timeout)
+    #[error("{0}")]
+    Reqwest(#[from] reqwest::Error),
+
+    /// The API key (or credentials) of the request cannot be sent in a header
+    #[error("Invalid API key: {0}")]
+    InvalidApiKey(#[from] InvalidHeaderValue),
+
+    /// The [HttpTransport] of the client failed to send the request
+    #[error("TransportError: {0}")]
+    Transport(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    /// The credentials of the request could not be fetched from the [CredentialProvider](crate::credentials::CredentialProvider)
+    #[error("{0}")]
+    Credentials(#[from] CredentialError),
+
+    /// The request exceeds the budget of the [RateLimiter] of the client, configured with
+    /// [RateLimitBehavior::Error](crate::rate_limit::RateLimitBehavior::Error)
+    #[error("{0}")]
+    RateLimitExceeded(#[from] RateLimitExceeded),
+}
+
+impl HttpError {
+    /// The HTTP status of the error, if any
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            HttpError::Reqwest(error) => error.status(),
+            HttpError::RateLimitExceeded(_) => Some(StatusCode::TOO_MANY_REQUESTS),
+            _ => None,
+        }
+    }
+
+    /// Delay before the request may succeed, if known (e.g.: until the budget of the
+    /// [RateLimiter] allows it)
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            HttpError::RateLimitExceeded(error) => Some(error.retry_after),
+            _ => None,
+        }
+    }
+
+    /// Whether the request may succeed if retried (e.g.: connection error, or client-side rate
+    /// limit exceeded)
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            HttpError::Reqwest(error) => is_transient(error),
+            HttpError::RateLimitExceeded(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Error returned by an [HttpTransport]
+#[derive(Debug, thiserror::Error)]
+pub enum TransportError {
+    /// Error of a [reqwest::Client], handled like the errors of the default transport (e.g.:
+    /// connection errors are retried)
+    #[error("{0}")]
+    Reqwest(#[from] reqwest::Error),
+
+    /// Any other error, returned as an [HttpError::Transport] error (and not retried)
+    #[error("TransportError: {0}")]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+/// Transport sending the HTTP requests of the providers, implemented by [reqwest::Client].
+///
+/// The transports exchange plain [http] requests and responses, so that they can be implemented
+/// with any HTTP library. The body of the responses is read in full by the transport, so
+/// streaming responses are only received once complete.
+///
+/// # Example
+/// ```rust
+/// use bytes::Bytes;
+/// use futures::future::BoxFuture;
+/// use rig::{
+///     http_client::{HttpClientSettings, HttpTransport, TransportError},
+///     providers::openai,
+/// };
+///
+/// /// In-process transport answering every request with the same completion
+/// struct FakeOpenAI;
+///
+/// impl HttpTransport for FakeOpenAI {
+///     fn execute(
+///         &self,
+///         _request: http::Request<Bytes>,
+///     ) -> BoxFuture<'_, Result<http::Response<Bytes>, TransportError>> {
+///         Box::pin(async {
+///             let body = include_str!("fixtures/completion.json");
+///             Ok(http::Response::new(Bytes::from(body)))
+///         })
+///     }
+/// }
+///
+/// let openai = openai::Client::new("fake-key").with_transport(FakeOpenAI);
+/// ```
+pub trait HttpTransport: WasmCompatSend + WasmCompatSync {
+    /// Send `request` and return the response of the provider
+    fn execute(
+        &self,
+        request: http::Request<Bytes>,
+    ) -> WasmBoxedFuture<'_, Result<http::Response<Bytes>, TransportError>>;
+}
+
+impl HttpTransport for reqwest::Client {
+    fn execute(
+        &self,
+        request: http::Request<Bytes>,
+    ) -> WasmBoxedFuture<'_, Result<http::Response<Bytes>, TransportError>> {
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let response = self
+                .request(parts.method, parts.uri.to_string())
+                .headers(parts.headers)
+                .body(body)
+                .send()
+                .await?;
+
+            let mut builder = http::Response::builder().status(response.status());
+            if let Some(headers) = builder.headers_mut() {
+                *headers = response.headers().clone();
+            }
+            Ok(builder
+                .body(response.bytes().await?)
+                .expect("Response should build"))
+        })
+    }
+}
+
+/// Shared [HttpTransport] of an [HttpClient]
+#[derive(Clone)]
+struct Transport(Arc<dyn HttpTransport>);
+
+impl std::fmt::Debug for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Transport").finish_non_exhaustive()
+    }
+}
+
+impl Transport {
+    /// Send a request built by the [reqwest::Client] with the transport, converting it (and its
+    /// response) from (and to) the [http] types exchanged by the transport
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn send(&self, request: reqwest::Request) -> Result<reqwest::Response, HttpError> {
+        use http_body_util::BodyExt;
+
+        let (parts, body) = http::Request::<reqwest::Body>::try_from(request)?.into_parts();
+        let request = http::Request::from_parts(parts, body.collect().await?.to_bytes());
+
+        match self.0.execute(request).await {
+            Ok(response) => Ok(response.into()),
+            Err(TransportError::Reqwest(error)) => Err(error.into()),
+            Err(TransportError::Other(error)) => Err(HttpError::Transport(error)),
+        }
+    }
+
+    /// Only [reqwest::Client] can create responses on `wasm32`
+    #[cfg(target_arch = "wasm32")]
+    async fn send(&self, _request: reqwest::Request) -> Result<reqwest::Response, HttpError> {
+        Err(HttpError::Transport(
+            "Custom transports are not supported on wasm32".into(),
+        ))
+    }
+}
+
+/// Options of the [reqwest::Client] of a provider client, set on its `ClientBuilder` with the
+/// methods of [ReqwestClientSettings]
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestOptions {
+    client: Option<reqwest::Client>,
+    accept_invalid_certs: bool,
+}
+
+impl ReqwestOptions {
+    /// Whether the default [reqwest::Client] can be used
+    pub(crate) fn is_default(&self) -> bool {
+        self.client.is_none() && !self.accept_invalid_certs
+    }
+
+    /// The custom client set with `with_http_client`, if any, or a new default client
+    pub(crate) fn build(self) -> reqwest::Client {
+        self.build_with(reqwest::Client::builder())
+    }
+
+    /// The custom client set with `with_http_client`, if any, or a new client built by `builder`
+    /// (accepting invalid TLS certificates if `accept_invalid_certs` is set).
+    ///
+    /// The custom client wins over `accept_invalid_certs`, since the TLS configuration of a built
+    /// [reqwest::Client] cannot be changed. `accept_invalid_certs` is also ignored without a TLS
+    /// backend (the `reqwest-native-tls` or `reqwest-rustls` feature) and on `wasm32`. A warning
+    /// is logged in both cases.
+    ///
+    /// Panics if the client cannot be built.
+    pub fn build_with(self, builder: reqwest::ClientBuilder) -> reqwest::Client {
+        if let Some(client) = self.client {
+            if self.accept_invalid_certs {
+                tracing::warn!(
+                    target: "rig",
+                    "`danger_accept_invalid_certs` is ignored since a custom reqwest::Client is set \
+                     with `with_http_client`: set it on its reqwest::ClientBuilder instead"
+                );
+            }
+            return client;
+        }
+
+        #[cfg(all(
+            any(feature = "reqwest-native-tls", feature = "reqwest-rustls"),
+            not(target_arch = "wasm32")
+        ))]
+        let builder = builder.danger_accept_invalid_certs(self.accept_invalid_certs);
+
+        #[cfg(not(all(
+            any(feature = "reqwest-native-tls", feature = "reqwest-rustls"),
+            not(target_arch = "wasm32")
+        )))]
+        if self.accept_invalid_certs {
+            tracing::warn!(
+                target: "rig",
+                "`danger_accept_invalid_certs` is ignored without a TLS backend (enable the \
+                 `reqwest-native-tls` or `reqwest-rustls` feature) and on wasm32"
+            );
+        }
+
+        builder.build().expect("reqwest client should build")
+    }
+}
+
+/// Settings of the [reqwest::Client] shared by the `ClientBuilder`s of the providers
+///
+/// # Example
+/// ```rust
+/// use rig::{http_client::ReqwestClientSettings, providers::openai::ClientBuilder};
+///
+/// // Send the requests through a corporate proxy
+/// let openai = ClientBuilder::new("your-open-ai-api-key")
+///     .with_http_client(
+///         reqwest::Client::builder()
+///             .proxy(reqwest::Proxy::all("http://proxy.corp.example:8080")?)
+///             .build()?,
+///     )
+///     .build();
+/// # Ok::<(), reqwest::Error>(())
+/// ```
+pub trait ReqwestClientSettings: Sized {
+    /// Options of the [reqwest::Client] of the client being built
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions;
+
+    /// Use a custom [reqwest::Client] to send the requests (e.g.: to configure a proxy, custom
+    /// root certificates, connection pooling or the `User-Agent`)
+    fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.reqwest_options_mut().client = Some(http_client);
+        self
+    }
+
+    /// Accept invalid TLS certificates (e.g.: the self-signed certificate of an internal
+    /// gateway). Ignored, with a warning, if a custom client is set with `with_http_client`
+    /// (configure the custom client instead), without a TLS backend (the `reqwest-native-tls` or
+    /// `reqwest-rustls` feature) and on `wasm32`.
+    fn danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.reqwest_options_mut().accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+}
+
+/// HTTP settings shared by the provider clients (retries, rate limiting, authentication,
+/// transport and headers), applied to their [HttpClient]
+///
+/// # Example
+/// ```rust
+/// use rig::{
+///     http_client::{HttpClientSettings, RetryPolicy},
+///     providers::openai,
+///     rate_limit::RateLimiter,
+/// };
+///
+/// let openai = openai::Client::from_env()
+///     .with_retry_policy(RetryPolicy::default())
+///     .with_rate_limiter(RateLimiter::new().requests_per_minute(500));
+/// ```
+pub trait HttpClientSettings: Sized {
+    /// The [HttpClient] sending the requests of the client
+    fn http_client_mut(&mut self) -> &mut HttpClient;
+
+    /// How the provider expects the keys set with
+    /// [with_api_keys](HttpClientSettings::with_api_keys) and the credentials set with
+    /// [with_credentials](HttpClientSettings::with_credentials) to be sent (bearer tokens by
+    /// default)
+    fn api_key_placement(&self) -> ApiKeyPlacement {
+        ApiKeyPlacement::bearer()
+    }
+
+    /// Set the policy used to retry requests failing with a transient error
+    /// (see [RetryPolicy]). By default, requests are not retried.
+    fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.http_client_mut().retry_policy = retry_policy;
+        self
+    }
+
+    /// Attach a client-side rate limiter to the client (see [RateLimiter])
+    fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.http_client_mut().rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Distribute the requests across the keys of an [ApiKeyPool], replacing the API key the
+    /// client was created with
+    fn with_api_keys(mut self, api_keys: ApiKeyPool) -> Self {
+        let placement = self.api_key_placement();
+        self.http_client_mut().auth.api_keys = Some((api_keys, placement));
+        self
+    }
+
+    /// Fetch short-lived credentials (e.g.: tokens) from `provider`, refreshing them before they
+    /// expire, instead of using the API key the client was created with (see [Credentials])
+    fn with_credentials(mut self, provider: impl CredentialProvider + 'static) -> Self {
+        let placement = self.api_key_placement();
+        self.http_client_mut().auth.credentials = Some((Credentials::new(provider), placement));
+        self
+    }
+
+    /// Send the requests with a custom [HttpTransport] (e.g.: another HTTP library, or an
+    /// in-process transport for tests) instead of the [reqwest::Client]
+    fn with_transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.http_client_mut().transport = Some(Transport(Arc::new(transport)));
+        self
+    }
+
+    /// Add headers sent with every request (e.g.: the headers required by an API gateway)
+    fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.http_client_mut().headers.extend(headers);
+        self
+    }
+}
+
+/// HTTP client used by the providers, retrying failed requests according to its [RetryPolicy]
+/// and throttling them according to its [RateLimiter] (if any)
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    /// Headers set on every request
+    headers: HeaderMap,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
+    auth: Auth,
+    transport: Option<Transport>,
+}
+
+/// Credentials set on each request, replacing the API key set in the default headers
+#[derive(Clone, Debug, Default)]
+struct Auth {
+    api_keys: Option<(ApiKeyPool, ApiKeyPlacement)>,
+    credentials: Option<(Credentials, ApiKeyPlacement)>,
+}
+
+impl HttpClient {
+    /// Wrap a [reqwest::Client], without retries (see [RetryPolicy::none])
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            headers: HeaderMap::new(),
+            retry_policy: RetryPolicy::none(),
+            rate_limiter: None,
+            auth: Auth::default(),
+            transport: None,
+        }
+    }
+
+    /// Replace the underlying [reqwest::Client], keeping the default headers, retry policy
+    /// and rate limiter
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Set the headers sent with every request (e.g.: authentication headers)
+    pub fn with_default_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Add headers to the headers sent with every request, replacing the values of the headers
+    /// already set
+    pub fn with_extra_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Stop sending the header `name` with every request (e.g.: an API key replaced by
+    /// credentials sent in another header)
+    pub fn without_default_header(mut self, name: &str) -> Self {
+        self.headers.remove(name);
+        self
+    }
+
+    /// Set the policy used to retry failed requests
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set the rate limiter throttling the requests
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Send each request with a key of `api_keys`, placed in the request as described by
+    /// `placement` (replacing the key set in the default headers, if any)
+    pub fn with_api_keys(mut self, api_keys: ApiKeyPool, placement: ApiKeyPlacement) -> Self {
+        self.auth.api_keys = Some((api_keys, placement));
+        self
+    }
+
+    /// Send each request with the secret of `credentials`, placed in the request as described by
+    /// `placement` (replacing the key set in the default headers, if any)
+    pub fn with_credentials(
+        mut self,
+        credentials: Credentials,
+        placement: ApiKeyPlacement,
+    ) -> Self {
+        self.auth.credentials = Some((credentials, placement));
+        self
+    }
+
+    /// Send the requests with `transport` instead of the underlying [reqwest::Client] (which is
+    /// still used to build the requests)
+    pub fn with_transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.transport = Some(Transport(Arc::new(transport)));
+        self
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// The underlying [reqwest::Client]
+    pub fn inner(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    pub fn get(&self, url: impl IntoUrl) -> RequestBuilder {
+        RequestBuilder {
+            builder: self.client.get(url).headers(self.headers.clone()),
+            retry_policy: self.retry_policy.clone(),
+            idempotent: true,
+            rate_limiter: self.rate_limiter.clone(),
+            auth: self.auth.clone(),
+            transport: self.transport.clone(),
+        }
+    }
+
+    pub fn post(&self, url: impl IntoUrl) -> RequestBuilder {
+        RequestBuilder {
+            builder: self.client.post(url).headers(self.headers.clone()),
+            retry_policy: self.retry_policy.clone(),
+            idempotent: true,
+            rate_limiter: self.rate_limiter.clone(),
+            auth: self.auth.clone(),
+            transport: self.transport.clone(),
+        }
+    }
+
+    pub fn delete(&self, url: impl IntoUrl) -> RequestBuilder {
+        RequestBuilder {
+            builder: self.client.delete(url).headers(self.headers.clone()),
+            retry_policy: self.retry_policy.clone(),
+            idempotent: true,
+            rate_limiter: self.rate_limiter.clone(),
+            auth: self.auth.clone(),
+            transport: self.transport.clone(),
+        }
+    }
+}
+
+/// Request builder returned by the [HttpClient]
+pub struct RequestBuilder {
+    builder: reqwest::RequestBuilder,
+    retry_policy: RetryPolicy,
+    /// Whether the request can be safely replayed (i.e.: retried)
+    idempotent: bool,
+    rate_limiter: Option<RateLimiter>,
+    auth: Auth,
+    transport: Option<Transport>,
+}
+
+impl RequestBuilder {
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.builder = self.builder.headers(headers);
+        self
+    }
+
+    pub fn query<T: Serialize + ?Sized>(mut self, query: &T) -> Self {
+        self.builder = self.builder.query(query);
+        self
+    }
+
+    pub fn json<T: Serialize + ?Sized>(mut self, json: &T) -> Self {
+        self.builder = self.builder.json(json);
+        self
+    }
+
+    pub fn multipart(mut self, form: reqwest::multipart::Form) -> Self {
+        self.builder = self.builder.multipart(form);
+        self
+    }
+
+    /// Set whether the request can be safely replayed (true by default). Requests that are not
+    /// idempotent (e.g.: creating a batch) are never retried, since a request failing with a
+    /// transient error may still have been processed by the provider.
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = idempotent;
+        self
+    }
+
+    /// Send the request, retrying it according to the [RetryPolicy] of the client.
+    /// Note: requests that are not [idempotent](RequestBuilder::idempotent) or have a streaming
+    /// body (e.g.: multipart forms) are never retried.
+    pub async fn send(self) -> Result<reqwest::Response, HttpError> {
+        let mut retries = 0;
+        let tokens = self.estimated_tokens();
+
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                match rate_limiter.acquire(tokens).await {
+                    Ok(wait) => record_timing(|timing| timing.queue_time += wait),
+                    Err(error) => return Err(error.into()),
+                }
+            }
+
+            let sent = Instant::now();
+            let Some(builder) = self
+                .builder
+                .try_clone()
+                .filter(|_| self.idempotent && self.retry_policy.max_retries > 0)
+            else {
+                let result = Self::execute(self.builder, &self.auth, self.transport.as_ref()).await;
+                record_timing(|timing| timing.time_to_first_byte = Some(sent.elapsed()));
+                return result;
+            };
+
+            let result = Self::execute(builder, &self.auth, self.transport.as_ref()).await;
+            record_timing(|timing| timing.time_to_first_byte = Some(sent.elapsed()));
+
+            let retryable = match &result {
+                Ok(response) => self.retry_policy.should_retry(response.status()),
+                Err(error) => error.is_retryable(),
+            };
+
+            if !retryable || retries >= self.retry_policy.max_retries {
+                return result;
+            }
+
+            let delay = result
+                .as_ref()
+                .ok()
+                .and_then(|response| retry_after(response.headers()))
+                .unwrap_or_else(|| self.retry_policy.backoff(retries));
+
+            retries += 1;
+            record_timing(|timing| timing.retries += 1);
+            match &result {
+                Ok(response) => tracing::warn!(target: "rig",
+                    "Request failed with status {}, retrying in {delay:?} ({retries}/{})",
+                    response.status(),
+                    self.retry_policy.max_retries
+                ),
+                Err(error) => tracing::warn!(target: "rig",
+                    "Request failed: {error}, retrying in {delay:?} ({retries}/{})",
+                    self.retry_policy.max_retries
+                ),
+            }
+            tracing::Span::current().record("retries", retries);
+
+            futures_timer::Delay::new(delay).await;
+        }
+    }
+}
+
+impl RequestBuilder {
+    /// Send the request (with the [HttpTransport], if any), with a key of the [ApiKeyPool] or the
+    /// secret of the [Credentials] (if any). If the request is rate limited, its key is put on
+    /// cooldown and the request is sent again right away with another key that is not cooling
+    /// down (if any). The credentials are invalidated if they are rejected.
+    async fn execute(
+        builder: reqwest::RequestBuilder,
+        auth: &Auth,
+        transport: Option<&Transport>,
+    ) -> Result<reqwest::Response, HttpError> {
+        if auth.api_keys.is_none() && auth.credentials.is_none() && transport.is_none() {
+            return Ok(builder.send().await?);
+        }
+
+        let (client, request) = builder.build_split();
+        let mut request = request?;
+        let mut rotations = auth.api_keys.as_ref().map_or(0, |(pool, _)| pool.len() - 1);
+
+        loop {
+            // Copy of the request, sent with another key if the request is rate limited
+            let next = if rotations > 0 {
+                request.try_clone()
+            } else {
+                None
+            };
+
+            let (response, key) = Self::execute_once(&client, request, auth, transport).await?;
+
+            match (response.status(), &auth.api_keys, key, &auth.credentials) {
+                (StatusCode::TOO_MANY_REQUESTS, Some((pool, _)), Some(key), _) => {
+                    pool.mark_rate_limited(&key, retry_after(response.headers()));
+
+                    if let Some(next) = next.filter(|_| pool.has_available_key()) {
+                        tracing::debug!(target: "rig",
+                            "Request rate limited, sending it again with another API key"
+                        );
+                        rotations -= 1;
+                        request = next;
+                        continue;
+                    }
+                }
+                (StatusCode::UNAUTHORIZED, _, _, Some((credentials, _))) => {
+                    credentials.invalidate().await;
+                }
+                _ => {}
+            }
+
+            return Ok(response);
+        }
+    }
+
+    /// Send the request once, returning the response along with the key of the [ApiKeyPool] it
+    /// was sent with (if any)
+    async fn execute_once(
+        client: &reqwest::Client,
+        mut request: reqwest::Request,
+        auth: &Auth,
+        transport: Option<&Transport>,
+    ) -> Result<(reqwest::Response, Option<String>), HttpError> {
+        let key = match &auth.api_keys {
+            Some((pool, placement)) => {
+                let key = pool.next_key();
+                placement.apply(&mut request, &key)?;
+                Some(key)
+            }
+            None => None,
+        };
+
+        if let Some((credentials, placement)) = &auth.credentials {
+            placement.apply(&mut request, &credentials.secret().await?)?;
+        }
+
+        let response = match transport {
+            Some(transport) => transport.send(request).await?,
+            None => client.execute(request).await?,
+        };
+
+        Ok((response, key))
+    }
+
+    /// Estimated number of tokens of the request, used by the rate limiter
+    fn estimated_tokens(&self) -> u64 {
+        self.builder
+            .try_clone()
+            .and_then(|builder| builder.build().ok())
+            .and_then(|request| request.body()?.as_bytes().map(estimate_tokens))
+            .unwrap_or_default()
+    }
+}
+
+/// Request sent as is (without retries, rate limiting nor credentials)
+impl From<reqwest::RequestBuilder> for RequestBuilder {
+    fn from(builder: reqwest::RequestBuilder) -> Self {
+        Self {
+            builder,
+            retry_policy: RetryPolicy::none(),
+            idempotent: true,
+            rate_limiter: None,
+            auth: Auth::default(),
+            transport: None,
+        }
+    }
+}
+
+impl From<RequestBuilder> for reqwest::RequestBuilder {
+    fn from(request_builder: RequestBuilder) -> Self {
+        request_builder.builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(10), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::default();
+
+        assert!(policy.should_retry(StatusCode::TOO_MANY_REQUESTS));
+        assert!(policy.should_retry(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!policy.should_retry(StatusCode::BAD_REQUEST));
+        assert!(!policy.should_retry(StatusCode::OK));
+    }
+
+    #[test]
+    fn test_api_error() {
+        let error = ApiError::from_body(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(2)),
+            r#"{"error": {"message": "Rate limit reached", "type": "requests", "code": "rate_limit_exceeded"}}"#
+                .to_string(),
+        );
+        assert_eq!(error.message, "Rate limit reached");
+        assert_eq!(error.code.as_deref(), Some("rate_limit_exceeded"));
+        assert_eq!(error.error_type.as_deref(), Some("requests"));
+        assert!(error.is_retryable());
+        assert!(error.is_rate_limited());
+
+        let error = ApiError::from_body(
+            StatusCode::BAD_REQUEST,
+            None,
+            r#"{"error": {"code": 400, "message": "Invalid model", "status": "INVALID_ARGUMENT"}}"#
+                .to_string(),
+        );
+        assert_eq!(error.code.as_deref(), Some("400"));
+        assert_eq!(error.error_type.as_deref(), Some("INVALID_ARGUMENT"));
+        assert!(!error.is_retryable());
+
+        let error = ApiError::from_body(
+            StatusCode::NOT_FOUND,
+            None,
+            r#"{"error": "model not found"}"#.to_string(),
+        );
+        assert_eq!(error.message, "model not found");
+
+        let error = ApiError::from_body(
+            StatusCode::BAD_GATEWAY,
+            None,
+            "<html>Bad Gateway</html>".to_string(),
+        );
+        assert_eq!(error.message, "<html>Bad Gateway</html>");
+        assert!(error.is_retryable());
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert("retry-after", HeaderValue::from_static("2"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(2)));
+
+        headers.insert("retry-after-ms", HeaderValue::from_static("250"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(250)));
+
+        headers.insert("retry-after-ms", HeaderValue::from_static("invalid"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(2)));
+
+        headers.remove("retry-after-ms");
+        headers.insert(
+            "retry-after",
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        let date = httpdate::fmt_http_date(std::time::SystemTime::now() + Duration::from_secs(60));
+        headers.insert("retry-after", HeaderValue::from_str(&date).unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(50) && delay <= Duration::from_secs(60));
+    }
+
+    /// Transport echoing the authorization header of the requests, failing when there is none
+    struct EchoTransport;
+
+    impl HttpTransport for EchoTransport {
+        fn execute(
+            &self,
+            request: http::Request<Bytes>,
+        ) -> BoxFuture<'_, Result<http::Response<Bytes>, TransportError>> {
+            Box::pin(async move {
+                let authorization = request
+                    .headers()
+                    .get("authorization")
+                    .ok_or_else(|| TransportError::Other("Missing authorization".into()))?;
+
+                Ok(http::Response::new(Bytes::copy_from_slice(
+                    authorization.as_bytes(),
+                )))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transport() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer key"));
+
+        let client = HttpClient::new(reqwest::Client::new())
+            .with_default_headers(headers)
+            .with_transport(EchoTransport);
+        let response = client.post("https://example.com").send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "Bearer key");
+
+        let client = HttpClient::new(reqwest::Client::new())
+            .with_retry_policy(RetryPolicy::none())
+            .with_transport(EchoTransport);
+        let error = client.post("https://example.com").send().await.unwrap_err();
+        assert!(matches!(error, HttpError::Transport(_)));
+    }
+
+    /// Transport failing the first request with a `503 Service Unavailable`
+    #[derive(Default)]
+    struct FlakyTransport(std::sync::atomic::AtomicU32);
+
+    impl HttpTransport for FlakyTransport {
+        fn execute(
+            &self,
+            _request: http::Request<Bytes>,
+        ) -> BoxFuture<'_, Result<http::Response<Bytes>, TransportError>> {
+            Box::pin(async move {
+                let status = match self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+                    0 => StatusCode::SERVICE_UNAVAILABLE,
+                    _ => StatusCode::OK,
+                };
+                let mut response = http::Response::new(Bytes::new());
+                *response.status_mut() = status;
+                Ok(response)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_with_timing() {
+        let client = HttpClient::new(reqwest::Client::new())
+            .with_retry_policy(RetryPolicy {
+                max_retries: 1,
+                initial_backoff: Duration::from_millis(1),
+                ..Default::default()
+            })
+            .with_transport(FlakyTransport::default());
+
+        let (response, timing) = with_timing(client.post("https://example.com").send()).await;
+        assert_eq!(response.unwrap().status(), StatusCode::OK);
+        assert_eq!(timing.retries, 1);
+        assert!(timing.time_to_first_byte.is_some());
+        assert!(timing.total >= Duration::from_millis(1));
+
+        // Requests that are not idempotent are not retried
+        let client = client.with_transport(FlakyTransport::default());
+        let response = client
+            .post("https://example.com")
+            .idempotent(false)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // Requests sent outside of `with_timing` are not recorded
+        let (_, timing) = with_timing(async {}).await;
+        client.post("https://example.com").send().await.unwrap();
+        assert_eq!(timing.retries, 0);
+        assert_eq!(timing.time_to_first_byte, None);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_exceeded() {
+        let client = HttpClient::new(reqwest::Client::new())
+            .with_rate_limiter(
+                RateLimiter::new()
+                    .requests_per_minute(1)
+                    .behavior(crate::rate_limit::RateLimitBehavior::Error),
+            )
+            .with_transport(FlakyTransport(1.into()));
+
+        assert!(client.post("https://example.com").send().await.is_ok());
+
+        let error = client.post("https://example.com").send().await.unwrap_err();
+        assert!(matches!(error, HttpError::RateLimitExceeded(_)));
+        assert!(error.is_retryable());
+        assert_eq!(error.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+        assert!(error.retry_after().is_some());
+    }
+
+    /// Transport rate limiting the requests sent with the key `limited`
+    struct LimitedKeyTransport;
+
+    impl HttpTransport for LimitedKeyTransport {
+        fn execute(
+            &self,
+            request: http::Request<Bytes>,
+        ) -> BoxFuture<'_, Result<http::Response<Bytes>, TransportError>> {
+            Box::pin(async move {
+                let authorization = request.headers()["authorization"].to_str().unwrap();
+                let mut response = http::Response::new(Bytes::from(authorization.to_string()));
+                if authorization == "Bearer limited" {
+                    *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+                    response
+                        .headers_mut()
+                        .insert("retry-after", HeaderValue::from_static("3600"));
+                }
+                Ok(response)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_key_rotation() {
+        let pool = ApiKeyPool::new(["limited", "free"]);
+        let client = HttpClient::new(reqwest::Client::new())
+            .with_api_keys(pool.clone(), ApiKeyPlacement::bearer())
+            .with_transport(LimitedKeyTransport);
+
+        // The request is sent again with the free key without waiting for the retry-after delay
+        let response = client.post("https://example.com").send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "Bearer free");
+
+        // The limited key is cooling down
+        let response = client.post("https://example.com").send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "Bearer free");
+
+        // The rate limited response is returned when no key is available
+        let client = HttpClient::new(reqwest::Client::new())
+            .with_api_keys(ApiKeyPool::new(["limited"]), ApiKeyPlacement::bearer())
+            .with_transport(LimitedKeyTransport);
+        let response = client.post("https://example.com").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_api_key() {
+        let client = HttpClient::new(reqwest::Client::new())
+            .with_api_keys(ApiKeyPool::new(["invalid\nkey"]), ApiKeyPlacement::bearer())
+            .with_transport(EchoTransport);
+        let error = client.post("https://example.com").send().await.unwrap_err();
+        assert!(matches!(error, HttpError::InvalidApiKey(_)));
+    }
+
+    #[tokio::test]
+    async fn test_credentials() {
+        let mut headers = HeaderMap::new();
+        headers.insert("api-key", HeaderValue::from_static("key"));
+
+        let client = HttpClient::new(reqwest::Client::new())
+            .with_default_headers(headers)
+            .without_default_header("api-key")
+            .with_credentials(
+                Credentials::new(|| async {
+                    Ok::<_, CredentialError>(crate::credentials::Credential::new("token"))
+                }),
+                ApiKeyPlacement::bearer(),
+            )
+            .with_transport(EchoTransport);
+        let response = client.post("https://example.com").send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "Bearer token");
+
+        // The error of the credential provider is returned as is
+        let client = HttpClient::new(reqwest::Client::new())
+            .with_credentials(
+                Credentials::new(|| async {
+                    Err::<crate::credentials::Credential, _>(CredentialError::from(
+                        "No token".to_string(),
+                    ))
+                }),
+                ApiKeyPlacement::bearer(),
+            )
+            .with_transport(EchoTransport);
+        let error = client.post("https://example.com").send().await.unwrap_err();
+        assert!(matches!(error, HttpError::Credentials(_)));
+    }
+
+    #[test]
+    fn test_default_headers_kept_with_custom_client() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer key"));
+
+        let client = HttpClient::new(reqwest::Client::new())
+            .with_default_headers(headers)
+            .with_client(reqwest::Client::builder().build().unwrap());
+
+        let request = reqwest::RequestBuilder::from(client.post("https://example.com"))
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()["authorization"], "Bearer key");
+    }
+
+    #[test]
+    fn test_accept_invalid_certs_with_custom_client() {
+        let options = ReqwestOptions {
+            client: Some(reqwest::Client::new()),
+            accept_invalid_certs: true,
+        };
+
+        // The custom client wins instead of panicking
+        options.build();
+    }
+
+    #[test]
+    fn test_extra_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer key"));
+        headers.insert("x-gateway", HeaderValue::from_static("old"));
+
+        let mut extra_headers = HeaderMap::new();
+        extra_headers.insert("x-gateway", HeaderValue::from_static("new"));
+
+        let client = HttpClient::new(reqwest::Client::new())
+            .with_default_headers(headers)
+            .with_extra_headers(extra_headers);
+
+        let request = reqwest::RequestBuilder::from(client.get("https://example.com"))
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()["authorization"], "Bearer key");
+        assert_eq!(request.headers()["x-gateway"], "new");
+    }
+}
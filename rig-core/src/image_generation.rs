@@ -1,11 +1,13 @@
 use serde_json::Value;
 use thiserror::Error;
 
+use crate::wasm_compat::WasmCompatSend;
+
 #[derive(Debug, Error)]
 pub enum ImageGenerationError {
     /// Http error (e.g.: connection error, timeout, etc.)
     #[error("HttpError: {0}")]
-    HttpError(#[from] reqwest::Error),
+    HttpError(#[from] crate::http_client::HttpError),
 
     /// Json error (e.g.: serialization, deserialization)
     #[error("JsonError: {0}")]
@@ -23,6 +25,12 @@ pub enum ImageGenerationError {
     #[error("ProviderError: {0}")]
     ProviderError(String),
 }
+
+impl From<reqwest::Error> for ImageGenerationError {
+    fn from(error: reqwest::Error) -> Self {
+        ImageGenerationError::HttpError(error.into())
+    }
+}
 pub trait ImageGeneration<M: ImageGenerationModel> {
     /// Generates a transcription request builder for the given `file`.
     /// This function is meant to be called by the user to further customize the
@@ -38,7 +46,7 @@ pub trait ImageGeneration<M: ImageGenerationModel> {
         size: &(u32, u32),
     ) -> impl std::future::Future<
         Output = Result<ImageGenerationRequestBuilder<M>, ImageGenerationError>,
-    > + Send;
+    > + WasmCompatSend;
 }
 
 pub struct ImageGenerationResponse<T> {
@@ -54,7 +62,7 @@ pub trait ImageGenerationModel: Clone + Send + Sync {
         request: ImageGenerationRequest,
     ) -> impl std::future::Future<
         Output = Result<ImageGenerationResponse<Self::Response>, ImageGenerationError>,
-    > + Send;
+    > + WasmCompatSend;
 
     fn image_generation_request(&self) -> ImageGenerationRequestBuilder<Self> {
         ImageGenerationRequestBuilder::new(self.clone())
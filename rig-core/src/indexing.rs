@@ -0,0 +1,379 @@
+//! This module provides the [IncrementalIndexer], which keeps a vector store in sync with its
+//! sources (e.g.: the files of a knowledge base) without re-embedding the whole corpus on every
+//! run.
+//!
+//! The indexer tracks a content hash of each source and of each document derived from it (e.g.:
+//! the chunks of a file) in an [IndexState]. When indexing the corpus again, only the documents
+//! which are new or whose content changed are embedded and upserted, the documents which no longer
+//! exist (including all the documents of removed sources) are deleted, and unchanged sources are
+//! skipped entirely.
+//!
+//! The [IndexState] can be serialized to persist it between runs (e.g.: nightly re-index jobs).
+//! It is only updated once the vector store has been successfully updated, so a failed run is
+//! simply retried by the next one.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     chunking::{Chunker, RecursiveCharacterSplitter},
+//!     indexing::{IncrementalIndexer, IndexState},
+//!     loaders::{DocumentLoader, MarkdownLoader},
+//! };
+//!
+//! let state: IndexState = match std::fs::read_to_string("index_state.json") {
+//!     Ok(state) => serde_json::from_str(&state)?,
+//!     Err(_) => IndexState::default(),
+//! };
+//! let mut indexer = IncrementalIndexer::new(model, store).with_state(state);
+//!
+//! let splitter = RecursiveCharacterSplitter::new(1000, 200);
+//! let sources = MarkdownLoader::new()
+//!     .load_glob("docs/**/*.md")?
+//!     .into_iter()
+//!     .map(|document| {
+//!         let chunks = splitter.chunk_document(&document);
+//!         (document.id, chunks.into_iter().map(|chunk| (chunk.id.clone(), chunk)).collect())
+//!     });
+//!
+//! let report = indexer.index(sources).await?;
+//! println!("Embedded {} chunks, deleted {}", report.embedded_documents, report.deleted_documents);
+//!
+//! std::fs::write("index_state.json", serde_json::to_string(indexer.state())?)?;
+//! ```
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cache::fnv1a,
+    embeddings::{
+        embed::TextEmbedder, Embed, EmbedError, EmbeddingError, EmbeddingModel, EmbeddingsBuilder,
+    },
+    vector_store::{VectorStoreError, VectorStoreWriter},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexingError {
+    #[error("Embed error: {0}")]
+    EmbedError(#[from] EmbedError),
+
+    #[error("Embedding error: {0}")]
+    EmbeddingError(#[from] EmbeddingError),
+
+    #[error("Vector store error: {0}")]
+    VectorStoreError(#[from] VectorStoreError),
+
+    /// Json error (e.g.: serialization of a document to hash it)
+    #[error("Json error: {0}")]
+    JsonError(#[from] serde_json::Error),
+}
+
+/// Indexed state of a source
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SourceState {
+    /// Hash of the content of the source (i.e.: of all its documents)
+    pub hash: String,
+    /// Hash of the content of each document of the source, by document id
+    pub documents: BTreeMap<String, String>,
+}
+
+/// Sources indexed by an [IncrementalIndexer], with the hashes of their content
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IndexState {
+    pub sources: BTreeMap<String, SourceState>,
+}
+
+/// Changes made to the vector store by [IncrementalIndexer::index]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexingReport {
+    /// Ids of the sources indexed for the first time
+    pub new_sources: Vec<String>,
+    /// Ids of the sources whose content changed
+    pub changed_sources: Vec<String>,
+    /// Ids of the sources which no longer exist, whose documents were deleted
+    pub removed_sources: Vec<String>,
+    /// Number of sources whose content did not change
+    pub unchanged_sources: usize,
+    /// Number of documents embedded and upserted
+    pub embedded_documents: usize,
+    /// Number of documents deleted
+    pub deleted_documents: usize,
+}
+
+/// Indexes sources in a vector store, only embedding and upserting the documents which are new or
+/// changed since the previous run, and deleting the documents which no longer exist.
+/// See the [module documentation](crate::indexing) for more information.
+pub struct IncrementalIndexer<M: EmbeddingModel, S> {
+    model: M,
+    store: S,
+    state: IndexState,
+}
+
+impl<M: EmbeddingModel, S> IncrementalIndexer<M, S> {
+    /// Index documents in `store`, embedding them with `model`. The store is assumed to only
+    /// contain documents indexed by this indexer (with the state set by
+    /// [IncrementalIndexer::with_state]).
+    pub fn new(model: M, store: S) -> Self {
+        Self {
+            model,
+            store,
+            state: IndexState::default(),
+        }
+    }
+
+    /// Resume from the `state` of a previous run
+    pub fn with_state(mut self, state: IndexState) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// The sources currently indexed, to persist between runs
+    pub fn state(&self) -> &IndexState {
+        &self.state
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn store_mut(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    /// Consume the indexer, returning its store and state
+    pub fn into_parts(self) -> (S, IndexState) {
+        (self.store, self.state)
+    }
+
+    /// Index the whole corpus: `sources` are tuples of the form (source id, documents), where the
+    /// documents are tuples of the form (document id, document). Sources which were indexed
+    /// before but are missing from `sources` are removed from the store.
+    pub async fn index<T>(
+        &mut self,
+        sources: impl IntoIterator<Item = (String, Vec<(String, T)>)>,
+    ) -> Result<IndexingReport, IndexingError>
+    where
+        T: Embed + Serialize + Send,
+        S: VectorStoreWriter<T>,
+    {
+        let mut report = IndexingReport::default();
+        let mut state = IndexState::default();
+        let mut to_embed = vec![];
+        let mut to_delete = vec![];
+
+        for (source_id, documents) in sources {
+            let source = hash_source(&documents)?;
+            let previous = self.state.sources.get(&source_id);
+
+            match previous {
+                Some(previous) if previous.hash == source.hash => {
+                    report.unchanged_sources += 1;
+                }
+                Some(previous) => {
+                    report.changed_sources.push(source_id.clone());
+                    to_delete.extend(
+                        previous
+                            .documents
+                            .keys()
+                            .filter(|id| !source.documents.contains_key(*id))
+                            .cloned(),
+                    );
+                    to_embed.extend(
+                        documents.into_iter().filter(|(id, _)| {
+                            previous.documents.get(id) != source.documents.get(id)
+                        }),
+                    );
+                }
+                None => {
+                    report.new_sources.push(source_id.clone());
+                    to_embed.extend(documents);
+                }
+            }
+
+            state.sources.insert(source_id, source);
+        }
+
+        for (source_id, previous) in &self.state.sources {
+            if !state.sources.contains_key(source_id) {
+                report.removed_sources.push(source_id.clone());
+                to_delete.extend(previous.documents.keys().cloned());
+            }
+        }
+
+        // A document moved to another source must not be deleted
+        let indexed = state
+            .sources
+            .values()
+            .flat_map(|source| source.documents.keys())
+            .collect::<HashSet<_>>();
+        to_delete.retain(|id| !indexed.contains(id));
+
+        report.embedded_documents = to_embed.len();
+        report.deleted_documents = to_delete.len();
+
+        if !to_embed.is_empty() {
+            let (ids, documents): (Vec<_>, Vec<_>) = to_embed.into_iter().unzip();
+            let embeddings = EmbeddingsBuilder::new(self.model.clone())
+                .documents(documents)?
+                .build()
+                .await?;
+
+            self.store
+                .upsert_documents(
+                    ids.into_iter()
+                        .zip(embeddings)
+                        .map(|(id, (document, embeddings))| (id, document, embeddings))
+                        .collect(),
+                )
+                .await?;
+        }
+
+        if !to_delete.is_empty() {
+            self.store.delete_documents(to_delete).await?;
+        }
+
+        self.state = state;
+
+        Ok(report)
+    }
+}
+
+/// Hash the content of the documents of a source
+fn hash_source<T: Embed + Serialize>(
+    documents: &[(String, T)],
+) -> Result<SourceState, IndexingError> {
+    let mut hashes = HashMap::new();
+
+    for (id, document) in documents {
+        let mut embedder = TextEmbedder::default();
+        document.embed(&mut embedder)?;
+
+        // Both the embedded texts and the stored document (e.g.: its metadata) are hashed
+        let content = serde_json::to_string(&(&embedder.texts, document))?;
+        hashes.insert(id.clone(), format!("{:016x}", fnv1a(content.as_bytes())));
+    }
+
+    let documents = hashes.into_iter().collect::<BTreeMap<_, _>>();
+    let content = serde_json::to_string(&documents)?;
+
+    Ok(SourceState {
+        hash: format!("{:016x}", fnv1a(content.as_bytes())),
+        documents,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        providers::mock::MockEmbeddingModel, vector_store::in_memory_store::InMemoryVectorStore,
+    };
+
+    fn sources(sources: &[(&str, &[(&str, &str)])]) -> Vec<(String, Vec<(String, String)>)> {
+        sources
+            .iter()
+            .map(|(source, documents)| {
+                (
+                    source.to_string(),
+                    documents
+                        .iter()
+                        .map(|(id, text)| (id.to_string(), text.to_string()))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn stored(store: &InMemoryVectorStore<String>) -> Vec<(String, String)> {
+        let mut documents = store
+            .iter()
+            .map(|(id, (document, _))| (id.clone(), document.clone()))
+            .collect::<Vec<_>>();
+        documents.sort();
+        documents
+    }
+
+    #[tokio::test]
+    async fn test_incremental_indexing() {
+        let model = MockEmbeddingModel::new(3);
+        let mut indexer = IncrementalIndexer::new(model.clone(), InMemoryVectorStore::default());
+
+        let report = indexer
+            .index(sources(&[
+                (
+                    "a.md",
+                    &[("a#0", "Flurbos are green."), ("a#1", "They like jazz.")],
+                ),
+                ("b.md", &[("b#0", "Glarbs are tools.")]),
+                ("c.md", &[("c#0", "Linlingdongs are instruments.")]),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(report.new_sources, vec!["a.md", "b.md", "c.md"]);
+        assert_eq!(report.embedded_documents, 4);
+
+        assert_eq!(model.texts().len(), 4);
+
+        // Resume from the persisted state
+        let state = serde_json::to_string(indexer.state()).unwrap();
+        let (store, _) = indexer.into_parts();
+        let mut indexer = IncrementalIndexer::new(model.clone(), store)
+            .with_state(serde_json::from_str(&state).unwrap());
+
+        let report = indexer
+            .index(sources(&[
+                (
+                    "a.md",
+                    &[("a#0", "Flurbos are blue."), ("a#1", "They like jazz.")],
+                ),
+                ("b.md", &[("b#0", "Glarbs are tools.")]),
+                ("d.md", &[("d#0", "Plonks are hats.")]),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            IndexingReport {
+                new_sources: vec!["d.md".to_string()],
+                changed_sources: vec!["a.md".to_string()],
+                removed_sources: vec!["c.md".to_string()],
+                unchanged_sources: 1,
+                embedded_documents: 2,
+                deleted_documents: 1,
+            }
+        );
+        assert_eq!(
+            stored(indexer.store()),
+            vec![
+                ("a#0".to_string(), "Flurbos are blue.".to_string()),
+                ("a#1".to_string(), "They like jazz.".to_string()),
+                ("b#0".to_string(), "Glarbs are tools.".to_string()),
+                ("d#0".to_string(), "Plonks are hats.".to_string()),
+            ]
+        );
+
+        let report = indexer
+            .index(sources(&[
+                ("a.md", &[("a#0", "Flurbos are blue.")]),
+                ("b.md", &[("b#0", "Glarbs are tools.")]),
+                ("d.md", &[("d#0", "Plonks are hats.")]),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(report.changed_sources, vec!["a.md"]);
+        assert_eq!(report.embedded_documents, 0);
+        assert_eq!(report.deleted_documents, 1);
+        assert_eq!(indexer.store().len(), 3);
+    }
+
+    #[test]
+    fn test_hash_source() {
+        let hash = |text: &str| hash_source(&[("doc0".to_string(), text.to_string())]).unwrap();
+
+        assert_eq!(hash("Flurbos are green."), hash("Flurbos are green."));
+        assert_ne!(
+            hash("Flurbos are green.").hash,
+            hash("Flurbos are blue.").hash
+        );
+    }
+}
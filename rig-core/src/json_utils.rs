@@ -1,5 +1,5 @@
 use serde::de::{self, Deserializer, SeqAccess, Visitor};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use std::convert::Infallible;
 use std::fmt;
 use std::marker::PhantomData;
@@ -98,6 +98,19 @@ where
     deserializer.deserialize_any(StringOrVec(PhantomData))
 }
 
+/// Serialize an empty vector as `null` (the counterpart of [null_or_vec])
+pub fn empty_vec_as_null<T, S>(vec: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    if vec.is_empty() {
+        serializer.serialize_none()
+    } else {
+        vec.serialize(serializer)
+    }
+}
+
 pub fn null_or_vec<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
 where
     T: Deserialize<'de>,
@@ -0,0 +1,322 @@
+//! This module provides a pool of API keys, to distribute the requests sent to a provider across
+//! several keys (e.g.: the keys of several organizations, each with its own quota).
+//!
+//! An [ApiKeyPool] can be attached to any provider client authenticating with an API key with its
+//! `with_api_keys` method, in which case it replaces the API key the client was created with.
+//! Each request is sent with a key selected according to the [KeySelectionStrategy] of the pool.
+//! When a request is rate limited (`429 Too Many Requests`), its key is put on cooldown (for the
+//! delay requested by the provider, or the cooldown of the pool) and is not selected again until
+//! the cooldown is over, unless all the keys are cooling down. A rate limited request is sent again
+//! right away with another key if one is not cooling down (regardless of the retry policy of the
+//! client, since the provider did not process the request).
+//!
+//! The pool is cheap to clone and all clones share the same keys state, so a single pool can be
+//! attached to several clients.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     http_client::HttpClientSettings,
+//!     key_pool::{ApiKeyPool, KeySelectionStrategy},
+//!     providers::openai,
+//! };
+//!
+//! let keys = ApiKeyPool::new(["sk-org-1-key", "sk-org-2-key"])
+//!     .strategy(KeySelectionStrategy::LeastRecentlyLimited);
+//!
+//! let openai = openai::Client::from_env().with_api_keys(keys);
+//! ```
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::header::{HeaderName, HeaderValue, InvalidHeaderValue};
+
+use crate::wasm_compat::Instant;
+
+/// How an [ApiKeyPool] selects the key of a request among the keys that are not cooling down
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeySelectionStrategy {
+    /// Use the keys in turn
+    #[default]
+    RoundRobin,
+    /// Use the key that was rate limited the longest time ago (or never), and among those the
+    /// least recently used one
+    LeastRecentlyLimited,
+}
+
+/// How the API key is sent with the requests of a provider
+#[derive(Clone, Debug)]
+pub enum ApiKeyPlacement {
+    /// In a header, with the given prefix (e.g.: `Authorization: Bearer <key>`)
+    Header {
+        name: HeaderName,
+        prefix: &'static str,
+    },
+    /// In a query parameter (e.g.: `?key=<key>`)
+    Query(&'static str),
+}
+
+impl ApiKeyPlacement {
+    /// `Authorization: Bearer <key>` header
+    pub fn bearer() -> Self {
+        Self::Header {
+            name: reqwest::header::AUTHORIZATION,
+            prefix: "Bearer ",
+        }
+    }
+
+    /// Header containing the key only (e.g.: `x-api-key: <key>`)
+    pub fn header(name: &'static str) -> Self {
+        Self::Header {
+            name: HeaderName::from_static(name),
+            prefix: "",
+        }
+    }
+
+    /// Set the key of a request, replacing the existing one (if any). Fails if the key cannot be
+    /// sent in a header.
+    pub(crate) fn apply(
+        &self,
+        request: &mut reqwest::Request,
+        key: &str,
+    ) -> Result<(), InvalidHeaderValue> {
+        match self {
+            Self::Header { name, prefix } => {
+                let value = HeaderValue::from_str(&format!("{prefix}{key}"))?;
+                request.headers_mut().insert(name.clone(), value);
+            }
+            Self::Query(param) => {
+                let url = request.url_mut();
+                let query = url
+                    .query_pairs()
+                    .filter(|(name, _)| name != *param)
+                    .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                    .collect::<Vec<_>>();
+                url.query_pairs_mut()
+                    .clear()
+                    .extend_pairs(query)
+                    .append_pair(param, key);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct KeyState {
+    key: String,
+    cooldown_until: Option<Instant>,
+    last_limited: Option<Instant>,
+    last_used: Option<Instant>,
+}
+
+impl KeyState {
+    fn is_available(&self, now: Instant) -> bool {
+        // Not `Option::is_none_or`, which requires Rust 1.82
+        !matches!(self.cooldown_until, Some(until) if until > now)
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    keys: Vec<KeyState>,
+    /// Index of the next key to use with [KeySelectionStrategy::RoundRobin]
+    cursor: usize,
+}
+
+/// Pool of API keys shared by the requests of one or more provider clients
+#[derive(Clone, Debug)]
+pub struct ApiKeyPool {
+    strategy: KeySelectionStrategy,
+    cooldown: Duration,
+    state: Arc<Mutex<State>>,
+}
+
+impl ApiKeyPool {
+    /// Create a pool of the given keys, using them in turn and putting rate limited keys on
+    /// cooldown for one minute by default.
+    ///
+    /// Panics if `keys` is empty. A key which is not a valid header value is reported as an
+    /// [HttpError::InvalidApiKey](crate::http_client::HttpError::InvalidApiKey) error by the
+    /// requests using it.
+    pub fn new(keys: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let keys = keys
+            .into_iter()
+            .map(|key| KeyState {
+                key: key.into(),
+                cooldown_until: None,
+                last_limited: None,
+                last_used: None,
+            })
+            .collect::<Vec<_>>();
+        assert!(!keys.is_empty(), "API key pool should not be empty");
+
+        Self {
+            strategy: KeySelectionStrategy::default(),
+            cooldown: Duration::from_secs(60),
+            state: Arc::new(Mutex::new(State { keys, cursor: 0 })),
+        }
+    }
+
+    /// Set how the key of a request is selected (see [KeySelectionStrategy])
+    pub fn strategy(mut self, strategy: KeySelectionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Set how long a rate limited key is not used, unless the provider requests a specific delay
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Number of keys in the pool
+    pub fn len(&self) -> usize {
+        self.state
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .keys
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Select the key of the next request. If all the keys are cooling down, the key whose
+    /// cooldown ends first is selected.
+    pub fn next_key(&self) -> String {
+        self.next_key_at(Instant::now())
+    }
+
+    /// Whether a key is not cooling down
+    pub fn has_available_key(&self) -> bool {
+        self.has_available_key_at(Instant::now())
+    }
+
+    /// Put `key` on cooldown after it was rate limited, for `retry_after` if set or the cooldown
+    /// of the pool otherwise
+    pub fn mark_rate_limited(&self, key: &str, retry_after: Option<Duration>) {
+        self.mark_rate_limited_at(key, retry_after, Instant::now())
+    }
+
+    fn has_available_key_at(&self, now: Instant) -> bool {
+        let state = self.state.lock().expect("Mutex should not be poisoned");
+        state.keys.iter().any(|key| key.is_available(now))
+    }
+
+    fn next_key_at(&self, now: Instant) -> String {
+        let mut state = self.state.lock().expect("Mutex should not be poisoned");
+        let len = state.keys.len();
+        let available = |key: &KeyState| key.is_available(now);
+
+        let index = match self.strategy {
+            KeySelectionStrategy::RoundRobin => (0..len)
+                .map(|offset| (state.cursor + offset) % len)
+                .find(|index| available(&state.keys[*index])),
+            KeySelectionStrategy::LeastRecentlyLimited => state
+                .keys
+                .iter()
+                .enumerate()
+                .filter(|(_, key)| available(key))
+                .min_by_key(|(_, key)| (key.last_limited, key.last_used))
+                .map(|(index, _)| index),
+        }
+        .unwrap_or_else(|| {
+            state
+                .keys
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, key)| key.cooldown_until)
+                .map(|(index, _)| index)
+                .expect("API key pool should not be empty")
+        });
+
+        state.cursor = (index + 1) % len;
+        let key = &mut state.keys[index];
+        key.last_used = Some(now);
+        key.key.clone()
+    }
+
+    fn mark_rate_limited_at(&self, key: &str, retry_after: Option<Duration>, now: Instant) {
+        let mut state = self.state.lock().expect("Mutex should not be poisoned");
+
+        if let Some(state) = state.keys.iter_mut().find(|state| state.key == key) {
+            tracing::debug!(target: "rig", "API key rate limited, putting it on cooldown");
+            state.cooldown_until = Some(now + retry_after.unwrap_or(self.cooldown));
+            state.last_limited = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_robin() {
+        let pool = ApiKeyPool::new(["a", "b", "c"]);
+        let now = Instant::now();
+
+        assert_eq!(pool.next_key_at(now), "a");
+        assert_eq!(pool.next_key_at(now), "b");
+
+        pool.mark_rate_limited_at("c", None, now);
+        assert_eq!(pool.next_key_at(now), "a");
+        assert_eq!(pool.next_key_at(now), "b");
+
+        // The key is used again once its cooldown is over
+        let later = now + Duration::from_secs(60);
+        assert_eq!(pool.next_key_at(later), "c");
+    }
+
+    #[test]
+    fn test_least_recently_limited() {
+        let pool = ApiKeyPool::new(["a", "b"]).strategy(KeySelectionStrategy::LeastRecentlyLimited);
+        let now = Instant::now();
+
+        pool.mark_rate_limited_at("a", Some(Duration::from_secs(1)), now);
+        assert_eq!(pool.next_key_at(now), "b");
+
+        let later = now + Duration::from_secs(2);
+        pool.mark_rate_limited_at("b", Some(Duration::from_secs(1)), later);
+        assert_eq!(pool.next_key_at(later), "a");
+    }
+
+    #[test]
+    fn test_all_keys_cooling_down() {
+        let pool = ApiKeyPool::new(["a", "b"]);
+        let now = Instant::now();
+
+        pool.mark_rate_limited_at("a", Some(Duration::from_secs(10)), now);
+        pool.mark_rate_limited_at("b", Some(Duration::from_secs(5)), now);
+        assert!(!pool.has_available_key_at(now));
+        assert_eq!(pool.next_key_at(now), "b");
+    }
+
+    #[test]
+    fn test_query_placement() {
+        let mut request = reqwest::Request::new(
+            reqwest::Method::GET,
+            "https://example.com/models?alt=sse&key=old"
+                .parse()
+                .unwrap(),
+        );
+
+        ApiKeyPlacement::Query("key")
+            .apply(&mut request, "new")
+            .unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            "https://example.com/models?alt=sse&key=new"
+        );
+
+        assert!(ApiKeyPlacement::bearer()
+            .apply(&mut request, "invalid\nkey")
+            .is_err());
+    }
+}
@@ -0,0 +1,601 @@
+//! This module provides tower-style middleware layers for completion models.
+//!
+//! A [CompletionLayer] wraps a completion model into another completion model adding some
+//! behavior around its requests (e.g.: retrying failed requests, rate limiting, logging,
+//! validating requests and responses). Since the wrapped model is itself a completion model,
+//! layers compose: a [ModelBuilder] stacks several layers around a model, the first layer added
+//! being the outermost one (i.e.: the first to see the requests).
+//!
+//! The built-in layers are:
+//! - [RetryLayer]: retries the requests failing with a retryable error
+//!   (see [CompletionError::is_retryable]);
+//! - [RateLimitLayer]: enforces the budget of a [RateLimiter] on the requests;
+//! - [LogLayer]: logs the requests, responses and errors;
+//! - [GuardrailLayer]: validates the requests before they are sent and the responses before they
+//!   are returned;
+//! - [CacheLayer](crate::cache::CacheLayer): serves cached responses (see [crate::cache]).
+//!
+//! Custom layers can be defined by implementing [CompletionLayer], or from a closure with
+//! [layer_fn].
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//! use rig::{
+//!     completion::Prompt,
+//!     http_client::RetryPolicy,
+//!     layer::{GuardrailLayer, LogLayer, ModelBuilder, RateLimitLayer, RetryLayer},
+//!     providers::openai,
+//!     rate_limit::RateLimiter,
+//! };
+//!
+//! let openai = openai::Client::from_env();
+//!
+//! let model = ModelBuilder::new()
+//!     .layer(LogLayer::new())
+//!     .layer(RetryLayer::new(RetryPolicy::default()))
+//!     .layer(RateLimitLayer::new(RateLimiter::new().requests_per_minute(60)))
+//!     .layer(GuardrailLayer::new().check_response(|response| {
+//!         match response.choice.first() {
+//!             rig::message::AssistantContent::Text(text) if text.text.contains("password") => {
+//!                 Err("The response leaks a password".to_string())
+//!             }
+//!             _ => Ok(()),
+//!         }
+//!     }))
+//!     .build(openai.completion_model(openai::GPT_4O));
+//!
+//! let agent = rig::agent::AgentBuilder::new(model).build();
+//! let answer = agent.prompt("Hello!").await?;
+//! ```
+
+use std::sync::Arc;
+
+use crate::{
+    completion::{
+        CompletionError, CompletionModel, CompletionRequest, CompletionResponse, PreparedRequest,
+    },
+    http_client::RetryPolicy,
+    rate_limit::{estimate_tokens, RateLimiter},
+    wasm_compat::Instant,
+};
+
+/// Wraps a completion model of type `M` into another completion model
+pub trait CompletionLayer<M> {
+    /// The wrapped model
+    type Model: CompletionModel;
+
+    /// Wrap `model`
+    fn layer(&self, model: M) -> Self::Model;
+}
+
+/// Layer which does not wrap the model
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Identity;
+
+impl<M: CompletionModel> CompletionLayer<M> for Identity {
+    type Model = M;
+
+    fn layer(&self, model: M) -> M {
+        model
+    }
+}
+
+/// Two layers, `inner` wrapping the model first and `outer` wrapping the result
+#[derive(Clone, Debug)]
+pub struct Stack<Inner, Outer> {
+    inner: Inner,
+    outer: Outer,
+}
+
+impl<M, Inner, Outer> CompletionLayer<M> for Stack<Inner, Outer>
+where
+    Inner: CompletionLayer<M>,
+    Outer: CompletionLayer<Inner::Model>,
+{
+    type Model = Outer::Model;
+
+    fn layer(&self, model: M) -> Self::Model {
+        self.outer.layer(self.inner.layer(model))
+    }
+}
+
+/// Layer created from a closure (see [layer_fn])
+#[derive(Clone, Debug)]
+pub struct LayerFn<F>(F);
+
+/// Create a layer from a closure wrapping the model
+pub fn layer_fn<F>(f: F) -> LayerFn<F> {
+    LayerFn(f)
+}
+
+impl<M, F, W> CompletionLayer<M> for LayerFn<F>
+where
+    F: Fn(M) -> W,
+    W: CompletionModel,
+{
+    type Model = W;
+
+    fn layer(&self, model: M) -> W {
+        (self.0)(model)
+    }
+}
+
+/// Builder stacking layers around a completion model. The first layer added is the outermost.
+#[derive(Clone, Debug)]
+pub struct ModelBuilder<L> {
+    layer: L,
+}
+
+impl Default for ModelBuilder<Identity> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModelBuilder<Identity> {
+    pub fn new() -> Self {
+        Self { layer: Identity }
+    }
+}
+
+impl<L> ModelBuilder<L> {
+    /// Add a layer, wrapped by the layers added before it
+    pub fn layer<T>(self, layer: T) -> ModelBuilder<Stack<T, L>> {
+        ModelBuilder {
+            layer: Stack {
+                inner: layer,
+                outer: self.layer,
+            },
+        }
+    }
+
+    /// Wrap `model` with the layers
+    pub fn build<M>(&self, model: M) -> L::Model
+    where
+        L: CompletionLayer<M>,
+    {
+        self.layer.layer(model)
+    }
+}
+
+// ================================================================
+// Retry
+// ================================================================
+
+/// Layer retrying the requests failing with a retryable error
+/// (see [CompletionError::is_retryable]), waiting according to a [RetryPolicy] or the delay
+/// requested by the provider.
+///
+/// Note: the provider clients do not retry requests by default, but retries can be enabled on
+/// them with their `with_retry_policy` method (see [crate::http_client]). The retries of the
+/// client and of this layer multiply: a request may be sent up to
+/// `(1 + client retries) * (1 + layer retries)` times. Enable retries on either the client (to
+/// only retry transient HTTP errors) or this layer (e.g.: to retry the whole completion behind a
+/// [GuardrailLayer]), not both.
+#[derive(Clone, Debug)]
+pub struct RetryLayer {
+    retry_policy: RetryPolicy,
+}
+
+impl RetryLayer {
+    pub fn new(retry_policy: RetryPolicy) -> Self {
+        Self { retry_policy }
+    }
+}
+
+impl<M: CompletionModel> CompletionLayer<M> for RetryLayer {
+    type Model = Retry<M>;
+
+    fn layer(&self, model: M) -> Retry<M> {
+        Retry {
+            model,
+            retry_policy: self.retry_policy.clone(),
+        }
+    }
+}
+
+/// Completion model wrapped by a [RetryLayer]
+#[derive(Clone, Debug)]
+pub struct Retry<M> {
+    model: M,
+    retry_policy: RetryPolicy,
+}
+
+impl<M: CompletionModel> CompletionModel for Retry<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<M::Response>, CompletionError> {
+        let mut retries = 0;
+
+        loop {
+            match self.model.completion(request.clone()).await {
+                Err(error) if error.is_retryable() && retries < self.retry_policy.max_retries => {
+                    let backoff = error
+                        .retry_after()
+                        .unwrap_or_else(|| self.retry_policy.backoff(retries));
+                    tracing::warn!(target: "rig",
+                        "Completion failed: {error}, retrying in {backoff:?} ({}/{})",
+                        retries + 1,
+                        self.retry_policy.max_retries
+                    );
+
+                    futures_timer::Delay::new(backoff).await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn prepare_request(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<PreparedRequest, CompletionError> {
+        self.model.prepare_request(request)
+    }
+}
+
+// ================================================================
+// Rate limiting
+// ================================================================
+
+/// Layer enforcing the budget of a [RateLimiter] on the requests. The number of tokens of a
+/// request is estimated from the request prepared by the model (or the request itself if the
+/// model does not support dry runs).
+///
+/// When the budget is exceeded and the limiter is configured to fail requests, the requests fail
+/// with an [HttpError::RateLimitExceeded](crate::http_client::HttpError::RateLimitExceeded)
+/// error, like the requests of a provider client with a rate limiter.
+#[derive(Clone, Debug)]
+pub struct RateLimitLayer {
+    rate_limiter: RateLimiter,
+}
+
+impl RateLimitLayer {
+    pub fn new(rate_limiter: RateLimiter) -> Self {
+        Self { rate_limiter }
+    }
+}
+
+impl<M: CompletionModel> CompletionLayer<M> for RateLimitLayer {
+    type Model = RateLimited<M>;
+
+    fn layer(&self, model: M) -> RateLimited<M> {
+        RateLimited {
+            model,
+            rate_limiter: self.rate_limiter.clone(),
+        }
+    }
+}
+
+/// Completion model wrapped by a [RateLimitLayer]
+#[derive(Clone, Debug)]
+pub struct RateLimited<M> {
+    model: M,
+    rate_limiter: RateLimiter,
+}
+
+impl<M: CompletionModel> RateLimited<M> {
+    fn estimated_tokens(&self, request: &CompletionRequest) -> Result<u64, CompletionError> {
+        let body = match self.model.prepare_request(request.clone()) {
+            Ok(prepared_request) => serde_json::to_vec(&prepared_request.body)?,
+            Err(_) => serde_json::to_vec(request)?,
+        };
+
+        Ok(estimate_tokens(&body))
+    }
+}
+
+impl<M: CompletionModel> CompletionModel for RateLimited<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<M::Response>, CompletionError> {
+        let tokens = self.estimated_tokens(&request)?;
+
+        self.rate_limiter
+            .acquire(tokens)
+            .await
+            .map_err(|error| CompletionError::HttpError(error.into()))?;
+
+        self.model.completion(request).await
+    }
+
+    fn prepare_request(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<PreparedRequest, CompletionError> {
+        self.model.prepare_request(request)
+    }
+}
+
+// ================================================================
+// Logging
+// ================================================================
+
+/// Layer logging the requests (at the `debug` level), the responses (at the `info` level, with
+/// their latency and token usage) and the errors (at the `warn` level) of the model
+#[derive(Clone, Debug, Default)]
+pub struct LogLayer {
+    name: Option<String>,
+}
+
+impl LogLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name of the model in the logs (e.g.: to tell the models of a multi-agent application
+    /// apart)
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+}
+
+impl<M: CompletionModel> CompletionLayer<M> for LogLayer {
+    type Model = Logged<M>;
+
+    fn layer(&self, model: M) -> Logged<M> {
+        Logged {
+            model,
+            name: self.name.clone().unwrap_or_else(|| "model".to_string()),
+        }
+    }
+}
+
+/// Completion model wrapped by a [LogLayer]
+#[derive(Clone, Debug)]
+pub struct Logged<M> {
+    model: M,
+    name: String,
+}
+
+impl<M: CompletionModel> CompletionModel for Logged<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<M::Response>, CompletionError> {
+        tracing::debug!(target: "rig", "{} request: {:?}", self.name, request);
+
+        let start = Instant::now();
+        let result = self.model.completion(request).await;
+
+        match &result {
+            Ok(response) => tracing::info!(target: "rig",
+                "{} responded in {:?} ({})",
+                self.name,
+                start.elapsed(),
+                response.usage
+            ),
+            Err(error) => tracing::warn!(target: "rig",
+                "{} failed after {:?}: {error}",
+                self.name,
+                start.elapsed()
+            ),
+        }
+
+        result
+    }
+
+    fn prepare_request(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<PreparedRequest, CompletionError> {
+        self.model.prepare_request(request)
+    }
+}
+
+// ================================================================
+// Guardrails
+// ================================================================
+
+type RequestCheck = Arc<dyn Fn(&CompletionRequest) -> Result<(), String> + Send + Sync>;
+type ResponseCheck = Arc<dyn Fn(&CompletionResponse<()>) -> Result<(), String> + Send + Sync>;
+
+/// Layer validating the requests before they are sent to the model and the responses before
+/// they are returned.
+///
+/// Rejected requests fail with a [CompletionError::RequestError] (without calling the model) and
+/// rejected responses with a [CompletionError::ResponseError].
+#[derive(Clone, Default)]
+pub struct GuardrailLayer {
+    request_checks: Vec<RequestCheck>,
+    response_checks: Vec<ResponseCheck>,
+}
+
+impl GuardrailLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject the requests for which `check` returns an error
+    pub fn check_request(
+        mut self,
+        check: impl Fn(&CompletionRequest) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.request_checks.push(Arc::new(check));
+        self
+    }
+
+    /// Reject the responses for which `check` returns an error. The check is given the response
+    /// without its raw provider response.
+    pub fn check_response(
+        mut self,
+        check: impl Fn(&CompletionResponse<()>) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.response_checks.push(Arc::new(check));
+        self
+    }
+}
+
+impl<M: CompletionModel> CompletionLayer<M> for GuardrailLayer {
+    type Model = Guarded<M>;
+
+    fn layer(&self, model: M) -> Guarded<M> {
+        Guarded {
+            model,
+            guardrails: self.clone(),
+        }
+    }
+}
+
+/// Completion model wrapped by a [GuardrailLayer]
+#[derive(Clone)]
+pub struct Guarded<M> {
+    model: M,
+    guardrails: GuardrailLayer,
+}
+
+impl<M: CompletionModel> CompletionModel for Guarded<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<M::Response>, CompletionError> {
+        for check in &self.guardrails.request_checks {
+            check(&request).map_err(|error| CompletionError::RequestError(error.into()))?;
+        }
+
+        let response = self.model.completion(request).await?;
+
+        if !self.guardrails.response_checks.is_empty() {
+            let checked = CompletionResponse {
+                choice: response.choice.clone(),
+                usage: response.usage,
+                timing: response.timing,
+                raw_response: (),
+            };
+            for check in &self.guardrails.response_checks {
+                check(&checked).map_err(CompletionError::ResponseError)?;
+            }
+        }
+
+        Ok(response)
+    }
+
+    fn prepare_request(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<PreparedRequest, CompletionError> {
+        self.model.prepare_request(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Mutex, time::Duration};
+
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::{
+        completion::{Prompt, ResponseTiming, Usage},
+        http_client::{ApiError, HttpError},
+        message::AssistantContent,
+        rate_limit::RateLimitBehavior,
+        OneOrMany,
+    };
+
+    /// Model failing with a rate limit error until it was called `failures` times
+    #[derive(Clone)]
+    struct FlakyModel {
+        calls: Arc<Mutex<usize>>,
+        failures: usize,
+    }
+
+    impl CompletionModel for FlakyModel {
+        type Response = ();
+
+        async fn completion(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<CompletionResponse<()>, CompletionError> {
+            let mut calls = self.calls.lock().unwrap();
+            *calls += 1;
+
+            if *calls <= self.failures {
+                return Err(ApiError {
+                    status: StatusCode::TOO_MANY_REQUESTS,
+                    code: None,
+                    error_type: None,
+                    message: "Rate limited".to_string(),
+                    retry_after: Some(Duration::from_millis(1)),
+                }
+                .into());
+            }
+
+            Ok(CompletionResponse {
+                choice: OneOrMany::one(AssistantContent::text("Hello!")),
+                usage: Usage::default(),
+                timing: ResponseTiming::default(),
+                raw_response: (),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_model_builder() {
+        let calls = Arc::new(Mutex::new(0));
+        let model = ModelBuilder::new()
+            .layer(LogLayer::new().name("flaky"))
+            .layer(GuardrailLayer::new().check_request(
+                |request| match request.preamble.as_deref() {
+                    Some(preamble) if preamble.contains("secret") => {
+                        Err("The preamble leaks a secret".to_string())
+                    }
+                    _ => Ok(()),
+                },
+            ))
+            .layer(RetryLayer::new(RetryPolicy {
+                max_retries: 2,
+                ..Default::default()
+            }))
+            .build(FlakyModel {
+                calls: calls.clone(),
+                failures: 2,
+            });
+
+        let agent = crate::agent::AgentBuilder::new(model.clone()).build();
+        assert_eq!(agent.prompt("Hi").await.unwrap(), "Hello!");
+        assert_eq!(*calls.lock().unwrap(), 3);
+
+        let agent = crate::agent::AgentBuilder::new(model)
+            .preamble("The secret is 42")
+            .build();
+        assert!(agent.prompt("Hi").await.is_err());
+        assert_eq!(*calls.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_layer() {
+        let model = RateLimitLayer::new(
+            RateLimiter::new()
+                .requests_per_minute(1)
+                .behavior(RateLimitBehavior::Error),
+        )
+        .layer(FlakyModel {
+            calls: Arc::new(Mutex::new(0)),
+            failures: 0,
+        });
+
+        assert!(model.completion_request("Hi").send().await.is_ok());
+
+        let error = model.completion_request("Hi").send().await.unwrap_err();
+        assert!(matches!(
+            error,
+            CompletionError::HttpError(HttpError::RateLimitExceeded(_))
+        ));
+        assert!(error.is_retryable());
+        assert_eq!(error.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+    }
+}
@@ -83,21 +83,45 @@
 pub mod agent;
 #[cfg(feature = "audio")]
 pub mod audio_generation;
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+#[cfg(feature = "cassette")]
+pub mod cassette;
+pub mod chunking;
+pub mod citations;
 pub mod cli_chatbot;
 pub mod completion;
+pub mod conversation;
+pub mod cost;
+pub mod credentials;
 pub mod embeddings;
 pub mod extractor;
+pub mod failover;
+pub mod http_client;
 #[cfg(feature = "image")]
 pub mod image_generation;
+pub mod indexing;
 pub(crate) mod json_utils;
+pub mod key_pool;
+pub mod layer;
 pub mod loaders;
 pub mod one_or_many;
 pub mod pipeline;
 pub mod providers;
+pub mod rate_limit;
+pub mod registry;
+pub mod retrieval;
+#[cfg(feature = "audio")]
+pub mod speech;
 pub mod streaming;
+pub mod telemetry;
+pub mod tokenizer;
 pub mod tool;
 pub mod transcription;
 pub mod vector_store;
+pub mod wasm_compat;
 
 // Re-export commonly used types and traits
 pub use completion::message;
@@ -0,0 +1,251 @@
+use std::path::{Path, PathBuf};
+
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::file::FileLoaderError;
+use crate::embeddings::{Embed, EmbedError, TextEmbedder};
+
+#[derive(Error, Debug)]
+pub enum DocumentLoaderError {
+    #[error("{0}")]
+    FileLoaderError(#[from] FileLoaderError),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// Error parsing the file (e.g.: corrupted PDF)
+    #[error("Parse error in {path}: {error}")]
+    ParseError {
+        path: PathBuf,
+        error: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
+    /// No loader is available for the extension of the file (or its feature is not enabled)
+    #[error("Unsupported document format: {0}")]
+    UnsupportedFormat(PathBuf),
+}
+
+impl DocumentLoaderError {
+    #[cfg(any(feature = "docx", feature = "pdf"))]
+    pub(crate) fn parse(
+        path: &Path,
+        error: impl Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
+    ) -> Self {
+        Self::ParseError {
+            path: path.to_path_buf(),
+            error: error.into(),
+        }
+    }
+}
+
+/// Where a [LoadedDocument] was loaded from
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentSource {
+    /// Path of a local file
+    Path(PathBuf),
+    /// URL of a web page
+    Url(String),
+}
+
+impl Default for DocumentSource {
+    fn default() -> Self {
+        Self::Path(PathBuf::new())
+    }
+}
+
+impl std::fmt::Display for DocumentSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Path(path) => write!(f, "{}", path.display()),
+            Self::Url(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+impl From<&Path> for DocumentSource {
+    fn from(path: &Path) -> Self {
+        Self::Path(path.to_path_buf())
+    }
+}
+
+/// Metadata of a [LoadedDocument]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DocumentMetadata {
+    /// File (or web page) the document was loaded from
+    pub source: DocumentSource,
+    /// Title of the file, if any (e.g.: `<title>` of an HTML page)
+    pub title: Option<String>,
+    /// Page number (starting from 1) of the document, for paginated formats (e.g.: PDF)
+    pub page: Option<usize>,
+    /// Headings the document is nested under, from the outermost to the innermost (e.g.:
+    /// `["Installation", "From source"]`), for formats split by sections (e.g.: Markdown)
+    pub headings: Vec<String>,
+}
+
+/// Text loaded from a file (or a part of a file, e.g.: a page or a section) with its metadata.
+///
+/// Loaded documents implement [Embed] (their text is embedded), so they can be added directly to
+/// an [EmbeddingsBuilder](crate::embeddings::EmbeddingsBuilder).
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LoadedDocument {
+    /// Identifier of the document, unique across the documents loaded from the same files
+    /// (e.g.: `"docs/manual.pdf#page=3"`)
+    pub id: String,
+    pub text: String,
+    pub metadata: DocumentMetadata,
+}
+
+impl Embed for LoadedDocument {
+    fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+        embedder.embed(self.text.clone());
+        Ok(())
+    }
+}
+
+/// Loader turning files of a given format into [LoadedDocument]s
+pub trait DocumentLoader {
+    /// Load the documents of the file at `path`
+    fn load(&self, path: &Path) -> Result<Vec<LoadedDocument>, DocumentLoaderError>;
+
+    /// Load the documents of all the files matching the glob `pattern`
+    fn load_glob(&self, pattern: &str) -> Result<Vec<LoadedDocument>, DocumentLoaderError> {
+        let mut documents = vec![];
+        for path in glob(pattern).map_err(FileLoaderError::PatternError)? {
+            let path = path.map_err(FileLoaderError::GlobError)?;
+            documents.extend(self.load(&path)?);
+        }
+        Ok(documents)
+    }
+}
+
+/// Load the documents of the file at `path` with the loader matching its extension:
+/// - `.md` and `.markdown` files with the [MarkdownLoader](super::MarkdownLoader);
+/// - `.html` and `.htm` files with the `HtmlLoader` (requires the `html` feature);
+/// - `.pdf` files with the `PdfLoader` (requires the `pdf` feature);
+/// - `.docx` files with the `DocxLoader` (requires the `docx` feature);
+/// - any other file is loaded as plain text.
+pub fn load_document(path: impl AsRef<Path>) -> Result<Vec<LoadedDocument>, DocumentLoaderError> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+
+    match extension.as_deref() {
+        Some("md" | "markdown") => super::MarkdownLoader::new().load(path),
+        #[cfg(feature = "html")]
+        Some("html" | "htm") => super::HtmlLoader::new().load(path),
+        #[cfg(feature = "pdf")]
+        Some("pdf") => super::PdfLoader.load(path),
+        #[cfg(feature = "docx")]
+        Some("docx") => super::DocxLoader.load(path),
+        // The loader of the format is behind a feature which is not enabled
+        Some(extension) if matches!(extension, "html" | "htm" | "pdf" | "docx") => {
+            Err(DocumentLoaderError::UnsupportedFormat(path.to_path_buf()))
+        }
+        _ => Ok(vec![LoadedDocument {
+            id: path.display().to_string(),
+            text: std::fs::read_to_string(path)?,
+            metadata: DocumentMetadata {
+                source: path.into(),
+                ..Default::default()
+            },
+        }]),
+    }
+}
+
+/// Splits the text of a file into sections at its headings, keeping track of the headings each
+/// section is nested under
+pub(crate) struct SectionSplitter {
+    source: PathBuf,
+    /// Headings (with their level) enclosing the current section
+    headings: Vec<(usize, String)>,
+    /// Headings enclosing the current section, when it started
+    section_headings: Vec<String>,
+    text: String,
+    /// Whether the current section has text besides its heading
+    has_body: bool,
+    first_heading: Option<String>,
+    documents: Vec<LoadedDocument>,
+}
+
+impl SectionSplitter {
+    pub(crate) fn new(source: &Path) -> Self {
+        Self {
+            source: source.to_path_buf(),
+            headings: vec![],
+            section_headings: vec![],
+            text: String::new(),
+            has_body: false,
+            first_heading: None,
+            documents: vec![],
+        }
+    }
+
+    /// Start a new section at a heading of `level` (1 for the outermost headings)
+    pub(crate) fn heading(&mut self, level: usize, heading: &str) {
+        self.flush();
+
+        if level == 1 && self.first_heading.is_none() {
+            self.first_heading = Some(heading.to_string());
+        }
+        self.headings.retain(|(enclosing, _)| *enclosing < level);
+        self.headings.push((level, heading.to_string()));
+        self.section_headings = self
+            .headings
+            .iter()
+            .map(|(_, heading)| heading.clone())
+            .collect();
+
+        self.text.push_str(heading);
+        self.text.push('\n');
+    }
+
+    /// Add a line of text to the current section
+    pub(crate) fn line(&mut self, line: &str) {
+        self.has_body |= !line.trim().is_empty();
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    /// End the current section. Sections without text besides their heading are dropped (their
+    /// heading is kept in the metadata of their subsections).
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.text);
+        if !std::mem::take(&mut self.has_body) {
+            return;
+        }
+
+        self.documents.push(LoadedDocument {
+            id: format!(
+                "{}#section={}",
+                self.source.display(),
+                self.documents.len() + 1
+            ),
+            text: text.trim().to_string(),
+            metadata: DocumentMetadata {
+                source: self.source.as_path().into(),
+                title: None,
+                page: None,
+                headings: self.section_headings.clone(),
+            },
+        });
+    }
+
+    /// The sections of the file, whose title is `title` (or its first top-level heading)
+    pub(crate) fn finish(mut self, title: Option<String>) -> Vec<LoadedDocument> {
+        self.flush();
+
+        let title = title.or(self.first_heading);
+        self.documents
+            .into_iter()
+            .map(|mut document| {
+                document.metadata.title = title.clone();
+                document
+            })
+            .collect()
+    }
+}
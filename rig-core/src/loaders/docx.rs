@@ -0,0 +1,212 @@
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use zip::{result::ZipError, ZipArchive};
+
+use super::document::{DocumentLoader, DocumentLoaderError, LoadedDocument, SectionSplitter};
+
+/// [DocxLoader] loads Word (`.docx`) files, split into one document per section (i.e.: per
+/// paragraph with a `Heading N` style). Each document keeps track of the headings it is nested
+/// under, and of the title of the file (from its properties, or its first `Title` paragraph).
+///
+/// Note: the [DocxLoader] requires the `docx` feature to be enabled in the `Cargo.toml` file.
+///
+/// # Example
+/// ```rust
+/// use rig::loaders::{DocumentLoader, DocxLoader};
+///
+/// let documents = DocxLoader.load_glob("reports/*.docx")?;
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DocxLoader;
+
+impl DocxLoader {
+    /// Split the `.docx` archive read from `reader`, of the file at `source`, into sections
+    pub fn parse<R: Read + Seek>(
+        &self,
+        source: &Path,
+        reader: R,
+    ) -> Result<Vec<LoadedDocument>, DocumentLoaderError> {
+        let mut archive =
+            ZipArchive::new(reader).map_err(|err| DocumentLoaderError::parse(source, err))?;
+
+        let document = read_entry(&mut archive, "word/document.xml")
+            .map_err(|err| DocumentLoaderError::parse(source, err))?;
+        let properties = match read_entry(&mut archive, "docProps/core.xml") {
+            Ok(properties) => Some(properties),
+            Err(ZipError::FileNotFound) => None,
+            Err(err) => return Err(DocumentLoaderError::parse(source, err)),
+        };
+
+        let mut title = properties
+            .map(|properties| core_title(&properties))
+            .transpose()
+            .map_err(|err| DocumentLoaderError::parse(source, err))?
+            .flatten();
+
+        let mut sections = SectionSplitter::new(source);
+        for (style, text) in
+            paragraphs(&document).map_err(|err| DocumentLoaderError::parse(source, err))?
+        {
+            match style.as_deref().map(heading_level) {
+                Some(Some(0)) => {
+                    title.get_or_insert(text);
+                }
+                Some(Some(level)) => sections.heading(level, &text),
+                _ => sections.line(&text),
+            }
+        }
+
+        Ok(sections.finish(title))
+    }
+}
+
+impl DocumentLoader for DocxLoader {
+    fn load(&self, path: &Path) -> Result<Vec<LoadedDocument>, DocumentLoaderError> {
+        self.parse(path, File::open(path)?)
+    }
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<String, ZipError> {
+    let mut content = String::new();
+    archive.by_name(name)?.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Level of the heading of a paragraph style (`0` for the `Title` style), if it is a heading
+fn heading_level(style: &str) -> Option<usize> {
+    let style = style.to_lowercase();
+    if style == "title" {
+        return Some(0);
+    }
+
+    style
+        .strip_prefix("heading")?
+        .trim()
+        .parse()
+        .ok()
+        .filter(|level| (1..=9).contains(level))
+}
+
+/// The paragraphs (with their style, if any) of the `word/document.xml` of a `.docx` file
+fn paragraphs(xml: &str) -> Result<Vec<(Option<String>, String)>, quick_xml::Error> {
+    let mut reader = Reader::from_str(xml);
+
+    let mut paragraphs = vec![];
+    let mut style = None;
+    let mut text = String::new();
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.local_name().as_ref() == b"t" => in_text = true,
+            Event::End(e) if e.local_name().as_ref() == b"t" => in_text = false,
+            Event::Text(e) if in_text => text.push_str(&e.unescape()?),
+            Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                b"pStyle" => style = attribute(&e, b"val")?,
+                b"tab" => text.push('\t'),
+                b"br" | b"cr" => text.push('\n'),
+                _ => {}
+            },
+            Event::End(e) if e.local_name().as_ref() == b"p" => {
+                paragraphs.push((style.take(), std::mem::take(&mut text)));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(paragraphs)
+}
+
+/// The `dc:title` of the `docProps/core.xml` of a `.docx` file
+fn core_title(xml: &str) -> Result<Option<String>, quick_xml::Error> {
+    let mut reader = Reader::from_str(xml);
+    let mut in_title = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.local_name().as_ref() == b"title" => in_title = true,
+            Event::Text(e) if in_title => {
+                let title = e.unescape()?.trim().to_string();
+                return Ok((!title.is_empty()).then_some(title));
+            }
+            Event::End(_) => in_title = false,
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Result<Option<String>, quick_xml::Error> {
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(quick_xml::Error::from)?;
+        if attribute.key.local_name().as_ref() == name {
+            return Ok(Some(attribute.unescape_value()?.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::*;
+
+    const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:body>
+    <w:p><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>Quarterly report</w:t></w:r></w:p>
+    <w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Revenue</w:t></w:r></w:p>
+    <w:p><w:r><w:t xml:space="preserve">Revenue grew </w:t></w:r><w:r><w:t>by 10% &amp; more.</w:t></w:r></w:p>
+    <w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>Europe</w:t></w:r></w:p>
+    <w:p><w:r><w:t>Sales</w:t><w:tab/><w:t>12</w:t></w:r></w:p>
+  </w:body>
+</w:document>"#;
+
+    #[test]
+    fn test_docx_loader() {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        writer
+            .start_file("word/document.xml", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(DOCUMENT.as_bytes()).unwrap();
+        let archive = writer.finish().unwrap();
+
+        let documents = DocxLoader
+            .parse(Path::new("report.docx"), Cursor::new(archive.into_inner()))
+            .unwrap();
+
+        let sections = documents
+            .iter()
+            .map(|document| (document.metadata.headings.clone(), document.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sections,
+            vec![
+                (
+                    vec!["Revenue".to_string()],
+                    "Revenue\nRevenue grew by 10% & more."
+                ),
+                (
+                    vec!["Revenue".to_string(), "Europe".to_string()],
+                    "Europe\nSales\t12"
+                ),
+            ]
+        );
+        assert_eq!(
+            documents[0].metadata.title.as_deref(),
+            Some("Quarterly report")
+        );
+    }
+}
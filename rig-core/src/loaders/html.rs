@@ -0,0 +1,220 @@
+use std::path::Path;
+
+use scraper::{ElementRef, Html, Node, Selector};
+
+use super::document::{DocumentLoader, DocumentLoaderError, DocumentMetadata, LoadedDocument};
+
+/// Elements which are not part of the main content of a page
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "iframe", "nav", "header", "footer", "aside",
+    "form", "button",
+];
+
+/// Elements whose content starts on a new line
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+/// Content extracted from an HTML page by [HtmlLoader::extract]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HtmlContent {
+    /// Title of the page (`<title>`, or its first `<h1>`)
+    pub title: Option<String>,
+    /// Text of the main content of the page
+    pub text: String,
+    /// Targets (`href`) of the links of the page, as written in the page (i.e.: possibly
+    /// relative)
+    pub links: Vec<String>,
+}
+
+/// [HtmlLoader] loads HTML files, extracting the text of their main content ("readability"
+/// extraction): the text of the `<article>` or `<main>` element of the page (or its `<body>`),
+/// without the scripts, styles, navigation, headers, footers, sidebars and forms.
+///
+/// Note: the [HtmlLoader] requires the `html` feature to be enabled in the `Cargo.toml` file.
+///
+/// # Example
+/// ```rust
+/// use rig::loaders::{DocumentLoader, HtmlLoader};
+///
+/// let documents = HtmlLoader::new().load_glob("site/**/*.html")?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HtmlLoader;
+
+impl HtmlLoader {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract the title, main content and links of an HTML page
+    pub fn extract(html: &str) -> HtmlContent {
+        let document = Html::parse_document(html);
+        let first_text = |selector: &str| {
+            document
+                .select(&Self::selector(selector))
+                .map(|element| normalize_whitespace(&element.text().collect::<String>()))
+                .find(|text| !text.is_empty())
+        };
+        let title = first_text("title").or_else(|| first_text("h1"));
+
+        let body = document
+            .select(&Self::selector("body"))
+            .next()
+            .unwrap_or_else(|| document.root_element());
+        // The main content is the `<article>` or `<main>` element, unless it is suspiciously
+        // short compared to the rest of the page (e.g.: a teaser)
+        let body_text = element_text(body);
+        let text = ["article", "main", "[role=\"main\"]"]
+            .iter()
+            .find_map(|candidate| {
+                let element = document.select(&Self::selector(candidate)).next()?;
+                let text = element_text(element);
+                (text.len() * 4 >= body_text.len()).then_some(text)
+            })
+            .unwrap_or(body_text);
+
+        let links = document
+            .select(&Self::selector("a[href]"))
+            .filter_map(|link| link.value().attr("href"))
+            .map(|href| href.trim().to_string())
+            .filter(|href| !href.is_empty())
+            .collect();
+
+        HtmlContent { title, text, links }
+    }
+
+    fn selector(selector: &str) -> Selector {
+        Selector::parse(selector).expect("Selector should parse")
+    }
+}
+
+impl DocumentLoader for HtmlLoader {
+    fn load(&self, path: &Path) -> Result<Vec<LoadedDocument>, DocumentLoaderError> {
+        let html = std::fs::read_to_string(path)?;
+        let content = Self::extract(&html);
+
+        Ok(vec![LoadedDocument {
+            id: path.display().to_string(),
+            text: content.text,
+            metadata: DocumentMetadata {
+                source: path.into(),
+                title: content.title,
+                ..Default::default()
+            },
+        }])
+    }
+}
+
+/// Text of an element, without the skipped elements, with one line per block element
+fn element_text(element: ElementRef) -> String {
+    let mut text = String::new();
+    collect_text(element, &mut text);
+
+    let mut lines = vec![];
+    for line in text.lines().map(normalize_whitespace) {
+        // Keep at most one blank line between paragraphs
+        if !line.is_empty() || lines.last().is_some_and(|last: &String| !last.is_empty()) {
+            lines.push(line);
+        }
+    }
+
+    lines.join("\n").trim().to_string()
+}
+
+fn collect_text(element: ElementRef, text: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            // Line breaks in the source are not line breaks of the text
+            Node::Text(content) => {
+                text.extend(
+                    content
+                        .chars()
+                        .map(|c| if c.is_whitespace() { ' ' } else { c }),
+                )
+            }
+            Node::Element(child_element) => {
+                let name = child_element.name();
+                if SKIPPED_ELEMENTS.contains(&name) {
+                    continue;
+                }
+
+                let block = BLOCK_ELEMENTS.contains(&name);
+                if block {
+                    text.push('\n');
+                }
+                if let Some(child) = ElementRef::wrap(child) {
+                    collect_text(child, text);
+                }
+                if block {
+                    text.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let html = r#"
+            <html>
+              <head><title> Rig   docs </title><style>p { color: red; }</style></head>
+              <body>
+                <nav><a href="/">Home</a></nav>
+                <article>
+                  <h1>Agents</h1>
+                  <p>An agent combines a <b>model</b>
+                     with a preamble.</p>
+                  <script>track();</script>
+                  <p>See <a href="tools.html">tools</a>.</p>
+                </article>
+                <footer>Copyright</footer>
+              </body>
+            </html>
+        "#;
+
+        let content = HtmlLoader::extract(html);
+        assert_eq!(content.title.as_deref(), Some("Rig docs"));
+        assert_eq!(
+            content.text,
+            "Agents\n\nAn agent combines a model with a preamble.\n\nSee tools."
+        );
+        assert_eq!(content.links, vec!["/", "tools.html"]);
+    }
+}
@@ -0,0 +1,187 @@
+use std::path::Path;
+
+use super::document::{DocumentLoader, DocumentLoaderError, LoadedDocument, SectionSplitter};
+
+/// [MarkdownLoader] loads Markdown files, split into one document per section (i.e.: per
+/// heading). Each document keeps track of the headings it is nested under, and of the title of
+/// the file (the `title` of its YAML front matter, or its first top-level heading).
+///
+/// Headings inside fenced code blocks are ignored.
+///
+/// # Example
+/// ```rust
+/// use rig::loaders::{DocumentLoader, MarkdownLoader};
+///
+/// // Split the files at the `#` and `##` headings only
+/// let documents = MarkdownLoader::new().split_level(2).load_glob("docs/**/*.md")?;
+///
+/// for document in documents {
+///     println!("{} {:?}: {}", document.id, document.metadata.headings, document.text);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MarkdownLoader {
+    split_level: usize,
+}
+
+impl Default for MarkdownLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownLoader {
+    /// Create a loader splitting the files at all their headings
+    pub fn new() -> Self {
+        Self { split_level: 6 }
+    }
+
+    /// Only split the files at the headings of level `split_level` or lower (e.g.: `2` to split
+    /// at the `#` and `##` headings). The deeper headings are kept in the text of the sections.
+    /// A level of `0` does not split the files.
+    pub fn split_level(mut self, split_level: usize) -> Self {
+        self.split_level = split_level;
+        self
+    }
+
+    /// Split the Markdown text `markdown` of the file at `source` into sections
+    pub fn parse(&self, source: &Path, markdown: &str) -> Vec<LoadedDocument> {
+        let (title, markdown) = front_matter(markdown);
+
+        let mut sections = SectionSplitter::new(source);
+        let mut fence: Option<&str> = None;
+
+        for line in markdown.lines() {
+            let trimmed = line.trim_start();
+
+            match fence {
+                Some(marker) if trimmed.starts_with(marker) => fence = None,
+                Some(_) => {}
+                None if trimmed.starts_with("```") => fence = Some("```"),
+                None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+                None => match atx_heading(line) {
+                    Some((level, heading)) if level <= self.split_level => {
+                        sections.heading(level, heading);
+                        continue;
+                    }
+                    _ => {}
+                },
+            }
+
+            sections.line(line);
+        }
+
+        sections.finish(title)
+    }
+}
+
+impl DocumentLoader for MarkdownLoader {
+    fn load(&self, path: &Path) -> Result<Vec<LoadedDocument>, DocumentLoaderError> {
+        let markdown = std::fs::read_to_string(path)?;
+        Ok(self.parse(path, &markdown))
+    }
+}
+
+/// Parse an ATX heading (e.g.: `## Installation`) into its level and text
+pub(crate) fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    // Lines indented by 4 spaces or more are code blocks
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+/// Split the YAML front matter (if any) from the Markdown text, returning its `title`
+fn front_matter(markdown: &str) -> (Option<String>, &str) {
+    let Some(rest) = markdown.strip_prefix("---\n") else {
+        return (None, markdown);
+    };
+    let Some(end) = rest.find("\n---") else {
+        return (None, markdown);
+    };
+
+    let title = rest[..end].lines().find_map(|line| {
+        let title = line
+            .strip_prefix("title:")?
+            .trim()
+            .trim_matches(['"', '\'']);
+        (!title.is_empty()).then(|| title.to_string())
+    });
+    let body = rest[end + 4..].trim_start_matches(['-']);
+
+    (title, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARKDOWN: &str = r#"---
+title: "User guide"
+---
+Introduction.
+
+# Installation
+
+Install with cargo.
+
+## From source
+
+```sh
+# Not a heading
+cargo build
+```
+
+# Usage
+### Advanced
+Run it.
+"#;
+
+    #[test]
+    fn test_markdown_loader() {
+        let documents = MarkdownLoader::new().parse(Path::new("guide.md"), MARKDOWN);
+
+        let sections = documents
+            .iter()
+            .map(|document| (document.metadata.headings.clone(), document.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sections,
+            vec![
+                (vec![], "Introduction."),
+                (
+                    vec!["Installation".to_string()],
+                    "Installation\n\nInstall with cargo."
+                ),
+                (
+                    vec!["Installation".to_string(), "From source".to_string()],
+                    "From source\n\n```sh\n# Not a heading\ncargo build\n```"
+                ),
+                (
+                    vec!["Usage".to_string(), "Advanced".to_string()],
+                    "Advanced\nRun it."
+                ),
+            ]
+        );
+        assert_eq!(documents[1].id, "guide.md#section=2");
+        assert_eq!(documents[1].metadata.title.as_deref(), Some("User guide"));
+    }
+
+    #[test]
+    fn test_split_level() {
+        let documents = MarkdownLoader::new()
+            .split_level(1)
+            .parse(Path::new("guide.md"), MARKDOWN);
+
+        assert_eq!(documents.len(), 3);
+        assert_eq!(documents[2].text, "Usage\n### Advanced\nRun it.");
+    }
+}
@@ -15,16 +15,54 @@
 //! and keeping track of the chapter numbers along with their contents.
 //!
 //! Note: The [EpubFileLoader] requires the `epub` feature to be enabled in the `Cargo.toml` file.
+//!
+//! The [DocumentLoader] trait defines loaders turning files into [LoadedDocument]s: text along with
+//! metadata such as the title of the file, the page number or the headings of the section the text
+//! comes from. Loaded documents can be added directly to an
+//! [EmbeddingsBuilder](crate::embeddings::EmbeddingsBuilder). Loaders are provided for Markdown
+//! ([MarkdownLoader]), HTML (`HtmlLoader`, `html` feature), Word (`DocxLoader`, `docx` feature)
+//! and PDF (`PdfLoader`, `pdf` feature) files, and [load_document] picks the loader matching the
+//! extension of a file.
+//!
+//! The [WebLoader] fetches web pages (extracting their main content like the `HtmlLoader`) and
+//! can crawl sites, honoring their `robots.txt`.
+//!
+//! Note: The [WebLoader] requires the `html` feature to be enabled in the `Cargo.toml` file.
 
+pub mod document;
 pub mod file;
+pub mod markdown;
 
+pub use document::{
+    load_document, DocumentLoader, DocumentLoaderError, DocumentMetadata, DocumentSource,
+    LoadedDocument,
+};
 pub use file::FileLoader;
+pub use markdown::MarkdownLoader;
+
+#[cfg(feature = "html")]
+pub mod html;
+
+#[cfg(feature = "html")]
+pub use html::{HtmlContent, HtmlLoader};
+
+#[cfg(feature = "html")]
+pub mod web;
+
+#[cfg(feature = "html")]
+pub use web::{CrawlScope, WebLoader, WebLoaderError};
+
+#[cfg(feature = "docx")]
+pub mod docx;
+
+#[cfg(feature = "docx")]
+pub use docx::DocxLoader;
 
 #[cfg(feature = "pdf")]
 pub mod pdf;
 
 #[cfg(feature = "pdf")]
-pub use pdf::PdfFileLoader;
+pub use pdf::{PdfFileLoader, PdfLoader};
 
 #[cfg(feature = "epub")]
 pub mod epub;
@@ -1,9 +1,13 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use glob::glob;
 use lopdf::{Document, Error as LopdfError};
 use thiserror::Error;
 
+use super::document::{DocumentLoader, DocumentLoaderError, DocumentMetadata, LoadedDocument};
 use super::file::FileLoaderError;
 
 #[derive(Error, Debug)]
@@ -404,11 +408,54 @@ impl<T> Iterator for IntoIter<'_, T> {
     }
 }
 
+// ================================================================
+// PdfLoader definitions and implementations
+// ================================================================
+
+/// [PdfLoader] is a [DocumentLoader] loading each page of a PDF file as a separate document,
+///  with its page number (starting from 1) in its metadata.
+///
+/// # Example Usage
+///
+/// ```rust
+/// use rig::loaders::{DocumentLoader, PdfLoader};
+///
+/// let pages = PdfLoader.load_glob("tests/data/*.pdf")?;
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PdfLoader;
+
+impl DocumentLoader for PdfLoader {
+    fn load(&self, path: &Path) -> Result<Vec<LoadedDocument>, DocumentLoaderError> {
+        let doc = Document::load(path).map_err(|err| DocumentLoaderError::parse(path, err))?;
+
+        doc.get_pages()
+            .into_keys()
+            .map(|page_no| {
+                let text = doc
+                    .extract_text(&[page_no])
+                    .map_err(|err| DocumentLoaderError::parse(path, err))?;
+
+                Ok(LoadedDocument {
+                    id: format!("{}#page={}", path.display(), page_no),
+                    text,
+                    metadata: DocumentMetadata {
+                        source: path.into(),
+                        page: Some(page_no as usize),
+                        ..Default::default()
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
 
-    use super::PdfFileLoader;
+    use super::{PdfFileLoader, PdfLoader};
+    use crate::loaders::DocumentLoader;
 
     #[test]
     fn test_pdf_loader() {
@@ -453,4 +500,23 @@ mod tests {
         assert!(!actual.is_empty());
         assert!(expected == actual)
     }
+
+    #[test]
+    fn test_pdf_document_loader() {
+        let documents = PdfLoader.load_glob("tests/data/pages.pdf").unwrap();
+
+        let pages = documents
+            .iter()
+            .map(|document| (document.metadata.page, document.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pages,
+            vec![
+                (Some(1), "Page\n1\n"),
+                (Some(2), "Page\n2\n"),
+                (Some(3), "Page\n3\n"),
+            ]
+        );
+        assert_eq!(documents[0].id, "tests/data/pages.pdf#page=1");
+    }
 }
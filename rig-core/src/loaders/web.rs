@@ -0,0 +1,608 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE, USER_AGENT},
+    Url,
+};
+use thiserror::Error;
+
+use super::{
+    document::{DocumentMetadata, DocumentSource, LoadedDocument},
+    html::HtmlLoader,
+};
+use crate::http_client::{ApiError, HttpClient, HttpTransport};
+
+const DEFAULT_USER_AGENT: &str = concat!("rig/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Error, Debug)]
+pub enum WebLoaderError {
+    #[error("HttpError: {0}")]
+    HttpError(#[from] crate::http_client::HttpError),
+
+    /// The server responded with an error status
+    #[error("ApiError: {0}")]
+    ApiError(#[from] ApiError),
+
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+
+    /// The `robots.txt` of the site does not allow the page to be crawled
+    #[error("Disallowed by robots.txt: {0}")]
+    DisallowedByRobots(Url),
+
+    /// The page is neither HTML nor text (e.g.: an image)
+    #[error("Unsupported content type {content_type} for {url}")]
+    UnsupportedContentType { url: Url, content_type: String },
+}
+
+impl From<reqwest::Error> for WebLoaderError {
+    fn from(error: reqwest::Error) -> Self {
+        WebLoaderError::HttpError(error.into())
+    }
+}
+
+/// Pages a [WebLoader] may follow links to when crawling, relative to the page the crawl starts
+/// from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CrawlScope {
+    /// Pages of the same host (e.g.: `docs.rig.rs`)
+    #[default]
+    Host,
+    /// Pages of the same host and of its subdomains (e.g.: `rig.rs` and `docs.rig.rs`)
+    Subdomains,
+    /// Pages of the same host under the same directory (e.g.: `docs.rig.rs/guides/...` when
+    /// starting from `docs.rig.rs/guides/intro`)
+    PathPrefix,
+    /// Any page
+    Any,
+}
+
+impl CrawlScope {
+    fn contains(&self, start: &Url, url: &Url) -> bool {
+        let same_host = start.host_str() == url.host_str();
+
+        match self {
+            Self::Host => same_host,
+            Self::Subdomains => match (start.host_str(), url.host_str()) {
+                (Some(start), Some(host)) => {
+                    let domain = start.strip_prefix("www.").unwrap_or(start);
+                    host == start || host == domain || host.ends_with(&format!(".{domain}"))
+                }
+                _ => false,
+            },
+            Self::PathPrefix => {
+                let directory = &start.path()[..=start.path().rfind('/').unwrap_or(0)];
+                same_host && url.path().starts_with(directory)
+            }
+            Self::Any => true,
+        }
+    }
+}
+
+/// [WebLoader] fetches web pages and extracts their main content (like the [HtmlLoader]), and
+/// can crawl a site by following the links of the pages, up to a configurable depth and within a
+/// configurable [CrawlScope]. Each page is loaded as a [LoadedDocument] identified by its URL,
+/// ready to be chunked and embedded.
+///
+/// The loader honors the `robots.txt` of the sites (for its user agent, `rig/<version>` by
+/// default) unless [WebLoader::ignore_robots_txt] is used. The requests are sent with an
+/// [HttpClient], so failed requests are retried with its default [RetryPolicy](crate::http_client::RetryPolicy).
+///
+/// Note: the [WebLoader] requires the `html` feature to be enabled in the `Cargo.toml` file.
+///
+/// # Example
+/// ```rust
+/// use rig::{
+///     chunking::{Chunker, RecursiveCharacterSplitter},
+///     loaders::{CrawlScope, WebLoader},
+/// };
+///
+/// let pages = WebLoader::new()
+///     .max_depth(2)
+///     .max_pages(50)
+///     .scope(CrawlScope::PathPrefix)
+///     .crawl("https://docs.rig.rs/guides/")
+///     .await?;
+///
+/// let chunks = RecursiveCharacterSplitter::new(1000, 100).chunk_documents(&pages);
+/// ```
+#[derive(Clone, Debug)]
+pub struct WebLoader {
+    http_client: HttpClient,
+    user_agent: String,
+    max_depth: usize,
+    max_pages: usize,
+    scope: CrawlScope,
+    respect_robots_txt: bool,
+    /// `robots.txt` of the sites visited, by origin
+    robots: Arc<Mutex<HashMap<String, Arc<RobotsTxt>>>>,
+}
+
+impl Default for WebLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebLoader {
+    /// Create a loader which only loads the pages it is given (i.e.: a maximum depth of 0) and at
+    /// most 100 pages per crawl
+    pub fn new() -> Self {
+        Self {
+            http_client: HttpClient::new(reqwest::Client::new()),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            max_depth: 0,
+            max_pages: 100,
+            scope: CrawlScope::default(),
+            respect_robots_txt: true,
+            robots: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Use your own `reqwest::Client`
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = self.http_client.with_client(client);
+        self
+    }
+
+    /// Send the requests with `transport` instead of the underlying `reqwest::Client`
+    pub fn with_transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.http_client = self.http_client.with_transport(transport);
+        self
+    }
+
+    /// Set the `User-Agent` sent with the requests, and matched against the `robots.txt` rules
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.to_string();
+        self
+    }
+
+    /// Follow links up to `max_depth` pages away from the page the crawl starts from
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Stop crawling after loading `max_pages` pages
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// Only follow links to the pages within `scope`
+    pub fn scope(mut self, scope: CrawlScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Load pages even if the `robots.txt` of their site disallows it
+    pub fn ignore_robots_txt(mut self) -> Self {
+        self.respect_robots_txt = false;
+        self
+    }
+
+    /// Load the page at `url`
+    pub async fn load(&self, url: &str) -> Result<LoadedDocument, WebLoaderError> {
+        let url = parse_url(url)?;
+        let (document, _) = self.fetch(&url).await?;
+        Ok(document)
+    }
+
+    /// Load the page at `url` and the pages linked from it, breadth first, up to the maximum
+    /// depth and number of pages of the loader. The pages linked from `url` which fail to load
+    /// (e.g.: broken links, or pages disallowed by `robots.txt`) are skipped.
+    pub async fn crawl(&self, url: &str) -> Result<Vec<LoadedDocument>, WebLoaderError> {
+        let start = parse_url(url)?;
+
+        let mut queue = VecDeque::from([(start.clone(), 0)]);
+        let mut seen = HashSet::from([start.to_string()]);
+        let mut documents = vec![];
+
+        while let Some((url, depth)) = queue.pop_front() {
+            if documents.len() >= self.max_pages {
+                break;
+            }
+
+            let (document, links) = match self.fetch(&url).await {
+                Ok(page) => page,
+                Err(error) if depth > 0 => {
+                    tracing::warn!(target: "rig", "Skipping {url}: {error}");
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            documents.push(document);
+
+            if depth < self.max_depth {
+                for link in links {
+                    if self.scope.contains(&start, &link) && seen.insert(link.to_string()) {
+                        queue.push_back((link, depth + 1));
+                    }
+                }
+            }
+        }
+
+        Ok(documents)
+    }
+
+    /// Fetch the page at `url`, returning it with the (absolute) URLs it links to
+    async fn fetch(&self, url: &Url) -> Result<(LoadedDocument, Vec<Url>), WebLoaderError> {
+        if self.respect_robots_txt && !self.robots_txt(url).await.is_allowed(url) {
+            return Err(WebLoaderError::DisallowedByRobots(url.clone()));
+        }
+
+        let response = self
+            .http_client
+            .get(url.clone())
+            .headers(self.headers())
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await?.into());
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .unwrap_or("text/html")
+            .to_lowercase();
+        let body = response.text().await?;
+
+        let (title, text, links) = if content_type.contains("html") {
+            let content = HtmlLoader::extract(&body);
+            let links = content
+                .links
+                .iter()
+                .filter_map(|href| url.join(href).ok())
+                .filter(|link| matches!(link.scheme(), "http" | "https"))
+                .map(|mut link| {
+                    link.set_fragment(None);
+                    link
+                })
+                .collect();
+            (content.title, content.text, links)
+        } else if content_type.starts_with("text/") {
+            (None, body, vec![])
+        } else {
+            return Err(WebLoaderError::UnsupportedContentType {
+                url: url.clone(),
+                content_type,
+            });
+        };
+
+        let document = LoadedDocument {
+            id: url.to_string(),
+            text,
+            metadata: DocumentMetadata {
+                source: DocumentSource::Url(url.to_string()),
+                title,
+                ..Default::default()
+            },
+        };
+
+        Ok((document, links))
+    }
+
+    /// The `robots.txt` of the site of `url`, fetched once per site. Sites without a readable
+    /// `robots.txt` allow every page, except when it is unreachable because of a server error
+    /// (`5xx` status), which disallows every page (RFC 9309, section 2.3.1.4).
+    async fn robots_txt(&self, url: &Url) -> Arc<RobotsTxt> {
+        let origin = url.origin().ascii_serialization();
+        if let Some(robots) = self
+            .robots
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .get(&origin)
+        {
+            return robots.clone();
+        }
+
+        let response = self
+            .http_client
+            .get(format!("{origin}/robots.txt"))
+            .headers(self.headers())
+            .send()
+            .await;
+        let robots = match response {
+            Ok(response) if response.status().is_success() => match response.text().await {
+                Ok(robots) => RobotsTxt::parse(&robots, &self.user_agent),
+                Err(_) => RobotsTxt::default(),
+            },
+            Ok(response) if response.status().is_server_error() => {
+                tracing::warn!(
+                    target: "rig",
+                    "Failed to fetch {origin}/robots.txt ({}), disallowing the site",
+                    response.status()
+                );
+                RobotsTxt::disallow_all()
+            }
+            Ok(_) => RobotsTxt::default(),
+            Err(error) => {
+                tracing::warn!(target: "rig", "Failed to fetch {origin}/robots.txt: {error}");
+                RobotsTxt::default()
+            }
+        };
+
+        self.robots
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .entry(origin)
+            .or_insert_with(|| Arc::new(robots))
+            .clone()
+    }
+
+    fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Ok(user_agent) = HeaderValue::from_str(&self.user_agent) {
+            headers.insert(USER_AGENT, user_agent);
+        }
+        headers
+    }
+}
+
+fn parse_url(url: &str) -> Result<Url, WebLoaderError> {
+    Url::parse(url).map_err(|error| WebLoaderError::InvalidUrl(format!("{url} ({error})")))
+}
+
+/// Rules of a `robots.txt` applying to a user agent
+#[derive(Debug, Default, PartialEq, Eq)]
+struct RobotsTxt {
+    rules: Vec<RobotsRule>,
+}
+
+/// Path pattern of a `robots.txt` file, allowed (`true`) or disallowed (`false`)
+type RobotsRule = (bool, String);
+
+impl RobotsTxt {
+    /// Rules disallowing every page
+    fn disallow_all() -> Self {
+        Self {
+            rules: vec![(false, "/".to_string())],
+        }
+    }
+
+    /// Parse the rules of the groups of `robots` applying to `user_agent` (or, if there are none,
+    /// to every user agent)
+    fn parse(robots: &str, user_agent: &str) -> Self {
+        let product = user_agent
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        // Groups of rules, with the user agents they apply to
+        let mut groups: Vec<(Vec<String>, Vec<RobotsRule>)> = vec![];
+        let mut in_rules = true;
+
+        for line in robots.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        groups.push((vec![], vec![]));
+                        in_rules = false;
+                    }
+                    if let Some((agents, _)) = groups.last_mut() {
+                        agents.push(value.to_lowercase());
+                    }
+                }
+                key @ ("allow" | "disallow") => {
+                    in_rules = true;
+                    // An empty `Disallow` allows everything
+                    if !value.is_empty() {
+                        if let Some((_, rules)) = groups.last_mut() {
+                            rules.push((key == "allow", value.to_string()));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let rules = |matches: &dyn Fn(&str) -> bool| {
+            groups
+                .iter()
+                .filter(|(agents, _)| agents.iter().any(|agent| matches(agent)))
+                .flat_map(|(_, rules)| rules.clone())
+                .collect::<Vec<_>>()
+        };
+        let mut specific = rules(&|agent| agent != "*" && product.starts_with(agent));
+        if specific.is_empty() {
+            specific = rules(&|agent| agent == "*");
+        }
+
+        Self { rules: specific }
+    }
+
+    /// Whether the page at `url` may be crawled: the longest matching rule applies, and `Allow`
+    /// rules win ties
+    fn is_allowed(&self, url: &Url) -> bool {
+        let path = match url.query() {
+            Some(query) => format!("{}?{query}", url.path()),
+            None => url.path().to_string(),
+        };
+
+        let rule = self
+            .rules
+            .iter()
+            .filter(|(_, pattern)| pattern_matches(pattern, &path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow));
+
+        // Not `Option::is_none_or`, which requires Rust 1.82
+        !matches!(rule, Some((false, _)))
+    }
+}
+
+/// Whether the `robots.txt` path `pattern` (with `*` wildcards and an optional `$` end anchor)
+/// matches `path`
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+
+    for (index, part) in parts.iter().enumerate() {
+        if anchored && index == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures::future::BoxFuture;
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::http_client::TransportError;
+
+    /// Transport serving a small site from memory
+    struct FakeSite;
+
+    impl HttpTransport for FakeSite {
+        fn execute(
+            &self,
+            request: http::Request<Bytes>,
+        ) -> BoxFuture<'_, Result<http::Response<Bytes>, TransportError>> {
+            Box::pin(async move {
+                let (status, content_type, body) = match request.uri().path() {
+                    "/robots.txt" if request.uri().host() == Some("down.example") => {
+                        (StatusCode::SERVICE_UNAVAILABLE, "text/plain", "Unavailable")
+                    }
+                    "/robots.txt" => (
+                        StatusCode::OK,
+                        "text/plain",
+                        "User-agent: *\nDisallow: /private\n",
+                    ),
+                    "/" => (
+                        StatusCode::OK,
+                        "text/html",
+                        r#"<title>Home</title><p>Welcome.</p>
+                        <a href="/guide#intro">Guide</a> <a href="private/notes">Notes</a>
+                        <a href="https://other.example/">Elsewhere</a>"#,
+                    ),
+                    "/guide" => (
+                        StatusCode::OK,
+                        "text/html; charset=utf-8",
+                        r#"<title>Guide</title><p>Read this.</p><a href="/deeper">Deeper</a>"#,
+                    ),
+                    _ => (StatusCode::NOT_FOUND, "text/plain", "Not found"),
+                };
+
+                Ok(http::Response::builder()
+                    .status(status)
+                    .header(CONTENT_TYPE, content_type)
+                    .body(Bytes::from(body))
+                    .unwrap())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_crawl() {
+        let loader = WebLoader::new().max_depth(1).with_transport(FakeSite);
+
+        let pages = loader.crawl("https://site.example/").await.unwrap();
+        let pages = pages
+            .iter()
+            .map(|page| {
+                (
+                    page.id.as_str(),
+                    page.metadata.title.as_deref(),
+                    page.text.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pages,
+            vec![
+                (
+                    "https://site.example/",
+                    Some("Home"),
+                    "Welcome.\nGuide Notes Elsewhere"
+                ),
+                (
+                    "https://site.example/guide",
+                    Some("Guide"),
+                    "Read this.\nDeeper"
+                ),
+            ]
+        );
+
+        assert!(matches!(
+            loader.load("https://site.example/private/notes").await,
+            Err(WebLoaderError::DisallowedByRobots(_))
+        ));
+        assert!(matches!(
+            loader.load("https://site.example/missing").await,
+            Err(WebLoaderError::ApiError(error)) if error.status == StatusCode::NOT_FOUND
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_robots_txt_server_error() {
+        let loader = WebLoader::new().with_transport(FakeSite);
+        assert!(matches!(
+            loader.load("https://down.example/guide").await,
+            Err(WebLoaderError::DisallowedByRobots(_))
+        ));
+
+        let page = loader
+            .ignore_robots_txt()
+            .load("https://down.example/guide")
+            .await
+            .unwrap();
+        assert_eq!(
+            page.metadata.source,
+            DocumentSource::Url("https://down.example/guide".to_string())
+        );
+    }
+
+    #[test]
+    fn test_robots_txt() {
+        let robots = "\
+User-agent: *
+Disallow: /
+
+User-agent: rig
+User-agent: other-bot
+Disallow: /private   # secrets
+Allow: /private/public
+Disallow: /*.pdf$
+";
+        let robots = RobotsTxt::parse(robots, "rig/0.9");
+        let allowed = |url: &str| robots.is_allowed(&Url::parse(url).unwrap());
+
+        assert!(allowed("https://site.example/docs"));
+        assert!(!allowed("https://site.example/private/keys"));
+        assert!(allowed("https://site.example/private/public/page"));
+        assert!(!allowed("https://site.example/docs/manual.pdf"));
+        assert!(allowed("https://site.example/docs/manual.pdf?page=2"));
+
+        let robots = RobotsTxt::parse("User-agent: *\nDisallow: /", "crawler");
+        assert!(!robots.is_allowed(&Url::parse("https://site.example/").unwrap()));
+    }
+}
@@ -64,7 +64,7 @@ macro_rules! conditional {
                 type Input = $enum<Value>;
                 type Output = Out;
 
-                fn call(&self, input: Self::Input) -> impl std::future::Future<Output=Self::Output> + Send {
+                fn call(&self, input: Self::Input) -> impl std::future::Future<Output=Self::Output> + $crate::wasm_compat::WasmCompatSend {
                     async move {
                         match input {
                             $(
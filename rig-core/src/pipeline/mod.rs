@@ -89,6 +89,7 @@
 
 pub mod agent_ops;
 pub mod op;
+pub mod summarize;
 pub mod try_op;
 #[macro_use]
 pub mod parallel;
@@ -100,7 +101,12 @@ use std::future::Future;
 pub use op::{map, passthrough, then, Op};
 pub use try_op::TryOp;
 
-use crate::{completion, extractor::Extractor, vector_store};
+use crate::{
+    completion,
+    extractor::Extractor,
+    vector_store,
+    wasm_compat::{WasmCompatSend, WasmCompatSync},
+};
 
 pub struct PipelineBuilder<E> {
     _error: std::marker::PhantomData<E>,
@@ -151,7 +157,7 @@ impl<E> PipelineBuilder<E> {
     where
         F: Fn(Input) -> Fut + Send + Sync,
         Input: Send + Sync,
-        Fut: Future + Send + Sync,
+        Fut: Future + WasmCompatSend + WasmCompatSync,
         Fut::Output: Send + Sync,
         Self: Sized,
     {
@@ -277,6 +283,29 @@ impl<E> PipelineBuilder<E> {
     {
         agent_ops::Extract::new(extractor)
     }
+
+    /// Add a map-reduce summarization operation to the current pipeline/op. The summarization
+    /// operation expects the current pipeline to output a string. The text is split into chunks
+    /// which are summarized in parallel with the given `model`, then the summaries are
+    /// recursively summarized into a final summary within a token budget.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rig::pipeline::{self, Op};
+    ///
+    /// let pipeline = pipeline::new()
+    ///     .summarize(openai_client.completion_model("gpt-4o-mini"))
+    ///     .max_tokens(200);
+    ///
+    /// let summary = pipeline.call(std::fs::read_to_string("report.txt")?).await?;
+    /// ```
+    pub fn summarize<M, Input>(self, model: M) -> summarize::Summarize<M, Input>
+    where
+        M: completion::CompletionModel,
+        Input: Into<String> + Send + Sync,
+    {
+        summarize::Summarize::new(model)
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -4,6 +4,8 @@ use std::future::Future;
 use futures::join;
 use futures::stream;
 
+use crate::wasm_compat::{WasmCompatSend, WasmCompatSync};
+
 // ================================================================
 // Core Op trait
 // ================================================================
@@ -11,11 +13,15 @@ pub trait Op: Send + Sync {
     type Input: Send + Sync;
     type Output: Send + Sync;
 
-    fn call(&self, input: Self::Input) -> impl Future<Output = Self::Output> + Send;
+    fn call(&self, input: Self::Input) -> impl Future<Output = Self::Output> + WasmCompatSend;
 
     /// Execute the current pipeline with the given inputs. `n` is the number of concurrent
     /// inputs that will be processed concurrently.
-    fn batch_call<I>(&self, n: usize, input: I) -> impl Future<Output = Vec<Self::Output>> + Send
+    fn batch_call<I>(
+        &self,
+        n: usize,
+        input: I,
+    ) -> impl Future<Output = Vec<Self::Output>> + WasmCompatSend
     where
         I: IntoIterator<Item = Self::Input> + Send,
         I::IntoIter: Send,
@@ -74,7 +80,7 @@ pub trait Op: Send + Sync {
     fn then<F, Fut>(self, f: F) -> Sequential<Self, Then<F, Fut::Output>>
     where
         F: Fn(Self::Output) -> Fut + Send + Sync,
-        Fut: Future + Send + Sync,
+        Fut: Future + WasmCompatSend + WasmCompatSync,
         Fut::Output: Send + Sync,
         Self: Sized,
     {
@@ -302,7 +308,7 @@ impl<F, Input, Fut> Op for Then<F, Input>
 where
     F: Fn(Input) -> Fut + Send + Sync,
     Input: Send + Sync,
-    Fut: Future + Send,
+    Fut: Future + WasmCompatSend,
     Fut::Output: Send + Sync,
 {
     type Input = Input;
@@ -318,7 +324,7 @@ pub fn then<F, Input, Fut>(f: F) -> Then<F, Input>
 where
     F: Fn(Input) -> Fut + Send + Sync,
     Input: Send + Sync,
-    Fut: Future + Send,
+    Fut: Future + WasmCompatSend,
     Fut::Output: Send + Sync,
 {
     Then::new(f)
@@ -0,0 +1,298 @@
+use std::sync::Arc;
+
+use super::{Op, TryOp};
+use crate::{
+    chunking::{Chunker, TokenSplitter},
+    completion::{CompletionModel, PromptError},
+    retrieval::response_text,
+    tokenizer::Tokenizer,
+};
+
+const MAP_PREAMBLE: &str = "You summarize an excerpt of a longer document. Write a concise \
+    summary of the excerpt, keeping its key facts, figures, names and conclusions.";
+
+const REDUCE_PREAMBLE: &str = "You combine the summaries of consecutive parts of a document, \
+    given in order, into a single concise summary, keeping the key facts, figures, names and \
+    conclusions and removing repetitions.";
+
+const DOCUMENT_PREAMBLE: &str = "You summarize documents. Write a concise summary of the \
+    document, keeping its key facts, figures, names and conclusions.";
+
+/// Number of attempts to shorten a final summary exceeding the token budget
+const MAX_SHORTENING_ATTEMPTS: usize = 2;
+
+/// Map-reduce summarization operation: the input text is split into chunks which are summarized
+/// in parallel (map), then the summaries are grouped and summarized again (reduce), recursively,
+/// until a single summary within the token budget remains.
+///
+/// Short texts (of at most `chunk_tokens` tokens) are summarized in a single request.
+pub struct Summarize<M, In> {
+    model: M,
+    chunk_tokens: usize,
+    max_tokens: usize,
+    concurrency: usize,
+    counter: Arc<dyn Fn(&str) -> usize + Send + Sync>,
+    instructions: Option<String>,
+    _in: std::marker::PhantomData<In>,
+}
+
+impl<M: CompletionModel, In> Summarize<M, In> {
+    pub(crate) fn new(model: M) -> Self {
+        Self {
+            model,
+            chunk_tokens: 4000,
+            max_tokens: 500,
+            concurrency: 8,
+            counter: Arc::new(|text: &str| text.trim_end().len().div_ceil(4)),
+            instructions: None,
+            _in: std::marker::PhantomData,
+        }
+    }
+
+    /// Maximum number of tokens of the text given to the model in each request (4000 by default).
+    /// Should be at least twice the token budget of the summary.
+    pub fn chunk_tokens(mut self, chunk_tokens: usize) -> Self {
+        self.chunk_tokens = chunk_tokens.max(1);
+        self
+    }
+
+    /// Token budget of the summary (500 by default), also used for the intermediate summaries
+    pub fn max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens.max(1);
+        self
+    }
+
+    /// Maximum number of concurrent requests to the model (8 by default)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Count the tokens of the texts with `counter` (estimated as 4 bytes of text each by default)
+    pub fn counter(mut self, counter: impl Fn(&str) -> usize + Send + Sync + 'static) -> Self {
+        self.counter = Arc::new(counter);
+        self
+    }
+
+    /// Count the tokens of the texts with `tokenizer` (e.g.: the [Tokenizer] of the model)
+    pub fn tokenizer(self, tokenizer: impl Tokenizer + 'static) -> Self {
+        self.counter(move |text| tokenizer.count_text(text))
+    }
+
+    /// Additional instructions given to the model for every summary (e.g.: "Focus on the
+    /// financial results.")
+    pub fn instructions(mut self, instructions: &str) -> Self {
+        self.instructions = Some(instructions.to_string());
+        self
+    }
+
+    /// Preamble of the requests, with the token budget and the additional instructions
+    fn preamble(&self, preamble: &str) -> String {
+        let words = (self.max_tokens * 3 / 4).max(1);
+        let mut preamble = format!(
+            "{preamble} The summary must be at most {words} words long. Answer with the summary \
+            only."
+        );
+        if let Some(instructions) = &self.instructions {
+            preamble.push(' ');
+            preamble.push_str(instructions);
+        }
+        preamble
+    }
+
+    /// Group consecutive `summaries` into texts of at most `chunk_tokens` tokens, with at least
+    /// two summaries per text so that each reduction step makes progress
+    fn group(&self, summaries: Vec<String>) -> Vec<String> {
+        let mut groups: Vec<Vec<String>> = vec![];
+
+        for summary in summaries {
+            match groups.last_mut() {
+                Some(group)
+                    if group.len() < 2
+                        || (self.counter)(&format!("{}\n\n{summary}", group.join("\n\n")))
+                            <= self.chunk_tokens =>
+                {
+                    group.push(summary)
+                }
+                _ => groups.push(vec![summary]),
+            }
+        }
+
+        groups.into_iter().map(|group| group.join("\n\n")).collect()
+    }
+}
+
+impl<M, In> Op for Summarize<M, In>
+where
+    M: CompletionModel,
+    In: Into<String> + Send + Sync,
+{
+    type Input = In;
+    type Output = Result<String, PromptError>;
+
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        let text: String = input.into();
+
+        let mut texts: Vec<String> = if (self.counter)(&text) <= self.chunk_tokens {
+            vec![]
+        } else {
+            let counter = self.counter.clone();
+            TokenSplitter::new(self.chunk_tokens, 0)
+                .counter(move |text| counter(text))
+                .chunk("document", &text)
+                .into_iter()
+                .map(|chunk| chunk.text)
+                .collect()
+        };
+
+        let mut summary = if texts.len() < 2 {
+            SummarizeStep::new(self, DOCUMENT_PREAMBLE)
+                .call(text)
+                .await?
+        } else {
+            // Map: summarize the chunks
+            texts = SummarizeStep::new(self, MAP_PREAMBLE)
+                .try_batch_call(self.concurrency, texts)
+                .await?;
+
+            // Reduce: summarize groups of summaries until a single one remains
+            let reduce = SummarizeStep::new(self, REDUCE_PREAMBLE);
+            while texts.len() > 1 {
+                let groups = self.group(texts);
+                texts = reduce.try_batch_call(self.concurrency, groups).await?;
+            }
+            texts.pop().unwrap_or_default()
+        };
+
+        for _ in 0..MAX_SHORTENING_ATTEMPTS {
+            if (self.counter)(&summary) <= self.max_tokens {
+                break;
+            }
+            summary = SummarizeStep::new(self, DOCUMENT_PREAMBLE)
+                .call(summary)
+                .await?;
+        }
+
+        Ok(summary)
+    }
+}
+
+/// Create a new map-reduce summarization operation.
+///
+/// The op will summarize the input text with `model`, splitting it into chunks summarized in
+/// parallel if it is too long for a single request.
+///
+/// # Example
+/// ```rust
+/// use rig::pipeline::{summarize::summarize, Op};
+///
+/// let summarize = summarize(openai.completion_model("gpt-4o-mini"))
+///     .chunk_tokens(8000)
+///     .max_tokens(300)
+///     .instructions("Focus on the decisions made.");
+///
+/// let summary = summarize.call(transcript).await?;
+/// ```
+pub fn summarize<M, In>(model: M) -> Summarize<M, In>
+where
+    M: CompletionModel,
+    In: Into<String> + Send + Sync,
+{
+    Summarize::new(model)
+}
+
+/// Single summarization request, with the given preamble
+struct SummarizeStep<'a, M> {
+    model: &'a M,
+    preamble: String,
+}
+
+impl<'a, M: CompletionModel> SummarizeStep<'a, M> {
+    fn new<In>(summarize: &'a Summarize<M, In>, preamble: &str) -> Self {
+        Self {
+            model: &summarize.model,
+            preamble: summarize.preamble(preamble),
+        }
+    }
+}
+
+impl<M: CompletionModel> Op for SummarizeStep<'_, M> {
+    type Input = String;
+    type Output = Result<String, PromptError>;
+
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        let response = self
+            .model
+            .completion_request(input)
+            .preamble(self.preamble.clone())
+            .temperature(0.0)
+            .send()
+            .await?;
+
+        Ok(response_text(&response.choice).trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockCompletionModel;
+
+    fn words(text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+
+    #[tokio::test]
+    async fn test_summarize_short_text() {
+        let model = MockCompletionModel::new().text(" Flurbos are green. ");
+
+        let summary = summarize(model.clone())
+            .call("Flurbos are green aliens living on cold planets.")
+            .await
+            .unwrap();
+
+        assert_eq!(summary, "Flurbos are green.");
+        assert_eq!(model.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_map_reduce() {
+        let text = (0..16)
+            .map(|i| format!("Flurbo part {i} ends."))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // 8 chunks of 8 words, summarized into 8 summaries of 2 words, reduced 4 by 4 into 2
+        // summaries, reduced into 1
+        let mut model = MockCompletionModel::new();
+        for _ in 0..8 {
+            model = model.text("Flurbo part.");
+        }
+        let model = model
+            .text("Flurbo parts.")
+            .text("Flurbo parts.")
+            .text("The flurbo story.");
+
+        let summary = summarize(model.clone())
+            .counter(words)
+            .chunk_tokens(8)
+            .max_tokens(4)
+            .concurrency(2)
+            .call(text)
+            .await
+            .unwrap();
+
+        assert_eq!(summary, "The flurbo story.");
+
+        let requests = model.requests();
+        assert_eq!(requests.len(), 11);
+        assert_eq!(
+            requests[8].prompt.rag_text().as_deref(),
+            Some("Flurbo part.\n\nFlurbo part.\n\nFlurbo part.\n\nFlurbo part.")
+        );
+        assert_eq!(
+            requests[10].prompt.rag_text().as_deref(),
+            Some("Flurbo parts.\n\nFlurbo parts.")
+        );
+    }
+}
@@ -5,6 +5,7 @@ use futures::stream;
 use futures::try_join;
 
 use super::op::{self};
+use crate::wasm_compat::{WasmCompatSend, WasmCompatSync};
 
 // ================================================================
 // Core TryOp trait
@@ -18,7 +19,7 @@ pub trait TryOp: Send + Sync {
     fn try_call(
         &self,
         input: Self::Input,
-    ) -> impl Future<Output = Result<Self::Output, Self::Error>> + Send;
+    ) -> impl Future<Output = Result<Self::Output, Self::Error>> + WasmCompatSend;
 
     /// Execute the current op with the given inputs. `n` is the number of concurrent
     /// inputs that will be processed concurrently.
@@ -40,7 +41,7 @@ pub trait TryOp: Send + Sync {
         &self,
         n: usize,
         input: I,
-    ) -> impl Future<Output = Result<Vec<Self::Output>, Self::Error>> + Send
+    ) -> impl Future<Output = Result<Vec<Self::Output>, Self::Error>> + WasmCompatSend
     where
         I: IntoIterator<Item = Self::Input> + Send,
         I::IntoIter: Send,
@@ -121,7 +122,7 @@ pub trait TryOp: Send + Sync {
     fn and_then<F, Fut, Output>(self, f: F) -> AndThen<Self, op::Then<F, Self::Output>>
     where
         F: Fn(Self::Output) -> Fut + Send + Sync,
-        Fut: Future<Output = Result<Output, Self::Error>> + Send + Sync,
+        Fut: Future<Output = Result<Output, Self::Error>> + WasmCompatSend + WasmCompatSync,
         Output: Send + Sync,
         Self: Sized,
     {
@@ -146,7 +147,7 @@ pub trait TryOp: Send + Sync {
     fn or_else<F, Fut, E>(self, f: F) -> OrElse<Self, op::Then<F, Self::Error>>
     where
         F: Fn(Self::Error) -> Fut + Send + Sync,
-        Fut: Future<Output = Result<Self::Output, E>> + Send + Sync,
+        Fut: Future<Output = Result<Self::Output, E>> + WasmCompatSend + WasmCompatSync,
         E: Send + Sync,
         Self: Sized,
     {
@@ -0,0 +1,262 @@
+// ================================================================
+// Anthropic Message Batches API
+// ================================================================
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+
+use super::completion::{CompletionModel, CompletionResponse};
+use crate::{
+    batch::{BatchCompletionModel, BatchRequest, BatchResult, BatchState, BatchStatus},
+    completion::CompletionError,
+    http_client::ApiError,
+};
+
+#[derive(Debug, Deserialize)]
+struct MessageBatch {
+    id: String,
+    /// `in_progress`, `canceling` or `ended`
+    processing_status: String,
+    request_counts: RequestCounts,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestCounts {
+    processing: usize,
+    succeeded: usize,
+    errored: usize,
+    canceled: usize,
+    expired: usize,
+}
+
+/// Line of the results of a batch
+#[derive(Debug, Deserialize)]
+struct MessageBatchOutput {
+    custom_id: String,
+    result: MessageBatchResult,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum MessageBatchResult {
+    Succeeded { message: CompletionResponse },
+    Errored { error: ErrorResponse },
+    Canceled,
+    Expired,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: ApiErrorResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorResponse {
+    message: String,
+}
+
+impl From<MessageBatchOutput> for BatchResult<CompletionResponse> {
+    fn from(output: MessageBatchOutput) -> Self {
+        let response = match output.result {
+            MessageBatchResult::Succeeded { message } => message.try_into(),
+            MessageBatchResult::Errored { error } => {
+                Err(CompletionError::ProviderError(error.error.message))
+            }
+            MessageBatchResult::Canceled => Err(CompletionError::ProviderError(
+                "The request was canceled".to_string(),
+            )),
+            MessageBatchResult::Expired => Err(CompletionError::ProviderError(
+                "The request expired".to_string(),
+            )),
+        };
+
+        BatchResult {
+            custom_id: output.custom_id,
+            response,
+        }
+    }
+}
+
+async fn parse<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, CompletionError> {
+    if response.status().is_success() {
+        Ok(response.json().await?)
+    } else {
+        Err(ApiError::from_response(response).await?.into())
+    }
+}
+
+impl BatchCompletionModel for CompletionModel {
+    async fn submit_batch(&self, requests: Vec<BatchRequest>) -> Result<String, CompletionError> {
+        let requests = requests
+            .into_iter()
+            .map(|BatchRequest { custom_id, request }| {
+                Ok(json!({
+                    "custom_id": custom_id,
+                    "params": self.create_completion_request(request)?,
+                }))
+            })
+            .collect::<Result<Vec<_>, CompletionError>>()?;
+
+        let batch: MessageBatch = parse(
+            self.client
+                .post("/v1/messages/batches")
+                .json(&json!({ "requests": requests }))
+                .idempotent(false)
+                .send()
+                .await?,
+        )
+        .await?;
+
+        Ok(batch.id)
+    }
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus, CompletionError> {
+        let batch: MessageBatch = parse(
+            self.client
+                .get(&format!("/v1/messages/batches/{batch_id}"))
+                .send()
+                .await?,
+        )
+        .await?;
+        let counts = batch.request_counts;
+
+        Ok(BatchStatus {
+            state: match batch.processing_status.as_str() {
+                "ended" => BatchState::Ended,
+                _ => BatchState::InProgress,
+            },
+            total: counts.processing
+                + counts.succeeded
+                + counts.errored
+                + counts.canceled
+                + counts.expired,
+            succeeded: counts.succeeded,
+            failed: counts.errored + counts.canceled + counts.expired,
+        })
+    }
+
+    async fn batch_results(
+        &self,
+        batch_id: &str,
+    ) -> Result<Vec<BatchResult<CompletionResponse>>, CompletionError> {
+        let response = self
+            .client
+            .get(&format!("/v1/messages/batches/{batch_id}/results"))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await?.into());
+        }
+
+        response
+            .text()
+            .await?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Ok(serde_json::from_str::<MessageBatchOutput>(line)?.into()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use futures::future::BoxFuture;
+
+    use super::*;
+    use crate::{
+        completion::CompletionModel as _,
+        http_client::{HttpClientSettings, HttpTransport, TransportError},
+        message::AssistantContent,
+        providers::anthropic::{ClientBuilder, CLAUDE_3_HAIKU},
+    };
+
+    /// Transport faking the Message Batches endpoints of the Anthropic API
+    struct FakeBatchApi;
+
+    impl HttpTransport for FakeBatchApi {
+        fn execute(
+            &self,
+            request: http::Request<Bytes>,
+        ) -> BoxFuture<'_, Result<http::Response<Bytes>, TransportError>> {
+            Box::pin(async move {
+                let batch = |status: &str| {
+                    json!({
+                        "id": "msgbatch_1",
+                        "type": "message_batch",
+                        "processing_status": status,
+                        "request_counts": {
+                            "processing": 0,
+                            "succeeded": 1,
+                            "errored": 0,
+                            "canceled": 0,
+                            "expired": 1
+                        }
+                    })
+                    .to_string()
+                };
+
+                let body = match (request.method().as_str(), request.uri().path()) {
+                    ("POST", "/v1/messages/batches") => batch("in_progress"),
+                    ("GET", "/v1/messages/batches/msgbatch_1") => batch("ended"),
+                    ("GET", "/v1/messages/batches/msgbatch_1/results") => [
+                        json!({"custom_id": "b", "result": {"type": "expired"}}),
+                        json!({
+                            "custom_id": "a",
+                            "result": {
+                                "type": "succeeded",
+                                "message": {
+                                    "id": "msg_1",
+                                    "type": "message",
+                                    "role": "assistant",
+                                    "model": CLAUDE_3_HAIKU,
+                                    "content": [{"type": "text", "text": "positive"}],
+                                    "stop_reason": "end_turn",
+                                    "stop_sequence": null,
+                                    "usage": {"input_tokens": 10, "output_tokens": 1}
+                                }
+                            }
+                        }),
+                    ]
+                    .map(|line| line.to_string())
+                    .join("\n"),
+                    (method, path) => panic!("Unexpected request {method} {path}"),
+                };
+
+                Ok(http::Response::new(Bytes::from(body)))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_batch() {
+        let model = ClientBuilder::new("fake-key")
+            .build()
+            .with_transport(FakeBatchApi)
+            .completion_model(CLAUDE_3_HAIKU);
+
+        let results = model
+            .run_batch(
+                vec![
+                    BatchRequest::new("a", model.completion_request("Great!").build()),
+                    BatchRequest::new("b", model.completion_request("Meh.").build()),
+                ],
+                Duration::from_millis(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(results[0].custom_id, "a");
+        assert_eq!(
+            results[0].response.as_ref().unwrap().choice.first(),
+            AssistantContent::text("positive")
+        );
+        assert_eq!(results[1].custom_id, "b");
+        assert!(matches!(
+            &results[1].response,
+            Err(CompletionError::ProviderError(message)) if message == "The request expired"
+        ));
+    }
+}
@@ -1,5 +1,9 @@
 //! Anthropic client api implementation
 
+use crate::http_client::{
+    HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
+use crate::key_pool::ApiKeyPlacement;
 use crate::{agent::AgentBuilder, extractor::ExtractorBuilder};
 
 use schemars::JsonSchema;
@@ -18,6 +22,7 @@ pub struct ClientBuilder<'a> {
     base_url: &'a str,
     anthropic_version: &'a str,
     anthropic_betas: Option<Vec<&'a str>>,
+    reqwest_options: ReqwestOptions,
 }
 
 /// Create a new anthropic client using the builder
@@ -39,6 +44,7 @@ impl<'a> ClientBuilder<'a> {
             base_url: ANTHROPIC_API_BASE_URL,
             anthropic_version: ANTHROPIC_VERSION_LATEST,
             anthropic_betas: None,
+            reqwest_options: ReqwestOptions::default(),
         }
     }
 
@@ -63,19 +69,29 @@ impl<'a> ClientBuilder<'a> {
     }
 
     pub fn build(self) -> Client {
-        Client::new(
+        let mut client = Client::new(
             self.api_key,
             self.base_url,
             self.anthropic_betas,
             self.anthropic_version,
-        )
+        );
+        if !self.reqwest_options.is_default() {
+            client.http_client = client.http_client.with_client(self.reqwest_options.build());
+        }
+        client
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
     }
 }
 
 #[derive(Clone)]
 pub struct Client {
     base_url: String,
-    http_client: reqwest::Client,
+    http_client: HttpClient,
 }
 
 impl Client {
@@ -89,30 +105,33 @@ impl Client {
     pub fn new(api_key: &str, base_url: &str, betas: Option<Vec<&str>>, version: &str) -> Self {
         Self {
             base_url: base_url.to_string(),
-            http_client: reqwest::Client::builder()
-                .default_headers({
-                    let mut headers = reqwest::header::HeaderMap::new();
-                    headers.insert("x-api-key", api_key.parse().expect("API key should parse"));
+            http_client: HttpClient::new(reqwest::Client::new()).with_default_headers({
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert("x-api-key", api_key.parse().expect("API key should parse"));
+                headers.insert(
+                    "anthropic-version",
+                    version.parse().expect("Anthropic version should parse"),
+                );
+                if let Some(betas) = betas {
                     headers.insert(
-                        "anthropic-version",
-                        version.parse().expect("Anthropic version should parse"),
+                        "anthropic-beta",
+                        betas
+                            .join(",")
+                            .parse()
+                            .expect("Anthropic betas should parse"),
                     );
-                    if let Some(betas) = betas {
-                        headers.insert(
-                            "anthropic-beta",
-                            betas
-                                .join(",")
-                                .parse()
-                                .expect("Anthropic betas should parse"),
-                        );
-                    }
-                    headers
-                })
-                .build()
-                .expect("Anthropic reqwest client should build"),
+                }
+                headers
+            }),
         }
     }
 
+    /// Create a new Anthropic client with the given API key and base API URL, using the latest
+    /// Anthropic version.
+    pub fn from_url(api_key: &str, base_url: &str) -> Self {
+        ClientBuilder::new(api_key).base_url(base_url).build()
+    }
+
     /// Create a new Anthropic client from the `ANTHROPIC_API_KEY` environment variable.
     /// Panics if the environment variable is not set.
     pub fn from_env() -> Self {
@@ -120,11 +139,16 @@ impl Client {
         ClientBuilder::new(&api_key).build()
     }
 
-    pub fn post(&self, path: &str) -> reqwest::RequestBuilder {
+    pub fn post(&self, path: &str) -> RequestBuilder {
         let url = format!("{}/{}", self.base_url, path).replace("//", "/");
         self.http_client.post(url)
     }
 
+    pub fn get(&self, path: &str) -> RequestBuilder {
+        let url = format!("{}/{}", self.base_url, path).replace("//", "/");
+        self.http_client.get(url)
+    }
+
     pub fn completion_model(&self, model: &str) -> CompletionModel {
         CompletionModel::new(self.clone(), model)
     }
@@ -154,3 +178,13 @@ impl Client {
         ExtractorBuilder::new(self.completion_model(model))
     }
 }
+
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+
+    fn api_key_placement(&self) -> ApiKeyPlacement {
+        ApiKeyPlacement::header("x-api-key")
+    }
+}
@@ -4,6 +4,7 @@ use std::{convert::Infallible, str::FromStr};
 
 use crate::{
     completion::{self, CompletionError},
+    http_client::ApiError,
     json_utils,
     message::{self, MessageError},
     one_or_many::string_or_one_or_many,
@@ -118,11 +119,29 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
 
         Ok(completion::CompletionResponse {
             choice,
+            usage: completion::Usage::new(
+                response.usage.input_tokens,
+                response.usage.output_tokens,
+            ),
+            timing: completion::ResponseTiming::default(),
             raw_response: response,
         })
     }
 }
 
+/// Export a message to the Anthropic messages format
+pub fn to_anthropic_json(message: &message::Message) -> Result<serde_json::Value, MessageError> {
+    let message: Message = message.clone().try_into()?;
+    serde_json::to_value(message).map_err(MessageError::JsonError)
+}
+
+/// Import a message in the Anthropic messages format
+pub fn from_anthropic_json(json: serde_json::Value) -> Result<message::Message, MessageError> {
+    serde_json::from_value::<Message>(json)
+        .map_err(MessageError::JsonError)?
+        .try_into()
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Message {
     pub role: Role,
@@ -130,6 +149,20 @@ pub struct Message {
     pub content: OneOrMany<Content>,
 }
 
+impl Message {
+    /// Trailing assistant message with the beginning of the answer, which the model continues
+    /// (the API rejects a final assistant message ending with whitespace, or empty)
+    pub(crate) fn prefill(prefill: &str) -> Option<Self> {
+        let prefill = prefill.trim_end();
+        (!prefill.is_empty()).then(|| Message {
+            role: Role::Assistant,
+            content: OneOrMany::one(Content::Text {
+                text: prefill.to_string(),
+            }),
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum Role {
@@ -175,7 +208,7 @@ impl FromStr for Content {
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ToolResultContent {
     Text { text: String },
-    Image(ImageSource),
+    Image { source: ImageSource },
 }
 
 impl FromStr for ToolResultContent {
@@ -186,18 +219,39 @@ impl FromStr for ToolResultContent {
     }
 }
 
+/// Source of an image: base64 encoded data, a URL or a file uploaded with the Files API
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
-pub struct ImageSource {
-    pub data: String,
-    pub media_type: ImageFormat,
-    pub r#type: SourceType,
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ImageSource {
+    Base64 {
+        data: String,
+        media_type: ImageFormat,
+    },
+    Url {
+        url: String,
+    },
+    File {
+        file_id: String,
+    },
 }
 
+/// Source of a document: base64 encoded data (PDF), plain text or a file uploaded with the Files
+/// API (which requires the `files-api-2025-04-14` beta, see
+/// [ClientBuilder::anthropic_beta](super::client::ClientBuilder::anthropic_beta))
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
-pub struct DocumentSource {
-    pub data: String,
-    pub media_type: DocumentFormat,
-    pub r#type: SourceType,
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DocumentSource {
+    Base64 {
+        data: String,
+        media_type: DocumentFormat,
+    },
+    Text {
+        data: String,
+        media_type: DocumentFormat,
+    },
+    File {
+        file_id: String,
+    },
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
@@ -218,12 +272,8 @@ pub enum ImageFormat {
 pub enum DocumentFormat {
     #[serde(rename = "application/pdf")]
     PDF,
-}
-
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
-#[serde(rename_all = "lowercase")]
-pub enum SourceType {
-    BASE64,
+    #[serde(rename = "text/plain")]
+    TXT,
 }
 
 impl From<String> for Content {
@@ -238,27 +288,6 @@ impl From<String> for ToolResultContent {
     }
 }
 
-impl TryFrom<message::ContentFormat> for SourceType {
-    type Error = MessageError;
-
-    fn try_from(format: message::ContentFormat) -> Result<Self, Self::Error> {
-        match format {
-            message::ContentFormat::Base64 => Ok(SourceType::BASE64),
-            message::ContentFormat::String => Err(MessageError::ConversionError(
-                "Image urls are not supported in Anthropic".to_owned(),
-            )),
-        }
-    }
-}
-
-impl From<SourceType> for message::ContentFormat {
-    fn from(source_type: SourceType) -> Self {
-        match source_type {
-            SourceType::BASE64 => message::ContentFormat::Base64,
-        }
-    }
-}
-
 impl TryFrom<message::ImageMediaType> for ImageFormat {
     type Error = MessageError;
 
@@ -288,6 +317,94 @@ impl From<ImageFormat> for message::ImageMediaType {
     }
 }
 
+impl TryFrom<message::Image> for ImageSource {
+    type Error = MessageError;
+
+    fn try_from(image: message::Image) -> Result<Self, Self::Error> {
+        if image.format == Some(message::ContentFormat::FileId) {
+            return Ok(ImageSource::File {
+                file_id: image.data,
+            });
+        }
+
+        let image = image.resolve_data_url();
+        if image.is_url() {
+            return Ok(ImageSource::Url { url: image.data });
+        }
+
+        Ok(ImageSource::Base64 {
+            data: image.data,
+            media_type: image
+                .media_type
+                .ok_or(MessageError::ConversionError(
+                    "Image media type is required".to_owned(),
+                ))?
+                .try_into()?,
+        })
+    }
+}
+
+impl From<ImageSource> for message::Image {
+    fn from(source: ImageSource) -> Self {
+        match source {
+            ImageSource::Base64 { data, media_type } => {
+                message::Image::base64(data, media_type.into())
+            }
+            ImageSource::Url { url } => message::Image::url(url),
+            ImageSource::File { file_id } => message::Image {
+                data: file_id,
+                format: Some(message::ContentFormat::FileId),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl TryFrom<message::Document> for DocumentSource {
+    type Error = MessageError;
+
+    fn try_from(document: message::Document) -> Result<Self, Self::Error> {
+        match (document.format, document.media_type) {
+            (Some(message::ContentFormat::FileId), _) => Ok(DocumentSource::File {
+                file_id: document.data,
+            }),
+            (Some(message::ContentFormat::String), _) => Ok(DocumentSource::Text {
+                data: document.data,
+                media_type: DocumentFormat::TXT,
+            }),
+            (_, Some(message::DocumentMediaType::PDF) | None) => Ok(DocumentSource::Base64 {
+                data: document.data,
+                media_type: DocumentFormat::PDF,
+            }),
+            (_, Some(media_type)) => Err(MessageError::ConversionError(format!(
+                "Unsupported base64 document media type {:?}, Anthropic only supports PDF",
+                media_type
+            ))),
+        }
+    }
+}
+
+impl From<DocumentSource> for message::Document {
+    fn from(source: DocumentSource) -> Self {
+        match source {
+            DocumentSource::Base64 { data, media_type } => {
+                message::Document::base64(data, media_type.into())
+            }
+            DocumentSource::Text { data, .. } => message::Document::text(data),
+            DocumentSource::File { file_id } => message::Document::file_id(file_id, None),
+        }
+    }
+}
+
+impl From<DocumentFormat> for message::DocumentMediaType {
+    fn from(format: DocumentFormat) -> Self {
+        match format {
+            DocumentFormat::PDF => message::DocumentMediaType::PDF,
+            DocumentFormat::TXT => message::DocumentMediaType::TXT,
+        }
+    }
+}
+
 impl From<message::AssistantContent> for Content {
     fn from(text: message::AssistantContent) -> Self {
         match text {
@@ -322,58 +439,20 @@ impl TryFrom<message::Message> for Message {
                                     Ok(ToolResultContent::Text { text })
                                 }
                                 message::ToolResultContent::Image(image) => {
-                                    let media_type =
-                                        image.media_type.ok_or(MessageError::ConversionError(
-                                            "Image media type is required".to_owned(),
-                                        ))?;
-                                    let format =
-                                        image.format.ok_or(MessageError::ConversionError(
-                                            "Image format is required".to_owned(),
-                                        ))?;
-                                    Ok(ToolResultContent::Image(ImageSource {
-                                        data: image.data,
-                                        media_type: media_type.try_into()?,
-                                        r#type: format.try_into()?,
-                                    }))
+                                    Ok(ToolResultContent::Image {
+                                        source: image.try_into()?,
+                                    })
                                 }
                             })?,
                             is_error: None,
                         })
                     }
-                    message::UserContent::Image(message::Image {
-                        data,
-                        format,
-                        media_type,
-                        ..
-                    }) => {
-                        let source = ImageSource {
-                            data,
-                            media_type: match media_type {
-                                Some(media_type) => media_type.try_into()?,
-                                None => {
-                                    return Err(MessageError::ConversionError(
-                                        "Image media type is required".to_owned(),
-                                    ))
-                                }
-                            },
-                            r#type: match format {
-                                Some(format) => format.try_into()?,
-                                None => SourceType::BASE64,
-                            },
-                        };
-                        Ok(Content::Image { source })
-                    }
-                    message::UserContent::Document(message::Document { data, format, .. }) => {
-                        let source = DocumentSource {
-                            data,
-                            media_type: DocumentFormat::PDF,
-                            r#type: match format {
-                                Some(format) => format.try_into()?,
-                                None => SourceType::BASE64,
-                            },
-                        };
-                        Ok(Content::Document { source })
-                    }
+                    message::UserContent::Image(image) => Ok(Content::Image {
+                        source: image.try_into()?,
+                    }),
+                    message::UserContent::Document(document) => Ok(Content::Document {
+                        source: document.try_into()?,
+                    }),
                     message::UserContent::Audio { .. } => Err(MessageError::ConversionError(
                         "Audio is not supported in Anthropic".to_owned(),
                     )),
@@ -384,6 +463,13 @@ impl TryFrom<message::Message> for Message {
                 content: content.map(|content| content.into()),
                 role: Role::Assistant,
             },
+
+            message::Message::System { .. } => {
+                return Err(MessageError::ConversionError(
+                    "System messages must be merged into the system prompt for Anthropic"
+                        .to_owned(),
+                ))
+            }
         })
     }
 }
@@ -411,16 +497,7 @@ impl From<ToolResultContent> for message::ToolResultContent {
     fn from(content: ToolResultContent) -> Self {
         match content {
             ToolResultContent::Text { text } => message::ToolResultContent::text(text),
-            ToolResultContent::Image(ImageSource {
-                data,
-                media_type: format,
-                r#type,
-            }) => message::ToolResultContent::image(
-                data,
-                Some(r#type.into()),
-                Some(format.into()),
-                None,
-            ),
+            ToolResultContent::Image { source } => message::ToolResultContent::Image(source.into()),
         }
     }
 }
@@ -442,17 +519,10 @@ impl TryFrom<Message> for message::Message {
                             tool_use_id,
                             content.map(|content| content.into()),
                         ),
-                        Content::Image { source } => message::UserContent::Image(message::Image {
-                            data: source.data,
-                            format: Some(message::ContentFormat::Base64),
-                            media_type: Some(source.media_type.into()),
-                            detail: None,
-                        }),
-                        Content::Document { source } => message::UserContent::document(
-                            source.data,
-                            Some(message::ContentFormat::Base64),
-                            Some(message::DocumentMediaType::PDF),
-                        ),
+                        Content::Image { source } => message::UserContent::Image(source.into()),
+                        Content::Document { source } => {
+                            message::UserContent::Document(source.into())
+                        }
                         _ => {
                             return Err(MessageError::ConversionError(
                                 "Unsupported content type for User role".to_owned(),
@@ -491,54 +561,18 @@ impl CompletionModel {
             default_max_tokens: calculate_max_tokens(model),
         }
     }
-}
-
-/// Anthropic requires a `max_tokens` parameter to be set, which is dependent on the model. If not
-/// set or if set too high, the request will fail. The following values are based on the models
-/// available at the time of writing.
-///
-/// Dev Note: This is really bad design, I'm not sure why they did it like this..
-fn calculate_max_tokens(model: &str) -> Option<u64> {
-    if model.starts_with("claude-3-5-sonnet") || model.starts_with("claude-3-5-haiku") {
-        Some(8192)
-    } else if model.starts_with("claude-3-opus")
-        || model.starts_with("claude-3-sonnet")
-        || model.starts_with("claude-3-haiku")
-    {
-        Some(4096)
-    } else {
-        None
-    }
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct Metadata {
-    user_id: Option<String>,
-}
-
-#[derive(Default, Debug, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
-pub enum ToolChoice {
-    #[default]
-    Auto,
-    Any,
-    Tool {
-        name: String,
-    },
-}
 
-impl completion::CompletionModel for CompletionModel {
-    type Response = CompletionResponse;
-
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+    pub(crate) fn create_completion_request(
         &self,
         completion_request: completion::CompletionRequest,
-    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+    ) -> Result<serde_json::Value, CompletionError> {
         // Note: Ideally we'd introduce provider-specific Request models to handle the
         // specific requirements of each provider. For now, we just manually check while
         // building the request as a raw JSON document.
 
+        // Anthropic only supports a system prompt
+        let completion_request = completion_request.merge_system_messages();
+
         // Check if max_tokens is set, required for Anthropic
         let max_tokens = if let Some(tokens) = completion_request.max_tokens {
             tokens
@@ -567,6 +601,13 @@ impl completion::CompletionModel for CompletionModel {
 
         messages.push(prompt_message);
 
+        messages.extend(
+            completion_request
+                .prefill
+                .as_deref()
+                .and_then(Message::prefill),
+        );
+
         let mut request = json!({
             "model": self.model,
             "messages": messages,
@@ -602,6 +643,99 @@ impl completion::CompletionModel for CompletionModel {
 
         tracing::debug!("Anthropic completion request: {request}");
 
+        Ok(request)
+    }
+
+    /// Count the input tokens of `completion_request` with the token counting endpoint of the
+    /// Anthropic API (the tokenizer of Claude models is not public)
+    #[cfg_attr(feature = "worker", worker::send)]
+    pub async fn count_tokens(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<u64, CompletionError> {
+        let mut request = self.create_completion_request(completion_request)?;
+        // The endpoint only accepts the input of the request
+        if let Some(request) = request.as_object_mut() {
+            for param in ["max_tokens", "temperature", "stream"] {
+                request.remove(param);
+            }
+        }
+
+        let response = self
+            .client
+            .post("/v1/messages/count_tokens")
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(response.json::<TokenCount>().await?.input_tokens)
+        } else {
+            Err(ApiError::from_response(response).await?.into())
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenCount {
+    input_tokens: u64,
+}
+
+/// Anthropic requires a `max_tokens` parameter to be set, which is dependent on the model. If not
+/// set or if set too high, the request will fail. The following values are based on the models
+/// available at the time of writing.
+///
+/// Dev Note: This is really bad design, I'm not sure why they did it like this..
+fn calculate_max_tokens(model: &str) -> Option<u64> {
+    if model.starts_with("claude-3-5-sonnet") || model.starts_with("claude-3-5-haiku") {
+        Some(8192)
+    } else if model.starts_with("claude-3-opus")
+        || model.starts_with("claude-3-sonnet")
+        || model.starts_with("claude-3-haiku")
+    {
+        Some(4096)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Metadata {
+    user_id: Option<String>,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolChoice {
+    #[default]
+    Auto,
+    Any,
+    Tool {
+        name: String,
+    },
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = CompletionResponse;
+
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client.post("/v1/messages").json(&request),
+        )
+    }
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
+        let request = self.create_completion_request(completion_request)?;
+
         let response = self
             .client
             .post("/v1/messages")
@@ -621,7 +755,7 @@ impl completion::CompletionModel for CompletionModel {
                 ApiResponse::Error(error) => Err(CompletionError::ProviderError(error.message)),
             }
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -766,10 +900,9 @@ mod tests {
                     Content::Image { source } => {
                         assert_eq!(
                             source,
-                            ImageSource {
+                            ImageSource::Base64 {
                                 data: "/9j/4AAQSkZJRg...".to_owned(),
                                 media_type: ImageFormat::JPEG,
-                                r#type: SourceType::BASE64,
                             }
                         );
                     }
@@ -950,4 +1083,253 @@ mod tests {
         assert_eq!(assistant_message, original_assistant_message);
         assert_eq!(tool_message, original_tool_message);
     }
+
+    #[test]
+    fn test_image_conversion() {
+        let user_message = message::Message::User {
+            content: OneOrMany::many(vec![
+                message::UserContent::image_url("data:image/png;base64,aGVsbG8="),
+                message::UserContent::image_url("https://example.com/flurbo.png"),
+            ])
+            .unwrap(),
+        };
+
+        let converted_user_message: Message = user_message.try_into().unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&converted_user_message).unwrap(),
+            json!({
+                "role": "user",
+                "content": [
+                    {
+                        "type": "image",
+                        "source": {
+                            "type": "base64",
+                            "data": "aGVsbG8=",
+                            "media_type": "image/png"
+                        }
+                    },
+                    {
+                        "type": "image",
+                        "source": {
+                            "type": "url",
+                            "url": "https://example.com/flurbo.png"
+                        }
+                    }
+                ]
+            })
+        );
+
+        let original_user_message: message::Message = converted_user_message.try_into().unwrap();
+
+        assert_eq!(
+            original_user_message,
+            message::Message::User {
+                content: OneOrMany::many(vec![
+                    message::UserContent::image_base64("aGVsbG8=", message::ImageMediaType::PNG),
+                    message::UserContent::image_url("https://example.com/flurbo.png"),
+                ])
+                .unwrap(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_document_conversion() {
+        let user_message = message::Message::User {
+            content: OneOrMany::many(vec![
+                message::UserContent::Document(message::Document::from_bytes(
+                    "%PDF",
+                    message::DocumentMediaType::PDF,
+                )),
+                message::UserContent::Document(message::Document::text("The grass is green.")),
+                message::UserContent::Document(message::Document::file_id(
+                    "file_011CNha8iCJcU1wXNR6q4V8w",
+                    None,
+                )),
+            ])
+            .unwrap(),
+        };
+
+        let converted_user_message: Message = user_message.clone().try_into().unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&converted_user_message).unwrap(),
+            json!({
+                "role": "user",
+                "content": [
+                    {
+                        "type": "document",
+                        "source": {
+                            "type": "base64",
+                            "data": "JVBERg==",
+                            "media_type": "application/pdf"
+                        }
+                    },
+                    {
+                        "type": "document",
+                        "source": {
+                            "type": "text",
+                            "data": "The grass is green.",
+                            "media_type": "text/plain"
+                        }
+                    },
+                    {
+                        "type": "document",
+                        "source": {
+                            "type": "file",
+                            "file_id": "file_011CNha8iCJcU1wXNR6q4V8w"
+                        }
+                    }
+                ]
+            })
+        );
+
+        let original_user_message: message::Message = converted_user_message.try_into().unwrap();
+        assert_eq!(original_user_message, user_message);
+
+        let html = message::Message::User {
+            content: OneOrMany::one(message::UserContent::Document(message::Document::base64(
+                "PGh0bWw+",
+                message::DocumentMediaType::HTML,
+            ))),
+        };
+        assert!(Message::try_from(html).is_err());
+    }
+
+    #[test]
+    fn test_anthropic_json() {
+        let history = vec![
+            message::Message::user("What is the weather in Paris?"),
+            message::Message::Assistant {
+                content: OneOrMany::one(message::AssistantContent::tool_call(
+                    "toolu_1",
+                    "get_weather",
+                    json!({"city": "Paris"}),
+                )),
+            },
+            message::Message::User {
+                content: OneOrMany::one(message::UserContent::tool_result(
+                    "toolu_1",
+                    OneOrMany::one(message::ToolResultContent::text("15 degrees")),
+                )),
+            },
+        ];
+
+        let json = history
+            .iter()
+            .map(to_anthropic_json)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            json,
+            vec![
+                json!({
+                    "role": "user",
+                    "content": [{"type": "text", "text": "What is the weather in Paris?"}]
+                }),
+                json!({
+                    "role": "assistant",
+                    "content": [{
+                        "type": "tool_use",
+                        "id": "toolu_1",
+                        "name": "get_weather",
+                        "input": {"city": "Paris"}
+                    }]
+                }),
+                json!({
+                    "role": "user",
+                    "content": [{
+                        "type": "tool_result",
+                        "tool_use_id": "toolu_1",
+                        "content": [{"type": "text", "text": "15 degrees"}]
+                    }]
+                }),
+            ]
+        );
+
+        let imported = json
+            .into_iter()
+            .map(from_anthropic_json)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(imported, history);
+
+        let imported = from_anthropic_json(json!({
+            "role": "user",
+            "content": "What is the weather in Paris?"
+        }))
+        .unwrap();
+        assert_eq!(imported, history[0]);
+    }
+
+    #[test]
+    fn test_prefill() {
+        use completion::CompletionModel as _;
+
+        let model = Client::new("TEST", "https://api.anthropic.com", None, "2023-06-01")
+            .completion_model(CLAUDE_3_5_SONNET);
+
+        let request = model
+            .completion_request("Give me the weather in Paris as JSON")
+            .prefill("{\n")
+            .dry_run()
+            .unwrap();
+
+        assert_eq!(
+            request.body["messages"],
+            json!([
+                {
+                    "role": "user",
+                    "content": [{"type": "text", "text": "Give me the weather in Paris as JSON"}]
+                },
+                {
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "{"}]
+                }
+            ])
+        );
+
+        // A blank prefill is not sent
+        let request = model
+            .completion_request("Hello")
+            .prefill(" ")
+            .dry_run()
+            .unwrap();
+        assert_eq!(request.body["messages"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_system_messages() {
+        use completion::CompletionModel as _;
+
+        let model = Client::new("TEST", "https://api.anthropic.com", None, "2023-06-01")
+            .completion_model(CLAUDE_3_5_SONNET);
+
+        let request = model
+            .completion_request("Quelle heure est-il ?")
+            .preamble("You are a helpful assistant.".to_string())
+            .messages(vec![
+                message::Message::user("Hello"),
+                message::Message::assistant("Hi!"),
+                message::Message::system("Now answer in French."),
+            ])
+            .dry_run()
+            .unwrap();
+
+        assert_eq!(
+            request.body["system"],
+            "You are a helpful assistant.\n\nNow answer in French."
+        );
+        assert_eq!(
+            request.body["messages"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|message| message["role"].as_str().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["user", "assistant", "user"]
+        );
+    }
 }
@@ -5,7 +5,8 @@ use serde_json::json;
 
 use super::completion::{CompletionModel, Content, Message, ToolChoice, ToolDefinition, Usage};
 use super::decoders::sse::from_response as sse_from_response;
-use crate::completion::{CompletionError, CompletionRequest};
+use crate::completion::{self, CompletionError, CompletionRequest};
+use crate::http_client::ApiError;
 use crate::json_utils::merge_inplace;
 use crate::message::MessageError;
 use crate::streaming::{StreamingChoice, StreamingCompletionModel, StreamingResult};
@@ -80,6 +81,10 @@ impl StreamingCompletionModel for CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<StreamingResult, CompletionError> {
+        crate::telemetry::record_model(&self.model);
+        // Anthropic only supports a system prompt
+        let completion_request = completion_request.merge_system_messages();
+
         let max_tokens = if let Some(tokens) = completion_request.max_tokens {
             tokens
         } else if let Some(tokens) = self.default_max_tokens {
@@ -107,6 +112,13 @@ impl StreamingCompletionModel for CompletionModel {
 
         messages.push(prompt_message);
 
+        messages.extend(
+            completion_request
+                .prefill
+                .as_deref()
+                .and_then(Message::prefill),
+        );
+
         let mut request = json!({
             "model": self.model,
             "messages": messages,
@@ -149,7 +161,7 @@ impl StreamingCompletionModel for CompletionModel {
             .await?;
 
         if !response.status().is_success() {
-            return Err(CompletionError::ProviderError(response.text().await?));
+            return Err(ApiError::from_response(response).await?.into());
         }
 
         // Use our SSE decoder to directly handle Server-Sent Events format
@@ -157,6 +169,7 @@ impl StreamingCompletionModel for CompletionModel {
 
         Ok(Box::pin(stream! {
             let mut current_tool_call: Option<ToolCallState> = None;
+            let mut input_tokens = 0;
             let mut sse_stream = Box::pin(sse_stream);
 
             while let Some(sse_result) = sse_stream.next().await {
@@ -165,7 +178,7 @@ impl StreamingCompletionModel for CompletionModel {
                         // Parse the SSE data as a StreamingEvent
                         match serde_json::from_str::<StreamingEvent>(&sse.data) {
                             Ok(event) => {
-                                if let Some(result) = handle_event(&event, &mut current_tool_call) {
+                                if let Some(result) = handle_event(&event, &mut current_tool_call, &mut input_tokens) {
                                     yield result;
                                 }
                             },
@@ -191,8 +204,23 @@ impl StreamingCompletionModel for CompletionModel {
 fn handle_event(
     event: &StreamingEvent,
     current_tool_call: &mut Option<ToolCallState>,
+    input_tokens: &mut u64,
 ) -> Option<Result<StreamingChoice, CompletionError>> {
     match event {
+        // The input tokens are reported at the start of the message, the (cumulative) output
+        // tokens at its end
+        StreamingEvent::MessageStart { message } => {
+            *input_tokens = message.usage.input_tokens;
+            None
+        }
+        StreamingEvent::MessageDelta { usage, .. } => {
+            Some(Ok(StreamingChoice::Usage(completion::Usage::new(
+                usage
+                    .input_tokens
+                    .map_or(*input_tokens, |tokens| tokens as u64),
+                usage.output_tokens as u64,
+            ))))
+        }
         StreamingEvent::ContentBlockDelta { delta, .. } => match delta {
             ContentDelta::TextDelta { text } => {
                 if current_tool_call.is_none() {
@@ -239,10 +267,6 @@ fn handle_event(
             }
         }
         // Ignore other event types or handle as needed
-        StreamingEvent::MessageStart { .. }
-        | StreamingEvent::MessageDelta { .. }
-        | StreamingEvent::MessageStop
-        | StreamingEvent::Ping
-        | StreamingEvent::Unknown => None,
+        StreamingEvent::MessageStop | StreamingEvent::Ping | StreamingEvent::Unknown => None,
     }
 }
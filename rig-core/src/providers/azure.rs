@@ -10,6 +10,11 @@
 //! ```
 
 use super::openai::{send_compatible_streaming_request, TranscriptionResponse};
+use crate::credentials::{CredentialProvider, Credentials};
+use crate::http_client::{
+    ApiError, HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
+use crate::key_pool::ApiKeyPlacement;
 
 use crate::json_utils::merge;
 use crate::streaming::{StreamingCompletionModel, StreamingResult};
@@ -31,11 +36,71 @@ use serde_json::json;
 // Main Azure OpenAI Client
 // ================================================================
 
+/// Create a new Azure OpenAI client using the builder (see [Client::new] for the arguments)
+#[derive(Clone)]
+pub struct ClientBuilder<'a> {
+    auth: AzureOpenAIAuth,
+    api_version: &'a str,
+    azure_endpoint: &'a str,
+    reqwest_options: ReqwestOptions,
+}
+
+impl<'a> ClientBuilder<'a> {
+    pub fn new(
+        auth: impl Into<AzureOpenAIAuth>,
+        api_version: &'a str,
+        azure_endpoint: &'a str,
+    ) -> Self {
+        Self {
+            auth: auth.into(),
+            api_version,
+            azure_endpoint,
+            reqwest_options: ReqwestOptions::default(),
+        }
+    }
+
+    pub fn build(self) -> Client {
+        let mut headers = reqwest::header::HeaderMap::new();
+        let api_key_placement = match self.auth {
+            AzureOpenAIAuth::ApiKey(api_key) => {
+                headers.insert("api-key", api_key.parse().expect("API key should parse"));
+                ApiKeyPlacement::header("api-key")
+            }
+            AzureOpenAIAuth::Token(token) => {
+                headers.insert(
+                    "Authorization",
+                    format!("Bearer {}", token)
+                        .parse()
+                        .expect("Token should parse"),
+                );
+                ApiKeyPlacement::bearer()
+            }
+        };
+
+        Client {
+            api_version: self.api_version.to_string(),
+            api_key_placement,
+            azure_endpoint: self.azure_endpoint.to_string(),
+            http_client: HttpClient::new(self.reqwest_options.build())
+                .with_default_headers(headers),
+        }
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     api_version: String,
     azure_endpoint: String,
-    http_client: reqwest::Client,
+    /// How the keys of an [ApiKeyPool] are sent (`api-key` header for API keys, `Authorization`
+    /// header for tokens)
+    api_key_placement: ApiKeyPlacement,
+    http_client: HttpClient,
 }
 
 #[derive(Clone)]
@@ -59,29 +124,7 @@ impl Client {
     /// * `api_version` - API version to use (e.g., "2024-10-21" for GA, "2024-10-01-preview" for preview)
     /// * `azure_endpoint` - Azure OpenAI endpoint URL, for example: https://{your-resource-name}.openai.azure.com
     pub fn new(auth: impl Into<AzureOpenAIAuth>, api_version: &str, azure_endpoint: &str) -> Self {
-        let mut headers = reqwest::header::HeaderMap::new();
-        match auth.into() {
-            AzureOpenAIAuth::ApiKey(api_key) => {
-                headers.insert("api-key", api_key.parse().expect("API key should parse"));
-            }
-            AzureOpenAIAuth::Token(token) => {
-                headers.insert(
-                    "Authorization",
-                    format!("Bearer {}", token)
-                        .parse()
-                        .expect("Token should parse"),
-                );
-            }
-        }
-
-        Self {
-            api_version: api_version.to_string(),
-            azure_endpoint: azure_endpoint.to_string(),
-            http_client: reqwest::Client::builder()
-                .default_headers(headers)
-                .build()
-                .expect("Azure OpenAI reqwest client should build"),
-        }
+        ClientBuilder::new(auth, api_version, azure_endpoint).build()
     }
 
     /// Creates a new Azure OpenAI client from an API key.
@@ -130,7 +173,7 @@ impl Client {
         Self::new(auth, &api_version, &azure_endpoint)
     }
 
-    fn post_embedding(&self, deployment_id: &str) -> reqwest::RequestBuilder {
+    fn post_embedding(&self, deployment_id: &str) -> RequestBuilder {
         let url = format!(
             "{}/openai/deployments/{}/embeddings?api-version={}",
             self.azure_endpoint, deployment_id, self.api_version
@@ -139,7 +182,7 @@ impl Client {
         self.http_client.post(url)
     }
 
-    fn post_chat_completion(&self, deployment_id: &str) -> reqwest::RequestBuilder {
+    fn post_chat_completion(&self, deployment_id: &str) -> RequestBuilder {
         let url = format!(
             "{}/openai/deployments/{}/chat/completions?api-version={}",
             self.azure_endpoint, deployment_id, self.api_version
@@ -148,7 +191,7 @@ impl Client {
         self.http_client.post(url)
     }
 
-    fn post_transcription(&self, deployment_id: &str) -> reqwest::RequestBuilder {
+    fn post_transcription(&self, deployment_id: &str) -> RequestBuilder {
         let url = format!(
             "{}/openai/deployments/{}/audio/translations?api-version={}",
             self.azure_endpoint, deployment_id, self.api_version
@@ -158,7 +201,7 @@ impl Client {
     }
 
     #[cfg(feature = "image")]
-    fn post_image_generation(&self, deployment_id: &str) -> reqwest::RequestBuilder {
+    fn post_image_generation(&self, deployment_id: &str) -> RequestBuilder {
         let url = format!(
             "{}/openai/deployments/{}/images/generations?api-version={}",
             self.azure_endpoint, deployment_id, self.api_version
@@ -168,7 +211,7 @@ impl Client {
     }
 
     #[cfg(feature = "audio")]
-    fn post_audio_generation(&self, deployment_id: &str) -> reqwest::RequestBuilder {
+    fn post_audio_generation(&self, deployment_id: &str) -> RequestBuilder {
         let url = format!(
             "{}/openai/deployments/{}/audio/speech?api-version={}",
             self.azure_endpoint, deployment_id, self.api_version
@@ -291,6 +334,27 @@ impl Client {
     }
 }
 
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+
+    /// The keys are sent like the credential the client was created with: in the `api-key`
+    /// header for API keys, or as bearer tokens for tokens
+    fn api_key_placement(&self) -> ApiKeyPlacement {
+        self.api_key_placement.clone()
+    }
+
+    /// The credentials are sent as bearer tokens, and the `api-key` header is no longer sent
+    fn with_credentials(mut self, provider: impl CredentialProvider + 'static) -> Self {
+        self.http_client = self
+            .http_client
+            .without_default_header("api-key")
+            .with_credentials(Credentials::new(provider), ApiKeyPlacement::bearer());
+        self
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ApiErrorResponse {
     message: String,
@@ -378,6 +442,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
         &self,
         documents: impl IntoIterator<Item = String>,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        crate::telemetry::record_model(&self.model);
         let documents = documents.into_iter().collect::<Vec<_>>();
 
         let response = self
@@ -416,7 +481,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
                 ApiResponse::Err(err) => Err(EmbeddingError::ProviderError(err.message)),
             }
         } else {
-            Err(EmbeddingError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -480,6 +545,8 @@ impl CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<serde_json::Value, CompletionError> {
+        completion_request.reject_prefill("Azure OpenAI")?;
+
         // Add preamble to chat history (if available)
         let mut full_history: Vec<openai::Message> = match &completion_request.preamble {
             Some(preamble) => vec![openai::Message::system(preamble)],
@@ -532,11 +599,22 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = openai::CompletionResponse;
 
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client.post_chat_completion(&self.model).json(&request),
+        )
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<openai::CompletionResponse>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let request = self.create_completion_request(completion_request)?;
 
         let response = self
@@ -561,7 +639,7 @@ impl completion::CompletionModel for CompletionModel {
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.message)),
             }
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -573,7 +651,10 @@ impl StreamingCompletionModel for CompletionModel {
     async fn stream(&self, request: CompletionRequest) -> Result<StreamingResult, CompletionError> {
         let mut request = self.create_completion_request(request)?;
 
-        request = merge(request, json!({"stream": true}));
+        request = merge(
+            request,
+            json!({"stream": true, "stream_options": {"include_usage": true}}),
+        );
 
         let builder = self
             .client
@@ -819,6 +900,7 @@ mod azure_tests {
                 temperature: Some(0.0),
                 tools: vec![],
                 additional_params: None,
+                prefill: None,
             })
             .await
             .unwrap();
@@ -2,6 +2,9 @@ use crate::{
     agent::AgentBuilder, embeddings::EmbeddingsBuilder, extractor::ExtractorBuilder, Embed,
 };
 
+use crate::http_client::{
+    HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -24,34 +27,64 @@ pub enum ApiResponse<T> {
 // ================================================================
 const COHERE_API_BASE_URL: &str = "https://api.cohere.ai";
 
+/// Create a new Cohere client using the builder
+#[derive(Clone)]
+pub struct ClientBuilder<'a> {
+    api_key: &'a str,
+    base_url: &'a str,
+    reqwest_options: ReqwestOptions,
+}
+
+impl<'a> ClientBuilder<'a> {
+    pub fn new(api_key: &'a str) -> Self {
+        Self {
+            api_key,
+            base_url: COHERE_API_BASE_URL,
+            reqwest_options: ReqwestOptions::default(),
+        }
+    }
+
+    pub fn base_url(mut self, base_url: &'a str) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn build(self) -> Client {
+        Client {
+            base_url: self.base_url.to_string(),
+            http_client: HttpClient::new(self.reqwest_options.build()).with_default_headers({
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert(
+                    "Authorization",
+                    format!("Bearer {}", self.api_key)
+                        .parse()
+                        .expect("Bearer token should parse"),
+                );
+                headers
+            }),
+        }
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     base_url: String,
-    http_client: reqwest::Client,
+    http_client: HttpClient,
 }
 
 impl Client {
     pub fn new(api_key: &str) -> Self {
-        Self::from_url(api_key, COHERE_API_BASE_URL)
+        ClientBuilder::new(api_key).build()
     }
 
     pub fn from_url(api_key: &str, base_url: &str) -> Self {
-        Self {
-            base_url: base_url.to_string(),
-            http_client: reqwest::Client::builder()
-                .default_headers({
-                    let mut headers = reqwest::header::HeaderMap::new();
-                    headers.insert(
-                        "Authorization",
-                        format!("Bearer {}", api_key)
-                            .parse()
-                            .expect("Bearer token should parse"),
-                    );
-                    headers
-                })
-                .build()
-                .expect("Cohere reqwest client should build"),
-        }
+        ClientBuilder::new(api_key).base_url(base_url).build()
     }
 
     /// Create a new Cohere client from the `COHERE_API_KEY` environment variable.
@@ -61,7 +94,7 @@ impl Client {
         Self::new(&api_key)
     }
 
-    pub fn post(&self, path: &str) -> reqwest::RequestBuilder {
+    pub fn post(&self, path: &str) -> RequestBuilder {
         let url = format!("{}/{}", self.base_url, path).replace("//", "/");
         self.http_client.post(url)
     }
@@ -114,3 +147,9 @@ impl Client {
         ExtractorBuilder::new(self.completion_model(model))
     }
 }
+
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use crate::{
     completion::{self, CompletionError},
+    http_client::ApiError,
     json_utils, message, OneOrMany,
 };
 
@@ -103,8 +104,22 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
             })?
         };
 
+        let usage = response
+            .usage
+            .as_ref()
+            .and_then(|usage| usage.tokens.as_ref())
+            .map(|tokens| {
+                completion::Usage::new(
+                    tokens.input_tokens.unwrap_or_default() as u64,
+                    tokens.output_tokens.unwrap_or_default() as u64,
+                )
+            })
+            .unwrap_or_default();
+
         Ok(completion::CompletionResponse {
             choice: OneOrMany::many(model_response).expect("There is atleast one content"),
+            usage,
+            timing: completion::ResponseTiming::default(),
             raw_response: response,
         })
     }
@@ -334,6 +349,7 @@ impl TryFrom<message::Message> for Vec<Message> {
                     tool_plan: None,
                 }]
             }
+            message::Message::System { content } => vec![Message::System { content }],
         })
     }
 }
@@ -412,7 +428,7 @@ impl TryFrom<Message> for message::Message {
                     )),
                 })
             }
-            Message::System { content } => Ok(message::Message::user(content)),
+            Message::System { content } => Ok(message::Message::system(content)),
         }
     }
 }
@@ -430,16 +446,13 @@ impl CompletionModel {
             model: model.to_string(),
         }
     }
-}
 
-impl completion::CompletionModel for CompletionModel {
-    type Response = CompletionResponse;
-
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+    pub(crate) fn create_completion_request(
         &self,
         completion_request: completion::CompletionRequest,
-    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+    ) -> Result<serde_json::Value, CompletionError> {
+        completion_request.reject_prefill("Cohere")?;
+
         let prompt = completion_request.prompt_with_context();
 
         let mut messages: Vec<message::Message> =
@@ -473,18 +486,38 @@ impl completion::CompletionModel for CompletionModel {
             serde_json::to_string_pretty(&request)?
         );
 
-        let response = self
-            .client
-            .post("/v2/chat")
-            .json(
-                &if let Some(ref params) = completion_request.additional_params {
-                    json_utils::merge(request.clone(), params.clone())
-                } else {
-                    request.clone()
-                },
-            )
-            .send()
-            .await?;
+        Ok(
+            if let Some(ref params) = completion_request.additional_params {
+                json_utils::merge(request, params.clone())
+            } else {
+                request
+            },
+        )
+    }
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = CompletionResponse;
+
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client.post("/v2/chat").json(&request),
+        )
+    }
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
+        let request = self.create_completion_request(completion_request)?;
+
+        let response = self.client.post("/v2/chat").json(&request).send().await?;
 
         if response.status().is_success() {
             let text_response = response.text().await?;
@@ -495,7 +528,7 @@ impl completion::CompletionModel for CompletionModel {
                 json_response.try_into()?;
             Ok(completion)
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -1,6 +1,7 @@
 use super::{client::ApiResponse, Client};
 
 use crate::embeddings::{self, EmbeddingError};
+use crate::http_client::ApiError;
 
 use serde::Deserialize;
 use serde_json::json;
@@ -75,6 +76,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
         &self,
         documents: impl IntoIterator<Item = String>,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        crate::telemetry::record_model(&self.model);
         let documents = documents.into_iter().collect::<Vec<_>>();
 
         let response = self
@@ -125,7 +127,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
                 ApiResponse::Err(error) => Err(EmbeddingError::ProviderError(error.message)),
             }
         } else {
-            Err(EmbeddingError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
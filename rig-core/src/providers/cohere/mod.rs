@@ -13,8 +13,8 @@ pub mod client;
 pub mod completion;
 pub mod embeddings;
 
-pub use client::Client;
 pub use client::{ApiErrorResponse, ApiResponse};
+pub use client::{Client, ClientBuilder};
 pub use completion::CompletionModel;
 pub use embeddings::EmbeddingModel;
 
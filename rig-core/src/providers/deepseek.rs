@@ -9,6 +9,9 @@
 //! let deepseek_chat = client.completion_model(deepseek::DEEPSEEK_CHAT);
 //! ```
 
+use crate::http_client::{
+    ApiError, HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
 use crate::json_utils::merge;
 use crate::providers::openai::send_compatible_streaming_request;
 use crate::streaming::{StreamingCompletionModel, StreamingResult};
@@ -17,7 +20,6 @@ use crate::{
     extractor::ExtractorBuilder,
     json_utils, message, OneOrMany,
 };
-use reqwest::Client as HttpClient;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -27,6 +29,51 @@ use serde_json::json;
 // ================================================================
 const DEEPSEEK_API_BASE_URL: &str = "https://api.deepseek.com";
 
+/// Create a new DeepSeek client using the builder
+#[derive(Clone)]
+pub struct ClientBuilder<'a> {
+    api_key: &'a str,
+    base_url: &'a str,
+    reqwest_options: ReqwestOptions,
+}
+
+impl<'a> ClientBuilder<'a> {
+    pub fn new(api_key: &'a str) -> Self {
+        Self {
+            api_key,
+            base_url: DEEPSEEK_API_BASE_URL,
+            reqwest_options: ReqwestOptions::default(),
+        }
+    }
+
+    pub fn base_url(mut self, base_url: &'a str) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn build(self) -> Client {
+        Client {
+            base_url: self.base_url.to_string(),
+            http_client: HttpClient::new(self.reqwest_options.build()).with_default_headers({
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert(
+                    "Authorization",
+                    format!("Bearer {}", self.api_key)
+                        .parse()
+                        .expect("Bearer token should parse"),
+                );
+                headers
+            }),
+        }
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     pub base_url: String,
@@ -36,7 +83,7 @@ pub struct Client {
 impl Client {
     // Create a new DeepSeek client from an API key.
     pub fn new(api_key: &str) -> Self {
-        Self::from_url(api_key, DEEPSEEK_API_BASE_URL)
+        ClientBuilder::new(api_key).build()
     }
 
     // If you prefer the environment variable approach:
@@ -47,26 +94,10 @@ impl Client {
 
     // Handy for advanced usage, e.g. letting user override base_url or set timeouts:
     pub fn from_url(api_key: &str, base_url: &str) -> Self {
-        // Possibly configure a custom HTTP client here if needed.
-        Self {
-            base_url: base_url.to_string(),
-            http_client: reqwest::Client::builder()
-                .default_headers({
-                    let mut headers = reqwest::header::HeaderMap::new();
-                    headers.insert(
-                        "Authorization",
-                        format!("Bearer {}", api_key)
-                            .parse()
-                            .expect("Bearer token should parse"),
-                    );
-                    headers
-                })
-                .build()
-                .expect("DeepSeek reqwest client should build"),
-        }
+        ClientBuilder::new(api_key).base_url(base_url).build()
     }
 
-    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+    fn post(&self, path: &str) -> RequestBuilder {
         let url = format!("{}/{}", self.base_url, path).replace("//", "/");
         self.http_client.post(url)
     }
@@ -93,6 +124,12 @@ impl Client {
     }
 }
 
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ApiErrorResponse {
     message: String,
@@ -116,7 +153,24 @@ impl From<ApiErrorResponse> for CompletionError {
 pub struct CompletionResponse {
     // We'll match the JSON:
     pub choices: Vec<Choice>,
-    // you may want usage or other fields
+    pub usage: Option<Usage>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl From<&Usage> for completion::Usage {
+    fn from(usage: &Usage) -> Self {
+        completion::Usage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -274,6 +328,7 @@ impl TryFrom<message::Message> for Vec<Message> {
 
                 Ok(messages)
             }
+            message::Message::System { content } => Ok(vec![Message::system(&content)]),
         }
     }
 }
@@ -362,6 +417,8 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
 
         Ok(completion::CompletionResponse {
             choice,
+            usage: response.usage.as_ref().map(Into::into).unwrap_or_default(),
+            timing: completion::ResponseTiming::default(),
             raw_response: response,
         })
     }
@@ -379,6 +436,8 @@ impl DeepSeekCompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<serde_json::Value, CompletionError> {
+        completion_request.reject_prefill("DeepSeek")?;
+
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => vec![Message::system(preamble)],
@@ -431,6 +490,16 @@ impl DeepSeekCompletionModel {
 impl CompletionModel for DeepSeekCompletionModel {
     type Response = CompletionResponse;
 
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client.post("/chat/completions").json(&request),
+        )
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
@@ -439,6 +508,7 @@ impl CompletionModel for DeepSeekCompletionModel {
         completion::CompletionResponse<CompletionResponse>,
         crate::completion::CompletionError,
     > {
+        crate::telemetry::record_model(&self.model);
         let request = self.create_completion_request(completion_request)?;
 
         let response = self
@@ -457,7 +527,7 @@ impl CompletionModel for DeepSeekCompletionModel {
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.message)),
             }
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -467,6 +537,7 @@ impl StreamingCompletionModel for DeepSeekCompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<StreamingResult, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let mut request = self.create_completion_request(completion_request)?;
 
         request = merge(request, json!({"stream": true}));
@@ -566,13 +637,19 @@ mod tests {
         let result: Result<CompletionResponse, _> = serde_path_to_error::deserialize(jd);
 
         match result {
-            Ok(response) => match &response.choices.first().unwrap().message {
-                Message::Assistant { content, .. } => assert_eq!(
-                    content,
-                    "Why don’t skeletons fight each other?  \nBecause they don’t have the guts! 😄"
-                ),
-                _ => panic!("Expected assistant message"),
-            },
+            Ok(response) => {
+                match &response.choices.first().unwrap().message {
+                    Message::Assistant { content, .. } => assert_eq!(
+                        content,
+                        "Why don’t skeletons fight each other?  \nBecause they don’t have the guts! 😄"
+                    ),
+                    _ => panic!("Expected assistant message"),
+                }
+                assert_eq!(
+                    completion::Usage::from(response.usage.as_ref().unwrap()),
+                    completion::Usage::new(13, 32)
+                );
+            }
             Err(err) => {
                 panic!("Deserialization error at {}: {}", err.path(), err);
             }
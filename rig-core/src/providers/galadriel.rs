@@ -11,6 +11,9 @@
 //! let gpt4o = client.completion_model(galadriel::GPT_4O);
 //! ```
 use super::openai;
+use crate::http_client::{
+    ApiError, HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
 use crate::json_utils::merge;
 use crate::providers::openai::send_compatible_streaming_request;
 use crate::streaming::{StreamingCompletionModel, StreamingResult};
@@ -29,10 +32,70 @@ use serde_json::{json, Value};
 // ================================================================
 const GALADRIEL_API_BASE_URL: &str = "https://api.galadriel.com/v1/verified";
 
+/// Create a new Galadriel client using the builder
+#[derive(Clone)]
+pub struct ClientBuilder<'a> {
+    api_key: &'a str,
+    base_url: &'a str,
+    fine_tune_api_key: Option<&'a str>,
+    reqwest_options: ReqwestOptions,
+}
+
+impl<'a> ClientBuilder<'a> {
+    pub fn new(api_key: &'a str) -> Self {
+        Self {
+            api_key,
+            base_url: GALADRIEL_API_BASE_URL,
+            fine_tune_api_key: None,
+            reqwest_options: ReqwestOptions::default(),
+        }
+    }
+
+    pub fn base_url(mut self, base_url: &'a str) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn fine_tune_api_key(mut self, fine_tune_api_key: &'a str) -> Self {
+        self.fine_tune_api_key = Some(fine_tune_api_key);
+        self
+    }
+
+    pub fn build(self) -> Client {
+        Client {
+            base_url: self.base_url.to_string(),
+            http_client: HttpClient::new(self.reqwest_options.build()).with_default_headers({
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert(
+                    "Authorization",
+                    format!("Bearer {}", self.api_key)
+                        .parse()
+                        .expect("Bearer token should parse"),
+                );
+                if let Some(key) = self.fine_tune_api_key {
+                    headers.insert(
+                        "Fine-Tune-Authorization",
+                        format!("Bearer {}", key)
+                            .parse()
+                            .expect("Bearer token should parse"),
+                    );
+                }
+                headers
+            }),
+        }
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     base_url: String,
-    http_client: reqwest::Client,
+    http_client: HttpClient,
 }
 
 impl Client {
@@ -51,30 +114,12 @@ impl Client {
         base_url: &str,
         fine_tune_api_key: Option<&str>,
     ) -> Self {
-        Self {
-            base_url: base_url.to_string(),
-            http_client: reqwest::Client::builder()
-                .default_headers({
-                    let mut headers = reqwest::header::HeaderMap::new();
-                    headers.insert(
-                        "Authorization",
-                        format!("Bearer {}", api_key)
-                            .parse()
-                            .expect("Bearer token should parse"),
-                    );
-                    if let Some(key) = fine_tune_api_key {
-                        headers.insert(
-                            "Fine-Tune-Authorization",
-                            format!("Bearer {}", key)
-                                .parse()
-                                .expect("Bearer token should parse"),
-                        );
-                    }
-                    headers
-                })
-                .build()
-                .expect("Galadriel reqwest client should build"),
+        let builder = ClientBuilder::new(api_key).base_url(base_url);
+        match fine_tune_api_key {
+            Some(fine_tune_api_key) => builder.fine_tune_api_key(fine_tune_api_key),
+            None => builder,
         }
+        .build()
     }
 
     /// Create a new Galadriel client from the `GALADRIEL_API_KEY` environment variable,
@@ -85,7 +130,7 @@ impl Client {
         let fine_tune_api_key = std::env::var("GALADRIEL_FINE_TUNE_API_KEY").ok();
         Self::new(&api_key, fine_tune_api_key.as_deref())
     }
-    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+    fn post(&self, path: &str) -> RequestBuilder {
         let url = format!("{}/{}", self.base_url, path).replace("//", "/");
         self.http_client.post(url)
     }
@@ -132,6 +177,12 @@ impl Client {
     }
 }
 
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ApiErrorResponse {
     message: String,
@@ -253,6 +304,16 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
 
         Ok(completion::CompletionResponse {
             choice,
+            usage: response
+                .usage
+                .as_ref()
+                .map(|usage| completion::Usage {
+                    input_tokens: usage.prompt_tokens as u64,
+                    output_tokens: usage.total_tokens.saturating_sub(usage.prompt_tokens) as u64,
+                    total_tokens: usage.total_tokens as u64,
+                })
+                .unwrap_or_default(),
+            timing: completion::ResponseTiming::default(),
             raw_response: response,
         })
     }
@@ -311,6 +372,11 @@ impl TryFrom<Message> for message::Message {
                     message::MessageError::ConversionError("Empty assistant message".to_string())
                 })?,
             }),
+            "system" => Ok(Self::System {
+                content: message.content.ok_or_else(|| {
+                    message::MessageError::ConversionError("Empty system message".to_string())
+                })?,
+            }),
             _ => Err(message::MessageError::ConversionError(format!(
                 "Unknown role: {}",
                 message.role
@@ -361,6 +427,11 @@ impl TryFrom<message::Message> for Message {
                     tool_calls,
                 })
             }
+            message::Message::System { content } => Ok(Self {
+                role: "system".to_string(),
+                content: Some(content),
+                tool_calls: vec![],
+            }),
         }
     }
 }
@@ -398,6 +469,8 @@ impl CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<Value, CompletionError> {
+        completion_request.reject_prefill("Galadriel")?;
+
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => vec![Message {
@@ -460,11 +533,22 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client.post("/chat/completions").json(&request),
+        )
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let request = self.create_completion_request(completion_request)?;
 
         let response = self
@@ -489,7 +573,7 @@ impl completion::CompletionModel for CompletionModel {
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.message)),
             }
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
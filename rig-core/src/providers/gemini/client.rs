@@ -1,3 +1,8 @@
+use crate::credentials::{CredentialProvider, Credentials};
+use crate::http_client::{
+    HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
+use crate::key_pool::ApiKeyPlacement;
 use crate::{
     agent::AgentBuilder,
     embeddings::{self},
@@ -16,33 +21,64 @@ use super::{
 // ================================================================
 const GEMINI_API_BASE_URL: &str = "https://generativelanguage.googleapis.com";
 
+/// Create a new Google Gemini client using the builder
+#[derive(Clone)]
+pub struct ClientBuilder<'a> {
+    api_key: &'a str,
+    base_url: &'a str,
+    reqwest_options: ReqwestOptions,
+}
+
+impl<'a> ClientBuilder<'a> {
+    pub fn new(api_key: &'a str) -> Self {
+        Self {
+            api_key,
+            base_url: GEMINI_API_BASE_URL,
+            reqwest_options: ReqwestOptions::default(),
+        }
+    }
+
+    pub fn base_url(mut self, base_url: &'a str) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn build(self) -> Client {
+        Client {
+            base_url: self.base_url.to_string(),
+            api_key: Some(self.api_key.to_string()),
+            http_client: HttpClient::new(self.reqwest_options.build()).with_default_headers({
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert(
+                    reqwest::header::CONTENT_TYPE,
+                    "application/json".parse().unwrap(),
+                );
+                headers
+            }),
+        }
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     base_url: String,
-    api_key: String,
-    http_client: reqwest::Client,
+    /// API key sent in the `key` query parameter (`None` when using credentials)
+    api_key: Option<String>,
+    http_client: HttpClient,
 }
 
 impl Client {
     pub fn new(api_key: &str) -> Self {
-        Self::from_url(api_key, GEMINI_API_BASE_URL)
+        ClientBuilder::new(api_key).build()
     }
     pub fn from_url(api_key: &str, base_url: &str) -> Self {
-        Self {
-            base_url: base_url.to_string(),
-            api_key: api_key.to_string(),
-            http_client: reqwest::Client::builder()
-                .default_headers({
-                    let mut headers = reqwest::header::HeaderMap::new();
-                    headers.insert(
-                        reqwest::header::CONTENT_TYPE,
-                        "application/json".parse().unwrap(),
-                    );
-                    headers
-                })
-                .build()
-                .expect("Gemini reqwest client should build"),
-        }
+        ClientBuilder::new(api_key).base_url(base_url).build()
     }
 
     /// Create a new Google Gemini client from the `GEMINI_API_KEY` environment variable.
@@ -52,19 +88,26 @@ impl Client {
         Self::new(&api_key)
     }
 
-    pub fn post(&self, path: &str) -> reqwest::RequestBuilder {
-        let url = format!("{}/{}?key={}", self.base_url, path, self.api_key).replace("//", "/");
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        let url = format!("{}/{}", self.base_url, path).replace("//", "/");
 
-        tracing::debug!("POST {}/{}?key={}", self.base_url, path, "****");
-        self.http_client.post(url)
+        tracing::debug!("POST {}/{}", self.base_url, path);
+        self.with_api_key(self.http_client.post(url))
     }
 
-    pub fn post_sse(&self, path: &str) -> reqwest::RequestBuilder {
-        let url =
-            format!("{}/{}?alt=sse&key={}", self.base_url, path, self.api_key).replace("//", "/");
+    pub fn post_sse(&self, path: &str) -> RequestBuilder {
+        let url = format!("{}/{}?alt=sse", self.base_url, path).replace("//", "/");
 
-        tracing::debug!("POST {}/{}?alt=sse&key={}", self.base_url, path, "****");
-        self.http_client.post(url)
+        tracing::debug!("POST {}/{}?alt=sse", self.base_url, path);
+        self.with_api_key(self.http_client.post(url))
+    }
+
+    /// Add the API key (if any) to the query parameters of the request
+    fn with_api_key(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(api_key) => request.query(&[("key", api_key)]),
+            None => request,
+        }
     }
 
     /// Create an embedding model with the given name.
@@ -164,6 +207,25 @@ impl Client {
     }
 }
 
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+
+    fn api_key_placement(&self) -> ApiKeyPlacement {
+        ApiKeyPlacement::Query("key")
+    }
+
+    /// The credentials are sent as bearer tokens, and the `key` query parameter is no longer sent
+    fn with_credentials(mut self, provider: impl CredentialProvider + 'static) -> Self {
+        self.api_key = None;
+        self.http_client = self
+            .http_client
+            .with_credentials(Credentials::new(provider), ApiKeyPlacement::bearer());
+        self
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ApiErrorResponse {
     pub message: String,
@@ -18,12 +18,13 @@ use gemini_api_types::{
     Content, FunctionDeclaration, GenerateContentRequest, GenerateContentResponse,
     GenerationConfig, Part, Role, Tool,
 };
-use serde_json::{Map, Value};
+use serde_json::{json, Map, Value};
 use std::convert::TryFrom;
 
 use crate::{
     completion::{self, CompletionError, CompletionRequest},
-    OneOrMany,
+    http_client::ApiError,
+    json_utils, OneOrMany,
 };
 
 use super::Client;
@@ -45,16 +46,64 @@ impl CompletionModel {
             model: model.to_string(),
         }
     }
+
+    /// Count the input tokens of `completion_request` with the token counting endpoint of the
+    /// Gemini API
+    #[cfg_attr(feature = "worker", worker::send)]
+    pub async fn count_tokens(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<u64, CompletionError> {
+        let mut request = serde_json::to_value(create_request_body(completion_request)?)?;
+        json_utils::merge_inplace(
+            &mut request,
+            json!({ "model": format!("models/{}", self.model) }),
+        );
+
+        let response = self
+            .client
+            .post(&format!("/v1beta/models/{}:countTokens", self.model))
+            .json(&json!({ "generateContentRequest": request }))
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(response.json::<TokenCount>().await?.total_tokens)
+        } else {
+            Err(CompletionError::from(
+                ApiError::from_response(response).await?,
+            ))
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenCount {
+    total_tokens: u64,
 }
 
 impl completion::CompletionModel for CompletionModel {
     type Response = GenerateContentResponse;
 
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = create_request_body(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client
+                .post(&format!("/v1beta/models/{}:generateContent", self.model))
+                .json(&request),
+        )
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<GenerateContentResponse>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let request = create_request_body(completion_request)?;
 
         tracing::debug!(
@@ -85,14 +134,21 @@ impl completion::CompletionModel for CompletionModel {
 
             Ok(completion::CompletionResponse::try_from(response))
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            Err(CompletionError::from(
+                ApiError::from_response(response).await?,
+            ))
         }?
     }
 }
 
 pub(crate) fn create_request_body(
-    mut completion_request: CompletionRequest,
+    completion_request: CompletionRequest,
 ) -> Result<GenerateContentRequest, CompletionError> {
+    completion_request.reject_prefill("Gemini")?;
+
+    // Gemini only supports a system instruction
+    let mut completion_request = completion_request.merge_system_messages();
+
     let mut full_history = Vec::new();
     full_history.append(&mut completion_request.chat_history);
     full_history.push(completion_request.prompt_with_context());
@@ -192,6 +248,12 @@ impl TryFrom<GenerateContentResponse> for completion::CompletionResponse<Generat
 
         Ok(completion::CompletionResponse {
             choice,
+            usage: response
+                .usage_metadata
+                .as_ref()
+                .map(Into::into)
+                .unwrap_or_default(),
+            timing: completion::ResponseTiming::default(),
             raw_response: response,
         })
     }
@@ -207,7 +269,7 @@ pub mod gemini_api_types {
     use serde_json::Value;
 
     use crate::{
-        completion::CompletionError,
+        completion::{self, CompletionError},
         message::{self, MimeType as _},
         one_or_many::string_or_one_or_many,
         providers::gemini::gemini_api_types::{CodeExecutionResult, ExecutableCode},
@@ -281,6 +343,12 @@ pub mod gemini_api_types {
                     role: Some(Role::Model),
                     parts: content.map(|content| content.into()),
                 },
+                message::Message::System { .. } => {
+                    return Err(message::MessageError::ConversionError(
+                        "System messages must be merged into the system instruction for Gemini"
+                            .to_string(),
+                    ))
+                }
             })
         }
     }
@@ -328,6 +396,41 @@ pub mod gemini_api_types {
                                     }
                                 }
                             }
+                            Part::FileData(file_data) => {
+                                let media_type = file_data
+                                    .mime_type
+                                    .as_deref()
+                                    .and_then(message::MediaType::from_mime_type);
+
+                                match media_type {
+                                    Some(message::MediaType::Image(media_type)) => {
+                                        message::UserContent::Image(message::Image {
+                                            media_type: Some(media_type),
+                                            ..message::Image::url(file_data.file_uri)
+                                        })
+                                    }
+                                    Some(message::MediaType::Audio(media_type)) => {
+                                        message::UserContent::Audio(message::Audio::url(
+                                            file_data.file_uri,
+                                            Some(media_type),
+                                        ))
+                                    }
+                                    Some(message::MediaType::Document(media_type)) => {
+                                        message::UserContent::Document(message::Document::file_id(
+                                            file_data.file_uri,
+                                            Some(media_type),
+                                        ))
+                                    }
+                                    _ => {
+                                        return Err(message::MessageError::ConversionError(
+                                            format!(
+                                                "Unsupported file data media type {:?}",
+                                                file_data.mime_type
+                                            ),
+                                        ))
+                                    }
+                                }
+                            }
                             _ => {
                                 return Err(message::MessageError::ConversionError(format!(
                                     "Unsupported gemini content part type: {:?}",
@@ -424,62 +527,97 @@ pub mod gemini_api_types {
                         })?),
                     }))
                 }
-                message::UserContent::Image(message::Image {
-                    data, media_type, ..
-                }) => match media_type {
-                    Some(media_type) => match media_type {
-                        message::ImageMediaType::JPEG
-                        | message::ImageMediaType::PNG
-                        | message::ImageMediaType::WEBP
-                        | message::ImageMediaType::HEIC
-                        | message::ImageMediaType::HEIF => Ok(Self::InlineData(Blob {
-                            mime_type: media_type.to_mime_type().to_owned(),
-                            data,
+                message::UserContent::Image(image) => {
+                    let image = image.resolve_data_url();
+                    let mime_type = match &image.media_type {
+                        Some(
+                            media_type @ (message::ImageMediaType::JPEG
+                            | message::ImageMediaType::PNG
+                            | message::ImageMediaType::WEBP
+                            | message::ImageMediaType::HEIC
+                            | message::ImageMediaType::HEIF),
+                        ) => Some(media_type.to_mime_type().to_owned()),
+                        Some(media_type) => {
+                            return Err(message::MessageError::ConversionError(format!(
+                                "Unsupported image media type {:?}",
+                                media_type
+                            )))
+                        }
+                        None => None,
+                    };
+
+                    match mime_type {
+                        _ if image.is_url() => Ok(Self::FileData(FileData {
+                            mime_type,
+                            file_uri: image.data,
                         })),
-                        _ => Err(message::MessageError::ConversionError(format!(
-                            "Unsupported image media type {:?}",
-                            media_type
-                        ))),
-                    },
-                    None => Err(message::MessageError::ConversionError(
-                        "Media type for image is required for Anthropic".to_string(),
-                    )),
-                },
-                message::UserContent::Document(message::Document {
-                    data, media_type, ..
-                }) => match media_type {
-                    Some(media_type) => match media_type {
-                        message::DocumentMediaType::PDF
-                        | message::DocumentMediaType::TXT
-                        | message::DocumentMediaType::RTF
-                        | message::DocumentMediaType::HTML
-                        | message::DocumentMediaType::CSS
-                        | message::DocumentMediaType::MARKDOWN
-                        | message::DocumentMediaType::CSV
-                        | message::DocumentMediaType::XML => Ok(Self::InlineData(Blob {
-                            mime_type: media_type.to_mime_type().to_owned(),
-                            data,
+                        Some(mime_type) => Ok(Self::InlineData(Blob {
+                            mime_type,
+                            data: image.data,
                         })),
-                        _ => Err(message::MessageError::ConversionError(format!(
-                            "Unsupported document media type {:?}",
-                            media_type
-                        ))),
-                    },
-                    None => Err(message::MessageError::ConversionError(
-                        "Media type for document is required for Anthropic".to_string(),
-                    )),
-                },
-                message::UserContent::Audio(message::Audio {
-                    data, media_type, ..
-                }) => match media_type {
-                    Some(media_type) => Ok(Self::InlineData(Blob {
-                        mime_type: media_type.to_mime_type().to_owned(),
-                        data,
-                    })),
-                    None => Err(message::MessageError::ConversionError(
-                        "Media type for audio is required for Anthropic".to_string(),
-                    )),
-                },
+                        None => Err(message::MessageError::ConversionError(
+                            "Media type for image is required for Gemini".to_string(),
+                        )),
+                    }
+                }
+                message::UserContent::Document(document) => {
+                    let mime_type = match &document.media_type {
+                        Some(
+                            media_type @ (message::DocumentMediaType::PDF
+                            | message::DocumentMediaType::TXT
+                            | message::DocumentMediaType::RTF
+                            | message::DocumentMediaType::HTML
+                            | message::DocumentMediaType::CSS
+                            | message::DocumentMediaType::MARKDOWN
+                            | message::DocumentMediaType::CSV
+                            | message::DocumentMediaType::XML),
+                        ) => Some(media_type.to_mime_type().to_owned()),
+                        Some(media_type) => {
+                            return Err(message::MessageError::ConversionError(format!(
+                                "Unsupported document media type {:?}",
+                                media_type
+                            )))
+                        }
+                        None => None,
+                    };
+
+                    match (document.format, mime_type) {
+                        (Some(message::ContentFormat::FileId), mime_type) => {
+                            Ok(Self::FileData(FileData {
+                                mime_type,
+                                file_uri: document.data,
+                            }))
+                        }
+                        (Some(message::ContentFormat::String), _) => Ok(Self::Text(document.data)),
+                        (_, Some(mime_type)) => Ok(Self::InlineData(Blob {
+                            mime_type,
+                            data: document.data,
+                        })),
+                        (_, None) => Err(message::MessageError::ConversionError(
+                            "Media type for document is required for Gemini".to_string(),
+                        )),
+                    }
+                }
+                message::UserContent::Audio(audio) => {
+                    let mime_type = audio
+                        .media_type
+                        .as_ref()
+                        .map(|media_type| media_type.to_mime_type().to_owned());
+
+                    match mime_type {
+                        _ if audio.is_url() => Ok(Self::FileData(FileData {
+                            mime_type,
+                            file_uri: audio.data,
+                        })),
+                        Some(mime_type) => Ok(Self::InlineData(Blob {
+                            mime_type,
+                            data: audio.data,
+                        })),
+                        None => Err(message::MessageError::ConversionError(
+                            "Media type for audio is required for Gemini".to_string(),
+                        )),
+                    }
+                }
             }
         }
     }
@@ -611,6 +749,16 @@ pub mod gemini_api_types {
         pub total_token_count: i32,
     }
 
+    impl From<&UsageMetadata> for completion::Usage {
+        fn from(usage: &UsageMetadata) -> Self {
+            completion::Usage {
+                input_tokens: usage.prompt_token_count as u64,
+                output_tokens: usage.candidates_token_count as u64,
+                total_tokens: usage.total_token_count as u64,
+            }
+        }
+    }
+
     impl std::fmt::Display for UsageMetadata {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             write!(
@@ -982,6 +1130,7 @@ mod tests {
     use crate::message;
 
     use super::*;
+    use gemini_api_types::{Blob, FileData};
     use serde_json::json;
 
     #[test]
@@ -1142,4 +1291,130 @@ mod tests {
             panic!("Expected function call part");
         }
     }
+
+    #[test]
+    fn test_message_conversion_image() {
+        let msg = message::Message::User {
+            content: OneOrMany::many(vec![
+                message::UserContent::image_url("data:image/png;base64,aGVsbG8="),
+                message::UserContent::Image(message::Image {
+                    media_type: Some(message::ImageMediaType::JPEG),
+                    ..message::Image::url("gs://flurbos/flurbo.jpg")
+                }),
+            ])
+            .unwrap(),
+        };
+
+        let content: Content = msg.clone().try_into().unwrap();
+        assert_eq!(
+            content.parts.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                Part::InlineData(Blob {
+                    mime_type: "image/png".to_string(),
+                    data: "aGVsbG8=".to_string(),
+                }),
+                Part::FileData(FileData {
+                    mime_type: Some("image/jpeg".to_string()),
+                    file_uri: "gs://flurbos/flurbo.jpg".to_string(),
+                }),
+            ]
+        );
+
+        let converted: message::Message = content.try_into().unwrap();
+        match converted {
+            message::Message::User { content } => {
+                assert_eq!(
+                    content.first(),
+                    message::UserContent::Image(message::Image {
+                        detail: Some(message::ImageDetail::default()),
+                        ..message::Image::base64("aGVsbG8=", message::ImageMediaType::PNG)
+                    })
+                );
+            }
+            _ => panic!("Expected user message"),
+        }
+    }
+
+    #[test]
+    fn test_message_conversion_audio() {
+        let msg = message::Message::User {
+            content: OneOrMany::many(vec![
+                message::UserContent::Audio(message::Audio::from_bytes(
+                    b"hello",
+                    message::AudioMediaType::WAV,
+                )),
+                message::UserContent::Audio(message::Audio::url(
+                    "https://generativelanguage.googleapis.com/v1beta/files/voice-note",
+                    Some(message::AudioMediaType::MP3),
+                )),
+            ])
+            .unwrap(),
+        };
+
+        let content: Content = msg.clone().try_into().unwrap();
+        assert_eq!(
+            content.parts.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                Part::InlineData(Blob {
+                    mime_type: "audio/wav".to_string(),
+                    data: "aGVsbG8=".to_string(),
+                }),
+                Part::FileData(FileData {
+                    mime_type: Some("audio/mp3".to_string()),
+                    file_uri: "https://generativelanguage.googleapis.com/v1beta/files/voice-note"
+                        .to_string(),
+                }),
+            ]
+        );
+
+        let converted: message::Message = content.try_into().unwrap();
+        assert_eq!(converted, msg);
+    }
+
+    #[test]
+    fn test_message_conversion_document() {
+        let msg = message::Message::User {
+            content: OneOrMany::many(vec![
+                message::UserContent::Document(message::Document::from_bytes(
+                    "%PDF",
+                    message::DocumentMediaType::PDF,
+                )),
+                message::UserContent::Document(message::Document::file_id(
+                    "https://generativelanguage.googleapis.com/v1beta/files/report",
+                    Some(message::DocumentMediaType::PDF),
+                )),
+            ])
+            .unwrap(),
+        };
+
+        let content: Content = msg.clone().try_into().unwrap();
+        assert_eq!(
+            content.parts.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                Part::InlineData(Blob {
+                    mime_type: "application/pdf".to_string(),
+                    data: "JVBERg==".to_string(),
+                }),
+                Part::FileData(FileData {
+                    mime_type: Some("application/pdf".to_string()),
+                    file_uri: "https://generativelanguage.googleapis.com/v1beta/files/report"
+                        .to_string(),
+                }),
+            ]
+        );
+
+        let converted: message::Message = content.try_into().unwrap();
+        assert_eq!(converted, msg);
+
+        let text = message::Message::User {
+            content: OneOrMany::one(message::UserContent::Document(message::Document::text(
+                "The grass is green.",
+            ))),
+        };
+        let content: Content = text.try_into().unwrap();
+        assert_eq!(
+            content.parts.first(),
+            Part::Text("The grass is green.".to_string())
+        );
+    }
 }
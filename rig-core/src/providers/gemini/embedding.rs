@@ -46,6 +46,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
         &self,
         documents: impl IntoIterator<Item = String> + Send,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        crate::telemetry::record_model(&self.model);
         let documents: Vec<_> = documents.into_iter().collect();
         let mut request_body = json!({
             "model": format!("models/{}", self.model),
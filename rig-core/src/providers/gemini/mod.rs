@@ -15,7 +15,7 @@ pub mod embedding;
 pub mod streaming;
 pub mod transcription;
 
-pub use client::Client;
+pub use client::{Client, ClientBuilder};
 
 pub mod gemini_api_types {
     use serde::{Deserialize, Serialize};
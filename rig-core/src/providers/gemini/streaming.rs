@@ -4,10 +4,15 @@ use serde::Deserialize;
 
 use crate::{
     completion::{CompletionError, CompletionRequest},
+    http_client::ApiError,
     streaming::{self, StreamingCompletionModel, StreamingResult},
 };
 
-use super::completion::{create_request_body, gemini_api_types::ContentCandidate, CompletionModel};
+use super::completion::{
+    create_request_body,
+    gemini_api_types::{ContentCandidate, UsageMetadata},
+    CompletionModel,
+};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -15,6 +20,8 @@ pub struct StreamGenerateContentResponse {
     /// Candidate responses from the model.
     pub candidates: Vec<ContentCandidate>,
     pub model_version: Option<String>,
+    /// Usage of the response so far (the usage of the last chunk is the usage of the response)
+    pub usage_metadata: Option<UsageMetadata>,
 }
 
 impl StreamingCompletionModel for CompletionModel {
@@ -22,6 +29,7 @@ impl StreamingCompletionModel for CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<StreamingResult, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let request = create_request_body(completion_request)?;
 
         let response = self
@@ -35,15 +43,12 @@ impl StreamingCompletionModel for CompletionModel {
             .await?;
 
         if !response.status().is_success() {
-            return Err(CompletionError::ProviderError(format!(
-                "{}: {}",
-                response.status(),
-                response.text().await?
-            )));
+            return Err(ApiError::from_response(response).await?.into());
         }
 
         Ok(Box::pin(stream! {
             let mut stream = response.bytes_stream();
+            let mut usage = None;
 
             while let Some(chunk_result) = stream.next().await {
                 let chunk = match chunk_result {
@@ -70,7 +75,13 @@ impl StreamingCompletionModel for CompletionModel {
                         continue;
                     };
 
-                    let choice = data.candidates.first().expect("Should have at least one choice");
+                    if let Some(usage_metadata) = &data.usage_metadata {
+                        usage = Some(usage_metadata.into());
+                    }
+
+                    let Some(choice) = data.candidates.first() else {
+                        continue;
+                    };
 
                     match choice.content.parts.first() {
                         super::completion::gemini_api_types::Part::Text(text)
@@ -81,6 +92,10 @@ impl StreamingCompletionModel for CompletionModel {
                     };
                 }
             }
+
+            if let Some(usage) = usage {
+                yield Ok(streaming::StreamingChoice::Usage(usage));
+            }
         }))
     }
 }
@@ -9,6 +9,9 @@
 //! let gpt4o = client.completion_model(groq::GPT_4O);
 //! ```
 use super::openai::{send_compatible_streaming_request, CompletionResponse, TranscriptionResponse};
+use crate::http_client::{
+    ApiError, HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
 use crate::json_utils::merge;
 use crate::streaming::{StreamingCompletionModel, StreamingResult};
 use crate::{
@@ -31,36 +34,66 @@ use serde_json::{json, Value};
 // ================================================================
 const GROQ_API_BASE_URL: &str = "https://api.groq.com/openai/v1";
 
+/// Create a new Groq client using the builder
+#[derive(Clone)]
+pub struct ClientBuilder<'a> {
+    api_key: &'a str,
+    base_url: &'a str,
+    reqwest_options: ReqwestOptions,
+}
+
+impl<'a> ClientBuilder<'a> {
+    pub fn new(api_key: &'a str) -> Self {
+        Self {
+            api_key,
+            base_url: GROQ_API_BASE_URL,
+            reqwest_options: ReqwestOptions::default(),
+        }
+    }
+
+    pub fn base_url(mut self, base_url: &'a str) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn build(self) -> Client {
+        Client {
+            base_url: self.base_url.to_string(),
+            http_client: HttpClient::new(self.reqwest_options.build()).with_default_headers({
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert(
+                    "Authorization",
+                    format!("Bearer {}", self.api_key)
+                        .parse()
+                        .expect("Bearer token should parse"),
+                );
+                headers
+            }),
+        }
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     base_url: String,
-    http_client: reqwest::Client,
+    http_client: HttpClient,
 }
 
 impl Client {
     /// Create a new Groq client with the given API key.
     pub fn new(api_key: &str) -> Self {
-        Self::from_url(api_key, GROQ_API_BASE_URL)
+        ClientBuilder::new(api_key).build()
     }
 
     /// Create a new Groq client with the given API key and base API URL.
     pub fn from_url(api_key: &str, base_url: &str) -> Self {
-        Self {
-            base_url: base_url.to_string(),
-            http_client: reqwest::Client::builder()
-                .default_headers({
-                    let mut headers = reqwest::header::HeaderMap::new();
-                    headers.insert(
-                        "Authorization",
-                        format!("Bearer {}", api_key)
-                            .parse()
-                            .expect("Bearer token should parse"),
-                    );
-                    headers
-                })
-                .build()
-                .expect("Groq reqwest client should build"),
-        }
+        ClientBuilder::new(api_key).base_url(base_url).build()
     }
 
     /// Create a new Groq client from the `GROQ_API_KEY` environment variable.
@@ -70,7 +103,7 @@ impl Client {
         Self::new(&api_key)
     }
 
-    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+    fn post(&self, path: &str) -> RequestBuilder {
         let url = format!("{}/{}", self.base_url, path).replace("//", "/");
         self.http_client.post(url)
     }
@@ -132,6 +165,12 @@ impl Client {
     }
 }
 
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ApiErrorResponse {
     message: String,
@@ -177,6 +216,11 @@ impl TryFrom<Message> for message::Message {
                         })?,
                 ),
             }),
+            "system" => Ok(Self::System {
+                content: message.content.ok_or_else(|| {
+                    message::MessageError::ConversionError("Empty system message".to_string())
+                })?,
+            }),
             _ => Err(message::MessageError::ConversionError(format!(
                 "Unknown role: {}",
                 message.role
@@ -226,6 +270,10 @@ impl TryFrom<message::Message> for Message {
                     content: text_content,
                 })
             }
+            message::Message::System { content } => Ok(Self {
+                role: "system".to_string(),
+                content: Some(content),
+            }),
         }
     }
 }
@@ -279,6 +327,8 @@ impl CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<Value, CompletionError> {
+        completion_request.reject_prefill("Groq")?;
+
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => vec![Message {
@@ -331,11 +381,22 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client.post("/chat/completions").json(&request),
+        )
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let request = self.create_completion_request(completion_request)?;
 
         let response = self
@@ -357,7 +418,7 @@ impl completion::CompletionModel for CompletionModel {
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.message)),
             }
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -2,6 +2,9 @@ use std::fmt::Display;
 
 use super::completion::CompletionModel;
 use crate::agent::AgentBuilder;
+use crate::http_client::{
+    HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
 #[cfg(feature = "image")]
 use crate::image_generation::ImageGenerationError;
 #[cfg(feature = "image")]
@@ -106,6 +109,7 @@ pub struct ClientBuilder {
     api_key: String,
     base_url: String,
     sub_provider: SubProvider,
+    reqwest_options: ReqwestOptions,
 }
 
 impl ClientBuilder {
@@ -114,6 +118,7 @@ impl ClientBuilder {
             api_key: api_key.to_string(),
             base_url: HUGGINGFACE_API_BASE_URL.to_string(),
             sub_provider: SubProvider::default(),
+            reqwest_options: ReqwestOptions::default(),
         }
     }
 
@@ -132,14 +137,25 @@ impl ClientBuilder {
 
         let base_url = format!("{}/{}", self.base_url, route).replace("//", "/");
 
-        Client::from_url(self.api_key.as_str(), base_url.as_str(), self.sub_provider)
+        let mut client =
+            Client::from_url(self.api_key.as_str(), base_url.as_str(), self.sub_provider);
+        if !self.reqwest_options.is_default() {
+            client.http_client = client.http_client.with_client(self.reqwest_options.build());
+        }
+        client
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
     }
 }
 
 #[derive(Clone)]
 pub struct Client {
     base_url: String,
-    http_client: reqwest::Client,
+    http_client: HttpClient,
     pub(crate) sub_provider: SubProvider,
 }
 
@@ -153,25 +169,22 @@ impl Client {
 
     /// Create a new Client with the given API key and base API URL.
     pub fn from_url(api_key: &str, base_url: &str, sub_provider: SubProvider) -> Self {
-        let http_client = reqwest::Client::builder()
-            .default_headers({
-                let mut headers = reqwest::header::HeaderMap::new();
-                headers.insert(
-                    "Authorization",
-                    format!("Bearer {api_key}")
-                        .parse()
-                        .expect("Failed to parse API key"),
-                );
-                headers.insert(
-                    "Content-Type",
-                    "application/json"
-                        .parse()
-                        .expect("Failed to parse Content-Type"),
-                );
-                headers
-            })
-            .build()
-            .expect("Failed to build HTTP client");
+        let http_client = HttpClient::new(reqwest::Client::new()).with_default_headers({
+            let mut headers = reqwest::header::HeaderMap::new();
+            headers.insert(
+                "Authorization",
+                format!("Bearer {api_key}")
+                    .parse()
+                    .expect("Failed to parse API key"),
+            );
+            headers.insert(
+                "Content-Type",
+                "application/json"
+                    .parse()
+                    .expect("Failed to parse Content-Type"),
+            );
+            headers
+        });
 
         Self {
             base_url: base_url.to_owned(),
@@ -186,7 +199,7 @@ impl Client {
         Self::new(&api_key)
     }
 
-    pub(crate) fn post(&self, path: &str) -> reqwest::RequestBuilder {
+    pub(crate) fn post(&self, path: &str) -> RequestBuilder {
         let url = format!("{}/{}", self.base_url, path).replace("//", "/");
         self.http_client.post(url)
     }
@@ -256,3 +269,9 @@ impl Client {
         AgentBuilder::new(self.completion_model(model))
     }
 }
+
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+}
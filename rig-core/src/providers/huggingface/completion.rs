@@ -5,6 +5,7 @@ use serde_json::{json, Value};
 
 use crate::{
     completion::{self, CompletionError, CompletionRequest},
+    http_client::ApiError,
     json_utils,
     message::{self},
     one_or_many::string_or_one_or_many,
@@ -204,14 +205,11 @@ impl TryFrom<message::UserContent> for UserContent {
     fn try_from(content: message::UserContent) -> Result<Self, Self::Error> {
         match content {
             message::UserContent::Text(text) => Ok(UserContent::Text { text: text.text }),
-            message::UserContent::Image(message::Image { data, format, .. }) => match format {
-                Some(message::ContentFormat::String) => Ok(UserContent::ImageUrl {
-                    image_url: ImageUrl { url: data },
-                }),
-                _ => Err(message::MessageError::ConversionError(
-                    "Huggingface only supports images as urls".into(),
-                )),
-            },
+            message::UserContent::Image(image) => Ok(UserContent::ImageUrl {
+                image_url: ImageUrl {
+                    url: image.to_url(),
+                },
+            }),
             _ => Err(message::MessageError::ConversionError(
                 "Huggingface only supports text and images".into(),
             )),
@@ -330,6 +328,7 @@ impl TryFrom<message::Message> for Vec<Message> {
                         .collect::<Vec<_>>(),
                 }])
             }
+            message::Message::System { content } => Ok(vec![Message::system(&content)]),
         }
     }
 }
@@ -378,12 +377,14 @@ impl TryFrom<Message> for message::Message {
                 )),
             },
 
-            // System messages should get stripped out when converting message's, this is just a
-            // stop gap to avoid obnoxious error handling or panic occurring.
-            Message::System { content, .. } => message::Message::User {
-                content: content.map(|c| match c {
-                    SystemContent::Text { text } => message::UserContent::text(text),
-                }),
+            Message::System { content, .. } => message::Message::System {
+                content: content
+                    .into_iter()
+                    .map(|content| match content {
+                        SystemContent::Text { text } => text,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
             },
         })
     }
@@ -474,6 +475,12 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
 
         Ok(completion::CompletionResponse {
             choice,
+            usage: completion::Usage {
+                input_tokens: response.usage.prompt_tokens as u64,
+                output_tokens: response.usage.completion_tokens as u64,
+                total_tokens: response.usage.total_tokens as u64,
+            },
+            timing: completion::ResponseTiming::default(),
             raw_response: response,
         })
     }
@@ -498,6 +505,8 @@ impl CompletionModel {
         &self,
         completion_request: &CompletionRequest,
     ) -> Result<serde_json::Value, CompletionError> {
+        completion_request.reject_prefill("Hugging Face")?;
+
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => vec![Message::system(preamble)],
             None => vec![],
@@ -542,11 +551,27 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_request_body(&completion_request)?;
+        let request = if let Some(ref params) = completion_request.additional_params {
+            json_utils::merge(request, params.clone())
+        } else {
+            request
+        };
+
+        let path = self.client.sub_provider.completion_endpoint(&self.model);
+        completion::PreparedRequest::from_request_builder(self.client.post(&path).json(&request))
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let request = self.create_request_body(&completion_request)?;
 
         let path = self.client.sub_provider.completion_endpoint(&self.model);
@@ -574,11 +599,7 @@ impl completion::CompletionModel for CompletionModel {
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.to_string())),
             }
         } else {
-            Err(CompletionError::ProviderError(format!(
-                "{}: {}",
-                response.status(),
-                response.text().await?
-            )))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -59,6 +59,7 @@ impl StreamingCompletionModel for CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<StreamingResult, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let mut request = self.create_request_body(&completion_request)?;
 
         // Enable streaming
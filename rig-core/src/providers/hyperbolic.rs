@@ -10,6 +10,9 @@
 //! ```
 
 use super::openai::{send_compatible_streaming_request, AssistantContent};
+use crate::http_client::{
+    ApiError, HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
 
 use crate::json_utils::merge_inplace;
 use crate::streaming::{StreamingCompletionModel, StreamingResult};
@@ -31,36 +34,66 @@ use serde_json::{json, Value};
 // ================================================================
 const HYPERBOLIC_API_BASE_URL: &str = "https://api.hyperbolic.xyz/v1";
 
+/// Create a new Hyperbolic client using the builder
+#[derive(Clone)]
+pub struct ClientBuilder<'a> {
+    api_key: &'a str,
+    base_url: &'a str,
+    reqwest_options: ReqwestOptions,
+}
+
+impl<'a> ClientBuilder<'a> {
+    pub fn new(api_key: &'a str) -> Self {
+        Self {
+            api_key,
+            base_url: HYPERBOLIC_API_BASE_URL,
+            reqwest_options: ReqwestOptions::default(),
+        }
+    }
+
+    pub fn base_url(mut self, base_url: &'a str) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn build(self) -> Client {
+        Client {
+            base_url: self.base_url.to_string(),
+            http_client: HttpClient::new(self.reqwest_options.build()).with_default_headers({
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert(
+                    "Authorization",
+                    format!("Bearer {}", self.api_key)
+                        .parse()
+                        .expect("Bearer token should parse"),
+                );
+                headers
+            }),
+        }
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     base_url: String,
-    http_client: reqwest::Client,
+    http_client: HttpClient,
 }
 
 impl Client {
     /// Create a new Hyperbolic client with the given API key.
     pub fn new(api_key: &str) -> Self {
-        Self::from_url(api_key, HYPERBOLIC_API_BASE_URL)
+        ClientBuilder::new(api_key).build()
     }
 
     /// Create a new OpenAI client with the given API key and base API URL.
     pub fn from_url(api_key: &str, base_url: &str) -> Self {
-        Self {
-            base_url: base_url.to_string(),
-            http_client: reqwest::Client::builder()
-                .default_headers({
-                    let mut headers = reqwest::header::HeaderMap::new();
-                    headers.insert(
-                        "Authorization",
-                        format!("Bearer {}", api_key)
-                            .parse()
-                            .expect("Bearer token should parse"),
-                    );
-                    headers
-                })
-                .build()
-                .expect("OpenAI reqwest client should build"),
-        }
+        ClientBuilder::new(api_key).base_url(base_url).build()
     }
 
     /// Create a new Hyperbolic client from the `HYPERBOLIC_API_KEY` environment variable.
@@ -70,7 +103,7 @@ impl Client {
         Self::new(&api_key)
     }
 
-    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+    fn post(&self, path: &str) -> RequestBuilder {
         let url = format!("{}/{}", self.base_url, path).replace("//", "/");
         self.http_client.post(url)
     }
@@ -106,7 +139,7 @@ impl Client {
         ImageGenerationModel::new(self.clone(), model)
     }
 
-    /// Create a completion model with the given name.
+    /// Create an audio generation model speaking the given language.
     ///
     /// # Example
     /// ```
@@ -149,6 +182,12 @@ impl Client {
     }
 }
 
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ApiErrorResponse {
     message: String,
@@ -282,6 +321,16 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
 
         Ok(completion::CompletionResponse {
             choice,
+            usage: response
+                .usage
+                .as_ref()
+                .map(|usage| completion::Usage {
+                    input_tokens: usage.prompt_tokens as u64,
+                    output_tokens: usage.total_tokens.saturating_sub(usage.prompt_tokens) as u64,
+                    total_tokens: usage.total_tokens as u64,
+                })
+                .unwrap_or_default(),
+            timing: completion::ResponseTiming::default(),
             raw_response: response,
         })
     }
@@ -306,6 +355,8 @@ impl CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<Value, CompletionError> {
+        completion_request.reject_prefill("Hyperbolic")?;
+
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => vec![Message::system(preamble)],
@@ -357,11 +408,22 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client.post("/chat/completions").json(&request),
+        )
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let request = self.create_completion_request(completion_request)?;
 
         let response = self
@@ -384,7 +446,7 @@ impl completion::CompletionModel for CompletionModel {
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.message)),
             }
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -394,6 +456,7 @@ impl StreamingCompletionModel for CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<StreamingResult, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let mut request = self.create_completion_request(completion_request)?;
 
         merge_inplace(&mut request, json!({"stream": true}));
@@ -541,14 +604,14 @@ mod audio_generation {
     #[derive(Clone)]
     pub struct AudioGenerationModel {
         client: Client,
-        pub langauge: String,
+        pub language: String,
     }
 
     impl AudioGenerationModel {
         pub(crate) fn new(client: Client, language: &str) -> AudioGenerationModel {
             Self {
                 client,
-                langauge: language.to_string(),
+                language: language.to_string(),
             }
         }
     }
@@ -584,7 +647,7 @@ mod audio_generation {
         ) -> Result<audio_generation::AudioGenerationResponse<Self::Response>, AudioGenerationError>
         {
             let request = json!({
-                "language": self.langauge,
+                "language": self.language,
                 "speaker": request.voice,
                 "text": request.text,
                 "speed": request.speed
@@ -7,6 +7,9 @@
 //! let client = mira::Client::new("YOUR_API_KEY");
 //!
 //! ```
+use crate::http_client::{
+    ApiError, HttpClient, HttpClientSettings, HttpError, ReqwestClientSettings, ReqwestOptions,
+};
 use crate::json_utils::merge;
 use crate::providers::openai::send_compatible_streaming_request;
 use crate::streaming::{StreamingCompletionModel, StreamingResult};
@@ -32,13 +35,19 @@ pub enum MiraError {
     #[error("API error: {0}")]
     ApiError(u16),
     #[error("Request error: {0}")]
-    RequestError(#[from] reqwest::Error),
+    RequestError(#[from] HttpError),
     #[error("UTF-8 error: {0}")]
     Utf8Error(#[from] FromUtf8Error),
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 }
 
+impl From<reqwest::Error> for MiraError {
+    fn from(error: reqwest::Error) -> Self {
+        MiraError::RequestError(error.into())
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ApiErrorResponse {
     message: String,
@@ -107,22 +116,34 @@ struct ModelInfo {
     id: String,
 }
 
+/// Create a new Mira client using the builder
 #[derive(Clone)]
-/// Client for interacting with the Mira API
-pub struct Client {
-    base_url: String,
-    client: reqwest::Client,
-    headers: HeaderMap,
+pub struct ClientBuilder<'a> {
+    api_key: &'a str,
+    base_url: &'a str,
+    reqwest_options: ReqwestOptions,
 }
 
-impl Client {
-    /// Create a new Mira client with the given API key
-    pub fn new(api_key: &str) -> Result<Self, MiraError> {
+impl<'a> ClientBuilder<'a> {
+    pub fn new(api_key: &'a str) -> Self {
+        Self {
+            api_key,
+            base_url: MIRA_API_BASE_URL,
+            reqwest_options: ReqwestOptions::default(),
+        }
+    }
+
+    pub fn base_url(mut self, base_url: &'a str) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn build(self) -> Result<Client, MiraError> {
         let mut headers = HeaderMap::new();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
         headers.insert(
             AUTHORIZATION,
-            HeaderValue::from_str(&format!("Bearer {}", api_key))
+            HeaderValue::from_str(&format!("Bearer {}", self.api_key))
                 .map_err(|_| MiraError::InvalidApiKey)?,
         );
         headers.insert(
@@ -134,14 +155,33 @@ impl Client {
             HeaderValue::from_static("rig-client/1.0"),
         );
 
-        Ok(Self {
-            base_url: MIRA_API_BASE_URL.to_string(),
-            client: reqwest::Client::builder()
-                .build()
-                .expect("Failed to build HTTP client"),
+        Ok(Client {
+            base_url: self.base_url.to_string(),
+            client: HttpClient::new(self.reqwest_options.build()),
             headers,
         })
     }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
+    }
+}
+
+#[derive(Clone)]
+/// Client for interacting with the Mira API
+pub struct Client {
+    base_url: String,
+    client: HttpClient,
+    headers: HeaderMap,
+}
+
+impl Client {
+    /// Create a new Mira client with the given API key
+    pub fn new(api_key: &str) -> Result<Self, MiraError> {
+        ClientBuilder::new(api_key).build()
+    }
 
     /// Create a new Mira client from the `MIRA_API_KEY` environment variable.
     /// Panics if the environment variable is not set.
@@ -150,6 +190,11 @@ impl Client {
         Self::new(&api_key)
     }
 
+    /// Create a new Mira client with the given API key and base API URL.
+    pub fn from_url(api_key: &str, base_url: &str) -> Result<Self, MiraError> {
+        ClientBuilder::new(api_key).base_url(base_url).build()
+    }
+
     /// Create a new Mira client with a custom base URL and API key
     pub fn new_with_base_url(
         api_key: &str,
@@ -209,6 +254,12 @@ impl Client {
     }
 }
 
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.client
+    }
+}
+
 #[derive(Clone)]
 pub struct CompletionModel {
     client: Client,
@@ -228,6 +279,8 @@ impl CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<Value, CompletionError> {
+        completion_request.reject_prefill("Mira")?;
+
         let mut messages = Vec::new();
 
         // Add preamble as user message if available
@@ -282,6 +335,7 @@ impl CompletionModel {
                         .join("\n");
                     ("assistant", text)
                 }
+                Message::System { content } => ("system", content),
             };
             messages.push(serde_json::json!({
                 "role": role,
@@ -304,11 +358,26 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client
+                .client
+                .post(format!("{}/v1/chat/completions", self.client.base_url))
+                .headers(self.client.headers.clone())
+                .json(&request),
+        )
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         if !completion_request.tools.is_empty() {
             tracing::warn!(target: "rig",
                 "Tool calls are not supported by the Mira provider. {} tools will be ignored.",
@@ -329,12 +398,7 @@ impl completion::CompletionModel for CompletionModel {
             .map_err(|e| CompletionError::ProviderError(e.to_string()))?;
 
         if !response.status().is_success() {
-            let status = response.status().as_u16();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(CompletionError::ProviderError(format!(
-                "API error: {} - {}",
-                status, error_text
-            )));
+            return Err(ApiError::from_response(response).await?.into());
         }
 
         let response: CompletionResponse = response
@@ -351,6 +415,7 @@ impl StreamingCompletionModel for CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<StreamingResult, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let mut request = self.create_completion_request(completion_request)?;
 
         request = merge(request, json!({"stream": true}));
@@ -411,7 +476,7 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
                             }
                         }).collect::<Result<Vec<_>, _>>()?
                     }
-                    Message::User { .. } => {
+                    Message::User { .. } | Message::System { .. } => {
                         tracing::warn!(target: "rig", "Received user message in response where assistant message was expected");
                         return Err(CompletionError::ResponseError(
                             "Received user message in response where assistant message was expected".to_owned()
@@ -430,8 +495,21 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
             )
         })?;
 
+        let usage = match &response {
+            CompletionResponse::Structured {
+                usage: Some(usage), ..
+            } => completion::Usage {
+                input_tokens: usage.prompt_tokens as u64,
+                output_tokens: usage.total_tokens.saturating_sub(usage.prompt_tokens) as u64,
+                total_tokens: usage.total_tokens as u64,
+            },
+            _ => completion::Usage::default(),
+        };
+
         Ok(completion::CompletionResponse {
             choice,
+            usage,
+            timing: completion::ResponseTiming::default(),
             raw_response: response,
         })
     }
@@ -484,6 +562,10 @@ impl From<Message> for serde_json::Value {
                     "content": text
                 })
             }
+            Message::System { content } => serde_json::json!({
+                "role": "system",
+                "content": content
+            }),
         }
     }
 }
@@ -529,6 +611,7 @@ impl TryFrom<serde_json::Value> for Message {
             "assistant" => Ok(Message::Assistant {
                 content: OneOrMany::one(AssistantContent::Text(message::Text { text: content })),
             }),
+            "system" => Ok(Message::System { content }),
             _ => Err(CompletionError::ResponseError(format!(
                 "Unsupported message role: {}",
                 role
@@ -627,6 +710,20 @@ mod tests {
         assert_eq!(original_message, final_message);
     }
 
+    #[test]
+    fn test_system_message_conversion() {
+        let system_message = message::Message::system("Now answer in French.");
+
+        let mira_value: serde_json::Value = system_message.clone().into();
+        assert_eq!(
+            mira_value,
+            json!({"role": "system", "content": "Now answer in French."})
+        );
+
+        let converted_message: Message = mira_value.try_into().unwrap();
+        assert_eq!(converted_message, system_message);
+    }
+
     #[test]
     fn test_completion_response_conversion() {
         let mira_response = CompletionResponse::Structured {
@@ -0,0 +1,416 @@
+//! Scriptable mock provider, to unit test agents, extractors and RAG pipelines without network
+//! access or recorded fixtures (see [crate::cassette] to record the interactions with a real
+//! provider instead).
+//!
+//! A [MockCompletionModel] is programmed with a script of turns (canned responses, tool calls,
+//! streamed tokens and injected errors), each completion or streaming request consuming the next
+//! turn. The requests it receives are recorded, so that tests can assert on the prompts, chat
+//! history and tools sent by the code under test.
+//!
+//! A [MockEmbeddingModel] returns deterministic embeddings (hashed bag of words, so that texts
+//! sharing words are similar), canned embeddings for given texts, or injected errors.
+//!
+//! # Example
+//! ```rust
+//! use rig::{completion::Prompt, providers::mock::MockCompletionModel};
+//! use serde_json::json;
+//!
+//! #[tokio::test]
+//! async fn test_calculator_agent() {
+//!     let model = MockCompletionModel::new()
+//!         .tool_call("add", json!({"x": 2, "y": 3}));
+//!
+//!     let agent = rig::agent::AgentBuilder::new(model.clone())
+//!         .tool(Adder)
+//!         .build();
+//!
+//!     // The agent calls the tool and returns its result
+//!     assert_eq!(agent.prompt("What is 2 + 3?").await.unwrap(), "5");
+//!     assert_eq!(model.requests().len(), 1);
+//! }
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use futures::stream;
+
+use crate::{
+    completion::{self, CompletionError, CompletionRequest, Usage},
+    embeddings::{self, EmbeddingError},
+    message::AssistantContent,
+    streaming::{StreamingChoice, StreamingCompletionModel, StreamingResult},
+    OneOrMany,
+};
+
+/// Turn of the script of a [MockCompletionModel]
+#[derive(Debug)]
+enum Turn {
+    /// Response returned at once by completions, streamed one content at a time
+    Response(OneOrMany<AssistantContent>),
+    /// Chunks streamed by streaming completions, concatenated by completions
+    Chunks(Vec<StreamingChoice>),
+    /// Error returned instead of a response
+    Error(CompletionError),
+}
+
+/// Completion model returning scripted responses.
+///
+/// The model is cheap to clone and all clones share the same script and recorded requests.
+/// Once the script is exhausted, requests fail with a [CompletionError::ProviderError].
+#[derive(Clone, Debug, Default)]
+pub struct MockCompletionModel {
+    script: Arc<Mutex<VecDeque<Turn>>>,
+    requests: Arc<Mutex<Vec<CompletionRequest>>>,
+    usage: Usage,
+}
+
+impl MockCompletionModel {
+    /// Create a model with an empty script
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(self, turn: Turn) -> Self {
+        self.script
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .push_back(turn);
+        self
+    }
+
+    /// Respond with a text message
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.push(Turn::Response(OneOrMany::one(AssistantContent::text(text))))
+    }
+
+    /// Respond with a call of the tool `name` with `arguments`
+    pub fn tool_call(self, name: impl Into<String>, arguments: serde_json::Value) -> Self {
+        let id = format!("call_{}", self.len());
+        self.push(Turn::Response(OneOrMany::one(AssistantContent::tool_call(
+            id, name, arguments,
+        ))))
+    }
+
+    /// Respond with arbitrary content (e.g.: text and several tool calls)
+    pub fn response(self, choice: OneOrMany<AssistantContent>) -> Self {
+        self.push(Turn::Response(choice))
+    }
+
+    /// Stream `tokens` one at a time (completions return the concatenated tokens)
+    pub fn stream_tokens<T: Into<String>>(self, tokens: impl IntoIterator<Item = T>) -> Self {
+        let chunks = tokens
+            .into_iter()
+            .map(|token| StreamingChoice::Message(token.into()))
+            .collect();
+        self.push(Turn::Chunks(chunks))
+    }
+
+    /// Fail the request with `error`
+    pub fn error(self, error: CompletionError) -> Self {
+        self.push(Turn::Error(error))
+    }
+
+    /// Token usage reported by all the responses of the model, and at the end of its streams
+    /// (zero by default, in which case streams report no usage)
+    pub fn usage(mut self, usage: Usage) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// Number of turns of the script not consumed yet
+    pub fn len(&self) -> usize {
+        self.script
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Requests received by the model so far, in order
+    pub fn requests(&self) -> Vec<CompletionRequest> {
+        self.requests
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .clone()
+    }
+
+    /// Record the request and consume the next turn of the script
+    fn next_turn(&self, request: CompletionRequest) -> Result<Turn, CompletionError> {
+        self.requests
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .push(request);
+
+        self.script
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .pop_front()
+            .ok_or_else(|| {
+                CompletionError::ProviderError(
+                    "MockCompletionModel has no scripted response left".to_string(),
+                )
+            })
+    }
+}
+
+impl completion::CompletionModel for MockCompletionModel {
+    type Response = ();
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<()>, CompletionError> {
+        let choice = match self.next_turn(request)? {
+            Turn::Response(choice) => choice,
+            Turn::Chunks(chunks) => {
+                let mut text = String::new();
+                let mut tool_calls = vec![];
+                for chunk in chunks {
+                    match chunk {
+                        StreamingChoice::Message(token) => text.push_str(&token),
+                        StreamingChoice::ToolCall(name, id, arguments) => {
+                            tool_calls.push(AssistantContent::tool_call(id, name, arguments))
+                        }
+                        StreamingChoice::Usage(_) => {}
+                    }
+                }
+
+                let text = (!text.is_empty()).then(|| AssistantContent::text(text));
+                OneOrMany::many(text.into_iter().chain(tool_calls)).map_err(|_| {
+                    CompletionError::ResponseError("Scripted stream is empty".to_string())
+                })?
+            }
+            Turn::Error(error) => return Err(error),
+        };
+
+        Ok(completion::CompletionResponse {
+            choice,
+            usage: self.usage,
+            timing: completion::ResponseTiming::default(),
+            raw_response: (),
+        })
+    }
+}
+
+impl StreamingCompletionModel for MockCompletionModel {
+    async fn stream(&self, request: CompletionRequest) -> Result<StreamingResult, CompletionError> {
+        let mut chunks = match self.next_turn(request)? {
+            Turn::Response(choice) => choice
+                .into_iter()
+                .map(|content| match content {
+                    AssistantContent::Text(text) => StreamingChoice::Message(text.text),
+                    AssistantContent::ToolCall(tool_call) => StreamingChoice::ToolCall(
+                        tool_call.function.name,
+                        tool_call.id,
+                        tool_call.function.arguments,
+                    ),
+                })
+                .collect::<Vec<_>>(),
+            Turn::Chunks(chunks) => chunks,
+            Turn::Error(error) => return Err(error),
+        };
+        if self.usage != Usage::default() {
+            chunks.push(StreamingChoice::Usage(self.usage));
+        }
+
+        Ok(Box::pin(stream::iter(chunks.into_iter().map(Ok))))
+    }
+}
+
+/// Embedding model returning deterministic embeddings.
+///
+/// By default, a text is embedded as the normalized bag of its (lowercased) words, each word
+/// being hashed into one of the dimensions, so that texts sharing words have similar embeddings.
+/// Canned embeddings can be set for given texts with [MockEmbeddingModel::embedding], and errors
+/// can be injected with [MockEmbeddingModel::error].
+///
+/// The model is cheap to clone and all clones share the same canned embeddings and errors.
+#[derive(Clone, Debug)]
+pub struct MockEmbeddingModel {
+    ndims: usize,
+    embeddings: Arc<Mutex<HashMap<String, Vec<f64>>>>,
+    errors: Arc<Mutex<VecDeque<EmbeddingError>>>,
+    texts: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockEmbeddingModel {
+    /// Create a model returning embeddings of `ndims` dimensions
+    pub fn new(ndims: usize) -> Self {
+        Self {
+            ndims: ndims.max(1),
+            embeddings: Default::default(),
+            errors: Default::default(),
+            texts: Default::default(),
+        }
+    }
+
+    /// Return `embedding` for `text`
+    pub fn embedding(self, text: impl Into<String>, embedding: Vec<f64>) -> Self {
+        self.embeddings
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .insert(text.into(), embedding);
+        self
+    }
+
+    /// Fail the next request with `error` (errors are returned in the order they were added)
+    pub fn error(self, error: EmbeddingError) -> Self {
+        self.errors
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .push_back(error);
+        self
+    }
+
+    /// Texts embedded by the model so far, in order
+    pub fn texts(&self) -> Vec<String> {
+        self.texts
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .clone()
+    }
+
+    fn embed(&self, text: &str) -> Vec<f64> {
+        if let Some(embedding) = self
+            .embeddings
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .get(text)
+        {
+            return embedding.clone();
+        }
+
+        let mut vec = vec![0.0; self.ndims];
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            let hash = word
+                .to_lowercase()
+                .bytes()
+                .fold(0xcbf29ce484222325u64, |hash, byte| {
+                    (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+                });
+            vec[(hash % self.ndims as u64) as usize] += 1.0;
+        }
+
+        let norm = vec.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            vec.iter_mut().for_each(|x| *x /= norm);
+        }
+        vec
+    }
+}
+
+impl embeddings::EmbeddingModel for MockEmbeddingModel {
+    const MAX_DOCUMENTS: usize = 1024;
+
+    fn ndims(&self) -> usize {
+        self.ndims
+    }
+
+    async fn embed_texts(
+        &self,
+        documents: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        let documents = documents.into_iter().collect::<Vec<_>>();
+
+        if let Some(error) = self
+            .errors
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .pop_front()
+        {
+            return Err(error);
+        }
+
+        self.texts
+            .lock()
+            .expect("Mutex should not be poisoned")
+            .extend(documents.iter().cloned());
+
+        Ok(documents
+            .into_iter()
+            .map(|document| embeddings::Embedding {
+                vec: self.embed(&document),
+                document,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        completion::{CompletionModel, Prompt},
+        embeddings::{distance::VectorDistance, EmbeddingModel},
+    };
+
+    #[tokio::test]
+    async fn test_mock_completion_model() {
+        let model = MockCompletionModel::new()
+            .text("Hello!")
+            .tool_call("add", json!({"x": 1, "y": 2}))
+            .stream_tokens(["Hel", "lo"])
+            .error(CompletionError::ProviderError("Overloaded".to_string()));
+
+        let agent = crate::agent::AgentBuilder::new(model.clone())
+            .preamble("Be nice")
+            .build();
+        assert_eq!(agent.prompt("Hi").await.unwrap(), "Hello!");
+        assert_eq!(model.requests()[0].preamble.as_deref(), Some("Be nice"));
+
+        let response = model.completion_request("Add").send().await.unwrap();
+        assert!(matches!(
+            response.choice.first(),
+            AssistantContent::ToolCall(_)
+        ));
+
+        let mut stream = model
+            .stream(model.completion_request("Hi").build())
+            .await
+            .unwrap();
+        let mut text = String::new();
+        while let Some(chunk) = stream.next().await {
+            text.push_str(&chunk.unwrap().to_string());
+        }
+        assert_eq!(text, "Hello");
+
+        assert!(model.completion_request("Hi").send().await.is_err());
+        assert!(model.is_empty());
+        assert!(model.completion_request("Hi").send().await.is_err());
+        assert_eq!(model.requests().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_mock_embedding_model() {
+        let model = MockEmbeddingModel::new(64)
+            .embedding("custom", vec![1.0; 64])
+            .error(EmbeddingError::ProviderError("Overloaded".to_string()));
+
+        assert!(model.embed_text("Hello").await.is_err());
+
+        let embeddings = model
+            .embed_texts(vec![
+                "The cat sat on the mat".to_string(),
+                "the cat sat on a mat".to_string(),
+                "custom".to_string(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(embeddings[0].vec.len(), 64);
+        assert!(embeddings[0].cosine_similarity(&embeddings[1], false) > 0.7);
+        assert_eq!(embeddings[2].vec, vec![1.0; 64]);
+        assert_eq!(model.texts().len(), 3);
+    }
+}
@@ -38,6 +38,9 @@
 //! let agent = client.agent("llama3.2");
 //! let extractor = client.extractor::<serde_json::Value>("llama3.2");
 //! ```
+use crate::http_client::{
+    ApiError, HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
 use crate::json_utils::merge_inplace;
 use crate::streaming::{StreamingChoice, StreamingCompletionModel, StreamingResult};
 use crate::{
@@ -51,7 +54,6 @@ use crate::{
 };
 use async_stream::stream;
 use futures::StreamExt;
-use reqwest;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -61,10 +63,41 @@ use std::{convert::TryFrom, str::FromStr};
 
 const OLLAMA_API_BASE_URL: &str = "http://localhost:11434";
 
+/// Create a new Ollama client using the builder
+#[derive(Clone, Default)]
+pub struct ClientBuilder<'a> {
+    base_url: Option<&'a str>,
+    reqwest_options: ReqwestOptions,
+}
+
+impl<'a> ClientBuilder<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn base_url(mut self, base_url: &'a str) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    pub fn build(self) -> Client {
+        Client {
+            base_url: self.base_url.unwrap_or(OLLAMA_API_BASE_URL).to_owned(),
+            http_client: HttpClient::new(self.reqwest_options.build()),
+        }
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     base_url: String,
-    http_client: reqwest::Client,
+    http_client: HttpClient,
 }
 
 impl Default for Client {
@@ -75,17 +108,12 @@ impl Default for Client {
 
 impl Client {
     pub fn new() -> Self {
-        Self::from_url(OLLAMA_API_BASE_URL)
+        ClientBuilder::new().build()
     }
     pub fn from_url(base_url: &str) -> Self {
-        Self {
-            base_url: base_url.to_owned(),
-            http_client: reqwest::Client::builder()
-                .build()
-                .expect("Ollama reqwest client should build"),
-        }
+        ClientBuilder::new().base_url(base_url).build()
     }
-    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+    fn post(&self, path: &str) -> RequestBuilder {
         let url = format!("{}/{}", self.base_url, path);
         self.http_client.post(url)
     }
@@ -112,6 +140,12 @@ impl Client {
     }
 }
 
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+}
+
 // ---------- API Error and Response Structures ----------
 
 #[derive(Debug, Deserialize)]
@@ -187,6 +221,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
         &self,
         documents: impl IntoIterator<Item = String>,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        crate::telemetry::record_model(&self.model);
         let docs: Vec<String> = documents.into_iter().collect();
         let payload = json!({
             "model": self.model,
@@ -216,7 +251,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
                 .map(|(vec, document)| embeddings::Embedding { document, vec })
                 .collect())
         } else {
-            Err(EmbeddingError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -295,6 +330,11 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
                 };
                 Ok(completion::CompletionResponse {
                     choice,
+                    usage: completion::Usage::new(
+                        raw_response.prompt_eval_count.unwrap_or_default(),
+                        raw_response.eval_count.unwrap_or_default(),
+                    ),
+                    timing: completion::ResponseTiming::default(),
                     raw_response,
                 })
             }
@@ -325,6 +365,8 @@ impl CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<Value, CompletionError> {
+        completion_request.reject_prefill("Ollama")?;
+
         // Convert internal prompt into a provider Message
         let prompt: Message = completion_request.prompt_with_context().try_into()?;
         let options = if let Some(extra) = completion_request.additional_params {
@@ -371,11 +413,22 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client.post("api/chat").json(&request),
+        )
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<Self::Response>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let request_payload = self.create_completion_request(completion_request)?;
 
         let response = self
@@ -396,11 +449,7 @@ impl completion::CompletionModel for CompletionModel {
             let conv: completion::CompletionResponse<CompletionResponse> = chat_resp.try_into()?;
             Ok(conv)
         } else {
-            let err_text = response
-                .text()
-                .await
-                .map_err(|e| CompletionError::ProviderError(e.to_string()))?;
-            Err(CompletionError::ProviderError(err_text))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -419,11 +468,7 @@ impl StreamingCompletionModel for CompletionModel {
             .map_err(|e| CompletionError::ProviderError(e.to_string()))?;
 
         if !response.status().is_success() {
-            let err_text = response
-                .text()
-                .await
-                .map_err(|e| CompletionError::ProviderError(e.to_string()))?;
-            return Err(CompletionError::ProviderError(err_text));
+            return Err(ApiError::from_response(response).await?.into());
         }
 
         Ok(Box::pin(stream! {
@@ -469,6 +514,14 @@ impl StreamingCompletionModel for CompletionModel {
                             continue;
                         }
                     }
+
+                    // The last chunk carries the token counts of the response
+                    if response.done {
+                        yield Ok(StreamingChoice::Usage(completion::Usage::new(
+                            response.prompt_eval_count.unwrap_or_default(),
+                            response.eval_count.unwrap_or_default(),
+                        )));
+                    }
                 }
             }
         }))
@@ -558,7 +611,6 @@ pub enum Message {
 /// Provider Message Conversions
 /// -----------------------------
 /// Conversion from an internal Rig message (crate::message::Message) to a provider Message.
-/// (Only User and Assistant variants are supported.)
 impl TryFrom<crate::message::Message> for Message {
     type Error = crate::message::MessageError;
     fn try_from(internal_msg: crate::message::Message) -> Result<Self, Self::Error> {
@@ -570,7 +622,9 @@ impl TryFrom<crate::message::Message> for Message {
                 for uc in content.into_iter() {
                     match uc {
                         crate::message::UserContent::Text(t) => texts.push(t.text),
-                        crate::message::UserContent::Image(img) => images.push(img.data),
+                        crate::message::UserContent::Image(img) => {
+                            images.push(img.resolve_data_url().data)
+                        }
                         _ => {} // Audio variant removed since Ollama API does not support it.
                     }
                 }
@@ -611,6 +665,7 @@ impl TryFrom<crate::message::Message> for Message {
                     tool_calls,
                 })
             }
+            InternalMessage::System { content } => Ok(Message::system(&content)),
         }
     }
 }
@@ -647,12 +702,8 @@ impl From<Message> for crate::completion::Message {
                     content: OneOrMany::many(assistant_contents).unwrap(),
                 }
             }
-            // System and ToolResult are converted to User message as needed.
-            Message::System { content, .. } => crate::completion::Message::User {
-                content: OneOrMany::one(crate::completion::message::UserContent::Text(Text {
-                    text: content,
-                })),
-            },
+            Message::System { content, .. } => crate::completion::Message::System { content },
+            // ToolResult is converted to User message.
             Message::ToolResult {
                 tool_call_id,
                 content,
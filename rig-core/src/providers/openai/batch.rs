@@ -0,0 +1,380 @@
+// ================================================================
+// OpenAI Batch API
+// ================================================================
+
+use reqwest::multipart::{Form, Part};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+
+use super::{ApiErrorResponse, CompletionModel, CompletionResponse};
+use crate::{
+    batch::{BatchCompletionModel, BatchRequest, BatchResult, BatchState, BatchStatus},
+    completion::CompletionError,
+    http_client::ApiError,
+};
+
+/// Endpoint of the requests of the batches (relative to the host of the API)
+const BATCH_ENDPOINT: &str = "/v1/chat/completions";
+
+#[derive(Debug, Deserialize)]
+struct File {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Batch {
+    id: String,
+    /// `validating`, `failed`, `in_progress`, `finalizing`, `completed`, `expired`, `cancelling`
+    /// or `cancelled`
+    status: String,
+    #[serde(default)]
+    request_counts: RequestCounts,
+    input_file_id: Option<String>,
+    output_file_id: Option<String>,
+    error_file_id: Option<String>,
+    errors: Option<BatchErrors>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RequestCounts {
+    total: usize,
+    completed: usize,
+    failed: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchErrors {
+    data: Vec<ApiErrorResponse>,
+}
+
+/// Line of the output (or error) file of a batch
+#[derive(Debug, Deserialize)]
+struct BatchOutput {
+    custom_id: String,
+    response: Option<BatchResponse>,
+    error: Option<ApiErrorResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchResponse {
+    status_code: u16,
+    body: serde_json::Value,
+}
+
+impl From<BatchOutput> for BatchResult<CompletionResponse> {
+    fn from(output: BatchOutput) -> Self {
+        let response = match (output.response, output.error) {
+            (_, Some(error)) => Err(CompletionError::ProviderError(error.message)),
+            (Some(response), None) if response.status_code == 200 => {
+                serde_json::from_value::<CompletionResponse>(response.body)
+                    .map_err(CompletionError::from)
+                    .and_then(|response| response.try_into())
+            }
+            (Some(response), None) => Err(CompletionError::ProviderError(
+                response.body["error"]["message"]
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| response.body.to_string()),
+            )),
+            (None, None) => Err(CompletionError::ResponseError(
+                "Batch output without response nor error".to_string(),
+            )),
+        };
+
+        BatchResult {
+            custom_id: output.custom_id,
+            response,
+        }
+    }
+}
+
+async fn parse<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, CompletionError> {
+    if response.status().is_success() {
+        Ok(response.json().await?)
+    } else {
+        Err(ApiError::from_response(response).await?.into())
+    }
+}
+
+impl CompletionModel {
+    async fn batch(&self, batch_id: &str) -> Result<Batch, CompletionError> {
+        parse(
+            self.client
+                .get(&format!("/batches/{batch_id}"))
+                .send()
+                .await?,
+        )
+        .await
+    }
+
+    /// Delete the file `file_id`, logging the errors: the results of the batch were already
+    /// retrieved, the files are only deleted to not accumulate in the storage of the organization
+    async fn delete_file(&self, file_id: &str) {
+        let result = match self
+            .client
+            .delete(&format!("/files/{file_id}"))
+            .send()
+            .await
+        {
+            Ok(response) => parse::<serde_json::Value>(response).await.map(|_| ()),
+            Err(error) => Err(error.into()),
+        };
+        if let Err(error) = result {
+            tracing::warn!(target: "rig", "Failed to delete the batch file {file_id}: {error}");
+        }
+    }
+}
+
+impl BatchCompletionModel for CompletionModel {
+    async fn submit_batch(&self, requests: Vec<BatchRequest>) -> Result<String, CompletionError> {
+        let mut jsonl = String::new();
+        for BatchRequest { custom_id, request } in requests {
+            let line = json!({
+                "custom_id": custom_id,
+                "method": "POST",
+                "url": BATCH_ENDPOINT,
+                "body": self.create_completion_request(request)?,
+            });
+            jsonl.push_str(&serde_json::to_string(&line)?);
+            jsonl.push('\n');
+        }
+
+        let form = Form::new().text("purpose", "batch").part(
+            "file",
+            Part::bytes(jsonl.into_bytes()).file_name("batch.jsonl"),
+        );
+        let file: File = parse(
+            self.client
+                .post("/files")
+                .multipart(form)
+                .idempotent(false)
+                .send()
+                .await?,
+        )
+        .await?;
+
+        let batch: Batch = parse(
+            self.client
+                .post("/batches")
+                .json(&json!({
+                    "input_file_id": file.id,
+                    "endpoint": BATCH_ENDPOINT,
+                    "completion_window": "24h",
+                }))
+                .idempotent(false)
+                .send()
+                .await?,
+        )
+        .await?;
+
+        Ok(batch.id)
+    }
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus, CompletionError> {
+        let batch = self.batch(batch_id).await?;
+
+        let state = match batch.status.as_str() {
+            "completed" | "expired" | "cancelled" => BatchState::Ended,
+            "failed" => BatchState::Failed(
+                batch
+                    .errors
+                    .map(|errors| {
+                        errors
+                            .data
+                            .into_iter()
+                            .map(|error| error.message)
+                            .collect::<Vec<_>>()
+                            .join("; ")
+                    })
+                    .unwrap_or(batch.status),
+            ),
+            _ => BatchState::InProgress,
+        };
+
+        Ok(BatchStatus {
+            state,
+            total: batch.request_counts.total,
+            succeeded: batch.request_counts.completed,
+            failed: batch.request_counts.failed,
+        })
+    }
+
+    async fn batch_results(
+        &self,
+        batch_id: &str,
+    ) -> Result<Vec<BatchResult<CompletionResponse>>, CompletionError> {
+        let batch = self.batch(batch_id).await?;
+
+        let mut results = vec![];
+        for file_id in [&batch.output_file_id, &batch.error_file_id]
+            .into_iter()
+            .flatten()
+        {
+            let response = self
+                .client
+                .get(&format!("/files/{file_id}/content"))
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(ApiError::from_response(response).await?.into());
+            }
+
+            for line in response.text().await?.lines() {
+                if !line.trim().is_empty() {
+                    results.push(serde_json::from_str::<BatchOutput>(line)?.into());
+                }
+            }
+        }
+
+        for file_id in [
+            batch.input_file_id,
+            batch.output_file_id,
+            batch.error_file_id,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            self.delete_file(&file_id).await;
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use bytes::Bytes;
+    use futures::future::BoxFuture;
+
+    use super::*;
+    use crate::{
+        completion::CompletionModel as _,
+        http_client::{HttpClientSettings, HttpTransport, TransportError},
+        message::AssistantContent,
+        providers::openai::{Client, GPT_4O_MINI},
+    };
+
+    /// Transport faking the files and batches endpoints of the OpenAI API, recording the deleted
+    /// files
+    #[derive(Clone, Default)]
+    struct FakeBatchApi {
+        deleted_files: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl HttpTransport for FakeBatchApi {
+        fn execute(
+            &self,
+            request: http::Request<Bytes>,
+        ) -> BoxFuture<'_, Result<http::Response<Bytes>, TransportError>> {
+            Box::pin(async move {
+                let completion = |text: &str| {
+                    json!({
+                        "id": "chatcmpl-1",
+                        "object": "chat.completion",
+                        "created": 0,
+                        "model": GPT_4O_MINI,
+                        "choices": [{
+                            "index": 0,
+                            "message": {"role": "assistant", "content": text},
+                            "finish_reason": "stop"
+                        }]
+                    })
+                };
+
+                let body = match (request.method().as_str(), request.uri().path()) {
+                    ("POST", "/v1/files") => json!({"id": "file-in"}).to_string(),
+                    ("POST", "/v1/batches") => {
+                        json!({"id": "batch_1", "status": "validating"}).to_string()
+                    }
+                    ("GET", "/v1/batches/batch_1") => json!({
+                        "id": "batch_1",
+                        "status": "completed",
+                        "request_counts": {"total": 2, "completed": 1, "failed": 1},
+                        "input_file_id": "file-in",
+                        "output_file_id": "file-out",
+                        "error_file_id": "file-err"
+                    })
+                    .to_string(),
+                    ("GET", "/v1/files/file-out/content") => json!({
+                        "custom_id": "b",
+                        "response": {"status_code": 200, "body": completion("positive")},
+                        "error": null
+                    })
+                    .to_string(),
+                    ("GET", "/v1/files/file-err/content") => json!({
+                        "custom_id": "a",
+                        "response": {
+                            "status_code": 400,
+                            "body": {"error": {"message": "Invalid request"}}
+                        },
+                        "error": null
+                    })
+                    .to_string(),
+                    ("DELETE", path) if path.starts_with("/v1/files/") => {
+                        let file_id = path.trim_start_matches("/v1/files/");
+                        self.deleted_files.lock().unwrap().push(file_id.to_string());
+                        json!({"id": file_id, "object": "file", "deleted": true}).to_string()
+                    }
+                    (method, path) => panic!("Unexpected request {method} {path}"),
+                };
+
+                Ok(http::Response::new(Bytes::from(body)))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_batch() {
+        let api = FakeBatchApi::default();
+        let model = Client::new("fake-key")
+            .with_transport(api.clone())
+            .completion_model(GPT_4O_MINI);
+
+        let results = model
+            .run_batch(
+                vec![
+                    BatchRequest::new("a", model.completion_request("Meh.").build()),
+                    BatchRequest::new("b", model.completion_request("Great!").build()),
+                ],
+                Duration::from_millis(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(results[0].custom_id, "a");
+        assert!(matches!(
+            &results[0].response,
+            Err(CompletionError::ProviderError(message)) if message == "Invalid request"
+        ));
+        assert_eq!(results[1].custom_id, "b");
+        assert_eq!(
+            results[1].response.as_ref().unwrap().choice.first(),
+            AssistantContent::text("positive")
+        );
+        assert_eq!(
+            *api.deleted_files.lock().unwrap(),
+            vec!["file-in", "file-out", "file-err"]
+        );
+    }
+
+    #[test]
+    fn test_batch_output() {
+        let output: BatchOutput = serde_json::from_value(json!({
+            "custom_id": "a",
+            "response": null,
+            "error": {"code": "batch_expired", "message": "The batch expired"}
+        }))
+        .unwrap();
+
+        let result = BatchResult::from(output);
+        assert!(matches!(
+            result.response,
+            Err(CompletionError::ProviderError(message)) if message == "The batch expired"
+        ));
+    }
+}
@@ -4,6 +4,9 @@ use super::completion::CompletionModel;
 use super::embedding::{
     EmbeddingModel, TEXT_EMBEDDING_3_LARGE, TEXT_EMBEDDING_3_SMALL, TEXT_EMBEDDING_ADA_002,
 };
+use crate::http_client::{
+    HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
 
 #[cfg(feature = "image")]
 use super::image_generation::ImageGenerationModel;
@@ -20,36 +23,81 @@ use serde::{Deserialize, Serialize};
 // Main OpenAI Client
 // ================================================================
 const OPENAI_API_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Create a new OpenAI client using the builder
+///
+/// # Example
+/// ```
+/// use rig::{http_client::ReqwestClientSettings, providers::openai::ClientBuilder};
+///
+/// // Send the requests through a corporate proxy
+/// let openai_client = ClientBuilder::new("your-open-ai-api-key")
+///     .with_http_client(
+///         reqwest::Client::builder()
+///             .proxy(reqwest::Proxy::all("http://proxy.corp.example:8080")?)
+///             .build()?,
+///     )
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct ClientBuilder<'a> {
+    api_key: &'a str,
+    base_url: &'a str,
+    reqwest_options: ReqwestOptions,
+}
+
+impl<'a> ClientBuilder<'a> {
+    pub fn new(api_key: &'a str) -> Self {
+        Self {
+            api_key,
+            base_url: OPENAI_API_BASE_URL,
+            reqwest_options: ReqwestOptions::default(),
+        }
+    }
+
+    pub fn base_url(mut self, base_url: &'a str) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn build(self) -> Client {
+        Client {
+            base_url: self.base_url.to_string(),
+            http_client: HttpClient::new(self.reqwest_options.build()).with_default_headers({
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert(
+                    "Authorization",
+                    format!("Bearer {}", self.api_key)
+                        .parse()
+                        .expect("Bearer token should parse"),
+                );
+                headers
+            }),
+        }
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     base_url: String,
-    http_client: reqwest::Client,
+    http_client: HttpClient,
 }
 
 impl Client {
     /// Create a new OpenAI client with the given API key.
     pub fn new(api_key: &str) -> Self {
-        Self::from_url(api_key, OPENAI_API_BASE_URL)
+        ClientBuilder::new(api_key).build()
     }
 
     /// Create a new OpenAI client with the given API key and base API URL.
     pub fn from_url(api_key: &str, base_url: &str) -> Self {
-        Self {
-            base_url: base_url.to_string(),
-            http_client: reqwest::Client::builder()
-                .default_headers({
-                    let mut headers = reqwest::header::HeaderMap::new();
-                    headers.insert(
-                        "Authorization",
-                        format!("Bearer {}", api_key)
-                            .parse()
-                            .expect("Bearer token should parse"),
-                    );
-                    headers
-                })
-                .build()
-                .expect("OpenAI reqwest client should build"),
-        }
+        ClientBuilder::new(api_key).base_url(base_url).build()
     }
 
     /// Create a new OpenAI client from the `OPENAI_API_KEY` environment variable.
@@ -59,11 +107,21 @@ impl Client {
         Self::new(&api_key)
     }
 
-    pub(crate) fn post(&self, path: &str) -> reqwest::RequestBuilder {
+    pub(crate) fn post(&self, path: &str) -> RequestBuilder {
         let url = format!("{}/{}", self.base_url, path).replace("//", "/");
         self.http_client.post(url)
     }
 
+    pub(crate) fn get(&self, path: &str) -> RequestBuilder {
+        let url = format!("{}/{}", self.base_url, path).replace("//", "/");
+        self.http_client.get(url)
+    }
+
+    pub(crate) fn delete(&self, path: &str) -> RequestBuilder {
+        let url = format!("{}/{}", self.base_url, path).replace("//", "/");
+        self.http_client.delete(url)
+    }
+
     /// Create an embedding model with the given name.
     /// Note: default embedding dimension of 0 will be used if model is not known.
     /// If this is the case, it's better to use function `embedding_model_with_ndims`
@@ -210,6 +268,12 @@ impl Client {
     }
 }
 
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Usage {
     pub prompt_tokens: usize,
@@ -241,7 +305,8 @@ pub(crate) enum ApiResponse<T> {
 mod tests {
     use crate::message::ImageDetail;
     use crate::providers::openai::{
-        AssistantContent, Function, ImageUrl, Message, ToolCall, ToolType, UserContent,
+        from_openai_json, to_openai_json, AssistantContent, Function, ImageUrl, InputAudio,
+        InputFile, Message, ToolCall, ToolType, UserContent,
     };
     use crate::{message, OneOrMany};
     use serde_path_to_error::deserialize;
@@ -530,4 +595,331 @@ mod tests {
         assert_eq!(original_user_message[0], user_message);
         assert_eq!(original_assistant_message[0], assistant_message);
     }
+
+    #[test]
+    fn test_image_conversion() {
+        let user_message = message::Message::User {
+            content: OneOrMany::many(vec![
+                message::UserContent::image_base64("aGVsbG8=", message::ImageMediaType::PNG),
+                message::UserContent::image_url("https://example.com/flurbo.png"),
+            ])
+            .unwrap(),
+        };
+
+        let converted_user_message: Vec<Message> = user_message.try_into().unwrap();
+
+        match converted_user_message[0].clone() {
+            Message::User { content, .. } => {
+                assert_eq!(
+                    content.iter().cloned().collect::<Vec<_>>(),
+                    vec![
+                        UserContent::Image {
+                            image_url: ImageUrl {
+                                url: "data:image/png;base64,aGVsbG8=".to_string(),
+                                detail: ImageDetail::Auto,
+                            }
+                        },
+                        UserContent::Image {
+                            image_url: ImageUrl {
+                                url: "https://example.com/flurbo.png".to_string(),
+                                detail: ImageDetail::Auto,
+                            }
+                        },
+                    ]
+                );
+            }
+            _ => panic!("Expected user message"),
+        }
+
+        let original_user_message: message::Message =
+            converted_user_message[0].clone().try_into().unwrap();
+
+        assert_eq!(
+            original_user_message,
+            message::Message::User {
+                content: OneOrMany::many(vec![
+                    message::UserContent::Image(
+                        message::Image::base64("aGVsbG8=", message::ImageMediaType::PNG)
+                            .with_detail(ImageDetail::Auto)
+                    ),
+                    message::UserContent::Image(
+                        message::Image::url("https://example.com/flurbo.png")
+                            .with_detail(ImageDetail::Auto)
+                    ),
+                ])
+                .unwrap(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_audio_conversion() {
+        let user_message = message::Message::User {
+            content: OneOrMany::many(vec![
+                message::UserContent::text("What is said in this voice note?"),
+                message::UserContent::Audio(message::Audio::from_bytes(
+                    b"hello",
+                    message::AudioMediaType::WAV,
+                )),
+            ])
+            .unwrap(),
+        };
+
+        let converted_user_message: Vec<Message> = user_message.clone().try_into().unwrap();
+
+        match converted_user_message[0].clone() {
+            Message::User { content, .. } => {
+                assert_eq!(
+                    content.iter().nth(1).unwrap(),
+                    &UserContent::Audio {
+                        input_audio: InputAudio {
+                            data: "aGVsbG8=".to_string(),
+                            format: message::AudioMediaType::WAV,
+                        }
+                    }
+                );
+            }
+            _ => panic!("Expected user message"),
+        }
+
+        let original_user_message: message::Message =
+            converted_user_message[0].clone().try_into().unwrap();
+        assert_eq!(original_user_message, user_message);
+
+        let flac_message: message::Message =
+            message::Audio::from_bytes(b"hello", message::AudioMediaType::FLAC).into();
+        assert!(Vec::<Message>::try_from(flac_message).is_err());
+    }
+
+    #[test]
+    fn test_document_conversion() {
+        let user_message = message::Message::User {
+            content: OneOrMany::many(vec![
+                message::UserContent::Document(message::Document::from_bytes(
+                    "%PDF",
+                    message::DocumentMediaType::PDF,
+                )),
+                message::UserContent::Document(message::Document::file_id("file-abc123", None)),
+            ])
+            .unwrap(),
+        };
+
+        let converted_user_message: Vec<Message> = user_message.clone().try_into().unwrap();
+
+        match converted_user_message[0].clone() {
+            Message::User { content, .. } => {
+                assert_eq!(
+                    content.iter().cloned().collect::<Vec<_>>(),
+                    vec![
+                        UserContent::File {
+                            file: InputFile {
+                                file_id: None,
+                                filename: Some("document.pdf".to_string()),
+                                file_data: Some("data:application/pdf;base64,JVBERg==".to_string()),
+                            }
+                        },
+                        UserContent::File {
+                            file: InputFile {
+                                file_id: Some("file-abc123".to_string()),
+                                filename: None,
+                                file_data: None,
+                            }
+                        },
+                    ]
+                );
+            }
+            _ => panic!("Expected user message"),
+        }
+
+        let original_user_message: message::Message =
+            converted_user_message[0].clone().try_into().unwrap();
+        assert_eq!(original_user_message, user_message);
+
+        let html_message = message::Message::User {
+            content: OneOrMany::one(message::UserContent::Document(message::Document::base64(
+                "PGh0bWw+",
+                message::DocumentMediaType::HTML,
+            ))),
+        };
+        assert!(Vec::<Message>::try_from(html_message).is_err());
+    }
+
+    #[test]
+    fn test_openai_json() {
+        let history = vec![
+            message::Message::user("What is the weather in Paris?"),
+            message::Message::Assistant {
+                content: OneOrMany::one(message::AssistantContent::tool_call(
+                    "call_1",
+                    "get_weather",
+                    serde_json::json!({"city": "Paris"}),
+                )),
+            },
+            message::Message::User {
+                content: OneOrMany::one(message::UserContent::tool_result(
+                    "call_1",
+                    OneOrMany::one(message::ToolResultContent::text("15 degrees")),
+                )),
+            },
+        ];
+
+        let json = history
+            .iter()
+            .map(to_openai_json)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+            .concat();
+
+        assert_eq!(
+            json,
+            vec![
+                serde_json::json!({
+                    "role": "user",
+                    "content": [{"type": "text", "text": "What is the weather in Paris?"}]
+                }),
+                serde_json::json!({
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+                    }]
+                }),
+                serde_json::json!({
+                    "role": "tool",
+                    "tool_call_id": "call_1",
+                    "content": [{"type": "text", "text": "15 degrees"}]
+                }),
+            ]
+        );
+
+        let imported = json
+            .into_iter()
+            .map(from_openai_json)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(imported, history);
+
+        // Shorthand content of other SDKs
+        let imported = vec![
+            serde_json::json!({"role": "user", "content": "What is the weather in Paris?"}),
+            serde_json::json!({
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\": \"Paris\"}"}
+                }]
+            }),
+            serde_json::json!({"role": "tool", "tool_call_id": "call_1", "content": "15 degrees"}),
+        ]
+        .into_iter()
+        .map(from_openai_json)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+        assert_eq!(imported, history);
+
+        assert!(from_openai_json(serde_json::json!({"role": "robot"})).is_err());
+    }
+
+    #[test]
+    fn test_prefill() {
+        use crate::completion::{CompletionError, CompletionModel as _};
+
+        let result = super::Client::new("TEST")
+            .completion_model(crate::providers::openai::GPT_4O)
+            .completion_request("Give me the weather in Paris as JSON")
+            .prefill("{")
+            .dry_run();
+
+        assert!(matches!(result, Err(CompletionError::RequestError(_))));
+    }
+
+    #[test]
+    fn test_system_message_conversion() {
+        let system_message = message::Message::system("Now answer in French.");
+
+        assert_eq!(
+            to_openai_json(&system_message).unwrap(),
+            vec![serde_json::json!({
+                "role": "system",
+                "content": [{"type": "text", "text": "Now answer in French."}]
+            })]
+        );
+
+        for role in ["system", "developer"] {
+            let imported = from_openai_json(serde_json::json!({
+                "role": role,
+                "content": "Now answer in French."
+            }))
+            .unwrap();
+            assert_eq!(imported, system_message);
+        }
+    }
+
+    #[test]
+    fn test_reasoning_model_developer_messages() {
+        use crate::completion::CompletionModel as _;
+
+        let request = super::Client::new("TEST")
+            .completion_model(crate::providers::openai::O3_MINI)
+            .completion_request("Hello!")
+            .preamble("Be concise.".to_string())
+            .messages(vec![message::Message::system("Now answer in French.")])
+            .dry_run()
+            .unwrap();
+
+        let roles = request.body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| message["role"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(roles, vec!["developer", "developer", "user"]);
+    }
+
+    /// Transport answering every request with an error and a `200 OK` status
+    struct OkErrorTransport;
+
+    impl crate::http_client::HttpTransport for OkErrorTransport {
+        fn execute(
+            &self,
+            _request: http::Request<bytes::Bytes>,
+        ) -> futures::future::BoxFuture<
+            '_,
+            Result<http::Response<bytes::Bytes>, crate::http_client::TransportError>,
+        > {
+            Box::pin(async {
+                let body = serde_json::json!({
+                    "message": "Invalid model",
+                    "type": "invalid_request_error",
+                    "code": "model_not_found"
+                });
+                Ok(http::Response::new(body.to_string().into()))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_with_success_status() {
+        use crate::completion::{CompletionError, CompletionModel};
+        use crate::http_client::HttpClientSettings;
+
+        let model = crate::providers::openai::Client::new("fake-key")
+            .with_transport(OkErrorTransport)
+            .completion_model(crate::providers::openai::GPT_4O_MINI);
+        let error = model
+            .completion(model.completion_request("Hello").build())
+            .await
+            .unwrap_err();
+
+        let CompletionError::ApiError(error) = error else {
+            panic!("Unexpected error: {error}");
+        };
+        assert_eq!(error.code.as_deref(), Some("model_not_found"));
+        assert_eq!(error.error_type.as_deref(), Some("invalid_request_error"));
+        assert_eq!(error.message, "Invalid model");
+    }
 }
@@ -4,6 +4,7 @@
 
 use super::{ApiErrorResponse, ApiResponse, Client, Usage};
 use crate::completion::{CompletionError, CompletionRequest};
+use crate::http_client::ApiError;
 use crate::message::{AudioMediaType, ImageDetail};
 use crate::one_or_many::string_or_one_or_many;
 use crate::{completion, json_utils, message, OneOrMany};
@@ -38,6 +39,10 @@ pub const GPT_4O: &str = "gpt-4o";
 pub const GPT_4O_MINI: &str = "gpt-4o-mini";
 /// `gpt-4o-2024-05-13` completion model
 pub const GPT_4O_2024_05_13: &str = "gpt-4o-2024-05-13";
+/// `gpt-4o-audio-preview` completion model (accepts audio content)
+pub const GPT_4O_AUDIO_PREVIEW: &str = "gpt-4o-audio-preview";
+/// `gpt-4o-mini-audio-preview` completion model (accepts audio content)
+pub const GPT_4O_MINI_AUDIO_PREVIEW: &str = "gpt-4o-mini-audio-preview";
 /// `gpt-4-turbo` completion model
 pub const GPT_4_TURBO: &str = "gpt-4-turbo";
 /// `gpt-4-turbo-2024-04-09` completion model
@@ -142,11 +147,23 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
 
         Ok(completion::CompletionResponse {
             choice,
+            usage: response.usage.as_ref().map(Into::into).unwrap_or_default(),
+            timing: completion::ResponseTiming::default(),
             raw_response: response,
         })
     }
 }
 
+impl From<&Usage> for completion::Usage {
+    fn from(usage: &Usage) -> Self {
+        completion::Usage {
+            input_tokens: usage.prompt_tokens as u64,
+            output_tokens: usage.total_tokens.saturating_sub(usage.prompt_tokens) as u64,
+            total_tokens: usage.total_tokens as u64,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Choice {
     pub index: usize,
@@ -164,6 +181,14 @@ pub enum Message {
         #[serde(skip_serializing_if = "Option::is_none")]
         name: Option<String>,
     },
+    /// System message of reasoning models (e.g.: `o1`), which the preamble and system messages are
+    /// sent as to them
+    Developer {
+        #[serde(deserialize_with = "string_or_one_or_many")]
+        content: OneOrMany<SystemContent>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
     User {
         #[serde(deserialize_with = "string_or_one_or_many")]
         content: OneOrMany<UserContent>,
@@ -171,7 +196,12 @@ pub enum Message {
         name: Option<String>,
     },
     Assistant {
-        #[serde(default, deserialize_with = "json_utils::string_or_vec")]
+        /// Content of the message, `null` when the message only has tool calls
+        #[serde(
+            default,
+            deserialize_with = "json_utils::string_or_vec",
+            serialize_with = "json_utils::empty_vec_as_null"
+        )]
         content: Vec<AssistantContent>,
         #[serde(skip_serializing_if = "Option::is_none")]
         refusal: Option<String>,
@@ -189,11 +219,72 @@ pub enum Message {
     #[serde(rename = "tool")]
     ToolResult {
         tool_call_id: String,
+        #[serde(deserialize_with = "string_or_one_or_many")]
         content: OneOrMany<ToolResultContent>,
     },
 }
 
+/// Export a message to the OpenAI chat completions format, the de facto interchange format of
+/// chat histories (used by other SDKs, fine-tuning datasets and logging systems).
+/// A user message with tool results becomes one OpenAI `tool` message per tool result, hence the
+/// list of messages.
+///
+/// # Example
+/// ```rust
+/// use rig::{message::Message, providers::openai};
+///
+/// let json = openai::to_openai_json(&Message::user("Hello!"))?;
+/// // [{"role": "user", "content": [{"type": "text", "text": "Hello!"}]}]
+/// ```
+pub fn to_openai_json(
+    message: &message::Message,
+) -> Result<Vec<serde_json::Value>, message::MessageError> {
+    let messages: Vec<Message> = message.clone().try_into()?;
+    messages
+        .into_iter()
+        .map(serde_json::to_value)
+        .collect::<Result<_, _>>()
+        .map_err(message::MessageError::JsonError)
+}
+
+/// Import a message in the OpenAI chat completions format (e.g.: a message of a chat history
+/// exported by another SDK). Content given as a string and assistant messages with a `null`
+/// content are supported. `developer` messages are imported as system messages.
+///
+/// # Example
+/// ```rust
+/// use rig::providers::openai;
+///
+/// let message = openai::from_openai_json(serde_json::json!({
+///     "role": "assistant",
+///     "content": "Hi, how can I help?"
+/// }))?;
+/// ```
+pub fn from_openai_json(
+    json: serde_json::Value,
+) -> Result<message::Message, message::MessageError> {
+    serde_json::from_value::<Message>(json)
+        .map_err(message::MessageError::JsonError)?
+        .try_into()
+}
+
+/// Whether `model` is a reasoning model (e.g.: `o1` or `o3-mini`), which takes developer messages
+/// instead of system messages
+fn is_reasoning_model(model: &str) -> bool {
+    ["o1", "o3", "o4"]
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+}
+
 impl Message {
+    /// Turn a system message into a developer message (other messages are unchanged)
+    fn into_developer(self) -> Self {
+        match self {
+            Message::System { content, name } => Message::Developer { content, name },
+            message => message,
+        }
+    }
+
     pub fn system(content: &str) -> Self {
         Message::System {
             content: OneOrMany::one(content.to_owned().into()),
@@ -241,6 +332,70 @@ pub enum UserContent {
     Audio {
         input_audio: InputAudio,
     },
+    File {
+        file: InputFile,
+    },
+}
+
+/// File attached to a user message: a file uploaded with the Files API, or the base64 encoded
+/// data of a PDF
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct InputFile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    /// Data URL of the file (e.g.: `data:application/pdf;base64,...`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_data: Option<String>,
+}
+
+/// Prefix of the data URLs of base64 encoded PDFs
+const PDF_DATA_URL_PREFIX: &str = "data:application/pdf;base64,";
+
+impl TryFrom<message::Document> for UserContent {
+    type Error = message::MessageError;
+
+    fn try_from(document: message::Document) -> Result<Self, Self::Error> {
+        match (document.format, document.media_type) {
+            (Some(message::ContentFormat::FileId), _) => Ok(UserContent::File {
+                file: InputFile {
+                    file_id: Some(document.data),
+                    filename: None,
+                    file_data: None,
+                },
+            }),
+            (Some(message::ContentFormat::String), _) => Ok(UserContent::Text {
+                text: document.data,
+            }),
+            (_, Some(message::DocumentMediaType::PDF) | None) => Ok(UserContent::File {
+                file: InputFile {
+                    file_id: None,
+                    filename: Some("document.pdf".to_string()),
+                    file_data: Some(format!("{PDF_DATA_URL_PREFIX}{}", document.data)),
+                },
+            }),
+            (_, Some(media_type)) => Err(message::MessageError::ConversionError(format!(
+                "Unsupported base64 document media type {:?}, OpenAI only supports PDF",
+                media_type
+            ))),
+        }
+    }
+}
+
+impl From<InputFile> for message::Document {
+    fn from(file: InputFile) -> Self {
+        match (file.file_id, file.file_data) {
+            (Some(file_id), _) => message::Document::file_id(file_id, None),
+            (None, file_data) => {
+                let file_data = file_data.unwrap_or_default();
+                let data = file_data
+                    .strip_prefix(PDF_DATA_URL_PREFIX)
+                    .unwrap_or(&file_data);
+                message::Document::base64(data, message::DocumentMediaType::PDF)
+            }
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -256,6 +411,33 @@ pub struct InputAudio {
     pub format: AudioMediaType,
 }
 
+impl TryFrom<message::Audio> for InputAudio {
+    type Error = message::MessageError;
+
+    fn try_from(audio: message::Audio) -> Result<Self, Self::Error> {
+        if audio.is_url() {
+            return Err(message::MessageError::ConversionError(
+                "Audio urls are not supported by OpenAI".into(),
+            ));
+        }
+
+        match audio.media_type {
+            Some(format @ (AudioMediaType::WAV | AudioMediaType::MP3)) => Ok(InputAudio {
+                data: audio.data,
+                format,
+            }),
+            None => Ok(InputAudio {
+                data: audio.data,
+                format: AudioMediaType::MP3,
+            }),
+            Some(media_type) => Err(message::MessageError::ConversionError(format!(
+                "Unsupported audio media type {:?}, OpenAI only supports wav and mp3",
+                media_type
+            ))),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct ToolResultContent {
     #[serde(default)]
@@ -363,36 +545,24 @@ impl TryFrom<message::Message> for Vec<Message> {
                     );
 
                     Ok(vec![Message::User {
-                        content: other_content.map(|content| match content {
-                            message::UserContent::Text(message::Text { text }) => {
-                                UserContent::Text { text }
-                            }
-                            message::UserContent::Image(message::Image {
-                                data, detail, ..
-                            }) => UserContent::Image {
-                                image_url: ImageUrl {
-                                    url: data,
-                                    detail: detail.unwrap_or_default(),
-                                },
-                            },
-                            message::UserContent::Document(message::Document { data, .. }) => {
-                                UserContent::Text { text: data }
-                            }
-                            message::UserContent::Audio(message::Audio {
-                                data,
-                                media_type,
-                                ..
-                            }) => UserContent::Audio {
-                                input_audio: InputAudio {
-                                    data,
-                                    format: match media_type {
-                                        Some(media_type) => media_type,
-                                        None => AudioMediaType::MP3,
+                        content: other_content.try_map(|content| {
+                            Ok::<_, message::MessageError>(match content {
+                                message::UserContent::Text(message::Text { text }) => {
+                                    UserContent::Text { text }
+                                }
+                                message::UserContent::Image(image) => UserContent::Image {
+                                    image_url: ImageUrl {
+                                        url: image.to_url(),
+                                        detail: image.detail.unwrap_or_default(),
                                     },
                                 },
-                            },
-                            _ => unreachable!(),
-                        }),
+                                message::UserContent::Document(document) => document.try_into()?,
+                                message::UserContent::Audio(audio) => UserContent::Audio {
+                                    input_audio: audio.try_into()?,
+                                },
+                                _ => unreachable!(),
+                            })
+                        })?,
                         name: None,
                     }])
                 }
@@ -425,6 +595,7 @@ impl TryFrom<message::Message> for Vec<Message> {
                         .collect::<Vec<_>>(),
                 }])
             }
+            message::Message::System { content } => Ok(vec![Message::system(&content)]),
         }
     }
 }
@@ -507,11 +678,15 @@ impl TryFrom<Message> for message::Message {
                 )),
             },
 
-            // System messages should get stripped out when converting message's, this is just a
-            // stop gap to avoid obnoxious error handling or panic occuring.
-            Message::System { content, .. } => message::Message::User {
-                content: content.map(|content| message::UserContent::text(content.text)),
-            },
+            Message::System { content, .. } | Message::Developer { content, .. } => {
+                message::Message::System {
+                    content: content
+                        .into_iter()
+                        .map(|content| content.text)
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                }
+            }
         })
     }
 }
@@ -520,17 +695,15 @@ impl From<UserContent> for message::UserContent {
     fn from(content: UserContent) -> Self {
         match content {
             UserContent::Text { text } => message::UserContent::text(text),
-            UserContent::Image { image_url } => message::UserContent::image(
-                image_url.url,
-                Some(message::ContentFormat::default()),
-                None,
-                Some(image_url.detail),
+            UserContent::Image { image_url } => message::UserContent::Image(
+                message::Image::from_url(image_url.url).with_detail(image_url.detail),
             ),
             UserContent::Audio { input_audio } => message::UserContent::audio(
                 input_audio.data,
                 Some(message::ContentFormat::default()),
                 Some(input_audio.format),
             ),
+            UserContent::File { file } => message::UserContent::Document(file.into()),
         }
     }
 }
@@ -605,6 +778,9 @@ impl CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<Value, CompletionError> {
+        // OpenAI answers a trailing assistant message instead of continuing it
+        completion_request.reject_prefill("OpenAI")?;
+
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => vec![Message::system(preamble)],
@@ -628,6 +804,13 @@ impl CompletionModel {
         full_history.extend(chat_history);
         full_history.extend(prompt);
 
+        if is_reasoning_model(&self.model) {
+            full_history = full_history
+                .into_iter()
+                .map(Message::into_developer)
+                .collect();
+        }
+
         let request = if completion_request.tools.is_empty() {
             json!({
                 "model": self.model,
@@ -669,11 +852,22 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = CompletionResponse;
 
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client.post("/chat/completions").json(&request),
+        )
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: CompletionRequest,
     ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let request = self.create_completion_request(completion_request)?;
 
         let response = self
@@ -684,8 +878,9 @@ impl completion::CompletionModel for CompletionModel {
             .await?;
 
         if response.status().is_success() {
+            let status = response.status();
             let t = response.text().await?;
-            tracing::debug!(target: "rig", "OpenAI completion error: {}", t);
+            tracing::debug!(target: "rig", "OpenAI completion response: {}", t);
 
             match serde_json::from_str::<ApiResponse<CompletionResponse>>(&t)? {
                 ApiResponse::Ok(response) => {
@@ -695,10 +890,11 @@ impl completion::CompletionModel for CompletionModel {
                     );
                     response.try_into()
                 }
-                ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.message)),
+                // Error returned with a success status
+                ApiResponse::Err(_) => Err(ApiError::from_body(status, None, t).into()),
             }
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -1,6 +1,7 @@
 use super::{ApiErrorResponse, ApiResponse, Client, Usage};
 use crate::embeddings;
 use crate::embeddings::EmbeddingError;
+use crate::http_client::ApiError;
 use serde::Deserialize;
 use serde_json::json;
 
@@ -63,6 +64,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
         &self,
         documents: impl IntoIterator<Item = String>,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        crate::telemetry::record_model(&self.model);
         let documents = documents.into_iter().collect::<Vec<_>>();
 
         let response = self
@@ -76,7 +78,10 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
             .await?;
 
         if response.status().is_success() {
-            match response.json::<ApiResponse<EmbeddingResponse>>().await? {
+            let status = response.status();
+            let body = response.text().await?;
+
+            match serde_json::from_str::<ApiResponse<EmbeddingResponse>>(&body)? {
                 ApiResponse::Ok(response) => {
                     tracing::info!(target: "rig",
                         "OpenAI embedding token usage: {}",
@@ -99,10 +104,11 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
                         })
                         .collect())
                 }
-                ApiResponse::Err(err) => Err(EmbeddingError::ProviderError(err.message)),
+                // Error returned with a success status
+                ApiResponse::Err(_) => Err(ApiError::from_body(status, None, body).into()),
             }
         } else {
-            Err(EmbeddingError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
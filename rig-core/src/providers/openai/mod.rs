@@ -8,6 +8,7 @@
 //!
 //! let gpt4o = client.completion_model(openai::GPT_4O);
 //! ```
+pub mod batch;
 pub mod client;
 pub mod completion;
 pub mod embedding;
@@ -1,12 +1,13 @@
+use super::client::Usage;
 use super::completion::CompletionModel;
 use crate::completion::{CompletionError, CompletionRequest};
+use crate::http_client::{ApiError, RequestBuilder};
 use crate::json_utils;
 use crate::json_utils::merge;
 use crate::streaming;
 use crate::streaming::{StreamingCompletionModel, StreamingResult};
 use async_stream::stream;
 use futures::StreamExt;
-use reqwest::RequestBuilder;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
@@ -43,7 +44,12 @@ struct StreamingChoice {
 
 #[derive(Deserialize)]
 struct StreamingCompletionResponse {
+    /// Empty in the last chunk, which only carries the usage
+    #[serde(default)]
     choices: Vec<StreamingChoice>,
+    /// Sent in the last chunk (by OpenAI, when `stream_options.include_usage` is set)
+    #[serde(default)]
+    usage: Option<Usage>,
 }
 
 impl StreamingCompletionModel for CompletionModel {
@@ -51,8 +57,12 @@ impl StreamingCompletionModel for CompletionModel {
         &self,
         completion_request: CompletionRequest,
     ) -> Result<StreamingResult, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let mut request = self.create_completion_request(completion_request)?;
-        request = merge(request, json!({"stream": true}));
+        request = merge(
+            request,
+            json!({"stream": true, "stream_options": {"include_usage": true}}),
+        );
 
         let builder = self.client.post("/chat/completions").json(&request);
         send_compatible_streaming_request(builder).await
@@ -60,16 +70,12 @@ impl StreamingCompletionModel for CompletionModel {
 }
 
 pub async fn send_compatible_streaming_request(
-    request_builder: RequestBuilder,
+    request_builder: impl Into<RequestBuilder>,
 ) -> Result<StreamingResult, CompletionError> {
-    let response = request_builder.send().await?;
+    let response = request_builder.into().send().await?;
 
     if !response.status().is_success() {
-        return Err(CompletionError::ProviderError(format!(
-            "{}: {}",
-            response.status(),
-            response.text().await?
-        )));
+        return Err(ApiError::from_response(response).await?.into());
     }
 
     // Handle OpenAI Compatible SSE chunks
@@ -127,7 +133,13 @@ pub async fn send_compatible_streaming_request(
                     continue;
                 };
 
-                let choice = data.choices.first().expect("Should have at least one choice");
+                if let Some(usage) = &data.usage {
+                    yield Ok(streaming::StreamingChoice::Usage(usage.into()));
+                }
+
+                let Some(choice) = data.choices.first() else {
+                    continue;
+                };
 
                 let delta = &choice.delta;
 
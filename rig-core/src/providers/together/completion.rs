@@ -5,6 +5,7 @@
 
 use crate::{
     completion::{self, CompletionError},
+    http_client::ApiError,
     json_utils,
     providers::openai,
 };
@@ -144,6 +145,8 @@ impl CompletionModel {
         &self,
         completion_request: completion::CompletionRequest,
     ) -> Result<serde_json::Value, CompletionError> {
+        completion_request.reject_prefill("Together AI")?;
+
         let mut full_history: Vec<openai::Message> = match &completion_request.preamble {
             Some(preamble) => vec![openai::Message::system(preamble)],
             None => vec![],
@@ -186,11 +189,22 @@ impl CompletionModel {
 impl completion::CompletionModel for CompletionModel {
     type Response = openai::CompletionResponse;
 
+    fn prepare_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client.post("/v1/chat/completions").json(&request),
+        )
+    }
+
     #[cfg_attr(feature = "worker", worker::send)]
     async fn completion(
         &self,
         completion_request: completion::CompletionRequest,
     ) -> Result<completion::CompletionResponse<openai::CompletionResponse>, CompletionError> {
+        crate::telemetry::record_model(&self.model);
         let request = self.create_completion_request(completion_request)?;
 
         let response = self
@@ -215,7 +229,7 @@ impl completion::CompletionModel for CompletionModel {
                 ApiResponse::Error(err) => Err(CompletionError::ProviderError(err.error)),
             }
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -14,7 +14,7 @@ pub mod completion;
 pub mod embedding;
 pub mod streaming;
 
-pub use client::Client;
+pub use client::{Client, ClientBuilder};
 pub use completion::{
     ALPACA_7B, CHRONOS_HERMES_13B, CODE_LLAMA_13B_INSTRUCT, CODE_LLAMA_13B_INSTRUCT_TOGETHER,
     CODE_LLAMA_34B_INSTRUCT, CODE_LLAMA_34B_INSTRUCT_TOGETHER, CODE_LLAMA_70B_INSTRUCT,
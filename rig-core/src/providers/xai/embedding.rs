@@ -7,6 +7,7 @@ use serde::Deserialize;
 use serde_json::json;
 
 use crate::embeddings::{self, EmbeddingError};
+use crate::http_client::ApiError;
 
 use super::{
     client::xai_api_types::{ApiErrorResponse, ApiResponse},
@@ -74,6 +75,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
         &self,
         documents: impl IntoIterator<Item = String>,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        crate::telemetry::record_model(&self.model);
         let documents = documents.into_iter().collect::<Vec<_>>();
 
         let response = self
@@ -108,7 +110,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
                 ApiResponse::Error(err) => Err(EmbeddingError::ProviderError(err.message())),
             }
         } else {
-            Err(EmbeddingError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -14,6 +14,6 @@ pub mod completion;
 pub mod embedding;
 pub mod streaming;
 
-pub use client::Client;
+pub use client::{Client, ClientBuilder};
 pub use completion::GROK_BETA;
 pub use embedding::EMBEDDING_V1;
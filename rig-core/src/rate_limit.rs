@@ -0,0 +1,238 @@
+//! This module provides a client-side rate limiter enforcing requests per minute (RPM) and
+//! tokens per minute (TPM) budgets on the requests sent to a provider.
+//!
+//! A [RateLimiter] can be attached to any provider client with its `with_rate_limiter` method.
+//! The limiter is cheap to clone and all clones share the same budget, so a single limiter can
+//! be attached to several clients (e.g.: the clients of the agents of a multi-agent application
+//! sharing the same provider quota).
+//!
+//! The number of tokens of a request is estimated from its body (roughly 4 bytes per token, plus
+//! the requested `max_tokens`). When a budget is exhausted, requests either wait until the budget
+//! is replenished ([RateLimitBehavior::Wait], the default) or immediately fail with an
+//! [HttpError::RateLimitExceeded](crate::http_client::HttpError::RateLimitExceeded) error
+//! ([RateLimitBehavior::Error]).
+//!
+//! # Example
+//! ```rust
+//! use rig::{http_client::HttpClientSettings, providers::openai, rate_limit::RateLimiter};
+//!
+//! let rate_limiter = RateLimiter::new()
+//!     .requests_per_minute(500)
+//!     .tokens_per_minute(30_000);
+//!
+//! let openai = openai::Client::from_env().with_rate_limiter(rate_limiter.clone());
+//! ```
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::wasm_compat::Instant;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// What to do with a request exceeding the budget of a [RateLimiter]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RateLimitBehavior {
+    /// Wait until the budget allows the request
+    #[default]
+    Wait,
+    /// Fail the request immediately
+    Error,
+}
+
+/// Error returned by [RateLimiter::acquire] when the budget is exceeded and the limiter is
+/// configured with [RateLimitBehavior::Error]
+#[derive(Debug, thiserror::Error)]
+#[error("Client-side rate limit exceeded, retry in {retry_after:?}")]
+pub struct RateLimitExceeded {
+    /// Time until the budget allows the request
+    pub retry_after: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Window {
+    /// Time and estimated tokens of the requests sent during the last minute
+    requests: VecDeque<(Instant, u64)>,
+}
+
+impl Window {
+    fn purge(&mut self, now: Instant) {
+        while let Some((sent_at, _)) = self.requests.front() {
+            if now.duration_since(*sent_at) < WINDOW {
+                break;
+            }
+            self.requests.pop_front();
+        }
+    }
+
+    fn tokens(&self) -> u64 {
+        self.requests.iter().map(|(_, tokens)| tokens).sum()
+    }
+}
+
+/// Shared requests per minute and tokens per minute budget
+#[derive(Clone, Debug, Default)]
+pub struct RateLimiter {
+    requests_per_minute: Option<u32>,
+    tokens_per_minute: Option<u64>,
+    behavior: RateLimitBehavior,
+    window: Arc<Mutex<Window>>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter without any budget
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of requests per minute. `0` removes the requests per minute budget
+    /// (instead of blocking every request forever).
+    pub fn requests_per_minute(mut self, requests_per_minute: u32) -> Self {
+        self.requests_per_minute = (requests_per_minute > 0).then_some(requests_per_minute);
+        self
+    }
+
+    /// Set the maximum number of (estimated) tokens per minute
+    pub fn tokens_per_minute(mut self, tokens_per_minute: u64) -> Self {
+        self.tokens_per_minute = Some(tokens_per_minute);
+        self
+    }
+
+    /// Set what to do with requests exceeding the budget (see [RateLimitBehavior])
+    pub fn behavior(mut self, behavior: RateLimitBehavior) -> Self {
+        self.behavior = behavior;
+        self
+    }
+
+    /// Reserve the budget for a request of `tokens` estimated tokens, waiting for the budget to
+    /// be replenished if needed. Returns the time spent waiting.
+    pub async fn acquire(&self, tokens: u64) -> Result<Duration, RateLimitExceeded> {
+        let start = Instant::now();
+
+        loop {
+            let wait = match self.try_acquire(tokens, Instant::now()) {
+                Ok(()) => return Ok(start.elapsed()),
+                Err(wait) => wait,
+            };
+
+            if self.behavior == RateLimitBehavior::Error {
+                return Err(RateLimitExceeded { retry_after: wait });
+            }
+
+            tracing::debug!(target: "rig", "Client-side rate limit reached, waiting {wait:?}");
+            futures_timer::Delay::new(wait).await;
+        }
+    }
+
+    /// Reserve the budget if available, otherwise return the time until it might be
+    fn try_acquire(&self, tokens: u64, now: Instant) -> Result<(), Duration> {
+        let mut window = self.window.lock().expect("Mutex should not be poisoned");
+        window.purge(now);
+
+        let requests_exceeded = self
+            .requests_per_minute
+            .is_some_and(|rpm| window.requests.len() >= rpm as usize);
+        // A request larger than the whole budget is let through once the window is empty,
+        // otherwise it would never be sent
+        let tokens_exceeded = self
+            .tokens_per_minute
+            .is_some_and(|tpm| !window.requests.is_empty() && window.tokens() + tokens > tpm);
+
+        if !requests_exceeded && !tokens_exceeded {
+            window.requests.push_back((now, tokens));
+            return Ok(());
+        }
+
+        // Wait until the oldest request leaves the window
+        let wait = window
+            .requests
+            .front()
+            .map(|(sent_at, _)| (*sent_at + WINDOW).saturating_duration_since(now))
+            .unwrap_or_default();
+
+        Err(wait.max(Duration::from_millis(10)))
+    }
+}
+
+/// Estimate the number of tokens of a JSON request body: roughly 4 bytes per token for the
+/// prompt, plus the maximum number of tokens requested for the response (if any).
+pub(crate) fn estimate_tokens(body: &[u8]) -> u64 {
+    let prompt_tokens = body.len() as u64 / 4;
+
+    let max_tokens = serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|body| {
+            ["max_tokens", "max_completion_tokens", "max_new_tokens"]
+                .iter()
+                .find_map(|field| body.get(field).and_then(serde_json::Value::as_u64))
+                .or_else(|| body.pointer("/generationConfig/maxOutputTokens")?.as_u64())
+        })
+        .unwrap_or_default();
+
+    prompt_tokens + max_tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_per_minute() {
+        let limiter = RateLimiter::new().requests_per_minute(2);
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire(0, now).is_ok());
+        assert!(limiter.try_acquire(0, now).is_ok());
+        assert_eq!(limiter.try_acquire(0, now), Err(WINDOW));
+
+        // The budget is replenished once the first requests leave the window
+        assert!(limiter.try_acquire(0, now + WINDOW).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_zero_requests_per_minute() {
+        let limiter = RateLimiter::new()
+            .requests_per_minute(0)
+            .behavior(RateLimitBehavior::Error);
+
+        for _ in 0..3 {
+            assert!(limiter.acquire(0).await.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_tokens_per_minute() {
+        let limiter = RateLimiter::new().tokens_per_minute(100);
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire(60, now).is_ok());
+        assert!(limiter.try_acquire(50, now).is_err());
+        assert!(limiter.try_acquire(40, now).is_ok());
+
+        // Requests larger than the budget go through once the window is empty
+        let limiter = RateLimiter::new().tokens_per_minute(100);
+        assert!(limiter.try_acquire(500, now).is_ok());
+    }
+
+    #[test]
+    fn test_shared_budget() {
+        let limiter = RateLimiter::new().requests_per_minute(1);
+        let shared = limiter.clone();
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire(0, now).is_ok());
+        assert!(shared.try_acquire(0, now).is_err());
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        let body = serde_json::json!({"prompt": "a".repeat(400), "max_tokens": 100});
+        let body = serde_json::to_vec(&body).unwrap();
+
+        assert_eq!(estimate_tokens(&body), body.len() as u64 / 4 + 100);
+        assert_eq!(estimate_tokens(b"not json"), 2);
+    }
+}
@@ -0,0 +1,437 @@
+//! This module provides a [ModelRegistry] resolving model identifiers of the form
+//! `"provider:model"` (e.g.: `"openai:gpt-4o-mini"`, `"anthropic:claude-3-7-sonnet-latest"`)
+//! into ready-to-use completion and embedding models, so that the choice of models can live in
+//! configuration files rather than in code.
+//!
+//! [ModelRegistry::from_env] registers all the built-in providers, whose credentials are read
+//! from the same environment variables as their `from_env` constructors (e.g.: `OPENAI_API_KEY`)
+//! when a model is resolved. Custom providers (or built-in providers configured differently) can
+//! be registered with [ModelRegistry::register_completion_provider] and
+//! [ModelRegistry::register_embedding_provider].
+//!
+//! The resolved models are type-erased ([BoxCompletionModel], [BoxEmbeddingModel],
+//! [BoxTranscriptionModel] and, with the `audio` feature, `BoxSpeechModel`), so that voice agents
+//! can switch their speech-to-text and text-to-speech providers as well.
+//!
+//! # Example
+//! ```rust
+//! use rig::{completion::Prompt, registry::ModelRegistry};
+//!
+//! let registry = ModelRegistry::from_env();
+//!
+//! // e.g.: "openai:gpt-4o-mini"
+//! let model_id = std::env::var("AGENT_MODEL")?;
+//! let agent = rig::agent::AgentBuilder::new(registry.completion_model(&model_id)?).build();
+//!
+//! let embedding_model = registry.embedding_model("openai:text-embedding-3-small")?;
+//! let transcription_model = registry.transcription_model("groq:whisper-large-v3")?;
+//! ```
+
+use std::{collections::HashMap, sync::Arc};
+
+#[cfg(feature = "audio")]
+use crate::{
+    audio_generation::{AudioGenerationModel, BoxAudioGenerationModel},
+    speech::BoxSpeechModel,
+};
+use crate::{
+    completion::{BoxCompletionModel, CompletionModel},
+    embeddings::BoxEmbeddingModel,
+    providers::{
+        anthropic, azure, cohere, deepseek, galadriel, gemini, groq, huggingface, hyperbolic, mira,
+        moonshot, ollama, openai, openrouter, perplexity, together, xai,
+    },
+    streaming::StreamingCompletionModel,
+    transcription::{BoxTranscriptionModel, TranscriptionModel},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    /// The model identifier is not of the form `provider:model`
+    #[error("Invalid model identifier `{0}`, expected `provider:model`")]
+    InvalidIdentifier(String),
+
+    /// No provider is registered under this name (for this kind of model)
+    #[error("Unknown provider `{0}`")]
+    UnknownProvider(String),
+
+    /// The environment variable holding the credentials of the provider is not set
+    #[error("Environment variable {0} not set")]
+    MissingCredentials(String),
+
+    /// Error creating the provider client
+    #[error("ProviderError: {0}")]
+    ProviderError(String),
+}
+
+type CompletionFactory =
+    Arc<dyn Fn(&str) -> Result<BoxCompletionModel, RegistryError> + Send + Sync>;
+type EmbeddingFactory = Arc<dyn Fn(&str) -> Result<BoxEmbeddingModel, RegistryError> + Send + Sync>;
+type TranscriptionFactory =
+    Arc<dyn Fn(&str) -> Result<BoxTranscriptionModel, RegistryError> + Send + Sync>;
+#[cfg(feature = "audio")]
+type AudioGenerationFactory =
+    Arc<dyn Fn(&str) -> Result<BoxAudioGenerationModel, RegistryError> + Send + Sync>;
+
+/// Registry of providers resolving `"provider:model"` identifiers into models
+#[derive(Clone, Default)]
+pub struct ModelRegistry {
+    completion_providers: HashMap<String, CompletionFactory>,
+    embedding_providers: HashMap<String, EmbeddingFactory>,
+    transcription_providers: HashMap<String, TranscriptionFactory>,
+    #[cfg(feature = "audio")]
+    audio_generation_providers: HashMap<String, AudioGenerationFactory>,
+}
+
+impl ModelRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry of all the built-in providers, reading their credentials from the
+    /// environment when a model is resolved
+    pub fn from_env() -> Self {
+        let registry = Self::new()
+            .register_completion_provider("anthropic", |model| {
+                let client = anthropic::ClientBuilder::new(&env("ANTHROPIC_API_KEY")?).build();
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("azure", |model| {
+                Ok(azure_client()?.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("cohere", |model| {
+                let client = cohere::Client::new(&env("COHERE_API_KEY")?);
+                Ok(client.completion_model(model).boxed())
+            })
+            .register_completion_provider("deepseek", |model| {
+                let client = deepseek::Client::new(&env("DEEPSEEK_API_KEY")?);
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("galadriel", |model| {
+                let fine_tune_api_key = std::env::var("GALADRIEL_FINE_TUNE_API_KEY").ok();
+                let client = galadriel::Client::new(
+                    &env("GALADRIEL_API_KEY")?,
+                    fine_tune_api_key.as_deref(),
+                );
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("gemini", |model| {
+                let client = gemini::Client::new(&env("GEMINI_API_KEY")?);
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("groq", |model| {
+                let client = groq::Client::new(&env("GROQ_API_KEY")?);
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("huggingface", |model| {
+                let client = huggingface::Client::new(&env("HUGGINGFACE_API_KEY")?);
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("hyperbolic", |model| {
+                let client = hyperbolic::Client::new(&env("HYPERBOLIC_API_KEY")?);
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("mira", |model| {
+                let client = mira::Client::new(&env("MIRA_API_KEY")?)
+                    .map_err(|error| RegistryError::ProviderError(error.to_string()))?;
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("moonshot", |model| {
+                let client = moonshot::Client::new(&env("MOONSHOT_API_KEY")?);
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("ollama", |model| {
+                Ok(ollama::Client::new()
+                    .completion_model(model)
+                    .boxed_streaming())
+            })
+            .register_completion_provider("openai", |model| {
+                let client = openai::Client::new(&env("OPENAI_API_KEY")?);
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("openrouter", |model| {
+                let client = openrouter::Client::new(&env("OPENROUTER_API_KEY")?);
+                Ok(client.completion_model(model).boxed())
+            })
+            .register_completion_provider("perplexity", |model| {
+                let client = perplexity::Client::new(&env("PERPLEXITY_API_KEY")?);
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("together", |model| {
+                let client = together::Client::new(&env("TOGETHER_API_KEY")?);
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_completion_provider("xai", |model| {
+                let client = xai::Client::new(&env("XAI_API_KEY")?);
+                Ok(client.completion_model(model).boxed_streaming())
+            })
+            .register_embedding_provider("azure", |model| {
+                Ok(BoxEmbeddingModel::new(
+                    azure_client()?.embedding_model(model),
+                ))
+            })
+            .register_embedding_provider("cohere", |model| {
+                let client = cohere::Client::new(&env("COHERE_API_KEY")?);
+                Ok(BoxEmbeddingModel::new(
+                    client.embedding_model(model, "search_document"),
+                ))
+            })
+            .register_embedding_provider("gemini", |model| {
+                let client = gemini::Client::new(&env("GEMINI_API_KEY")?);
+                Ok(BoxEmbeddingModel::new(client.embedding_model(model)))
+            })
+            .register_embedding_provider("ollama", |model| {
+                Ok(BoxEmbeddingModel::new(
+                    ollama::Client::new().embedding_model(model),
+                ))
+            })
+            .register_embedding_provider("openai", |model| {
+                let client = openai::Client::new(&env("OPENAI_API_KEY")?);
+                Ok(BoxEmbeddingModel::new(client.embedding_model(model)))
+            })
+            .register_embedding_provider("together", |model| {
+                let client = together::Client::new(&env("TOGETHER_API_KEY")?);
+                Ok(BoxEmbeddingModel::new(client.embedding_model(model)))
+            })
+            .register_embedding_provider("xai", |model| {
+                let client = xai::Client::new(&env("XAI_API_KEY")?);
+                Ok(BoxEmbeddingModel::new(client.embedding_model(model)))
+            })
+            .register_transcription_provider("azure", |model| {
+                Ok(azure_client()?.transcription_model(model).boxed())
+            })
+            .register_transcription_provider("gemini", |model| {
+                let client = gemini::Client::new(&env("GEMINI_API_KEY")?);
+                Ok(client.transcription_model(model).boxed())
+            })
+            .register_transcription_provider("groq", |model| {
+                let client = groq::Client::new(&env("GROQ_API_KEY")?);
+                Ok(client.transcription_model(model).boxed())
+            })
+            .register_transcription_provider("huggingface", |model| {
+                let client = huggingface::Client::new(&env("HUGGINGFACE_API_KEY")?);
+                Ok(client.transcription_model(model).boxed())
+            })
+            .register_transcription_provider("openai", |model| {
+                let client = openai::Client::new(&env("OPENAI_API_KEY")?);
+                Ok(client.transcription_model(model).boxed())
+            });
+
+        #[cfg(feature = "audio")]
+        let registry = registry
+            .register_audio_generation_provider("hyperbolic", |model| {
+                // The models of Hyperbolic are the languages of the voices (e.g.: "EN")
+                let client = hyperbolic::Client::new(&env("HYPERBOLIC_API_KEY")?);
+                Ok(client.audio_generation_model(model).boxed())
+            })
+            .register_audio_generation_provider("openai", |model| {
+                let client = openai::Client::new(&env("OPENAI_API_KEY")?);
+                Ok(client.audio_generation_model(model).boxed())
+            });
+
+        registry
+    }
+
+    /// Register a provider of completion models under `name`, replacing the provider registered
+    /// under the same name (if any). The factory is called with the model part of the identifier.
+    pub fn register_completion_provider(
+        mut self,
+        name: impl Into<String>,
+        factory: impl Fn(&str) -> Result<BoxCompletionModel, RegistryError> + Send + Sync + 'static,
+    ) -> Self {
+        self.completion_providers
+            .insert(name.into(), Arc::new(factory));
+        self
+    }
+
+    /// Register a provider of embedding models under `name`, replacing the provider registered
+    /// under the same name (if any). The factory is called with the model part of the identifier.
+    pub fn register_embedding_provider(
+        mut self,
+        name: impl Into<String>,
+        factory: impl Fn(&str) -> Result<BoxEmbeddingModel, RegistryError> + Send + Sync + 'static,
+    ) -> Self {
+        self.embedding_providers
+            .insert(name.into(), Arc::new(factory));
+        self
+    }
+
+    /// Register a provider of transcription models under `name`, replacing the provider
+    /// registered under the same name (if any). The factory is called with the model part of the
+    /// identifier.
+    pub fn register_transcription_provider(
+        mut self,
+        name: impl Into<String>,
+        factory: impl Fn(&str) -> Result<BoxTranscriptionModel, RegistryError> + Send + Sync + 'static,
+    ) -> Self {
+        self.transcription_providers
+            .insert(name.into(), Arc::new(factory));
+        self
+    }
+
+    /// Register a provider of audio generation models under `name`, replacing the provider
+    /// registered under the same name (if any). The factory is called with the model part of the
+    /// identifier.
+    #[cfg(feature = "audio")]
+    pub fn register_audio_generation_provider(
+        mut self,
+        name: impl Into<String>,
+        factory: impl Fn(&str) -> Result<BoxAudioGenerationModel, RegistryError> + Send + Sync + 'static,
+    ) -> Self {
+        self.audio_generation_providers
+            .insert(name.into(), Arc::new(factory));
+        self
+    }
+
+    /// Resolve a `"provider:model"` identifier into a completion model. The models of the built-in
+    /// providers supporting streaming can stream their responses.
+    pub fn completion_model(&self, id: &str) -> Result<BoxCompletionModel, RegistryError> {
+        let (provider, model) = parse_id(id)?;
+        let factory = self
+            .completion_providers
+            .get(provider)
+            .ok_or_else(|| RegistryError::UnknownProvider(provider.to_string()))?;
+
+        factory(model)
+    }
+
+    /// Resolve a `"provider:model"` identifier into an embedding model
+    pub fn embedding_model(&self, id: &str) -> Result<BoxEmbeddingModel, RegistryError> {
+        let (provider, model) = parse_id(id)?;
+        let factory = self
+            .embedding_providers
+            .get(provider)
+            .ok_or_else(|| RegistryError::UnknownProvider(provider.to_string()))?;
+
+        factory(model)
+    }
+
+    /// Resolve a `"provider:model"` identifier into a transcription model
+    pub fn transcription_model(&self, id: &str) -> Result<BoxTranscriptionModel, RegistryError> {
+        let (provider, model) = parse_id(id)?;
+        let factory = self
+            .transcription_providers
+            .get(provider)
+            .ok_or_else(|| RegistryError::UnknownProvider(provider.to_string()))?;
+
+        factory(model)
+    }
+
+    /// Resolve a `"provider:model"` identifier into an audio generation model (for Hyperbolic,
+    /// the model is the language of the voice, e.g.: `"hyperbolic:EN"`)
+    #[cfg(feature = "audio")]
+    pub fn audio_generation_model(
+        &self,
+        id: &str,
+    ) -> Result<BoxAudioGenerationModel, RegistryError> {
+        let (provider, model) = parse_id(id)?;
+        let factory = self
+            .audio_generation_providers
+            .get(provider)
+            .ok_or_else(|| RegistryError::UnknownProvider(provider.to_string()))?;
+
+        factory(model)
+    }
+
+    /// Resolve a `"provider:model"` identifier into a speech model, i.e. an audio generation
+    /// model (see [ModelRegistry::audio_generation_model])
+    #[cfg(feature = "audio")]
+    pub fn speech_model(&self, id: &str) -> Result<BoxSpeechModel, RegistryError> {
+        self.audio_generation_model(id)
+    }
+}
+
+/// Split a `"provider:model"` identifier. The model may itself contain colons
+/// (e.g.: `"ollama:llama3.2:3b"`).
+fn parse_id(id: &str) -> Result<(&str, &str), RegistryError> {
+    match id
+        .split_once(':')
+        .map(|(provider, model)| (provider.trim(), model.trim()))
+    {
+        Some((provider, model)) if !provider.is_empty() && !model.is_empty() => {
+            Ok((provider, model))
+        }
+        _ => Err(RegistryError::InvalidIdentifier(id.to_string())),
+    }
+}
+
+fn env(name: &str) -> Result<String, RegistryError> {
+    std::env::var(name).map_err(|_| RegistryError::MissingCredentials(name.to_string()))
+}
+
+fn azure_client() -> Result<azure::Client, RegistryError> {
+    let api_version = env("AZURE_API_VERSION")?;
+    let azure_endpoint = env("AZURE_ENDPOINT")?;
+
+    if let Ok(api_key) = std::env::var("AZURE_API_KEY") {
+        Ok(azure::Client::from_api_key(
+            &api_key,
+            &api_version,
+            &azure_endpoint,
+        ))
+    } else {
+        let token = env("AZURE_TOKEN")?;
+        Ok(azure::Client::from_token(
+            &token,
+            &api_version,
+            &azure_endpoint,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_id() {
+        assert_eq!(parse_id("openai:gpt-4o").unwrap(), ("openai", "gpt-4o"));
+        assert_eq!(
+            parse_id("ollama:llama3.2:3b").unwrap(),
+            ("ollama", "llama3.2:3b")
+        );
+        assert!(parse_id("gpt-4o").is_err());
+        assert!(parse_id(":gpt-4o").is_err());
+        assert!(parse_id("openai:").is_err());
+        assert!(parse_id(" :gpt-4o").is_err());
+        assert!(parse_id("openai: ").is_err());
+    }
+
+    #[test]
+    fn test_completion_model() {
+        let registry = ModelRegistry::from_env();
+
+        let model = registry.completion_model("ollama:llama3.2:3b").unwrap();
+        let request = model
+            .completion_request("Hello")
+            .dry_run()
+            .expect("Request should be prepared");
+        assert_eq!(request.body["model"], "llama3.2:3b");
+        assert!(model.is_streaming());
+
+        assert!(matches!(
+            registry.completion_model("unknown:model"),
+            Err(RegistryError::UnknownProvider(_))
+        ));
+        assert!(matches!(
+            registry.embedding_model("perplexity:sonar"),
+            Err(RegistryError::UnknownProvider(_))
+        ));
+        assert!(matches!(
+            registry.transcription_model("ollama:whisper"),
+            Err(RegistryError::UnknownProvider(_))
+        ));
+    }
+
+    #[cfg(feature = "audio")]
+    #[test]
+    fn test_speech_model() {
+        let registry = ModelRegistry::from_env();
+
+        assert!(matches!(
+            registry.speech_model("groq:tts"),
+            Err(RegistryError::UnknownProvider(_))
+        ));
+    }
+}
@@ -0,0 +1,188 @@
+use futures::future::join_all;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::response_text;
+use crate::{
+    completion::CompletionModel,
+    vector_store::{VectorStoreError, VectorStoreIndex},
+};
+
+const COMPRESSION_PREAMBLE: &str = "You extract the parts of a document which are relevant to a \
+    query. Answer with the sentences of the document which help answer the query, copied \
+    verbatim and in their original order, and nothing else. If no part of the document is \
+    relevant, answer with NO_RELEVANT_CONTENT only.";
+
+const NO_RELEVANT_CONTENT: &str = "NO_RELEVANT_CONTENT";
+
+/// [CompressedIndex] wraps a vector store index and trims each document it retrieves down to the
+/// sentences relevant to the query (contextual compression), using a completion model (ideally
+/// a small, cheap one). Documents without any relevant sentence are dropped.
+///
+/// Documents which are strings are compressed as a whole. For documents which are objects, only
+/// their `text` field (or the field set with [CompressedIndex::text_field]) is compressed, and
+/// their other fields (e.g.: metadata) are kept as is. Other documents are returned untouched.
+///
+/// If the model fails to compress a document, the document is returned untouched.
+///
+/// # Example
+/// ```rust
+/// use rig::{providers::openai, retrieval::CompressedIndex};
+///
+/// let openai = openai::Client::from_env();
+///
+/// let index = CompressedIndex::new(index, openai.completion_model("gpt-4o-mini"))
+///     .text_field("content");
+///
+/// let chunks = index.top_n::<Chunk>("How do I define a tool?", 5).await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct CompressedIndex<I, M> {
+    index: I,
+    model: M,
+    text_field: String,
+    drop_irrelevant: bool,
+}
+
+impl<I: VectorStoreIndex, M: CompletionModel> CompressedIndex<I, M> {
+    /// Compress the documents retrieved from `index` with `model`
+    pub fn new(index: I, model: M) -> Self {
+        Self {
+            index,
+            model,
+            text_field: "text".to_string(),
+            drop_irrelevant: true,
+        }
+    }
+
+    /// Compress the `text_field` field of the documents which are objects (`text` by default)
+    pub fn text_field(mut self, text_field: &str) -> Self {
+        self.text_field = text_field.to_string();
+        self
+    }
+
+    /// Keep the documents without any sentence relevant to the query (untouched), instead of
+    /// dropping them
+    pub fn keep_irrelevant(mut self) -> Self {
+        self.drop_irrelevant = false;
+        self
+    }
+
+    /// Compress `document` for `query`, returning `None` if the document should be dropped
+    async fn compress(&self, query: &str, mut document: Value) -> Option<Value> {
+        let text = match &mut document {
+            Value::String(text) => text,
+            Value::Object(fields) => match fields.get_mut(&self.text_field) {
+                Some(Value::String(text)) => text,
+                _ => return Some(document),
+            },
+            _ => return Some(document),
+        };
+
+        let response = self
+            .model
+            .completion_request(format!("Query: {query}\n\nDocument:\n{text}"))
+            .preamble(COMPRESSION_PREAMBLE.to_string())
+            .temperature(0.0)
+            .send()
+            .await;
+
+        match response {
+            Ok(response) => {
+                let compressed = response_text(&response.choice);
+                let compressed = compressed.trim();
+
+                if compressed == NO_RELEVANT_CONTENT {
+                    return (!self.drop_irrelevant).then_some(document);
+                }
+                // The model should only ever remove text
+                if !compressed.is_empty() && compressed.len() < text.len() {
+                    *text = compressed.to_string();
+                }
+            }
+            Err(error) => {
+                tracing::warn!(target: "rig", "Failed to compress retrieved document: {error}");
+            }
+        }
+
+        Some(document)
+    }
+}
+
+impl<I: VectorStoreIndex, M: CompletionModel> VectorStoreIndex for CompressedIndex<I, M> {
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let documents = self.index.top_n::<Value>(query, n).await?;
+
+        let compressed = join_all(
+            documents
+                .into_iter()
+                .map(|(score, id, document)| async move {
+                    (score, id, self.compress(query, document).await)
+                }),
+        )
+        .await;
+
+        compressed
+            .into_iter()
+            .filter_map(|(score, id, document)| {
+                document.map(|document| {
+                    Ok::<_, VectorStoreError>((score, id, serde_json::from_value(document)?))
+                })
+            })
+            .collect()
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        self.index.top_n_ids(query, n).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{providers::mock::MockCompletionModel, retrieval::tests::FixedIndex};
+
+    #[tokio::test]
+    async fn test_compressed_index() {
+        let index = FixedIndex(vec![
+            (
+                0.9,
+                "doc0".to_string(),
+                json!({ "text": "Flurbos are green. They like jazz. They live on cold planets.", "page": 1 }),
+            ),
+            (0.8, "doc1".to_string(), json!("Glarbs are tools.")),
+            (0.7, "doc2".to_string(), json!(42)),
+        ]);
+        let model = MockCompletionModel::new()
+            .text("Flurbos are green.")
+            .text(NO_RELEVANT_CONTENT);
+
+        let documents = CompressedIndex::new(index, model.clone())
+            .top_n::<Value>("What color are flurbos?", 3)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            documents,
+            vec![
+                (
+                    0.9,
+                    "doc0".to_string(),
+                    json!({ "text": "Flurbos are green.", "page": 1 })
+                ),
+                (0.7, "doc2".to_string(), json!(42)),
+            ]
+        );
+        assert_eq!(model.requests().len(), 2);
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+
+use futures::future::try_join_all;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::QueryRewriter;
+use crate::vector_store::{VectorStoreError, VectorStoreIndex, VectorStoreIndexDyn};
+
+/// Constant of the reciprocal rank fusion used by default, from the original paper
+/// (Cormack et al., 2009)
+const DEFAULT_K: f64 = 60.0;
+
+/// Fuse ranked lists of documents with reciprocal rank fusion (RRF): each document is scored
+/// `sum(1 / (k + rank))` over the lists it appears in (ranks starting at 1), so that documents
+/// ranked well by several lists come first, regardless of the scale of the original scores.
+///
+/// The documents are identified by their id (the first occurrence of each document is kept) and
+/// returned by decreasing fused score, ties keeping the order of their first occurrence.
+///
+/// # Example
+/// ```rust
+/// use rig::retrieval::reciprocal_rank_fusion;
+///
+/// let fused = reciprocal_rank_fusion(
+///     vec![
+///         vec![("doc0".to_string(), ()), ("doc1".to_string(), ())],
+///         vec![("doc1".to_string(), ()), ("doc2".to_string(), ())],
+///     ],
+///     60.0,
+/// );
+///
+/// // doc1 is ranked by both lists
+/// assert_eq!(fused[0].1, "doc1");
+/// ```
+pub fn reciprocal_rank_fusion<T>(
+    results: impl IntoIterator<Item = Vec<(String, T)>>,
+    k: f64,
+) -> Vec<(f64, String, T)> {
+    let mut fused: Vec<(f64, String, T)> = vec![];
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for documents in results {
+        for (rank, (id, document)) in documents.into_iter().enumerate() {
+            let score = 1.0 / (k + rank as f64 + 1.0);
+            match positions.get(&id) {
+                Some(&i) => fused[i].0 += score,
+                None => {
+                    positions.insert(id.clone(), fused.len());
+                    fused.push((score, id, document));
+                }
+            }
+        }
+    }
+
+    // Stable sort: ties keep the order of their first occurrence
+    fused.sort_by(|(a, ..), (b, ..)| b.total_cmp(a));
+    fused
+}
+
+/// [FusedIndex] searches several indexes (e.g.: a vector index and a keyword index, or indexes of
+/// different collections) with several variants of the query (generated by a [QueryRewriter]),
+/// and fuses the ranked lists of documents with [reciprocal_rank_fusion], improving the recall on
+/// ambiguous questions.
+///
+/// The documents are returned with their fused score (not comparable with the scores of the
+/// underlying indexes) and can be reranked or given to an agent as is.
+///
+/// # Example
+/// ```rust
+/// use rig::{
+///     providers::openai,
+///     retrieval::{FusedIndex, ModelQueryRewriter, RewriteStrategy},
+/// };
+///
+/// let openai = openai::Client::from_env();
+///
+/// let index = FusedIndex::new(vector_store.index(embedding_model))
+///     .index(keyword_index)
+///     .query_rewriter(ModelQueryRewriter::new(
+///         openai.completion_model("gpt-4o-mini"),
+///         RewriteStrategy::MultiQuery(3),
+///     ));
+///
+/// let agent = openai.agent(openai::GPT_4O)
+///     .dynamic_context(5, index)
+///     .build();
+/// ```
+pub struct FusedIndex {
+    indexes: Vec<Box<dyn VectorStoreIndexDyn>>,
+    query_rewriter: Option<Box<dyn QueryRewriter>>,
+    k: f64,
+    candidates: Option<usize>,
+}
+
+impl std::fmt::Debug for FusedIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FusedIndex")
+            .field("indexes", &self.indexes.len())
+            .field("query_rewriter", &self.query_rewriter.is_some())
+            .field("k", &self.k)
+            .field("candidates", &self.candidates)
+            .finish()
+    }
+}
+
+impl FusedIndex {
+    /// Search `index` (add more indexes with [FusedIndex::index])
+    pub fn new(index: impl VectorStoreIndexDyn + 'static) -> Self {
+        Self {
+            indexes: vec![Box::new(index)],
+            query_rewriter: None,
+            k: DEFAULT_K,
+            candidates: None,
+        }
+    }
+
+    /// Also search `index`
+    pub fn index(mut self, index: impl VectorStoreIndexDyn + 'static) -> Self {
+        self.indexes.push(Box::new(index));
+        self
+    }
+
+    /// Search with the queries generated by `query_rewriter` from the query (e.g.: a
+    /// [ModelQueryRewriter](super::ModelQueryRewriter) with the
+    /// [MultiQuery](super::RewriteStrategy::MultiQuery) strategy) instead of the query itself
+    pub fn query_rewriter(mut self, query_rewriter: impl QueryRewriter + 'static) -> Self {
+        self.query_rewriter = Some(Box::new(query_rewriter));
+        self
+    }
+
+    /// Constant of the reciprocal rank fusion (60 by default): the lower, the more the best
+    /// ranked documents of each list weigh compared to the others
+    pub fn k(mut self, k: f64) -> Self {
+        self.k = k;
+        self
+    }
+
+    /// Number of documents retrieved per index and query (the number of documents requested by
+    /// default). More candidates give documents ranked lower by some lists a chance to come
+    /// first.
+    pub fn candidates(mut self, candidates: usize) -> Self {
+        self.candidates = Some(candidates.max(1));
+        self
+    }
+
+    /// Queries to search the indexes with: the query itself, or the queries of the query
+    /// rewriter (if any)
+    async fn queries(&self, query: &str) -> Vec<String> {
+        let Some(query_rewriter) = &self.query_rewriter else {
+            return vec![query.to_string()];
+        };
+
+        match query_rewriter.rewrite(query, &[], None).await {
+            Ok(queries) if !queries.is_empty() => queries,
+            Ok(_) => vec![query.to_string()],
+            Err(error) => {
+                tracing::warn!(target: "rig", "Failed to rewrite the query, using the query: {error}");
+                vec![query.to_string()]
+            }
+        }
+    }
+
+    /// Number of candidates to retrieve per index and query
+    fn num_candidates(&self, n: usize) -> usize {
+        self.candidates.unwrap_or(n).max(n)
+    }
+}
+
+impl VectorStoreIndex for FusedIndex {
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let queries = self.queries(query).await;
+        let candidates = self.num_candidates(n);
+
+        let results = try_join_all(self.indexes.iter().flat_map(|index| {
+            queries
+                .iter()
+                .map(move |query| index.top_n(query, candidates))
+        }))
+        .await?;
+
+        reciprocal_rank_fusion(
+            results.into_iter().map(|documents| {
+                documents
+                    .into_iter()
+                    .map(|(_, id, document)| (id, document))
+                    .collect::<Vec<(String, Value)>>()
+            }),
+            self.k,
+        )
+        .into_iter()
+        .take(n)
+        .map(|(score, id, document)| {
+            Ok::<_, VectorStoreError>((score, id, serde_json::from_value(document)?))
+        })
+        .collect()
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        let queries = self.queries(query).await;
+        let candidates = self.num_candidates(n);
+
+        let results = try_join_all(self.indexes.iter().flat_map(|index| {
+            queries
+                .iter()
+                .map(move |query| index.top_n_ids(query, candidates))
+        }))
+        .await?;
+
+        Ok(reciprocal_rank_fusion(
+            results
+                .into_iter()
+                .map(|ids| ids.into_iter().map(|(_, id)| (id, ())).collect::<Vec<_>>()),
+            self.k,
+        )
+        .into_iter()
+        .take(n)
+        .map(|(score, id, _)| (score, id))
+        .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::BoxFuture;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        completion::{CompletionError, Message, UsageTracker},
+        retrieval::tests::FixedIndex,
+    };
+
+    fn ranked(ids: &[&str]) -> Vec<(String, ())> {
+        ids.iter().map(|id| (id.to_string(), ())).collect()
+    }
+
+    /// Index returning the documents `{query}-0`, `{query}-1`, ...
+    struct QueryIndex;
+
+    impl VectorStoreIndex for QueryIndex {
+        async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+            &self,
+            query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+            (0..n)
+                .map(|i| {
+                    let id = format!("{query}-{i}");
+                    Ok::<_, VectorStoreError>((1.0, id.clone(), serde_json::from_value(json!(id))?))
+                })
+                .collect()
+        }
+
+        async fn top_n_ids(
+            &self,
+            query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+            Ok((0..n).map(|i| (1.0, format!("{query}-{i}"))).collect())
+        }
+    }
+
+    struct SplitRewriter;
+
+    impl QueryRewriter for SplitRewriter {
+        fn rewrite<'a>(
+            &'a self,
+            prompt: &'a str,
+            _chat_history: &'a [Message],
+            _usage_tracker: Option<&'a UsageTracker>,
+        ) -> BoxFuture<'a, Result<Vec<String>, CompletionError>> {
+            Box::pin(async move { Ok(prompt.split(' ').map(str::to_string).collect()) })
+        }
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let fused = reciprocal_rank_fusion(
+            vec![
+                ranked(&["doc0", "doc1", "doc2"]),
+                ranked(&["doc1", "doc3"]),
+                ranked(&["doc3", "doc1"]),
+            ],
+            1.0,
+        );
+
+        assert_eq!(
+            fused
+                .iter()
+                .map(|(_, id, _)| id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["doc1", "doc3", "doc0", "doc2"]
+        );
+        // 1/3 + 1/2 + 1/3
+        assert!((fused[0].0 - 7.0 / 6.0).abs() < 1e-9);
+        // 1/3 + 1/2
+        assert!((fused[1].0 - 5.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_fused_index() {
+        let index = FusedIndex::new(FixedIndex(vec![
+            (0.9, "doc0".to_string(), json!("flurbo")),
+            (0.8, "doc1".to_string(), json!("glarb")),
+        ]))
+        .index(FixedIndex(vec![
+            (10.0, "doc2".to_string(), json!("bloop")),
+            (5.0, "doc1".to_string(), json!("glarb")),
+        ]));
+
+        let documents = VectorStoreIndex::top_n::<String>(&index, "query", 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            documents
+                .iter()
+                .map(|(_, id, document)| (id.as_str(), document.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("doc1", "glarb"), ("doc0", "flurbo")]
+        );
+
+        let ids = VectorStoreIndex::top_n_ids(&index, "query", 3)
+            .await
+            .unwrap();
+        assert_eq!(
+            ids.iter().map(|(_, id)| id.as_str()).collect::<Vec<_>>(),
+            vec!["doc1", "doc0", "doc2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fused_index_query_variants() {
+        let index = FusedIndex::new(QueryIndex).query_rewriter(SplitRewriter);
+
+        let ids = VectorStoreIndex::top_n_ids(&index, "a b", 3).await.unwrap();
+        assert_eq!(
+            ids.iter().map(|(_, id)| id.as_str()).collect::<Vec<_>>(),
+            vec!["a-0", "b-0", "a-1"]
+        );
+    }
+}
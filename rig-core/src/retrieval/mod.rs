@@ -0,0 +1,106 @@
+//! This module provides building blocks to improve the documents retrieved from vector stores
+//! (e.g.: for the dynamic context of an [Agent](crate::agent::Agent)).
+//!
+//! The [CompressedIndex] wraps a vector store index and uses a (small, cheap) completion model to
+//! trim each retrieved document down to the sentences relevant to the query, reducing the size of
+//! the context given to the agent's model.
+//!
+//! A [QueryRewriter] (e.g.: a [ModelQueryRewriter]) set on an agent rewrites its prompts before
+//! retrieving its dynamic context, so that follow-up questions (e.g.: "what about the second
+//! one?") retrieve the documents relevant to the conversation. Several queries can be generated
+//! (e.g.: [RewriteStrategy::MultiQuery]) to retrieve a more diverse set of documents.
+//!
+//! The [FusedIndex] searches several indexes with several variants of the query and fuses the
+//! ranked lists of documents with [reciprocal_rank_fusion], improving the recall on ambiguous
+//! questions.
+//!
+//! The [ParentDocumentIndex] wraps an index of small chunks and returns the larger documents they
+//! were split from (fetched from a [DocumentStore]), combining the precision of searching small
+//! chunks with the context of larger documents.
+//!
+//! A [ContextPacker] fits the retrieved documents and the chat history of the requests of an
+//! agent into the context window of its model, keeping the most relevant documents and the most
+//! recent messages.
+//!
+//! # Example
+//! ```rust
+//! use rig::{providers::openai, retrieval::CompressedIndex};
+//!
+//! let openai = openai::Client::from_env();
+//!
+//! let index = CompressedIndex::new(vector_store.index(embedding_model), openai.completion_model("gpt-4o-mini"));
+//!
+//! let agent = openai.agent(openai::GPT_4O)
+//!     .dynamic_context(4, index)
+//!     .build();
+//! ```
+
+pub mod compression;
+pub mod fusion;
+pub mod packing;
+pub mod parent;
+pub mod rewrite;
+
+pub use compression::CompressedIndex;
+pub use fusion::{reciprocal_rank_fusion, FusedIndex};
+pub use packing::{ContextOverflowError, ContextPacker};
+pub use parent::{DocumentStore, ParentDocumentIndex};
+pub use rewrite::{ModelQueryRewriter, QueryRewriter, RewriteStrategy};
+
+use crate::{completion::AssistantContent, OneOrMany};
+
+/// Text of the answer of a model (ignoring its tool calls, if any)
+pub(crate) fn response_text(choice: &OneOrMany<AssistantContent>) -> String {
+    choice
+        .iter()
+        .filter_map(|content| match content {
+            AssistantContent::Text(text) => Some(text.text.as_str()),
+            AssistantContent::ToolCall(_) => None,
+        })
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    use crate::vector_store::{VectorStoreError, VectorStoreIndex};
+
+    /// Index returning the same documents for every query
+    pub(crate) struct FixedIndex(pub(crate) Vec<(f64, String, Value)>);
+
+    impl VectorStoreIndex for FixedIndex {
+        async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+            &self,
+            _query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+            self.0
+                .iter()
+                .take(n)
+                .map(|(score, id, document)| {
+                    Ok::<_, VectorStoreError>((
+                        *score,
+                        id.clone(),
+                        serde_json::from_value(document.clone())?,
+                    ))
+                })
+                .collect()
+        }
+
+        async fn top_n_ids(
+            &self,
+            query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+            Ok(self
+                .top_n::<Value>(query, n)
+                .await?
+                .into_iter()
+                .map(|(score, id, _)| (score, id))
+                .collect())
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use std::sync::Arc;
+
+use crate::{
+    completion::{CompletionRequest, Message},
+    message::UserContent,
+    tokenizer::{ApproximateTokenizer, CountTokens, Tokenizer},
+};
+
+/// Tokens reserved for the answer of the model when neither [ContextPacker::reserve_answer] nor
+/// the `max_tokens` of the request are set
+const DEFAULT_ANSWER_TOKENS: usize = 1024;
+
+/// Wrapper of the documents of a request (see [CompletionRequest::prompt_with_context])
+const ATTACHMENTS_WRAPPER: &str = "<attachments>\n</attachments>";
+
+/// Error returned when the parts of a request which cannot be dropped (preamble, prompt, tool
+/// definitions and the tokens reserved for the answer) do not fit in the context window
+#[derive(Debug, thiserror::Error)]
+#[error("Context window of {context_window} tokens exceeded: {required} tokens required")]
+pub struct ContextOverflowError {
+    pub context_window: usize,
+    pub required: usize,
+}
+
+/// [ContextPacker] fits the documents and chat history of completion requests into the context
+/// window of a model, instead of sending everything and failing once the window is exceeded.
+///
+/// The preamble, prompt and tool definitions are always kept, and tokens are reserved for the
+/// answer of the model (the `max_tokens` of the request by default). The rest of the window is
+/// packed with:
+/// 1. the most recent messages of the chat history, up to the history reservation (a quarter of
+///    the context window by default, see [ContextPacker::reserve_history]);
+/// 2. the documents, in order (i.e.: static context first, then the retrieved documents by
+///    decreasing score for agents), skipping the documents which do not fit;
+/// 3. older messages of the chat history, with the tokens left.
+///
+/// The chat history is truncated from its oldest messages, and never starts with an assistant
+/// message or a tool result (whose tool call would be missing).
+///
+/// # Example
+/// ```rust
+/// use rig::{retrieval::ContextPacker, tokenizer::TiktokenTokenizer};
+///
+/// let agent = openai.agent(openai::GPT_4O)
+///     .dynamic_context(20, index)
+///     .context_packer(
+///         ContextPacker::new(128_000)
+///             .tokenizer(TiktokenTokenizer::for_model(openai::GPT_4O))
+///             .reserve_answer(4096)
+///             .reserve_history(16_000),
+///     )
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct ContextPacker {
+    context_window: usize,
+    tokenizer: Arc<dyn Tokenizer>,
+    answer_tokens: Option<usize>,
+    history_tokens: usize,
+}
+
+impl std::fmt::Debug for ContextPacker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContextPacker")
+            .field("context_window", &self.context_window)
+            .field("answer_tokens", &self.answer_tokens)
+            .field("history_tokens", &self.history_tokens)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ContextPacker {
+    /// Fit requests into a context window of `context_window` tokens
+    pub fn new(context_window: usize) -> Self {
+        Self {
+            context_window,
+            tokenizer: Arc::new(ApproximateTokenizer::default()),
+            answer_tokens: None,
+            history_tokens: context_window / 4,
+        }
+    }
+
+    /// Count the tokens with `tokenizer` (estimated as 4 bytes of text each by default)
+    pub fn tokenizer(mut self, tokenizer: impl Tokenizer + 'static) -> Self {
+        self.tokenizer = Arc::new(tokenizer);
+        self
+    }
+
+    /// Reserve `tokens` for the answer of the model (the `max_tokens` of the request, or 1024
+    /// tokens, by default)
+    pub fn reserve_answer(mut self, tokens: usize) -> Self {
+        self.answer_tokens = Some(tokens);
+        self
+    }
+
+    /// Reserve up to `tokens` for the most recent messages of the chat history, before packing the
+    /// documents (a quarter of the context window by default)
+    pub fn reserve_history(mut self, tokens: usize) -> Self {
+        self.history_tokens = tokens;
+        self
+    }
+
+    /// Fit the documents and chat history of `request` into the context window
+    pub fn pack(
+        &self,
+        mut request: CompletionRequest,
+    ) -> Result<CompletionRequest, ContextOverflowError> {
+        let tokenizer = self.tokenizer.as_ref();
+
+        let answer = self
+            .answer_tokens
+            .or(request.max_tokens.map(|max_tokens| max_tokens as usize))
+            .unwrap_or(DEFAULT_ANSWER_TOKENS);
+        let preamble = request
+            .preamble
+            .as_ref()
+            .map(|preamble| tokenizer.count_text(preamble) + tokenizer.message_overhead())
+            .unwrap_or_default();
+        let attachments = if request.documents.is_empty() {
+            0
+        } else {
+            tokenizer.count_text(ATTACHMENTS_WRAPPER)
+        };
+        let required = answer
+            + preamble
+            + attachments
+            + request.prompt.count_tokens(tokenizer)
+            + request.tools.count_tokens(tokenizer);
+
+        let Some(mut available) = self.context_window.checked_sub(required) else {
+            return Err(ContextOverflowError {
+                context_window: self.context_window,
+                required,
+            });
+        };
+
+        // Most recent messages, within the history reservation
+        let history = request
+            .chat_history
+            .iter()
+            .map(|message| message.count_tokens(tokenizer))
+            .collect::<Vec<_>>();
+        let mut kept = 0;
+        let mut reserved = self.history_tokens.min(available);
+        while let Some(tokens) = history.len().checked_sub(kept + 1).map(|i| history[i]) {
+            if tokens > reserved {
+                break;
+            }
+            reserved -= tokens;
+            available -= tokens;
+            kept += 1;
+        }
+
+        // Documents, in order
+        let documents = std::mem::take(&mut request.documents);
+        let total = documents.len();
+        for document in documents {
+            let tokens = document.count_tokens(tokenizer);
+            if tokens <= available {
+                available -= tokens;
+                request.documents.push(document);
+            }
+        }
+        if request.documents.len() < total {
+            tracing::warn!(target: "rig",
+                "Dropped {} of {total} documents to fit the context window",
+                total - request.documents.len()
+            );
+        }
+
+        // Older messages, with the tokens left
+        while let Some(tokens) = history.len().checked_sub(kept + 1).map(|i| history[i]) {
+            if tokens > available {
+                break;
+            }
+            available -= tokens;
+            kept += 1;
+        }
+
+        let mut chat_history = request.chat_history.split_off(history.len() - kept);
+        let start = chat_history
+            .iter()
+            .position(|message| match message {
+                Message::User { content } => !content
+                    .iter()
+                    .any(|content| matches!(content, UserContent::ToolResult(_))),
+                Message::Assistant { .. } => false,
+                Message::System { .. } => true,
+            })
+            .unwrap_or(chat_history.len());
+        chat_history.drain(..start);
+
+        if chat_history.len() < history.len() {
+            tracing::warn!(target: "rig",
+                "Dropped {} of {} chat history messages to fit the context window",
+                history.len() - chat_history.len(),
+                history.len()
+            );
+        }
+        request.chat_history = chat_history;
+
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        agent::AgentBuilder,
+        completion::{CompletionRequestBuilder, Document},
+        providers::mock::MockCompletionModel,
+        retrieval::tests::FixedIndex,
+    };
+
+    fn document(id: &str, len: usize) -> Document {
+        Document {
+            id: id.to_string(),
+            text: "a".repeat(len),
+            additional_props: Default::default(),
+        }
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequestBuilder::new(MockCompletionModel::new(), "q")
+            .preamble("p".to_string())
+            .max_tokens(10)
+            .messages(vec![
+                Message::user("first question"),
+                Message::assistant("first answer"),
+                Message::user("more"),
+                Message::assistant("sure"),
+            ])
+            .documents(vec![
+                document("d0", 10),
+                document("d1", 100),
+                document("d2", 10),
+            ])
+            .build()
+    }
+
+    fn packer(context_window: usize) -> ContextPacker {
+        ContextPacker::new(context_window)
+            .tokenizer(ApproximateTokenizer::new(1.0).with_message_overhead(0))
+            .reserve_history(8)
+    }
+
+    fn ids(request: &CompletionRequest) -> Vec<&str> {
+        request
+            .documents
+            .iter()
+            .map(|document| document.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_pack() {
+        // 40 tokens for the answer, preamble, prompt and attachments wrapper, 8 tokens for the
+        // recent history, 33 tokens for each small document and 5 tokens left
+        let packed = packer(119).pack(request()).unwrap();
+        assert_eq!(ids(&packed), vec!["d0", "d2"]);
+        assert_eq!(
+            packed.chat_history,
+            vec![Message::user("more"), Message::assistant("sure")]
+        );
+
+        // The history can not start with the assistant message which now fits
+        let packed = packer(131).pack(request()).unwrap();
+        assert_eq!(ids(&packed), vec!["d0", "d2"]);
+        assert_eq!(packed.chat_history.len(), 2);
+
+        let packed = packer(1000).pack(request()).unwrap();
+        assert_eq!(ids(&packed), vec!["d0", "d1", "d2"]);
+        assert_eq!(packed.chat_history.len(), 4);
+    }
+
+    #[test]
+    fn test_context_overflow() {
+        let error = packer(30).pack(request()).unwrap_err();
+        assert_eq!(error.required, 40);
+    }
+
+    #[tokio::test]
+    async fn test_agent_context_packing() {
+        let model = MockCompletionModel::new().text("answer");
+        let agent = AgentBuilder::new(model.clone())
+            .dynamic_context(
+                1,
+                FixedIndex(vec![(0.5, "a".to_string(), json!("x".repeat(10)))]),
+            )
+            .dynamic_context(
+                1,
+                FixedIndex(vec![(0.9, "b".to_string(), json!("y".repeat(10)))]),
+            )
+            .context_packer(packer(83).reserve_answer(10))
+            .build();
+
+        let response = agent.prompt_with_sources("q").await.unwrap();
+
+        assert_eq!(
+            response
+                .sources
+                .iter()
+                .map(|source| source.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["b"]
+        );
+        assert_eq!(ids(&model.requests()[0]), vec!["b"]);
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    vector_store::{in_memory_store::InMemoryVectorStore, VectorStoreError, VectorStoreIndex},
+    wasm_compat::WasmBoxedFuture,
+};
+
+/// Store of documents by id (e.g.: the documents chunks were split from), used by the
+/// [ParentDocumentIndex] to resolve the parents of the retrieved chunks
+pub trait DocumentStore: Send + Sync {
+    /// Get the document `id`, if it exists
+    fn get_document<'a>(
+        &'a self,
+        id: &'a str,
+    ) -> WasmBoxedFuture<'a, Result<Option<Value>, VectorStoreError>>;
+}
+
+impl<D: Serialize + Send + Sync> DocumentStore for HashMap<String, D> {
+    fn get_document<'a>(
+        &'a self,
+        id: &'a str,
+    ) -> WasmBoxedFuture<'a, Result<Option<Value>, VectorStoreError>> {
+        Box::pin(async move { Ok(self.get(id).map(serde_json::to_value).transpose()?) })
+    }
+}
+
+impl<D: Serialize + Eq + Send + Sync> DocumentStore for InMemoryVectorStore<D> {
+    fn get_document<'a>(
+        &'a self,
+        id: &'a str,
+    ) -> WasmBoxedFuture<'a, Result<Option<Value>, VectorStoreError>> {
+        Box::pin(async move { InMemoryVectorStore::get_document::<Value>(self, id) })
+    }
+}
+
+/// [ParentDocumentIndex] wraps an index of small chunks (precise to retrieve) and returns the
+/// larger documents they were split from (e.g.: sections or whole documents, more useful as
+/// context) instead of the chunks themselves.
+///
+/// The id of the parent of each chunk is read from the `parent_id` field of the chunk (as set by
+/// the [chunking](crate::chunking) module, or the field set with
+/// [ParentDocumentIndex::parent_id_field]), and the parent is fetched from a [DocumentStore].
+/// The parents are returned with the score of their best ranked chunk and their own id. Chunks
+/// without a parent id, or whose parent is not found, are returned as is.
+///
+/// # Example
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use rig::{
+///     chunking::{Chunker, RecursiveCharacterSplitter},
+///     embeddings::EmbeddingsBuilder,
+///     loaders::{DocumentLoader, MarkdownLoader},
+///     retrieval::ParentDocumentIndex,
+///     vector_store::in_memory_store::InMemoryVectorStore,
+/// };
+///
+/// let sections = MarkdownLoader::new().split_level(2).load_glob("docs/**/*.md")?;
+/// let chunks = RecursiveCharacterSplitter::new(300, 50).chunk_documents(&sections);
+///
+/// let embeddings = EmbeddingsBuilder::new(model.clone())
+///     .documents(chunks)?
+///     .build()
+///     .await?;
+/// let index = InMemoryVectorStore::from_documents(embeddings).index(model);
+///
+/// // Search the chunks, but give the whole sections to the agent
+/// let sections = sections
+///     .into_iter()
+///     .map(|section| (section.id.clone(), section))
+///     .collect::<HashMap<_, _>>();
+/// let agent = openai.agent(openai::GPT_4O)
+///     .dynamic_context(3, ParentDocumentIndex::new(index, sections))
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParentDocumentIndex<I, S> {
+    index: I,
+    store: S,
+    parent_id_field: String,
+    oversampling: usize,
+}
+
+impl<I: VectorStoreIndex, S: DocumentStore> ParentDocumentIndex<I, S> {
+    /// Search the chunks of `index`, returning their parents from `store`
+    pub fn new(index: I, store: S) -> Self {
+        Self {
+            index,
+            store,
+            parent_id_field: "parent_id".to_string(),
+            oversampling: 4,
+        }
+    }
+
+    /// Read the id of the parent of the chunks from their `parent_id_field` field (`parent_id`
+    /// by default)
+    pub fn parent_id_field(mut self, parent_id_field: &str) -> Self {
+        self.parent_id_field = parent_id_field.to_string();
+        self
+    }
+
+    /// Search `oversampling` chunks per parent requested (4 by default), since several of the
+    /// best ranked chunks may belong to the same parent
+    pub fn oversampling(mut self, oversampling: usize) -> Self {
+        self.oversampling = oversampling.max(1);
+        self
+    }
+
+    /// The `n` best ranked chunks belonging to different parents, with the id of their parent
+    /// (if any)
+    async fn chunks(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, Value, Option<String>)>, VectorStoreError> {
+        let mut chunks: Vec<(f64, String, Value, Option<String>)> = vec![];
+
+        for (score, id, chunk) in self
+            .index
+            .top_n::<Value>(query, n * self.oversampling)
+            .await?
+        {
+            if chunks.len() >= n {
+                break;
+            }
+
+            let parent_id = chunk
+                .get(&self.parent_id_field)
+                .and_then(Value::as_str)
+                .map(str::to_string);
+
+            let key = parent_id.as_ref().unwrap_or(&id);
+            if chunks
+                .iter()
+                .any(|(_, id, _, parent_id)| parent_id.as_ref().unwrap_or(id) == key)
+            {
+                continue;
+            }
+
+            chunks.push((score, id, chunk, parent_id));
+        }
+
+        Ok(chunks)
+    }
+}
+
+impl<I: VectorStoreIndex, S: DocumentStore> VectorStoreIndex for ParentDocumentIndex<I, S> {
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let mut documents = vec![];
+
+        for (score, id, chunk, parent_id) in self.chunks(query, n).await? {
+            let Some(parent_id) = parent_id else {
+                documents.push((score, id, serde_json::from_value(chunk)?));
+                continue;
+            };
+
+            match self.store.get_document(&parent_id).await? {
+                Some(parent) => documents.push((score, parent_id, serde_json::from_value(parent)?)),
+                None => {
+                    tracing::warn!(target: "rig", "Parent document {parent_id} of chunk {id} not found");
+                    documents.push((score, id, serde_json::from_value(chunk)?));
+                }
+            }
+        }
+
+        Ok(documents)
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        Ok(self
+            .chunks(query, n)
+            .await?
+            .into_iter()
+            .map(|(score, id, _, parent_id)| (score, parent_id.unwrap_or(id)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::retrieval::tests::FixedIndex;
+
+    #[tokio::test]
+    async fn test_parent_document_index() {
+        let chunk = |id: &str, score: f64, parent_id: Option<&str>| {
+            (
+                score,
+                id.to_string(),
+                json!({ "text": format!("chunk {id}"), "parent_id": parent_id }),
+            )
+        };
+        let index = FixedIndex(vec![
+            chunk("a0", 0.9, Some("a")),
+            chunk("a1", 0.8, Some("a")),
+            chunk("b0", 0.7, Some("b")),
+            chunk("c0", 0.6, None),
+            chunk("z0", 0.5, Some("z")),
+        ]);
+        let parents = HashMap::from([
+            ("a".to_string(), "Section A".to_string()),
+            ("b".to_string(), "Section B".to_string()),
+        ]);
+        let index = ParentDocumentIndex::new(index, parents);
+
+        let documents = index.top_n::<Value>("query", 4).await.unwrap();
+        assert_eq!(
+            documents,
+            vec![
+                (0.9, "a".to_string(), json!("Section A")),
+                (0.7, "b".to_string(), json!("Section B")),
+                (
+                    0.6,
+                    "c0".to_string(),
+                    json!({ "text": "chunk c0", "parent_id": null })
+                ),
+                (
+                    0.5,
+                    "z0".to_string(),
+                    json!({ "text": "chunk z0", "parent_id": "z" })
+                ),
+            ]
+        );
+
+        let ids = index.top_n_ids("query", 2).await.unwrap();
+        assert_eq!(ids, vec![(0.9, "a".to_string()), (0.7, "b".to_string())]);
+    }
+}
@@ -0,0 +1,335 @@
+use super::response_text;
+use crate::{
+    completion::{AssistantContent, CompletionError, CompletionModel, Message, UsageTracker},
+    wasm_compat::WasmBoxedFuture,
+};
+
+/// Pre-retrieval step turning the prompt of an agent (and its chat history) into the queries its
+/// dynamic context is retrieved with. Set on an agent with
+/// [AgentBuilder::query_rewriter](crate::agent::AgentBuilder::query_rewriter).
+pub trait QueryRewriter: Send + Sync {
+    /// Queries to retrieve the documents relevant to `prompt`, sent after `chat_history`. The
+    /// token usage of the completion requests made to rewrite the prompt (if any) is added to
+    /// `usage_tracker` (e.g.: the usage tracker of the agent).
+    fn rewrite<'a>(
+        &'a self,
+        prompt: &'a str,
+        chat_history: &'a [Message],
+        usage_tracker: Option<&'a UsageTracker>,
+    ) -> WasmBoxedFuture<'a, Result<Vec<String>, CompletionError>>;
+}
+
+/// How a [ModelQueryRewriter] rewrites the prompts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RewriteStrategy {
+    /// Rewrite the prompt into a standalone query, resolving its references to the conversation
+    /// (e.g.: "what about the second one?")
+    Condense,
+    /// Retrieve with the standalone query and `n` alternative queries phrased differently
+    MultiQuery(usize),
+    /// Retrieve with a hypothetical answer to the prompt (Hypothetical Document Embeddings), which
+    /// is usually closer to the relevant documents than the question itself
+    Hyde,
+    /// Retrieve with the standalone query expanded with keywords and synonyms
+    Keywords,
+}
+
+impl RewriteStrategy {
+    fn preamble(&self) -> String {
+        match self {
+            Self::Condense => "Rewrite the latest message of the user into a standalone search \
+                query, resolving its references to the conversation (e.g.: \"the second one\"). \
+                Answer with the query only."
+                .to_string(),
+            Self::MultiQuery(n) => format!(
+                "Rewrite the latest message of the user into a standalone search query, resolving \
+                its references to the conversation (e.g.: \"the second one\"), followed by {n} \
+                alternative search queries phrased differently to retrieve other relevant \
+                documents. Answer with one query per line, without numbering, and nothing else."
+            ),
+            Self::Hyde => "Write a short passage answering the latest message of the user, as it \
+                would appear in a document of a knowledge base, resolving its references to the \
+                conversation (e.g.: \"the second one\"). Answer with the passage only."
+                .to_string(),
+            Self::Keywords => "Rewrite the latest message of the user into a standalone search \
+                query, resolving its references to the conversation (e.g.: \"the second one\"). \
+                Answer with the query on the first line, then keywords and synonyms relevant to \
+                it, separated by spaces, on the second line, and nothing else."
+                .to_string(),
+        }
+    }
+
+    /// Parse the queries out of the answer of the model
+    fn queries(&self, answer: &str) -> Vec<String> {
+        let lines = answer
+            .lines()
+            .map(strip_list_marker)
+            .filter(|line| !line.is_empty());
+
+        match self {
+            Self::Condense => lines.take(1).map(str::to_string).collect(),
+            Self::MultiQuery(n) => lines.take(n + 1).map(str::to_string).collect(),
+            Self::Hyde => {
+                let passage = answer.trim();
+                (!passage.is_empty())
+                    .then(|| passage.to_string())
+                    .into_iter()
+                    .collect()
+            }
+            Self::Keywords => {
+                let query = lines.take(2).collect::<Vec<_>>().join(" ");
+                (!query.is_empty()).then_some(query).into_iter().collect()
+            }
+        }
+    }
+}
+
+/// Strip the list marker (e.g.: `1.` or `-`) the model may have put before a line
+fn strip_list_marker(line: &str) -> &str {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix(['-', '*']) {
+        return rest.trim_start();
+    }
+
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    match line[digits..].strip_prefix(['.', ')']) {
+        Some(rest) if digits > 0 => rest.trim_start(),
+        _ => line,
+    }
+}
+
+/// [QueryRewriter] asking a completion model (ideally a small, cheap one) to rewrite the prompts
+/// according to a [RewriteStrategy], given the latest messages of the conversation.
+/// If the model gives no usable answer, the prompt is used as is.
+///
+/// # Example
+/// ```rust
+/// use rig::{
+///     providers::openai,
+///     retrieval::{ModelQueryRewriter, RewriteStrategy},
+/// };
+///
+/// let openai = openai::Client::from_env();
+///
+/// let agent = openai.agent(openai::GPT_4O)
+///     .dynamic_context(4, index)
+///     .query_rewriter(ModelQueryRewriter::new(
+///         openai.completion_model("gpt-4o-mini"),
+///         RewriteStrategy::MultiQuery(3),
+///     ))
+///     .build();
+///
+/// // Retrieves the documents about the second product listed in the chat history
+/// let answer = agent.chat("What about the second one?", chat_history).await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ModelQueryRewriter<M> {
+    model: M,
+    strategy: RewriteStrategy,
+    history_len: usize,
+}
+
+impl<M: CompletionModel> ModelQueryRewriter<M> {
+    /// Rewrite the prompts with `model`, given the last 10 messages of the conversation
+    pub fn new(model: M, strategy: RewriteStrategy) -> Self {
+        Self {
+            model,
+            strategy,
+            history_len: 10,
+        }
+    }
+
+    /// Give the model the last `history_len` messages of the conversation
+    pub fn history_len(mut self, history_len: usize) -> Self {
+        self.history_len = history_len;
+        self
+    }
+}
+
+impl<M: CompletionModel> QueryRewriter for ModelQueryRewriter<M> {
+    fn rewrite<'a>(
+        &'a self,
+        prompt: &'a str,
+        chat_history: &'a [Message],
+        usage_tracker: Option<&'a UsageTracker>,
+    ) -> WasmBoxedFuture<'a, Result<Vec<String>, CompletionError>> {
+        Box::pin(async move {
+            let history = &chat_history[chat_history.len().saturating_sub(self.history_len)..];
+            let conversation = transcript(history);
+
+            let request = if conversation.is_empty() {
+                format!("Latest message: {prompt}")
+            } else {
+                format!("Conversation:\n{conversation}\n\nLatest message: {prompt}")
+            };
+
+            let mut request = self
+                .model
+                .completion_request(request)
+                .preamble(self.strategy.preamble())
+                .temperature(0.0);
+            if let Some(usage_tracker) = usage_tracker {
+                request = request.usage_tracker(usage_tracker.clone());
+            }
+            let response = request.send().await?;
+
+            let queries = self.strategy.queries(&response_text(&response.choice));
+            Ok(if queries.is_empty() {
+                vec![prompt.to_string()]
+            } else {
+                queries
+            })
+        })
+    }
+}
+
+/// Text of the messages of a conversation, one message per line (ignoring tool calls and
+/// non-text content)
+fn transcript(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .filter_map(|message| match message {
+            Message::User { .. } => message.rag_text().map(|text| format!("User: {text}")),
+            Message::Assistant { content } => {
+                let text = content
+                    .iter()
+                    .filter_map(|content| match content {
+                        AssistantContent::Text(text) => Some(text.text.as_str()),
+                        AssistantContent::ToolCall(_) => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("");
+                (!text.is_empty()).then(|| format!("Assistant: {text}"))
+            }
+            Message::System { content } => Some(format!("System: {content}")),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{
+        agent::AgentBuilder,
+        completion::{Chat, Usage},
+        providers::mock::MockCompletionModel,
+        vector_store::{VectorStoreError, VectorStoreIndex},
+    };
+
+    /// Index recording the queries it is searched with, returning the query as the document
+    #[derive(Clone, Default)]
+    struct RecordingIndex(Arc<Mutex<Vec<String>>>);
+
+    impl VectorStoreIndex for RecordingIndex {
+        async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+            &self,
+            query: &str,
+            _n: usize,
+        ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+            self.0.lock().unwrap().push(query.to_string());
+            Ok(vec![(
+                1.0,
+                query.to_string(),
+                serde_json::from_value(query.into())?,
+            )])
+        }
+
+        async fn top_n_ids(
+            &self,
+            query: &str,
+            _n: usize,
+        ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+            Ok(vec![(1.0, query.to_string())])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_query_rewriting() {
+        let index = RecordingIndex::default();
+        let rewriter = ModelQueryRewriter::new(
+            MockCompletionModel::new()
+                .text("Flurbo X2 price\nFlurbo X2 cost")
+                .usage(Usage::new(20, 5)),
+            RewriteStrategy::MultiQuery(1),
+        );
+        let model = MockCompletionModel::new()
+            .text("10 credits")
+            .usage(Usage::new(100, 3));
+
+        let agent = AgentBuilder::new(model.clone())
+            .dynamic_context(2, index.clone())
+            .query_rewriter(rewriter)
+            .build();
+        agent
+            .chat(
+                "And the second one?",
+                vec![Message::user("Price of the X1?")],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *index.0.lock().unwrap(),
+            vec!["Flurbo X2 price", "Flurbo X2 cost"]
+        );
+        let documents = &model.requests()[0].documents;
+        assert_eq!(
+            documents
+                .iter()
+                .map(|document| document.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Flurbo X2 price", "Flurbo X2 cost"]
+        );
+        assert_eq!(agent.total_usage(), Usage::new(120, 8));
+    }
+
+    #[tokio::test]
+    async fn test_multi_query() {
+        let model = MockCompletionModel::new()
+            .text("1. Price of the Flurbo X2\n2. Flurbo X2 cost\n- How much is the X2?\n\nExtra");
+        let rewriter = ModelQueryRewriter::new(model.clone(), RewriteStrategy::MultiQuery(2));
+
+        let history = vec![
+            Message::user("Which flurbos do you sell?"),
+            Message::assistant("The Flurbo X1 and the Flurbo X2."),
+        ];
+        let queries = rewriter
+            .rewrite("How much is the second one?", &history, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            queries,
+            vec![
+                "Price of the Flurbo X2",
+                "Flurbo X2 cost",
+                "How much is the X2?"
+            ]
+        );
+        assert_eq!(
+            model.requests()[0].prompt.rag_text().as_deref(),
+            Some(
+                "Conversation:\nUser: Which flurbos do you sell?\nAssistant: The Flurbo X1 and \
+                the Flurbo X2.\n\nLatest message: How much is the second one?"
+            )
+        );
+    }
+
+    #[test]
+    fn test_strategies() {
+        assert_eq!(
+            RewriteStrategy::Keywords.queries("Flurbo X2 price\ncost pricing"),
+            vec!["Flurbo X2 price cost pricing"]
+        );
+        assert_eq!(
+            RewriteStrategy::Hyde.queries(" The X2 costs 10 credits.\nIt is green. "),
+            vec!["The X2 costs 10 credits.\nIt is green."]
+        );
+        assert!(RewriteStrategy::Condense.queries("  \n").is_empty());
+    }
+}
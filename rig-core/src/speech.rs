@@ -0,0 +1,38 @@
+//! This module provides the speech synthesis (text-to-speech) interface of Rig, the counterpart
+//! of the [transcription](crate::transcription) (speech-to-text) interface.
+//!
+//! [SpeechModel] is the provider-agnostic interface of text-to-speech models, implemented by the
+//! audio generation models of the providers (e.g.: OpenAI, Azure and Hyperbolic), and
+//! [BoxSpeechModel] erases the type of a speech model so that the provider can be selected at
+//! runtime (e.g.: with [ModelRegistry::speech_model](crate::registry::ModelRegistry::speech_model)).
+//! The items of this module are the items of the [audio_generation](crate::audio_generation)
+//! module under their speech names, so that a model implementing one implements the other.
+//!
+//! Requires the `audio` feature.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     providers::openai,
+//!     speech::{BoxSpeechModel, SpeechModel},
+//! };
+//!
+//! let model: BoxSpeechModel = openai::Client::from_env()
+//!     .audio_generation_model(openai::TTS_1)
+//!     .boxed();
+//!
+//! let speech = model
+//!     .audio_generation_request()
+//!     .text("Hello, world!")
+//!     .voice("alloy")
+//!     .send()
+//!     .await?;
+//! std::fs::write("hello.mp3", speech.audio)?;
+//! ```
+
+pub use crate::audio_generation::{
+    AudioGenerationError as SpeechError, AudioGenerationModel as SpeechModel,
+    AudioGenerationRequest as SpeechRequest, AudioGenerationRequestBuilder as SpeechRequestBuilder,
+    AudioGenerationResponse as SpeechResponse, BoxAudioGenerationModel as BoxSpeechModel,
+    DynAudioGenerationModel as DynSpeechModel,
+};
@@ -11,7 +11,8 @@
 
 use crate::agent::Agent;
 use crate::completion::{
-    CompletionError, CompletionModel, CompletionRequest, CompletionRequestBuilder, Message,
+    BoxCompletionModel, CompletionError, CompletionModel, CompletionRequest,
+    CompletionRequestBuilder, Message, Usage,
 };
 use futures::{Stream, StreamExt};
 use std::boxed::Box;
@@ -21,12 +22,16 @@ use std::pin::Pin;
 
 /// Enum representing a streaming chunk from the model
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum StreamingChoice {
     /// A text chunk from a message response
     Message(String),
 
     /// A tool call response chunk
     ToolCall(String, String, serde_json::Value),
+
+    /// Token usage of the response, sent at the end of the stream by the providers reporting it
+    Usage(Usage),
 }
 
 impl Display for StreamingChoice {
@@ -36,6 +41,7 @@ impl Display for StreamingChoice {
             StreamingChoice::ToolCall(name, id, params) => {
                 write!(f, "Tool call: {} {} {:?}", name, id, params)
             }
+            StreamingChoice::Usage(usage) => write!(f, "Usage: {}", usage),
         }
     }
 }
@@ -83,6 +89,16 @@ pub trait StreamingCompletionModel: CompletionModel {
         &self,
         request: CompletionRequest,
     ) -> impl Future<Output = Result<StreamingResult, CompletionError>>;
+
+    /// Erases the type of the model like [CompletionModel::boxed], keeping its ability to stream
+    /// its responses (see [BoxCompletionModel::new_streaming]).
+    fn boxed_streaming(self) -> BoxCompletionModel
+    where
+        Self: 'static,
+        Self::Response: 'static,
+    {
+        BoxCompletionModel::new_streaming(self)
+    }
 }
 
 /// helper function to stream a completion request to stdout
@@ -105,6 +121,7 @@ pub async fn stream_to_stdout<M: StreamingCompletionModel>(
                     .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
                 println!("\nResult: {}", res);
             }
+            Ok(StreamingChoice::Usage(_)) => {}
             Err(e) => {
                 eprintln!("Error: {}", e);
                 break;
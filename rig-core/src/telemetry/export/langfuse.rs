@@ -0,0 +1,191 @@
+//! [Langfuse](https://langfuse.com) exporter, reporting runs through the
+//! [ingestion API](https://api.reference.langfuse.com/#tag/ingestion).
+//!
+//! Runs are reported as traces, completion steps as generations, other steps as spans and
+//! scores as trace scores.
+
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use super::{new_id, ExportError, Run, StepKind, TraceExporter};
+
+const LANGFUSE_HOST: &str = "https://cloud.langfuse.com";
+
+#[derive(Clone)]
+pub struct LangfuseExporter {
+    host: String,
+    public_key: String,
+    secret_key: String,
+    http_client: reqwest::Client,
+}
+
+impl LangfuseExporter {
+    /// Create a new exporter reporting to Langfuse Cloud
+    pub fn new(public_key: &str, secret_key: &str) -> Self {
+        Self {
+            host: LANGFUSE_HOST.to_string(),
+            public_key: public_key.to_string(),
+            secret_key: secret_key.to_string(),
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Create a new exporter from the `LANGFUSE_PUBLIC_KEY` and `LANGFUSE_SECRET_KEY`
+    /// environment variables, and the optional `LANGFUSE_HOST` environment variable.
+    /// Panics if the keys are not set.
+    pub fn from_env() -> Self {
+        let public_key = std::env::var("LANGFUSE_PUBLIC_KEY").expect("LANGFUSE_PUBLIC_KEY not set");
+        let secret_key = std::env::var("LANGFUSE_SECRET_KEY").expect("LANGFUSE_SECRET_KEY not set");
+        let exporter = Self::new(&public_key, &secret_key);
+
+        match std::env::var("LANGFUSE_HOST") {
+            Ok(host) => exporter.with_host(&host),
+            Err(_) => exporter,
+        }
+    }
+
+    /// Report to a self-hosted Langfuse instance
+    pub fn with_host(mut self, host: &str) -> Self {
+        self.host = host.trim_end_matches('/').to_string();
+        self
+    }
+}
+
+impl TraceExporter for LangfuseExporter {
+    async fn export(&self, run: &Run) -> Result<(), ExportError> {
+        let response = self
+            .http_client
+            .post(format!("{}/api/public/ingestion", self.host))
+            .basic_auth(&self.public_key, Some(&self.secret_key))
+            .json(&json!({ "batch": ingestion_batch(run) }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ExportError::ResponseError(response.text().await?));
+        }
+
+        // The ingestion API returns a 207 with the events that failed to be ingested
+        let body: Value = response.json().await?;
+        match body.get("errors").and_then(Value::as_array) {
+            Some(errors) if !errors.is_empty() => {
+                Err(ExportError::ResponseError(serde_json::to_string(errors)?))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn event(event_type: &str, body: Value) -> Value {
+    json!({
+        "id": new_id(),
+        "timestamp": Utc::now().to_rfc3339(),
+        "type": event_type,
+        "body": body,
+    })
+}
+
+/// Convert a run to a batch of Langfuse ingestion events
+fn ingestion_batch(run: &Run) -> Vec<Value> {
+    let trace = event(
+        "trace-create",
+        json!({
+            "id": run.id,
+            "name": run.name,
+            "timestamp": run.start_time.to_rfc3339(),
+            "input": run.input,
+            "output": run.output,
+            "metadata": run.metadata,
+        }),
+    );
+
+    let steps = run.steps.iter().map(|step| match &step.kind {
+        StepKind::Completion { model, usage } => event(
+            "generation-create",
+            json!({
+                "id": step.id,
+                "traceId": run.id,
+                "name": step.name,
+                "startTime": step.start_time.to_rfc3339(),
+                "endTime": step.end_time.to_rfc3339(),
+                "model": model,
+                "input": step.input,
+                "output": step.output,
+                "usage": {
+                    "input": usage.input_tokens,
+                    "output": usage.output_tokens,
+                    "total": usage.total_tokens,
+                    "unit": "TOKENS",
+                },
+            }),
+        ),
+        StepKind::ToolCall | StepKind::Retrieval => event(
+            "span-create",
+            json!({
+                "id": step.id,
+                "traceId": run.id,
+                "name": step.name,
+                "startTime": step.start_time.to_rfc3339(),
+                "endTime": step.end_time.to_rfc3339(),
+                "input": step.input,
+                "output": step.output,
+                "metadata": { "kind": step.kind },
+            }),
+        ),
+    });
+
+    let scores = run.scores.iter().map(|score| {
+        event(
+            "score-create",
+            json!({
+                "id": score.id,
+                "traceId": run.id,
+                "name": score.name,
+                "value": score.value,
+                "comment": score.comment,
+            }),
+        )
+    });
+
+    std::iter::once(trace).chain(steps).chain(scores).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::completion::Usage;
+
+    #[test]
+    fn test_ingestion_batch() {
+        let mut run = Run::new("agent", json!("question"));
+        run.record_completion(
+            "gpt-4o",
+            json!("question"),
+            json!("answer"),
+            Usage::new(3, 4),
+            Utc::now(),
+        )
+        .record_retrieval("question", json!(["doc0"]), Utc::now())
+        .score("quality", 0.5, Some("ok".to_string()))
+        .finish(json!("answer"));
+
+        let batch = ingestion_batch(&run);
+        let types = batch
+            .iter()
+            .map(|event| event["type"].as_str().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            types,
+            vec![
+                "trace-create",
+                "generation-create",
+                "span-create",
+                "score-create"
+            ]
+        );
+        assert_eq!(batch[1]["body"]["traceId"], json!(run.id));
+        assert_eq!(batch[1]["body"]["usage"]["total"], json!(7));
+        assert_eq!(batch[3]["body"]["comment"], json!("ok"));
+    }
+}
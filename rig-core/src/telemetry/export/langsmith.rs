@@ -0,0 +1,219 @@
+//! [LangSmith](https://smith.langchain.com) exporter, reporting runs through the
+//! [batch ingestion API](https://docs.smith.langchain.com/reference/data_formats/run_data_format).
+//!
+//! Runs are reported as `chain` runs with their steps as child `llm`, `tool` and `retriever`
+//! runs, and scores as feedback on the root run.
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use super::{ExportError, Run, StepKind, TraceExporter};
+
+const LANGSMITH_ENDPOINT: &str = "https://api.smith.langchain.com";
+
+#[derive(Clone)]
+pub struct LangSmithExporter {
+    endpoint: String,
+    api_key: String,
+    project: Option<String>,
+    http_client: reqwest::Client,
+}
+
+impl LangSmithExporter {
+    /// Create a new exporter reporting to the default LangSmith project
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            endpoint: LANGSMITH_ENDPOINT.to_string(),
+            api_key: api_key.to_string(),
+            project: None,
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Create a new exporter from the `LANGSMITH_API_KEY` environment variable, and the optional
+    /// `LANGSMITH_ENDPOINT` and `LANGSMITH_PROJECT` environment variables.
+    /// Panics if the API key is not set.
+    pub fn from_env() -> Self {
+        let api_key = std::env::var("LANGSMITH_API_KEY").expect("LANGSMITH_API_KEY not set");
+        let mut exporter = Self::new(&api_key);
+
+        if let Ok(endpoint) = std::env::var("LANGSMITH_ENDPOINT") {
+            exporter = exporter.with_endpoint(&endpoint);
+        }
+        if let Ok(project) = std::env::var("LANGSMITH_PROJECT") {
+            exporter = exporter.with_project(&project);
+        }
+        exporter
+    }
+
+    /// Report to a self-hosted LangSmith instance
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Report the runs to the given project
+    pub fn with_project(mut self, project: &str) -> Self {
+        self.project = Some(project.to_string());
+        self
+    }
+
+    async fn post(&self, path: &str, body: &Value) -> Result<(), ExportError> {
+        let response = self
+            .http_client
+            .post(format!("{}/{path}", self.endpoint))
+            .header("x-api-key", &self.api_key)
+            .json(body)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(ExportError::ResponseError(response.text().await?))
+        }
+    }
+}
+
+impl TraceExporter for LangSmithExporter {
+    async fn export(&self, run: &Run) -> Result<(), ExportError> {
+        self.post(
+            "runs/batch",
+            &json!({ "post": batch_runs(run, self.project.as_deref()) }),
+        )
+        .await?;
+
+        for score in &run.scores {
+            self.post(
+                "feedback",
+                &json!({
+                    "id": score.id,
+                    "run_id": run.id,
+                    "key": score.name,
+                    "score": score.value,
+                    "comment": score.comment,
+                }),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// LangSmith expects run inputs and outputs to be objects
+fn as_object(key: &str, value: &Value) -> Value {
+    match value {
+        Value::Object(_) => value.clone(),
+        _ => json!({ key: value }),
+    }
+}
+
+/// Segment of the `dotted_order` of a run, used by LangSmith to order runs within a trace
+fn dotted_order_segment(start_time: &DateTime<Utc>, id: &str) -> String {
+    format!(
+        "{}{:06}Z{id}",
+        start_time.format("%Y%m%dT%H%M%S"),
+        start_time.timestamp_subsec_micros()
+    )
+}
+
+/// Convert a run to the list of LangSmith runs (root run followed by its child runs)
+fn batch_runs(run: &Run, project: Option<&str>) -> Vec<Value> {
+    let root_order = dotted_order_segment(&run.start_time, &run.id);
+
+    let root = json!({
+        "id": run.id,
+        "trace_id": run.id,
+        "dotted_order": root_order,
+        "name": run.name,
+        "run_type": "chain",
+        "inputs": as_object("input", &run.input),
+        "outputs": run.output.as_ref().map(|output| as_object("output", output)),
+        "start_time": run.start_time.to_rfc3339(),
+        "end_time": run.end_time.map(|end_time| end_time.to_rfc3339()),
+        "session_name": project,
+        "extra": { "metadata": run.metadata },
+    });
+
+    let children = run.steps.iter().map(|step| {
+        let (run_type, extra) = match &step.kind {
+            StepKind::Completion { model, usage } => (
+                "llm",
+                json!({
+                    "metadata": { "ls_model_name": model },
+                    "usage_metadata": {
+                        "input_tokens": usage.input_tokens,
+                        "output_tokens": usage.output_tokens,
+                        "total_tokens": usage.total_tokens,
+                    },
+                }),
+            ),
+            StepKind::ToolCall => ("tool", json!({})),
+            StepKind::Retrieval => ("retriever", json!({})),
+        };
+
+        json!({
+            "id": step.id,
+            "trace_id": run.id,
+            "parent_run_id": run.id,
+            "dotted_order": format!(
+                "{root_order}.{}",
+                dotted_order_segment(&step.start_time, &step.id)
+            ),
+            "name": step.name,
+            "run_type": run_type,
+            "inputs": as_object("input", &step.input),
+            "outputs": as_object("output", &step.output),
+            "start_time": step.start_time.to_rfc3339(),
+            "end_time": step.end_time.to_rfc3339(),
+            "session_name": project,
+            "extra": extra,
+        })
+    });
+
+    std::iter::once(root).chain(children).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_runs() {
+        let mut run = Run::new("agent", json!("question"));
+        run.record_tool_call(
+            "search",
+            json!({"query": "rust"}),
+            json!("results"),
+            Utc::now(),
+        )
+        .finish(json!("answer"));
+
+        let runs = batch_runs(&run, Some("my-project"));
+
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0]["run_type"], json!("chain"));
+        assert_eq!(runs[0]["inputs"], json!({"input": "question"}));
+        assert_eq!(runs[1]["run_type"], json!("tool"));
+        assert_eq!(runs[1]["parent_run_id"], json!(run.id));
+        assert_eq!(runs[1]["inputs"], json!({"query": "rust"}));
+        assert_eq!(runs[1]["session_name"], json!("my-project"));
+        assert!(runs[1]["dotted_order"]
+            .as_str()
+            .unwrap()
+            .starts_with(runs[0]["dotted_order"].as_str().unwrap()));
+    }
+
+    #[test]
+    fn test_dotted_order_segment() {
+        let start_time = DateTime::parse_from_rfc3339("2024-01-02T03:04:05.123456Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            dotted_order_segment(&start_time, "abc"),
+            "20240102T030405123456Zabc"
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Export of agent runs to LLM observability platforms.
+//!
+//! A [Run] records the steps of an agent run (completions, tool calls and retrievals) along with
+//! its input, output and evaluation scores. Once the run is finished, it can be reported with any
+//! [TraceExporter], such as the [langfuse::LangfuseExporter] or the
+//! [langsmith::LangSmithExporter], so teams can reuse their existing evaluation tooling with rig.
+//!
+//! # Example
+//! ```rust
+//! use chrono::Utc;
+//! use rig::{
+//!     completion::Completion,
+//!     telemetry::export::{langfuse::LangfuseExporter, Run, TraceExporter},
+//! };
+//! use serde_json::json;
+//!
+//! let exporter = LangfuseExporter::from_env();
+//! let mut run = Run::new("support_agent", json!("How do I reset my password?"));
+//!
+//! let started_at = Utc::now();
+//! let response = agent.completion("How do I reset my password?", vec![]).await?.send().await?;
+//! run.record_completion(
+//!     "gpt-4o",
+//!     json!("How do I reset my password?"),
+//!     json!(response.choice),
+//!     response.usage,
+//!     started_at,
+//! );
+//!
+//! run.score("helpfulness", 0.9, None);
+//! run.finish(json!(response.choice));
+//!
+//! exporter.export(&run).await?;
+//! ```
+
+pub mod langfuse;
+pub mod langsmith;
+
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{completion::Usage, wasm_compat::WasmCompatSend};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    /// Http error (e.g.: connection error, timeout, etc.)
+    #[error("HttpError: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    /// Json error (e.g.: serialization, deserialization)
+    #[error("JsonError: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    /// Error returned by the observability platform
+    #[error("ResponseError: {0}")]
+    ResponseError(String),
+}
+
+/// Trait for exporters reporting finished [Run]s to an observability platform.
+pub trait TraceExporter: Send + Sync {
+    /// Report the run and all its steps and scores
+    fn export(&self, run: &Run) -> impl Future<Output = Result<(), ExportError>> + WasmCompatSend;
+}
+
+/// Kind of a [Step] of a run
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepKind {
+    /// Completion request sent to a model
+    Completion { model: String, usage: Usage },
+    /// Call of a tool
+    ToolCall,
+    /// Retrieval of documents from a vector store
+    Retrieval,
+}
+
+/// Single step of a [Run]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Step {
+    pub id: String,
+    pub name: String,
+    pub kind: StepKind,
+    pub input: Value,
+    pub output: Value,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+/// Evaluation score attached to a [Run] (e.g.: user feedback, LLM-as-a-judge grade)
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Score {
+    pub id: String,
+    pub name: String,
+    pub value: f64,
+    pub comment: Option<String>,
+}
+
+/// Record of an agent run, reported as a trace by [TraceExporter]s
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Run {
+    pub id: String,
+    pub name: String,
+    pub input: Value,
+    pub output: Option<Value>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub metadata: serde_json::Map<String, Value>,
+    pub steps: Vec<Step>,
+    pub scores: Vec<Score>,
+}
+
+impl Run {
+    /// Start a new run
+    pub fn new(name: &str, input: Value) -> Self {
+        Self {
+            id: new_id(),
+            name: name.to_string(),
+            input,
+            output: None,
+            start_time: Utc::now(),
+            end_time: None,
+            metadata: serde_json::Map::new(),
+            steps: Vec::new(),
+            scores: Vec::new(),
+        }
+    }
+
+    /// Add a metadata entry to the run (e.g.: user id, session id, environment)
+    pub fn metadata(&mut self, key: &str, value: Value) -> &mut Self {
+        self.metadata.insert(key.to_string(), value);
+        self
+    }
+
+    /// Record a completion request to `model` started at `started_at` and ending now
+    pub fn record_completion(
+        &mut self,
+        model: &str,
+        prompt: Value,
+        completion: Value,
+        usage: Usage,
+        started_at: DateTime<Utc>,
+    ) -> &mut Self {
+        self.record_step(
+            model,
+            StepKind::Completion {
+                model: model.to_string(),
+                usage,
+            },
+            prompt,
+            completion,
+            started_at,
+        )
+    }
+
+    /// Record a call of `tool` started at `started_at` and ending now
+    pub fn record_tool_call(
+        &mut self,
+        tool: &str,
+        args: Value,
+        result: Value,
+        started_at: DateTime<Utc>,
+    ) -> &mut Self {
+        self.record_step(tool, StepKind::ToolCall, args, result, started_at)
+    }
+
+    /// Record a retrieval of `documents` for `query` started at `started_at` and ending now
+    pub fn record_retrieval(
+        &mut self,
+        query: &str,
+        documents: Value,
+        started_at: DateTime<Utc>,
+    ) -> &mut Self {
+        self.record_step(
+            "retrieval",
+            StepKind::Retrieval,
+            Value::String(query.to_string()),
+            documents,
+            started_at,
+        )
+    }
+
+    /// Attach an evaluation score to the run
+    pub fn score(&mut self, name: &str, value: f64, comment: Option<String>) -> &mut Self {
+        self.scores.push(Score {
+            id: new_id(),
+            name: name.to_string(),
+            value,
+            comment,
+        });
+        self
+    }
+
+    /// Mark the run as finished with the given output
+    pub fn finish(&mut self, output: Value) -> &mut Self {
+        self.output = Some(output);
+        self.end_time = Some(Utc::now());
+        self
+    }
+
+    fn record_step(
+        &mut self,
+        name: &str,
+        kind: StepKind,
+        input: Value,
+        output: Value,
+        started_at: DateTime<Utc>,
+    ) -> &mut Self {
+        self.steps.push(Step {
+            id: new_id(),
+            name: name.to_string(),
+            kind,
+            input,
+            output,
+            start_time: started_at,
+            end_time: Utc::now(),
+        });
+        self
+    }
+}
+
+fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_run_recording() {
+        let mut run = Run::new("agent", json!("What is 2 + 2?"));
+        let started_at = Utc::now();
+
+        run.record_completion(
+            "gpt-4o",
+            json!("What is 2 + 2?"),
+            json!({"tool": "add"}),
+            Usage::new(10, 5),
+            started_at,
+        )
+        .record_tool_call("add", json!({"x": 2, "y": 2}), json!(4), started_at)
+        .score("correctness", 1.0, None)
+        .finish(json!("4"));
+
+        assert_eq!(run.steps.len(), 2);
+        assert_eq!(run.steps[1].kind, StepKind::ToolCall);
+        assert_eq!(run.scores[0].name, "correctness");
+        assert_eq!(run.output, Some(json!("4")));
+        assert!(run.end_time.is_some());
+    }
+}
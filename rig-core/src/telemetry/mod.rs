@@ -0,0 +1,84 @@
+//! Helpers used to instrument provider calls and agent steps with [tracing] spans.
+//!
+//! Every completion, streaming completion, embedding request, agent turn and tool call is
+//! wrapped in a span emitted under the `rig` target, so running an application with
+//! `RUST_LOG=rig=debug` (and a `tracing` subscriber installed) gives a complete picture of
+//! what an agent did. The spans carry the following fields (when known):
+//! - `provider`: name of the provider module (e.g.: `openai`, `anthropic`)
+//! - `model`: name of the model (e.g.: `gpt-4o`)
+//! - `input_tokens` / `output_tokens`: token usage reported by the provider
+//! - `latency_ms`: wall-clock duration of the call
+//! - `retries`: number of times the HTTP request was retried (see [crate::http_client])
+//! - `tool_calls` / `tool`: names of the tools requested by the model or being called
+//!
+//! With the `otel` feature enabled, the [otel] module provides a layer exporting these spans
+//! to OpenTelemetry following the GenAI semantic conventions.
+//!
+//! With the `trace-export` feature enabled, the [export] module provides exporters reporting
+//! agent runs (prompts, completions, tool calls, retrievals and scores) to Langfuse or LangSmith.
+
+use crate::{completion::ResponseTiming, wasm_compat::Instant};
+
+#[cfg(feature = "trace-export")]
+pub mod export;
+#[cfg(feature = "otel")]
+pub mod otel;
+
+/// Returns the name of the provider module a model type is defined in, derived from its
+/// type path (e.g.: `rig::providers::openai::completion::CompletionModel` -> `openai`).
+/// Types defined outside of a `providers` module are reported under their crate name.
+pub(crate) fn provider_name<M>() -> &'static str {
+    let path = std::any::type_name::<M>();
+    // Strip generic parameters so that wrapper types are named after the outer type
+    let path = path.split('<').next().unwrap_or(path);
+
+    let mut segments = path.split("::");
+    let crate_name = segments.next().unwrap_or(path);
+
+    segments
+        .skip_while(|segment| *segment != "providers")
+        .nth(1)
+        .unwrap_or(crate_name)
+}
+
+/// Record the name of the model used by the current provider call on the current span.
+pub(crate) fn record_model(model: &str) {
+    tracing::Span::current().record("model", model);
+}
+
+/// Record the token usage reported by the provider on the current span.
+pub(crate) fn record_token_usage(input_tokens: u64, output_tokens: u64) {
+    let span = tracing::Span::current();
+    span.record("input_tokens", input_tokens);
+    span.record("output_tokens", output_tokens);
+}
+
+/// Record the time elapsed since `start` on the current span.
+pub(crate) fn record_latency(start: Instant) {
+    tracing::Span::current().record("latency_ms", start.elapsed().as_millis() as u64);
+}
+
+/// Record the latency and retries of a request on the current span
+pub(crate) fn record_timing(timing: &ResponseTiming) {
+    let span = tracing::Span::current();
+    span.record("latency_ms", timing.total.as_millis() as u64);
+    span.record("retries", timing.retries);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_name() {
+        assert_eq!(
+            provider_name::<crate::providers::openai::CompletionModel>(),
+            "openai"
+        );
+        assert_eq!(
+            provider_name::<crate::providers::groq::CompletionModel>(),
+            "groq"
+        );
+        assert_eq!(provider_name::<String>(), "alloc");
+    }
+}
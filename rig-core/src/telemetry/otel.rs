@@ -0,0 +1,203 @@
+//! OpenTelemetry integration following the [GenAI semantic conventions](https://opentelemetry.io/docs/specs/semconv/gen-ai/).
+//!
+//! The [GenAiLayer] is a [tracing_subscriber::Layer] that translates the fields of the spans
+//! emitted by rig (see [crate::telemetry]) into `gen_ai.*` attributes (e.g.: `gen_ai.system`,
+//! `gen_ai.request.model`, `gen_ai.usage.input_tokens`) on the OpenTelemetry spans built by
+//! [tracing_opentelemetry], so rig applications can be plugged into existing LLM observability
+//! backends without any additional instrumentation.
+//!
+//! Note: the [GenAiLayer] must be registered *after* the [tracing_opentelemetry] layer, since it
+//! enriches the OpenTelemetry span data created by the latter.
+//!
+//! # Example
+//! ```rust
+//! use opentelemetry::trace::TracerProvider;
+//! use rig::telemetry::otel::GenAiLayer;
+//! use tracing_subscriber::prelude::*;
+//!
+//! let provider = opentelemetry_sdk::trace::TracerProvider::builder()
+//!     .with_simple_exporter(opentelemetry_stdout::SpanExporter::default())
+//!     .build();
+//!
+//! tracing_subscriber::registry()
+//!     .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("my-app")))
+//!     .with(GenAiLayer)
+//!     .init();
+//! ```
+
+use opentelemetry::{trace::SpanKind, KeyValue};
+use tracing::{
+    field::{Field, Visit},
+    span, Subscriber,
+};
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// `gen_ai.system` attribute: the provider of the model (e.g.: `openai`)
+pub const GEN_AI_SYSTEM: &str = "gen_ai.system";
+/// `gen_ai.operation.name` attribute: the operation performed (e.g.: `chat`, `embeddings`)
+pub const GEN_AI_OPERATION_NAME: &str = "gen_ai.operation.name";
+/// `gen_ai.request.model` attribute: the name of the model the request was sent to
+pub const GEN_AI_REQUEST_MODEL: &str = "gen_ai.request.model";
+/// `gen_ai.usage.input_tokens` attribute: the number of tokens in the prompt
+pub const GEN_AI_USAGE_INPUT_TOKENS: &str = "gen_ai.usage.input_tokens";
+/// `gen_ai.usage.output_tokens` attribute: the number of tokens in the response
+pub const GEN_AI_USAGE_OUTPUT_TOKENS: &str = "gen_ai.usage.output_tokens";
+/// `gen_ai.tool.name` attribute: the name of the tool being executed
+pub const GEN_AI_TOOL_NAME: &str = "gen_ai.tool.name";
+
+/// Layer adding GenAI semantic convention attributes to the OpenTelemetry spans of rig operations.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GenAiLayer;
+
+impl<S> Layer<S> for GenAiLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let metadata = attrs.metadata();
+        if metadata.target() != "rig" {
+            return;
+        }
+
+        let Some(operation) = operation_name(metadata.name()) else {
+            return;
+        };
+
+        let mut visitor = GenAiVisitor::default();
+        attrs.record(&mut visitor);
+
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        let Some(otel_data) = extensions.get_mut::<OtelData>() else {
+            return;
+        };
+
+        otel_data.builder.span_kind = Some(match operation {
+            "execute_tool" | "invoke_agent" => SpanKind::Internal,
+            _ => SpanKind::Client,
+        });
+        otel_data.builder.name = operation.into();
+
+        visitor
+            .attributes
+            .push(KeyValue::new(GEN_AI_OPERATION_NAME, operation));
+        apply(otel_data, visitor);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if span.metadata().target() != "rig" || operation_name(span.metadata().name()).is_none() {
+            return;
+        }
+
+        let mut visitor = GenAiVisitor::default();
+        values.record(&mut visitor);
+
+        let mut extensions = span.extensions_mut();
+        if let Some(otel_data) = extensions.get_mut::<OtelData>() {
+            apply(otel_data, visitor);
+        }
+    }
+}
+
+/// Maps the name of a rig span to the corresponding `gen_ai.operation.name`
+fn operation_name(span_name: &str) -> Option<&'static str> {
+    match span_name {
+        "completion" | "streaming_completion" => Some("chat"),
+        "embedding" => Some("embeddings"),
+        "agent_chat" => Some("invoke_agent"),
+        "tool_call" => Some("execute_tool"),
+        _ => None,
+    }
+}
+
+/// Add the attributes collected by `visitor` to the span. The name of the span is kept as the
+/// operation name: the model is recorded after the span is created (and may be recorded several
+/// times), so it is only reported as the `gen_ai.request.model` attribute.
+fn apply(otel_data: &mut OtelData, visitor: GenAiVisitor) {
+    let attributes = otel_data.builder.attributes.get_or_insert_with(Vec::new);
+    for attribute in visitor.attributes {
+        match attributes.iter_mut().find(|kv| kv.key == attribute.key) {
+            Some(existing) => *existing = attribute,
+            None => attributes.push(attribute),
+        }
+    }
+}
+
+#[derive(Default)]
+struct GenAiVisitor {
+    attributes: Vec<KeyValue>,
+}
+
+impl Visit for GenAiVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "provider" => self
+                .attributes
+                .push(KeyValue::new(GEN_AI_SYSTEM, value.to_string())),
+            "model" => self
+                .attributes
+                .push(KeyValue::new(GEN_AI_REQUEST_MODEL, value.to_string())),
+            "tool" => self
+                .attributes
+                .push(KeyValue::new(GEN_AI_TOOL_NAME, value.to_string())),
+            _ => {}
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "input_tokens" => self
+                .attributes
+                .push(KeyValue::new(GEN_AI_USAGE_INPUT_TOKENS, value as i64)),
+            "output_tokens" => self
+                .attributes
+                .push(KeyValue::new(GEN_AI_USAGE_OUTPUT_TOKENS, value as i64)),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation_name() {
+        assert_eq!(operation_name("completion"), Some("chat"));
+        assert_eq!(operation_name("streaming_completion"), Some("chat"));
+        assert_eq!(operation_name("embedding"), Some("embeddings"));
+        assert_eq!(operation_name("tool_call"), Some("execute_tool"));
+        assert_eq!(operation_name("something_else"), None);
+    }
+
+    #[test]
+    fn test_apply_keeps_span_name() {
+        let mut otel_data = OtelData {
+            parent_cx: opentelemetry::Context::new(),
+            builder: opentelemetry::trace::SpanBuilder::from_name("chat"),
+        };
+
+        for model in ["m1", "m2"] {
+            apply(
+                &mut otel_data,
+                GenAiVisitor {
+                    attributes: vec![KeyValue::new(GEN_AI_REQUEST_MODEL, model)],
+                },
+            );
+        }
+
+        assert_eq!(otel_data.builder.name, "chat");
+        assert_eq!(
+            otel_data.builder.attributes,
+            Some(vec![KeyValue::new(GEN_AI_REQUEST_MODEL, "m2")])
+        );
+    }
+}
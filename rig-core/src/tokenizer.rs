@@ -0,0 +1,391 @@
+//! This module provides utilities to count the tokens of texts, messages and completion requests,
+//! used to budget the context sent to models (e.g.: to truncate a chat history, pack retrieved
+//! documents or size chunks).
+//!
+//! A [Tokenizer] counts the tokens of a text according to a model family:
+//! - [TiktokenTokenizer] (requires the `tiktoken` feature) counts tokens exactly for OpenAI
+//!   models, with the `o200k_base` or `cl100k_base` encodings;
+//! - [ApproximateTokenizer] estimates tokens from the length of the text, with ratios tuned for
+//!   Anthropic and Gemini models, whose tokenizers are not public. For exact counts, use the
+//!   token counting endpoints of their providers (e.g.:
+//!   [anthropic::CompletionModel::count_tokens](crate::providers::anthropic::completion::CompletionModel::count_tokens)
+//!   and
+//!   [gemini::CompletionModel::count_tokens](crate::providers::gemini::completion::CompletionModel::count_tokens)).
+//!
+//! [tokenizer_for_model] picks the tokenizer for a model name.
+//!
+//! Anything implementing [CountTokens] (texts, [Message]s, [CompletionRequest]s, documents and
+//! tool definitions) can be counted with [Tokenizer::count_tokens]. Counts include the overhead of
+//! the message formatting of the model family, but remain estimates for media content.
+//!
+//! # Example
+//! ```rust
+//! use rig::{message::Message, tokenizer::{tokenizer_for_model, Tokenizer}};
+//!
+//! let tokenizer = tokenizer_for_model("gpt-4o");
+//!
+//! let history = vec![Message::user("Hello!"), Message::assistant("Hi! How can I help?")];
+//! let tokens: usize = history.iter().map(|message| tokenizer.count_tokens(message)).sum();
+//! ```
+use std::sync::Arc;
+
+use crate::{
+    completion::{CompletionRequest, Document, ToolDefinition},
+    message::{AssistantContent, ContentFormat, Message, ToolResultContent, UserContent},
+};
+
+/// Rough estimate of the tokens of an image, audio clip or binary document attached to a message.
+/// The actual count depends on the provider and the resolution or duration of the content.
+pub const MEDIA_TOKENS: usize = 1000;
+
+/// Counts the tokens of texts according to a model family
+pub trait Tokenizer: Send + Sync {
+    /// Number of tokens of `text`
+    fn count_text(&self, text: &str) -> usize;
+
+    /// Number of tokens added by the formatting of each message (e.g.: role markers)
+    fn message_overhead(&self) -> usize {
+        0
+    }
+
+    /// Number of tokens of `item` (e.g.: a [Message] or a [CompletionRequest])
+    fn count_tokens<T: CountTokens + ?Sized>(&self, item: &T) -> usize
+    where
+        Self: Sized,
+    {
+        item.count_tokens(self)
+    }
+}
+
+impl Tokenizer for Box<dyn Tokenizer> {
+    fn count_text(&self, text: &str) -> usize {
+        self.as_ref().count_text(text)
+    }
+
+    fn message_overhead(&self) -> usize {
+        self.as_ref().message_overhead()
+    }
+}
+
+impl Tokenizer for Arc<dyn Tokenizer> {
+    fn count_text(&self, text: &str) -> usize {
+        self.as_ref().count_text(text)
+    }
+
+    fn message_overhead(&self) -> usize {
+        self.as_ref().message_overhead()
+    }
+}
+
+/// Types whose tokens can be counted by a [Tokenizer]
+pub trait CountTokens {
+    fn count_tokens(&self, tokenizer: &dyn Tokenizer) -> usize;
+}
+
+impl CountTokens for str {
+    fn count_tokens(&self, tokenizer: &dyn Tokenizer) -> usize {
+        tokenizer.count_text(self)
+    }
+}
+
+impl CountTokens for String {
+    fn count_tokens(&self, tokenizer: &dyn Tokenizer) -> usize {
+        tokenizer.count_text(self)
+    }
+}
+
+impl CountTokens for Message {
+    fn count_tokens(&self, tokenizer: &dyn Tokenizer) -> usize {
+        let content: usize = match self {
+            Message::User { content } => content
+                .iter()
+                .map(|content| match content {
+                    UserContent::Text(text) => tokenizer.count_text(&text.text),
+                    UserContent::ToolResult(result) => result
+                        .content
+                        .iter()
+                        .map(|content| match content {
+                            ToolResultContent::Text(text) => tokenizer.count_text(&text.text),
+                            ToolResultContent::Image(_) => MEDIA_TOKENS,
+                        })
+                        .sum(),
+                    UserContent::Document(document)
+                        if matches!(document.format, Some(ContentFormat::String)) =>
+                    {
+                        tokenizer.count_text(&document.data)
+                    }
+                    UserContent::Image(_) | UserContent::Audio(_) | UserContent::Document(_) => {
+                        MEDIA_TOKENS
+                    }
+                })
+                .sum(),
+            Message::Assistant { content } => content
+                .iter()
+                .map(|content| match content {
+                    AssistantContent::Text(text) => tokenizer.count_text(&text.text),
+                    AssistantContent::ToolCall(call) => {
+                        tokenizer.count_text(&call.function.name)
+                            + tokenizer.count_text(&call.function.arguments.to_string())
+                    }
+                })
+                .sum(),
+            Message::System { content } => tokenizer.count_text(content),
+        };
+
+        content + tokenizer.message_overhead()
+    }
+}
+
+impl CountTokens for Document {
+    fn count_tokens(&self, tokenizer: &dyn Tokenizer) -> usize {
+        tokenizer.count_text(&self.to_string())
+    }
+}
+
+impl CountTokens for ToolDefinition {
+    fn count_tokens(&self, tokenizer: &dyn Tokenizer) -> usize {
+        tokenizer.count_text(&self.name)
+            + tokenizer.count_text(&self.description)
+            + tokenizer.count_text(&self.parameters.to_string())
+    }
+}
+
+impl CountTokens for CompletionRequest {
+    /// Number of input tokens of the request: preamble, chat history, prompt (with the documents)
+    /// and tool definitions
+    fn count_tokens(&self, tokenizer: &dyn Tokenizer) -> usize {
+        let preamble = self
+            .preamble
+            .as_ref()
+            .map(|preamble| tokenizer.count_text(preamble) + tokenizer.message_overhead())
+            .unwrap_or_default();
+        let chat_history: usize = self
+            .chat_history
+            .iter()
+            .map(|message| message.count_tokens(tokenizer))
+            .sum();
+        let tools: usize = self
+            .tools
+            .iter()
+            .map(|tool| tool.count_tokens(tokenizer))
+            .sum();
+
+        preamble + chat_history + self.prompt_with_context().count_tokens(tokenizer) + tools
+    }
+}
+
+impl<T: CountTokens + ?Sized> CountTokens for &T {
+    fn count_tokens(&self, tokenizer: &dyn Tokenizer) -> usize {
+        (**self).count_tokens(tokenizer)
+    }
+}
+
+impl<T: CountTokens> CountTokens for [T] {
+    fn count_tokens(&self, tokenizer: &dyn Tokenizer) -> usize {
+        self.iter().map(|item| item.count_tokens(tokenizer)).sum()
+    }
+}
+
+impl<T: CountTokens> CountTokens for Vec<T> {
+    fn count_tokens(&self, tokenizer: &dyn Tokenizer) -> usize {
+        self.as_slice().count_tokens(tokenizer)
+    }
+}
+
+/// [Tokenizer] estimating the tokens of texts from their length in bytes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ApproximateTokenizer {
+    bytes_per_token: f64,
+    message_overhead: usize,
+}
+
+impl ApproximateTokenizer {
+    /// Estimate tokens as `bytes_per_token` bytes of text each
+    pub fn new(bytes_per_token: f64) -> Self {
+        Self {
+            bytes_per_token: bytes_per_token.max(f64::EPSILON),
+            message_overhead: 4,
+        }
+    }
+
+    /// Estimate of the tokens of Anthropic (Claude) models
+    pub fn anthropic() -> Self {
+        Self::new(3.5)
+    }
+
+    /// Estimate of the tokens of Google Gemini models
+    pub fn gemini() -> Self {
+        Self::new(4.0)
+    }
+
+    /// Count `message_overhead` tokens for the formatting of each message (4 by default)
+    pub fn with_message_overhead(mut self, message_overhead: usize) -> Self {
+        self.message_overhead = message_overhead;
+        self
+    }
+}
+
+impl Default for ApproximateTokenizer {
+    /// Estimate tokens as 4 bytes of text each
+    fn default() -> Self {
+        Self::new(4.0)
+    }
+}
+
+impl Tokenizer for ApproximateTokenizer {
+    fn count_text(&self, text: &str) -> usize {
+        (text.len() as f64 / self.bytes_per_token).ceil() as usize
+    }
+
+    fn message_overhead(&self) -> usize {
+        self.message_overhead
+    }
+}
+
+/// [Tokenizer] counting the tokens of OpenAI models with their BPE encoding
+#[cfg(feature = "tiktoken")]
+#[derive(Clone)]
+pub struct TiktokenTokenizer {
+    bpe: Arc<tiktoken_rs::CoreBPE>,
+}
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenTokenizer {
+    /// Tokenizer of the `o200k_base` encoding (GPT-4o, GPT-4.1, o1, o3, ...)
+    pub fn o200k_base() -> Self {
+        Self {
+            bpe: Arc::new(tiktoken_rs::o200k_base().expect("o200k_base encoding should load")),
+        }
+    }
+
+    /// Tokenizer of the `cl100k_base` encoding (GPT-4, GPT-3.5, text-embedding-3, ...)
+    pub fn cl100k_base() -> Self {
+        Self {
+            bpe: Arc::new(tiktoken_rs::cl100k_base().expect("cl100k_base encoding should load")),
+        }
+    }
+
+    /// Tokenizer of the encoding of the OpenAI `model`
+    pub fn for_model(model: &str) -> Self {
+        if uses_o200k(model) {
+            Self::o200k_base()
+        } else {
+            Self::cl100k_base()
+        }
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl std::fmt::Debug for TiktokenTokenizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TiktokenTokenizer").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl Tokenizer for TiktokenTokenizer {
+    fn count_text(&self, text: &str) -> usize {
+        self.bpe.encode_ordinary(text).len()
+    }
+
+    fn message_overhead(&self) -> usize {
+        // Each message is wrapped in `<|start|>{role}\n{content}<|end|>\n`
+        3
+    }
+}
+
+/// Whether the OpenAI `model` uses the `o200k_base` encoding
+#[cfg_attr(not(feature = "tiktoken"), allow(dead_code))]
+fn uses_o200k(model: &str) -> bool {
+    [
+        "gpt-4o",
+        "gpt-4.1",
+        "gpt-4.5",
+        "gpt-5",
+        "chatgpt-4o",
+        "o1",
+        "o3",
+        "o4",
+    ]
+    .iter()
+    .any(|prefix| model.starts_with(prefix))
+}
+
+/// Whether `model` is an OpenAI model
+fn is_openai(model: &str) -> bool {
+    ["gpt-", "chatgpt-", "text-embedding-", "o1", "o3", "o4"]
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+}
+
+/// The tokenizer of the model family of `model`: exact for OpenAI models (with the `tiktoken`
+/// feature), approximate otherwise
+pub fn tokenizer_for_model(model: &str) -> Box<dyn Tokenizer> {
+    if is_openai(model) {
+        #[cfg(feature = "tiktoken")]
+        return Box::new(TiktokenTokenizer::for_model(model));
+    }
+
+    if model.starts_with("claude") {
+        Box::new(ApproximateTokenizer::anthropic())
+    } else if model.starts_with("gemini") {
+        Box::new(ApproximateTokenizer::gemini())
+    } else {
+        Box::new(ApproximateTokenizer::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::completion::CompletionRequestBuilder;
+    use crate::providers::mock::MockCompletionModel;
+
+    #[test]
+    fn test_approximate_tokenizer() {
+        let tokenizer = ApproximateTokenizer::default();
+
+        assert_eq!(tokenizer.count_tokens("Flurbos are green."), 5);
+        assert_eq!(
+            tokenizer.count_tokens(&Message::user("Flurbos are green.")),
+            9
+        );
+        assert_eq!(
+            tokenizer.count_tokens(&Message::assistant("Flurbos are green.")),
+            9
+        );
+        assert_eq!(tokenizer.count_tokens(""), 0);
+    }
+
+    #[test]
+    fn test_count_request() {
+        let tokenizer = ApproximateTokenizer::new(1.0).with_message_overhead(0);
+
+        let request = CompletionRequestBuilder::new(MockCompletionModel::new(), "abcd")
+            .preamble("ab".to_string())
+            .messages(vec![Message::user("abc"), Message::assistant("a")])
+            .tool(ToolDefinition {
+                name: "add".to_string(),
+                description: "sum".to_string(),
+                parameters: json!({}),
+            })
+            .build();
+
+        // preamble + history + prompt + tool (name, description and "{}")
+        assert_eq!(tokenizer.count_tokens(&request), 2 + 4 + 4 + 8);
+        assert_eq!(
+            tokenizer_for_model("claude-3-5-sonnet").count_tokens("1234567"),
+            2
+        );
+    }
+
+    #[test]
+    fn test_model_families() {
+        assert!(is_openai("gpt-4o-mini"));
+        assert!(uses_o200k("gpt-4o-mini"));
+        assert!(!uses_o200k("gpt-4-turbo"));
+        assert!(!is_openai("claude-3-opus"));
+    }
+}
@@ -9,14 +9,17 @@
 //! The [ToolSet] struct is a collection of tools that can be used by an [Agent](crate::agent::Agent)
 //! and optionally RAGged.
 
-use std::{collections::HashMap, pin::Pin};
+use std::collections::HashMap;
 
 use futures::Future;
 use serde::{Deserialize, Serialize};
+use tracing::{field, Instrument};
 
 use crate::{
     completion::{self, ToolDefinition},
     embeddings::{embed::EmbedError, tool::ToolSchema},
+    telemetry,
+    wasm_compat::{Instant, WasmBoxedSyncFuture, WasmCompatSend, WasmCompatSync},
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -102,7 +105,10 @@ pub trait Tool: Sized + Send + Sync {
 
     /// A method returning the tool definition. The user prompt can be used to
     /// tailor the definition to the specific use case.
-    fn definition(&self, _prompt: String) -> impl Future<Output = ToolDefinition> + Send + Sync;
+    fn definition(
+        &self,
+        _prompt: String,
+    ) -> impl Future<Output = ToolDefinition> + WasmCompatSend + WasmCompatSync;
 
     /// The tool execution method.
     /// Both the arguments and return value are a String since these values are meant to
@@ -110,7 +116,7 @@ pub trait Tool: Sized + Send + Sync {
     fn call(
         &self,
         args: Self::Args,
-    ) -> impl Future<Output = Result<Self::Output, Self::Error>> + Send + Sync;
+    ) -> impl Future<Output = Result<Self::Output, Self::Error>> + WasmCompatSend + WasmCompatSync;
 }
 
 /// Trait that represents an LLM tool that can be stored in a vector store and RAGged
@@ -144,15 +150,9 @@ pub trait ToolEmbedding: Tool {
 pub trait ToolDyn: Send + Sync {
     fn name(&self) -> String;
 
-    fn definition(
-        &self,
-        prompt: String,
-    ) -> Pin<Box<dyn Future<Output = ToolDefinition> + Send + Sync + '_>>;
+    fn definition(&self, prompt: String) -> WasmBoxedSyncFuture<'_, ToolDefinition>;
 
-    fn call(
-        &self,
-        args: String,
-    ) -> Pin<Box<dyn Future<Output = Result<String, ToolError>> + Send + Sync + '_>>;
+    fn call(&self, args: String) -> WasmBoxedSyncFuture<'_, Result<String, ToolError>>;
 }
 
 impl<T: Tool> ToolDyn for T {
@@ -160,17 +160,11 @@ impl<T: Tool> ToolDyn for T {
         self.name()
     }
 
-    fn definition(
-        &self,
-        prompt: String,
-    ) -> Pin<Box<dyn Future<Output = ToolDefinition> + Send + Sync + '_>> {
+    fn definition(&self, prompt: String) -> WasmBoxedSyncFuture<'_, ToolDefinition> {
         Box::pin(<Self as Tool>::definition(self, prompt))
     }
 
-    fn call(
-        &self,
-        args: String,
-    ) -> Pin<Box<dyn Future<Output = Result<String, ToolError>> + Send + Sync + '_>> {
+    fn call(&self, args: String) -> WasmBoxedSyncFuture<'_, Result<String, ToolError>> {
         Box::pin(async move {
             match serde_json::from_str(&args) {
                 Ok(args) => <Self as Tool>::call(self, args)
@@ -288,11 +282,23 @@ impl ToolSet {
     /// Call a tool with the given name and arguments
     pub async fn call(&self, toolname: &str, args: String) -> Result<String, ToolSetError> {
         if let Some(tool) = self.tools.get(toolname) {
-            tracing::info!(target: "rig",
-                "Calling tool {toolname} with args:\n{}",
-                serde_json::to_string_pretty(&args).unwrap_or_else(|_| args.clone())
+            let span = tracing::info_span!(target: "rig", "tool_call",
+                tool = toolname,
+                latency_ms = field::Empty,
             );
-            Ok(tool.call(args).await?)
+
+            async move {
+                tracing::info!(target: "rig",
+                    "Calling tool {toolname} with args:\n{}",
+                    serde_json::to_string_pretty(&args).unwrap_or_else(|_| args.clone())
+                );
+                let start = Instant::now();
+                let result = tool.call(args).await;
+                telemetry::record_latency(start);
+                Ok(result?)
+            }
+            .instrument(span)
+            .await
         } else {
             Err(ToolSetError::ToolNotFoundError(toolname.to_string()))
         }
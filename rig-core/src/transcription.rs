@@ -1,19 +1,52 @@
 //! This module provides functionality for working with audio transcription models.
 //! It provides traits, structs, and enums for generating audio transcription requests,
 //! handling transcription responses, and defining transcription models.
+//!
+//! [TranscriptionModel] is the provider-agnostic interface of speech-to-text models (see
+//! `SpeechModel`, with the `audio` feature, for text-to-speech), and
+//! [BoxTranscriptionModel] erases the type of a transcription model so that the provider can be
+//! selected at runtime (e.g.: with the [ModelRegistry](crate::registry::ModelRegistry)).
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     providers::{groq, openai},
+//!     transcription::{BoxTranscriptionModel, TranscriptionModel},
+//! };
+//!
+//! let model: BoxTranscriptionModel = match std::env::var("PROVIDER").as_deref() {
+//!     Ok("groq") => groq::Client::from_env()
+//!         .transcription_model("whisper-large-v3")
+//!         .boxed(),
+//!     _ => openai::Client::from_env()
+//!         .transcription_model(openai::WHISPER_1)
+//!         .boxed(),
+//! };
+//!
+//! let response = model
+//!     .transcription_request()
+//!     .load_file("audio.mp3")
+//!     .send()
+//!     .await?;
+//! println!("{}", response.text);
+//! ```
 
-use std::{fs, path::Path};
+use std::{fs, path::Path, sync::Arc};
 
 use thiserror::Error;
 
-use crate::json_utils;
+use crate::{
+    completion::BoxRawResponse,
+    json_utils,
+    wasm_compat::{WasmBoxedFuture, WasmCompatSend},
+};
 
 // Errors
 #[derive(Debug, Error)]
 pub enum TranscriptionError {
     /// Http error (e.g.: connection error, timeout, etc.)
     #[error("HttpError: {0}")]
-    HttpError(#[from] reqwest::Error),
+    HttpError(#[from] crate::http_client::HttpError),
 
     /// Json error (e.g.: serialization, deserialization)
     #[error("JsonError: {0}")]
@@ -32,6 +65,12 @@ pub enum TranscriptionError {
     ProviderError(String),
 }
 
+impl From<reqwest::Error> for TranscriptionError {
+    fn from(error: reqwest::Error) -> Self {
+        TranscriptionError::HttpError(error.into())
+    }
+}
+
 /// Trait defining a low-level LLM transcription interface
 pub trait Transcription<M: TranscriptionModel> {
     /// Generates a transcription request builder for the given `file`.
@@ -47,7 +86,7 @@ pub trait Transcription<M: TranscriptionModel> {
         filename: &str,
         data: &[u8],
     ) -> impl std::future::Future<Output = Result<TranscriptionRequestBuilder<M>, TranscriptionError>>
-           + Send;
+           + WasmCompatSend;
 }
 
 /// General transcription response struct that contains the transcription text
@@ -70,12 +109,22 @@ pub trait TranscriptionModel: Clone + Send + Sync {
         request: TranscriptionRequest,
     ) -> impl std::future::Future<
         Output = Result<TranscriptionResponse<Self::Response>, TranscriptionError>,
-    > + Send;
+    > + WasmCompatSend;
 
     /// Generates a transcription request builder for the given `file`
     fn transcription_request(&self) -> TranscriptionRequestBuilder<Self> {
         TranscriptionRequestBuilder::new(self.clone())
     }
+
+    /// Erases the type of the model, e.g.: to select the provider at runtime (see
+    /// [BoxTranscriptionModel]).
+    fn boxed(self) -> BoxTranscriptionModel
+    where
+        Self: 'static,
+        Self::Response: 'static,
+    {
+        BoxTranscriptionModel::new(self)
+    }
 }
 
 /// Struct representing a general transcription request that can be sent to a transcription model provider.
@@ -249,3 +298,112 @@ impl<M: TranscriptionModel> TranscriptionRequestBuilder<M> {
         model.transcription(self.build()).await
     }
 }
+
+/// Object safe version of [TranscriptionModel], implemented by all transcription models.
+///
+/// The methods are prefixed with `dyn_` so that they do not conflict with the methods of
+/// [TranscriptionModel] when both traits are in scope.
+pub trait DynTranscriptionModel: Send + Sync {
+    /// Generates a transcription response for the given transcription request
+    /// (see [TranscriptionModel::transcription]).
+    fn dyn_transcription(
+        &self,
+        request: TranscriptionRequest,
+    ) -> WasmBoxedFuture<'_, Result<TranscriptionResponse<BoxRawResponse>, TranscriptionError>>;
+}
+
+impl<M> DynTranscriptionModel for M
+where
+    M: TranscriptionModel + 'static,
+    M::Response: 'static,
+{
+    fn dyn_transcription(
+        &self,
+        request: TranscriptionRequest,
+    ) -> WasmBoxedFuture<'_, Result<TranscriptionResponse<BoxRawResponse>, TranscriptionError>>
+    {
+        Box::pin(async move {
+            let response = self.transcription(request).await?;
+
+            Ok(TranscriptionResponse {
+                text: response.text,
+                response: Box::new(response.response) as BoxRawResponse,
+            })
+        })
+    }
+}
+
+/// Type-erased transcription model (see
+/// [BoxCompletionModel](crate::completion::BoxCompletionModel) for completion models). Cheap to
+/// clone.
+#[derive(Clone)]
+pub struct BoxTranscriptionModel(Arc<dyn DynTranscriptionModel>);
+
+impl BoxTranscriptionModel {
+    pub fn new<M>(model: M) -> Self
+    where
+        M: TranscriptionModel + 'static,
+        M::Response: 'static,
+    {
+        Self(Arc::new(model))
+    }
+}
+
+impl TranscriptionModel for BoxTranscriptionModel {
+    /// The raw response of the wrapped model, which can be downcast to its `Response` type
+    type Response = BoxRawResponse;
+
+    async fn transcription(
+        &self,
+        request: TranscriptionRequest,
+    ) -> Result<TranscriptionResponse<BoxRawResponse>, TranscriptionError> {
+        self.0.dyn_transcription(request).await
+    }
+
+    fn boxed(self) -> BoxTranscriptionModel {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Model "transcribing" UTF-8 audio data, returning the length of the data as raw response
+    #[derive(Clone)]
+    struct Utf8Model;
+
+    impl TranscriptionModel for Utf8Model {
+        type Response = usize;
+
+        async fn transcription(
+            &self,
+            request: TranscriptionRequest,
+        ) -> Result<TranscriptionResponse<usize>, TranscriptionError> {
+            Ok(TranscriptionResponse {
+                text: format!(
+                    "[{}] {}",
+                    request.language,
+                    String::from_utf8_lossy(&request.data)
+                ),
+                response: request.data.len(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_box_transcription_model() {
+        let model = Utf8Model.boxed();
+
+        let response = model
+            .transcription_request()
+            .data(b"Hello".to_vec())
+            .language("fr".to_string())
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "[fr] Hello");
+        assert_eq!(response.response.downcast_ref::<usize>(), Some(&5));
+    }
+}
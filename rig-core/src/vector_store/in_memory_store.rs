@@ -7,7 +7,7 @@ use std::{
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 
-use super::{VectorStoreError, VectorStoreIndex};
+use super::{VectorStoreError, VectorStoreIndex, VectorStoreWriter};
 use crate::{
     embeddings::{distance::VectorDistance, Embedding, EmbeddingModel},
     OneOrMany,
@@ -157,6 +157,23 @@ impl<D: Serialize + Eq> InMemoryVectorStore<D> {
     }
 }
 
+impl<D: Serialize + Eq + Send + Sync> VectorStoreWriter<D> for InMemoryVectorStore<D> {
+    async fn upsert_documents(
+        &mut self,
+        documents: Vec<(String, D, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        self.add_documents_with_ids(documents);
+        Ok(())
+    }
+
+    async fn delete_documents(&mut self, ids: Vec<String>) -> Result<(), VectorStoreError> {
+        for id in ids {
+            self.embeddings.remove(&id);
+        }
+        Ok(())
+    }
+}
+
 /// RankingItem(distance, document_id, serializable document, embeddings document)
 #[derive(Eq, PartialEq)]
 struct RankingItem<'a, D: Serialize>(OrderedFloat<f64>, &'a String, &'a D, &'a String);
@@ -259,6 +276,21 @@ impl<M: EmbeddingModel + Sync, D: Serialize + Sync + Send + Eq> VectorStoreIndex
     }
 }
 
+impl<M: EmbeddingModel, D: Serialize + Eq + Send + Sync> VectorStoreWriter<D>
+    for InMemoryVectorIndex<M, D>
+{
+    async fn upsert_documents(
+        &mut self,
+        documents: Vec<(String, D, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        self.store.upsert_documents(documents).await
+    }
+
+    async fn delete_documents(&mut self, ids: Vec<String>) -> Result<(), VectorStoreError> {
+        self.store.delete_documents(ids).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::cmp::Reverse;
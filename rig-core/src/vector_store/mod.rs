@@ -1,8 +1,11 @@
-use futures::future::BoxFuture;
 use serde::Deserialize;
 use serde_json::Value;
 
-use crate::embeddings::EmbeddingError;
+use crate::{
+    embeddings::{Embedding, EmbeddingError},
+    wasm_compat::{WasmBoxedFuture, WasmCompatSend},
+    OneOrMany,
+};
 
 pub mod in_memory_store;
 
@@ -30,26 +33,44 @@ pub trait VectorStoreIndex: Send + Sync {
         &self,
         query: &str,
         n: usize,
-    ) -> impl std::future::Future<Output = Result<Vec<(f64, String, T)>, VectorStoreError>> + Send;
+    ) -> impl std::future::Future<Output = Result<Vec<(f64, String, T)>, VectorStoreError>>
+           + WasmCompatSend;
 
     /// Same as `top_n` but returns the document ids only.
     fn top_n_ids(
         &self,
         query: &str,
         n: usize,
-    ) -> impl std::future::Future<Output = Result<Vec<(f64, String)>, VectorStoreError>> + Send;
+    ) -> impl std::future::Future<Output = Result<Vec<(f64, String)>, VectorStoreError>> + WasmCompatSend;
+}
+
+/// Trait for vector stores whose documents can be upserted and deleted by id (e.g.: to keep the
+/// store in sync with its sources, see [IncrementalIndexer](crate::indexing::IncrementalIndexer))
+pub trait VectorStoreWriter<D>: Send + Sync {
+    /// Insert the documents with their embeddings, replacing the documents with the same ids.
+    /// The documents are tuples of the form (id, document, embeddings)
+    fn upsert_documents(
+        &mut self,
+        documents: Vec<(String, D, OneOrMany<Embedding>)>,
+    ) -> impl std::future::Future<Output = Result<(), VectorStoreError>> + WasmCompatSend;
+
+    /// Delete the documents with the given ids. Missing documents are ignored.
+    fn delete_documents(
+        &mut self,
+        ids: Vec<String>,
+    ) -> impl std::future::Future<Output = Result<(), VectorStoreError>> + WasmCompatSend;
 }
 
 pub type TopNResults = Result<Vec<(f64, String, Value)>, VectorStoreError>;
 
 pub trait VectorStoreIndexDyn: Send + Sync {
-    fn top_n<'a>(&'a self, query: &'a str, n: usize) -> BoxFuture<'a, TopNResults>;
+    fn top_n<'a>(&'a self, query: &'a str, n: usize) -> WasmBoxedFuture<'a, TopNResults>;
 
     fn top_n_ids<'a>(
         &'a self,
         query: &'a str,
         n: usize,
-    ) -> BoxFuture<'a, Result<Vec<(f64, String)>, VectorStoreError>>;
+    ) -> WasmBoxedFuture<'a, Result<Vec<(f64, String)>, VectorStoreError>>;
 }
 
 impl<I: VectorStoreIndex> VectorStoreIndexDyn for I {
@@ -57,7 +78,7 @@ impl<I: VectorStoreIndex> VectorStoreIndexDyn for I {
         &'a self,
         query: &'a str,
         n: usize,
-    ) -> BoxFuture<'a, Result<Vec<(f64, String, Value)>, VectorStoreError>> {
+    ) -> WasmBoxedFuture<'a, Result<Vec<(f64, String, Value)>, VectorStoreError>> {
         Box::pin(async move {
             Ok(self
                 .top_n::<serde_json::Value>(query, n)
@@ -72,7 +93,7 @@ impl<I: VectorStoreIndex> VectorStoreIndexDyn for I {
         &'a self,
         query: &'a str,
         n: usize,
-    ) -> BoxFuture<'a, Result<Vec<(f64, String)>, VectorStoreError>> {
+    ) -> WasmBoxedFuture<'a, Result<Vec<(f64, String)>, VectorStoreError>> {
         Box::pin(self.top_n_ids(query, n))
     }
 }
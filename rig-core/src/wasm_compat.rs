@@ -0,0 +1,85 @@
+//! This module provides the compatibility layer used to compile rig for `wasm32-unknown-unknown`
+//! (i.e.: to run agents client-side in web applications, calling the providers through a proxy).
+//!
+//! In the browser, the futures of the HTTP requests (sent with the `fetch` API by [reqwest]) hold
+//! JavaScript values and are not [Send]. Since the browser runs rig on a single thread, the
+//! traits of rig require their futures to be [WasmCompatSend] (and their types to be
+//! [WasmCompatSync]), which is [Send] (resp. [Sync]) on every other target and implemented by
+//! every type on `wasm32`.
+//!
+//! `std::time::Instant` and `std::time::SystemTime` panic in the browser: the [Instant] and
+//! [SystemTime] of this module are the ones of `std` on every other target and are based on the
+//! clock of the browser on `wasm32` (with the `wasm` feature enabled).
+//!
+//! # Example
+//! ```toml
+//! [target.'cfg(target_arch = "wasm32")'.dependencies]
+//! rig-core = { version = "0.10", default-features = false, features = ["wasm"] }
+//! ```
+//!
+//! ```rust
+//! use rig::{completion::Prompt, providers::openai};
+//! use wasm_bindgen::prelude::*;
+//!
+//! #[wasm_bindgen]
+//! pub async fn ask(question: String) -> Result<String, JsError> {
+//!     // The proxy adds the API key to the requests forwarded to OpenAI
+//!     let openai = openai::Client::from_url("", "https://proxy.example.com/openai/v1");
+//!     let agent = openai.agent(openai::GPT_4O_MINI).build();
+//!
+//!     Ok(agent.prompt(question.as_str()).await?)
+//! }
+//! ```
+
+use std::{future::Future, pin::Pin};
+
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+pub use std::time::{Instant, SystemTime};
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub use web_time::{Instant, SystemTime};
+
+/// [Send] on every target but `wasm32`
+#[cfg(not(target_arch = "wasm32"))]
+pub trait WasmCompatSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + ?Sized> WasmCompatSend for T {}
+
+/// [Send] on every target but `wasm32`
+#[cfg(target_arch = "wasm32")]
+pub trait WasmCompatSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> WasmCompatSend for T {}
+
+/// [Sync] on every target but `wasm32`
+#[cfg(not(target_arch = "wasm32"))]
+pub trait WasmCompatSync: Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Sync + ?Sized> WasmCompatSync for T {}
+
+/// [Sync] on every target but `wasm32`
+#[cfg(target_arch = "wasm32")]
+pub trait WasmCompatSync {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> WasmCompatSync for T {}
+
+/// Boxed future, [Send] on every target but `wasm32` (i.e.: a
+/// [BoxFuture](futures::future::BoxFuture) on every target but `wasm32`, where it is a
+/// [LocalBoxFuture](futures::future::LocalBoxFuture))
+#[cfg(not(target_arch = "wasm32"))]
+pub type WasmBoxedFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Boxed future, [Send] on every target but `wasm32` (i.e.: a
+/// [BoxFuture](futures::future::BoxFuture) on every target but `wasm32`, where it is a
+/// [LocalBoxFuture](futures::future::LocalBoxFuture))
+#[cfg(target_arch = "wasm32")]
+pub type WasmBoxedFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// Boxed future, [Send] and [Sync] on every target but `wasm32` (where it is a
+/// [LocalBoxFuture](futures::future::LocalBoxFuture))
+#[cfg(not(target_arch = "wasm32"))]
+pub type WasmBoxedSyncFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + Sync + 'a>>;
+
+/// Boxed future, [Send] and [Sync] on every target but `wasm32` (where it is a
+/// [LocalBoxFuture](futures::future::LocalBoxFuture))
+#[cfg(target_arch = "wasm32")]
+pub type WasmBoxedSyncFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
@@ -15,6 +15,9 @@ use rig::agent::AgentBuilder;
 use rig::completion::{CompletionError, CompletionRequest};
 use rig::embeddings::{EmbeddingError, EmbeddingsBuilder};
 use rig::extractor::ExtractorBuilder;
+use rig::http_client::{
+    ApiError, HttpClient, HttpClientSettings, RequestBuilder, ReqwestClientSettings, ReqwestOptions,
+};
 use rig::providers::openai::{self, Message};
 use rig::OneOrMany;
 use rig::{completion, embeddings, Embed};
@@ -29,37 +32,72 @@ use std::time::Duration;
 // ================================================================
 const ETERNALAI_API_BASE_URL: &str = "https://api.eternalai.org/v1";
 
+/// Create a new EternalAI client using the builder
+#[derive(Clone)]
+pub struct ClientBuilder<'a> {
+    api_key: &'a str,
+    base_url: &'a str,
+    reqwest_options: ReqwestOptions,
+}
+
+impl<'a> ClientBuilder<'a> {
+    pub fn new(api_key: &'a str) -> Self {
+        Self {
+            api_key,
+            base_url: ETERNALAI_API_BASE_URL,
+            reqwest_options: ReqwestOptions::default(),
+        }
+    }
+
+    pub fn base_url(mut self, base_url: &'a str) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Panics if the default client cannot be built.
+    pub fn build(self) -> Client {
+        // Default client with a 120 seconds timeout
+        let http_client = self
+            .reqwest_options
+            .build_with(reqwest::Client::builder().timeout(Duration::from_secs(120)));
+
+        Client {
+            base_url: self.base_url.to_string(),
+            http_client: HttpClient::new(http_client).with_default_headers({
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert(
+                    "Authorization",
+                    format!("Bearer {}", self.api_key)
+                        .parse()
+                        .expect("Bearer token should parse"),
+                );
+                headers
+            }),
+        }
+    }
+}
+
+impl ReqwestClientSettings for ClientBuilder<'_> {
+    fn reqwest_options_mut(&mut self) -> &mut ReqwestOptions {
+        &mut self.reqwest_options
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     base_url: String,
-    http_client: reqwest::Client,
+    http_client: HttpClient,
 }
 
 impl Client {
     /// Create a new EternalAI client with the given API key.
     pub fn new(api_key: &str) -> Self {
-        Self::from_url(api_key, ETERNALAI_API_BASE_URL)
+        ClientBuilder::new(api_key).build()
     }
 
     /// Create a new EternalAI client with the given API key and base API URL.
     pub fn from_url(api_key: &str, base_url: &str) -> Self {
-        Self {
-            base_url: base_url.to_string(),
-            http_client: reqwest::Client::builder()
-                .default_headers({
-                    let mut headers = reqwest::header::HeaderMap::new();
-                    headers.insert(
-                        "Authorization",
-                        format!("Bearer {}", api_key)
-                            .parse()
-                            .expect("Bearer token should parse"),
-                    );
-                    headers
-                })
-                .timeout(Duration::from_secs(120))
-                .build()
-                .expect("EternalAI reqwest client should build"),
-        }
+        ClientBuilder::new(api_key).base_url(base_url).build()
     }
 
     /// Create a new EternalAI client from the `ETERNALAI_API_KEY` environment variable.
@@ -69,7 +107,7 @@ impl Client {
         Self::new(&api_key)
     }
 
-    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+    fn post(&self, path: &str) -> RequestBuilder {
         let url = format!("{}/{}", self.base_url, path).replace("//", "/");
         self.http_client.post(url)
     }
@@ -173,6 +211,12 @@ impl Client {
     }
 }
 
+impl HttpClientSettings for Client {
+    fn http_client_mut(&mut self) -> &mut HttpClient {
+        &mut self.http_client
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ApiErrorResponse {
     message: String,
@@ -298,7 +342,7 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
                 ApiResponse::Err(err) => Err(EmbeddingError::ProviderError(err.message)),
             }
         } else {
-            Err(EmbeddingError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -402,10 +446,17 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
             )
         })?;
 
-        Ok(completion::CompletionResponse {
-            choice,
-            raw_response: response,
-        })
+        let usage = response
+            .usage
+            .as_ref()
+            .map(|usage| completion::Usage {
+                input_tokens: usage.prompt_tokens as u64,
+                output_tokens: usage.total_tokens.saturating_sub(usage.prompt_tokens) as u64,
+                total_tokens: usage.total_tokens as u64,
+            })
+            .unwrap_or_default();
+
+        Ok(completion::CompletionResponse::new(choice, response).with_usage(usage))
     }
 }
 
@@ -463,13 +514,51 @@ impl CompletionModel {
     }
 }
 
-impl completion::CompletionModel for CompletionModel {
-    type Response = CompletionResponse;
+impl CompletionModel {
+    /// Fetch the on-chain system prompt of the agent set by the `ETERNALAI_RPC_URL`,
+    /// `ETERNALAI_AGENT_CONTRACT_ADDRESS` and `ETERNALAI_AGENT_ID` environment variables (if set)
+    async fn on_chain_system_prompt(&self) -> Result<Option<String>, CompletionError> {
+        tracing::info!("Try to get on-chain system prompt");
+        let eternal_ai_rpc = std::env::var("ETERNALAI_RPC_URL").unwrap_or_else(|_| "".to_string());
+        let eternal_ai_contract =
+            std::env::var("ETERNALAI_AGENT_CONTRACT_ADDRESS").unwrap_or_else(|_| "".to_string());
+        let eternal_ai_agent_id =
+            std::env::var("ETERNALAI_AGENT_ID").unwrap_or_else(|_| "".to_string());
+        if eternal_ai_rpc.is_empty()
+            || eternal_ai_contract.is_empty()
+            || eternal_ai_agent_id.is_empty()
+        {
+            return Ok(None);
+        }
 
-    async fn completion(
+        tracing::info!(
+            "get on-chain system prompt with {}, {}, {}",
+            eternal_ai_rpc,
+            eternal_ai_contract,
+            eternal_ai_agent_id
+        );
+        let c_value: c_uint = eternal_ai_agent_id.parse::<u32>().unwrap_or(0);
+        let prompt = eternalai_system_prompt_manager_toolset::get_on_chain_system_prompt(
+            &eternal_ai_rpc,
+            &eternal_ai_contract,
+            c_value,
+        )
+        .await
+        .map_err(CompletionError::ProviderError)?;
+
+        if prompt.is_none() {
+            tracing::info!("on-chain system prompt is none")
+        }
+        Ok(prompt)
+    }
+
+    fn create_completion_request(
         &self,
         completion_request: CompletionRequest,
-    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        on_chain_system_prompt: Option<String>,
+    ) -> Result<Value, CompletionError> {
+        completion_request.reject_prefill("EternalAI")?;
+
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => vec![Message::system(preamble)],
@@ -478,41 +567,9 @@ impl completion::CompletionModel for CompletionModel {
 
         // Convert prompt to user message
         let prompt: Vec<Message> = completion_request.prompt_with_context().try_into()?;
-        tracing::info!("Try to get on-chain system prompt");
-        let eternal_ai_rpc = std::env::var("ETERNALAI_RPC_URL").unwrap_or_else(|_| "".to_string());
-        let eternal_ai_contract =
-            std::env::var("ETERNALAI_AGENT_CONTRACT_ADDRESS").unwrap_or_else(|_| "".to_string());
-        let eternal_ai_agent_id =
-            std::env::var("ETERNALAI_AGENT_ID").unwrap_or_else(|_| "".to_string());
-        if !eternal_ai_rpc.is_empty()
-            && !eternal_ai_contract.is_empty()
-            && !eternal_ai_agent_id.is_empty()
-        {
-            tracing::info!(
-                "get on-chain system prompt with {}, {}, {}",
-                eternal_ai_rpc,
-                eternal_ai_contract,
-                eternal_ai_agent_id
-            );
-            let c_value: c_uint = eternal_ai_agent_id.parse::<u32>().unwrap_or(0);
-            let prompt = match eternalai_system_prompt_manager_toolset::get_on_chain_system_prompt(
-                &eternal_ai_rpc,
-                &eternal_ai_contract,
-                c_value,
-            )
-            .await
-            {
-                Ok(value) => value,
-                Err(e) => return Err(CompletionError::ProviderError(e)),
-            };
-            match prompt {
-                None => {
-                    tracing::info!("on-chain system prompt is none")
-                }
-                Some(value) => {
-                    full_history.push(Message::system(&value));
-                }
-            }
+
+        if let Some(on_chain_system_prompt) = on_chain_system_prompt {
+            full_history.push(Message::system(&on_chain_system_prompt));
         }
 
         // Convert existing chat history
@@ -545,18 +602,44 @@ impl completion::CompletionModel for CompletionModel {
             })
         };
 
+        let request = if let Some(params) = completion_request.additional_params {
+            json_utils::merge(request, params)
+        } else {
+            request
+        };
+
+        Ok(request)
+    }
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = CompletionResponse;
+
+    /// Note: the on-chain system prompt of the agent (if any) is not fetched, so it is missing
+    /// from the prepared request.
+    fn prepare_request(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<completion::PreparedRequest, CompletionError> {
+        let request = self.create_completion_request(completion_request, None)?;
+        completion::PreparedRequest::from_request_builder(
+            self.client.post("/chat/completions").json(&request),
+        )
+    }
+
+    async fn completion(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let on_chain_system_prompt = self.on_chain_system_prompt().await?;
+        let request = self.create_completion_request(completion_request, on_chain_system_prompt)?;
+
         tracing::debug!(target: "rig", "Sending completion request: {}", request);
 
         let response = self
             .client
             .post("/chat/completions")
-            .json(
-                &if let Some(params) = completion_request.additional_params {
-                    json_utils::merge(request, params)
-                } else {
-                    request
-                },
-            )
+            .json(&request)
             .send()
             .await?;
 
@@ -581,7 +664,7 @@ impl completion::CompletionModel for CompletionModel {
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.message)),
             }
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            Err(ApiError::from_response(response).await?.into())
         }
     }
 }
@@ -0,0 +1,208 @@
+//! Graph traversal expansion of vector search results (GraphRAG).
+//!
+//! A vector search finds the nodes most similar to a query, but the context needed to answer a
+//! question often lives in their neighborhood (e.g.: the actors and director of the movies whose
+//! plot matches the query). A [GraphExpansion] set on a
+//! [Neo4jVectorIndex](crate::vector_index::Neo4jVectorIndex) traverses the graph from each node
+//! found and returns its neighboring nodes with it, in the `neighbors` property of the node.
+//!
+//! # Example
+//! ```no_run
+//! use rig::{providers::openai, vector_store::VectorStoreIndex};
+//! use rig_neo4j::{
+//!     graph::{GraphExpansion, Neighbor},
+//!     vector_index::SearchParams,
+//!     Neo4jClient,
+//! };
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Movie {
+//!     title: String,
+//!     plot: String,
+//!     neighbors: Vec<Neighbor<Person>>,
+//! }
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Person {
+//!     name: String,
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let model = openai::Client::from_env().embedding_model(openai::TEXT_EMBEDDING_ADA_002);
+//!     let client = Neo4jClient::connect("neo4j://localhost:7687", "neo4j", "password").await?;
+//!
+//!     let index = client
+//!         .get_index(model, "moviePlots", SearchParams::default())
+//!         .await?
+//!         .graph_expansion(
+//!             GraphExpansion::new()
+//!                 .relationship_types(["ACTED_IN", "DIRECTED"])
+//!                 .max_neighbors(10),
+//!         );
+//!
+//!     let movies = index.top_n::<Movie>("Batman", 3).await?;
+//!     println!("{:#?}", movies);
+//!     Ok(())
+//! }
+//! ```
+use serde::{Deserialize, Serialize};
+
+/// Direction of the relationships traversed by a [GraphExpansion]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Relationships from the nodes found to their neighbors
+    Outgoing,
+    /// Relationships from the neighbors to the nodes found
+    Incoming,
+    /// Relationships in both directions
+    #[default]
+    Both,
+}
+
+/// Traversal of the graph from the nodes found by a vector search, pulling their neighbors into
+/// the results.
+///
+/// #### Default Values
+/// - `depth`: 1 (direct neighbors only)
+/// - `relationship_types`: all relationship types
+/// - `direction`: Direction::Both
+/// - `max_neighbors`: 10 (per node found, the closest neighbors first)
+/// - `neighbor_filter`: none
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphExpansion {
+    pub depth: usize,
+    pub relationship_types: Vec<String>,
+    pub direction: Direction,
+    pub max_neighbors: usize,
+    pub neighbor_filter: Option<String>,
+}
+
+impl Default for GraphExpansion {
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            relationship_types: vec![],
+            direction: Direction::Both,
+            max_neighbors: 10,
+            neighbor_filter: None,
+        }
+    }
+}
+
+impl GraphExpansion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of relationships between a node found and its neighbors (at least 1)
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = depth.max(1);
+        self
+    }
+
+    /// Only traverse relationships of the given types (e.g.: `["ACTED_IN", "DIRECTED"]`)
+    pub fn relationship_types(
+        mut self,
+        relationship_types: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.relationship_types = relationship_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Maximum number of neighbors returned per node found
+    pub fn max_neighbors(mut self, max_neighbors: usize) -> Self {
+        self.max_neighbors = max_neighbors;
+        self
+    }
+
+    /// Only return the neighbors matching `filter`, a Cypher predicate on the `neighbor`
+    /// variable (e.g.: `neighbor:Person AND neighbor.born > 1970`).
+    /// See [Neo4j WHERE clause](https://neo4j.com/docs/cypher-manual/current/clauses/where/) for
+    /// more information.
+    pub fn neighbor_filter(mut self, filter: &str) -> Self {
+        self.neighbor_filter = Some(filter.to_string());
+        self
+    }
+
+    /// Cypher subquery collecting the `neighbors` of each `node` found, excluding the
+    /// `embedding_property` of the neighbors.
+    ///
+    /// Query template:
+    /// ```cypher
+    /// CALL {
+    ///     WITH node
+    ///     MATCH path = (node)-[:{relationship_types}*1..{depth}]-(neighbor)
+    ///     WHERE neighbor <> node AND {neighbor_filter}
+    ///     WITH neighbor, path ORDER BY length(path)
+    ///     WITH neighbor, collect(path)[0] AS path
+    ///     ORDER BY length(path)
+    ///     LIMIT $max_neighbors
+    ///     RETURN collect({ ... }) AS neighbors
+    /// }
+    /// ```
+    pub(crate) fn subquery(&self, embedding_property: &str) -> String {
+        let relationship_types = if self.relationship_types.is_empty() {
+            "".to_string()
+        } else {
+            format!(
+                ":{}",
+                self.relationship_types
+                    .iter()
+                    .map(|relationship_type| format!("`{}`", relationship_type.replace('`', "``")))
+                    .collect::<Vec<_>>()
+                    .join("|")
+            )
+        };
+        let relationship = format!("[{}*1..{}]", relationship_types, self.depth.max(1));
+        let pattern = match self.direction {
+            Direction::Outgoing => format!("(node)-{relationship}->(neighbor)"),
+            Direction::Incoming => format!("(node)<-{relationship}-(neighbor)"),
+            Direction::Both => format!("(node)-{relationship}-(neighbor)"),
+        };
+        let filter = match &self.neighbor_filter {
+            Some(filter) => format!(" AND ({})", filter),
+            None => "".to_string(),
+        };
+
+        format!(
+            "
+    CALL {{
+        WITH node
+        MATCH path = {pattern}
+        WHERE neighbor <> node{filter}
+        WITH neighbor, path ORDER BY length(path)
+        WITH neighbor, collect(path)[0] AS path
+        ORDER BY length(path)
+        LIMIT {max_neighbors}
+        RETURN collect({{
+            element_id: ID(neighbor),
+            labels: labels(neighbor),
+            relationships: [relationship IN relationships(path) | type(relationship)],
+            node: neighbor {{.*, {embedding_property}:null }}
+        }}) AS neighbors
+    }}
+",
+            max_neighbors = self.max_neighbors,
+        )
+    }
+}
+
+/// Neighbor of a node found by a vector search with a [GraphExpansion]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Neighbor<T = serde_json::Value> {
+    pub element_id: i64,
+    /// Labels of the neighbor (e.g.: `["Person"]`)
+    pub labels: Vec<String>,
+    /// Types of the relationships on the (shortest) path from the node found to the neighbor
+    pub relationships: Vec<String>,
+    /// Properties of the neighbor
+    pub node: T,
+}
@@ -37,6 +37,13 @@
 //!     }}
 //! ```
 //!
+//! ### Graph expansion (GraphRAG)
+//!
+//! The nodes found by a vector search can be returned with their neighbors in the graph (e.g.: the
+//! actors of the movies whose plot matches the query) by setting a
+//! [GraphExpansion](graph::GraphExpansion) on the index. See the [graph] module for more
+//! information.
+//!
 //! ## Simple example:
 //! More examples can be found in the [/examples](https://github.com/0xPlaygrounds/rig/tree/main/rig-neo4j/examples) folder.
 //! ```
@@ -85,6 +92,7 @@
 //!     println!("{:#?}", results);
 //! }
 //! ```
+pub mod graph;
 pub mod vector_index;
 use std::str::FromStr;
 
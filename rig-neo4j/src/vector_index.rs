@@ -11,13 +11,14 @@ use rig::{
 };
 use serde::{de::Error, Deserialize, Serialize};
 
-use crate::Neo4jClient;
+use crate::{graph::GraphExpansion, Neo4jClient};
 
 pub struct Neo4jVectorIndex<M: EmbeddingModel> {
     graph: Graph,
     embedding_model: M,
     search_params: SearchParams,
     index_config: IndexConfig,
+    graph_expansion: Option<GraphExpansion>,
 }
 
 /// The index name must be unique among both indexes and constraints.
@@ -113,9 +114,19 @@ impl<M: EmbeddingModel> Neo4jVectorIndex<M> {
             embedding_model,
             index_config,
             search_params,
+            graph_expansion: None,
         }
     }
 
+    /// Return the neighbors of the nodes found by [Neo4jVectorIndex::top_n], found by traversing
+    /// the graph according to `graph_expansion`, in the `neighbors` property of the nodes
+    /// (see [Neighbor](crate::graph::Neighbor)). The ids returned by
+    /// [Neo4jVectorIndex::top_n_ids] are not expanded.
+    pub fn graph_expansion(mut self, graph_expansion: GraphExpansion) -> Self {
+        self.graph_expansion = Some(graph_expansion);
+        self
+    }
+
     /// Build a Neo4j query that performs a vector search against an index.
     /// See [Query vector index](https://neo4j.com/docs/cypher-manual/current/indexes/semantic-indexes/vector-indexes/#query-vector-index) for more information.
     ///
@@ -126,6 +137,9 @@ impl<M: EmbeddingModel> Neo4jVectorIndex<M> {
     /// WHERE {where_clause}
     /// RETURN score, ID(node) as element_id, node {.*, embedding:null } as node
     /// ```
+    ///
+    /// With a [GraphExpansion], the neighbors of the nodes are collected by a `CALL` subquery
+    /// (after the WHERE clause) and returned in the `neighbors` property of the nodes.
     pub fn build_vector_search_query(
         &self,
         prompt_embedding: Embedding,
@@ -137,19 +151,29 @@ impl<M: EmbeddingModel> Neo4jVectorIndex<M> {
             None => "".to_string(),
         };
 
+        let (graph_expansion_subquery, neighbors) = match &self.graph_expansion {
+            Some(graph_expansion) if return_node => (
+                graph_expansion.subquery(&self.index_config.embedding_property),
+                ", neighbors: neighbors",
+            ),
+            _ => ("".to_string(), ""),
+        };
+
         // Propertiy containing the embedding vectors are excluded from the returned node
         let query = format!(
             "\
             {}\
             \t{}\n\
+            {}\
             \tRETURN score, ID(node) as element_id {}
             ",
             BASE_VECTOR_SEARCH_QUERY,
             where_clause,
+            graph_expansion_subquery,
             if return_node {
                 format!(
-                    ", node {{.*, {}:null }} as node",
-                    self.index_config.embedding_property
+                    ", node {{.*, {}:null{} }} as node",
+                    self.index_config.embedding_property, neighbors
                 )
             } else {
                 "".to_string()
@@ -12,7 +12,7 @@ use rig::{
     providers::openai,
     Embed, OneOrMany,
 };
-use rig_neo4j::{vector_index::SearchParams, Neo4jClient, ToBoltType};
+use rig_neo4j::{graph::GraphExpansion, vector_index::SearchParams, Neo4jClient, ToBoltType};
 
 const BOLT_PORT: u16 = 7687;
 const HTTP_PORT: u16 = 7474;
@@ -185,7 +185,7 @@ async fn vector_search_test() {
     // Create a vector index on our vector store
     // IMPORTANT: Reuse the same model that was used to generate the embeddings
     let index = neo4j_client
-        .get_index(model, "vector_index", SearchParams::default())
+        .get_index(model.clone(), "vector_index", SearchParams::default())
         .await
         .expect("");
 
@@ -204,7 +204,45 @@ async fn vector_search_test() {
             "document": "Definition of a *glarb-glarb*: A glarb-glarb is a ancient tool used by the ancestors of the inhabitants of planet Jiro to farm the land.",
             "embedding": serde_json::Value::Null
         })
-    )
+    );
+
+    // Link the glarb-glarb to a planet and pull it into the results with a graph expansion
+    neo4j_client
+        .graph
+        .run(neo4rs::query(
+            "MATCH (word:DocumentEmbeddings {id: 'doc1'})
+                CREATE (word)-[:USED_ON]->(:Planet {name: 'Jiro'})",
+        ))
+        .await
+        .expect("");
+
+    let index = neo4j_client
+        .get_index(model, "vector_index", SearchParams::default())
+        .await
+        .expect("")
+        .graph_expansion(GraphExpansion::new().relationship_types(["USED_ON"]));
+
+    let results = index
+        .top_n::<serde_json::Value>("What is a glarb?", 1)
+        .await
+        .expect("");
+
+    let (_, _, value) = &results.first().expect("");
+    let neighbors = value["neighbors"].as_array().expect("");
+
+    assert_eq!(neighbors.len(), 1);
+    assert_eq!(neighbors[0]["labels"], serde_json::json!(["Planet"]));
+    assert_eq!(
+        neighbors[0]["relationships"],
+        serde_json::json!(["USED_ON"])
+    );
+    assert_eq!(
+        neighbors[0]["node"],
+        serde_json::json!({
+            "name": "Jiro",
+            "embedding": serde_json::Value::Null
+        })
+    );
 }
 
 async fn create_embeddings(model: openai::EmbeddingModel) -> Vec<(Word, OneOrMany<Embedding>)> {